@@ -0,0 +1,136 @@
+// FM-AMM batch clearing (see notes/amm/3_price_in_amm_models.md).
+//
+// Every order in a batch trades at one price `p` (token0 priced in token1),
+// chosen so the pool's average price on its net trade equals its marginal price
+// afterwards, `p = (y - dy) / (x + dx)`. With `A` of token0 and `B` of token1
+// sold into the batch the pool takes `dx = A - B / p` and pays `dy = A * p - B`,
+// which solves to
+//
+//     p = (y + 2B) / (x + 2A)
+//
+// Both sides of the batch net against each other and only the imbalance moves
+// the curve. An order placed in front of yours in the same batch clears at your
+// price, so there is nothing to sandwich. Fees come off the inputs before
+// clearing and stay in the pool.
+use crate::constant_product::BPS;
+use crate::full_math::mul_div;
+use alloy_primitives::U256;
+
+/// `p = numerator / denominator`, kept as a fraction so each order's output is a
+/// single floor division.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClearingPrice {
+    pub numerator: U256,
+    pub denominator: U256,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchSettlement {
+    pub price: ClearingPrice,
+    /// Token0 owed to the token1 sellers, in total.
+    pub amount0_out: U256,
+    /// Token1 owed to the token0 sellers, in total.
+    pub amount1_out: U256,
+    pub reserve0: U256,
+    pub reserve1: U256,
+}
+
+/// Uniform price for `amount0_in` / `amount1_in` (after fees) against the reserves.
+pub fn clearing_price(reserve0: U256, reserve1: U256, amount0_in: U256, amount1_in: U256) -> Option<ClearingPrice> {
+    if reserve0 == U256::ZERO || reserve1 == U256::ZERO {
+        return None;
+    }
+    let two = U256::from(2);
+    Some(ClearingPrice {
+        numerator: reserve1.checked_add(amount1_in.checked_mul(two)?)?,
+        denominator: reserve0.checked_add(amount0_in.checked_mul(two)?)?,
+    })
+}
+
+/// Input net of a fee in basis points.
+pub fn after_fee(amount_in: U256, fee_bps: u64) -> U256 {
+    amount_in * U256::from(BPS - fee_bps) / U256::from(BPS)
+}
+
+/// Token1 for selling `amount0_in` (after fees) at `price`, rounded down.
+pub fn amount1_out(amount0_in: U256, price: &ClearingPrice) -> Option<U256> {
+    mul_div(amount0_in, price.numerator, price.denominator)
+}
+
+/// Token0 for selling `amount1_in` (after fees) at `price`, rounded down.
+pub fn amount0_out(amount1_in: U256, price: &ClearingPrice) -> Option<U256> {
+    mul_div(amount1_in, price.denominator, price.numerator)
+}
+
+/// Clears a batch with gross inputs `amount0_in` / `amount1_in`. Orders paid
+/// one by one with `amount*_out(after_fee(..))` never add up to more than the
+/// totals here, so the rounding dust stays with the pool.
+pub fn settle(reserve0: U256, reserve1: U256, amount0_in: U256, amount1_in: U256, fee_bps: u64) -> Option<BatchSettlement> {
+    let (net0, net1) = (after_fee(amount0_in, fee_bps), after_fee(amount1_in, fee_bps));
+    let price = clearing_price(reserve0, reserve1, net0, net1)?;
+    let amount0_out = amount0_out(net1, &price)?;
+    let amount1_out = amount1_out(net0, &price)?;
+    Some(BatchSettlement {
+        price,
+        amount0_out,
+        amount1_out,
+        // more than half of each original reserve always stays in the pool
+        reserve0: (reserve0 + amount0_in).checked_sub(amount0_out)?,
+        reserve1: (reserve1 + amount1_in).checked_sub(amount1_out)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn e18(value: u128) -> U256 {
+        U256::from(value * E18)
+    }
+
+    #[test]
+    fn the_pool_ends_at_the_clearing_price() {
+        let (reserve0, reserve1) = (e18(1_000), e18(2_000));
+        for (amount0_in, amount1_in) in [(e18(10), U256::ZERO), (U256::ZERO, e18(50)), (e18(30), e18(7)), (e18(400), e18(900))] {
+            let s = settle(reserve0, reserve1, amount0_in, amount1_in, 0).unwrap();
+            // marginal price after = p, up to the rounding of the outputs
+            let after = s.reserve1 * s.price.denominator;
+            let expected = s.reserve0 * s.price.numerator;
+            let gap = if after > expected { after - expected } else { expected - after };
+            assert!(gap <= s.price.numerator + s.price.denominator, "{amount0_in} {amount1_in}");
+            assert_eq!(s.reserve0, reserve0 + amount0_in - s.amount0_out);
+            assert_eq!(s.reserve1, reserve1 + amount1_in - s.amount1_out);
+        }
+    }
+
+    #[test]
+    fn matched_flows_leave_the_price_alone() {
+        // 10 token0 against the 20 token1 it's worth at a price of 2
+        let s = settle(e18(1_000), e18(2_000), e18(10), e18(20), 0).unwrap();
+        assert_eq!(s.price.numerator, s.price.denominator * U256::from(2));
+        assert_eq!((s.amount0_out, s.amount1_out), (e18(10), e18(20)));
+        assert_eq!((s.reserve0, s.reserve1), (e18(1_000), e18(2_000)));
+    }
+
+    #[test]
+    fn orders_never_claim_more_than_the_batch() {
+        let (reserve0, reserve1, fee_bps) = (e18(1_000), e18(3_000), 30);
+        let sellers0: [&[u128]; 3] = [&[E18], &[E18 / 3, E18 / 3, E18 / 3 + 1, 7], &[5 * E18 + 1, 999, 12_345_678_901, 1]];
+        let sellers1: [&[u128]; 3] = [&[], &[3 * E18 - 1, 1], &[E18 / 7; 7]];
+        for orders0 in sellers0 {
+            for orders1 in sellers1 {
+                let amount0_in = orders0.iter().map(|a| U256::from(*a)).sum();
+                let amount1_in = orders1.iter().map(|a| U256::from(*a)).sum();
+                let s = settle(reserve0, reserve1, amount0_in, amount1_in, fee_bps).unwrap();
+                let paid1: U256 = orders0.iter().map(|a| amount1_out(after_fee(U256::from(*a), fee_bps), &s.price).unwrap()).sum();
+                let paid0: U256 = orders1.iter().map(|a| amount0_out(after_fee(U256::from(*a), fee_bps), &s.price).unwrap()).sum();
+                assert!(paid1 <= s.amount1_out && paid0 <= s.amount0_out);
+                // dust is a few wei per order: the fee rounding once, times the price, plus the floor
+                assert!(s.amount1_out - paid1 <= U256::from(5 * orders0.len()), "{}", s.amount1_out - paid1);
+                assert!(s.amount0_out - paid0 <= U256::from(2 * orders1.len()), "{}", s.amount0_out - paid0);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// x * y = k pair math (see notes/amm/2_constant_product_formula.md)
+use crate::full_math::sqrt;
+use alloy_primitives::U256;
+
+/// Pair fee as numerator over `FEE_DENOMINATOR` (0.3%).
+pub const FEE_NUMERATOR: u64 = 997;
+pub const FEE_DENOMINATOR: u64 = 1000;
+/// Denominator for fees given in basis points.
+pub const BPS: u64 = 10_000;
+
+/// Output for selling `amount_in` into reserves `(reserve_in, reserve_out)`:
+/// `Δy = y * Δx * 0.997 / (x + Δx * 0.997)`.
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256) -> U256 {
+    if amount_in == U256::ZERO || reserve_in == U256::ZERO || reserve_out == U256::ZERO {
+        return U256::ZERO;
+    }
+    let amount_in_with_fee = amount_in * U256::from(FEE_NUMERATOR);
+    amount_in_with_fee * reserve_out / (reserve_in * U256::from(FEE_DENOMINATOR) + amount_in_with_fee)
+}
+
+/// `get_amount_out` for a pair charging `fee_bps` instead of the fixed 0.3%.
+pub fn get_amount_out_with_fee(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: u64) -> U256 {
+    if amount_in == U256::ZERO || reserve_in == U256::ZERO || reserve_out == U256::ZERO {
+        return U256::ZERO;
+    }
+    let amount_in_with_fee = amount_in * U256::from(BPS - fee_bps);
+    amount_in_with_fee * reserve_out / (reserve_in * U256::from(BPS) + amount_in_with_fee)
+}
+
+/// Input needed to buy `amount_out`, rounded up.
+pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256) -> Option<U256> {
+    if amount_out == U256::ZERO || amount_out >= reserve_out {
+        return None;
+    }
+    let numerator = reserve_in * amount_out * U256::from(FEE_DENOMINATOR);
+    let denominator = (reserve_out - amount_out) * U256::from(FEE_NUMERATOR);
+    Some(numerator / denominator + U256::from(1))
+}
+
+/// Part of `amount` to swap before adding single-sided liquidity, so the rest and
+/// the swap output match the pair's ratio after the swap. Solving
+/// `(amount - s) / (r + s) = out(s) / (y - out(s))` with the 0.3% fee gives
+/// `s = (sqrt(r * (3988009 * r + 3988000 * amount)) - 1997 * r) / 1994`.
+pub fn optimal_swap_amount(amount: U256, reserve_in: U256) -> U256 {
+    let root = sqrt(reserve_in * (U256::from(3_988_009) * reserve_in + U256::from(3_988_000) * amount));
+    (root - U256::from(1997) * reserve_in) / U256::from(1994)
+}
+
+/// `optimal_swap_amount` for a pair charging `fee_bps`: with `g = 1 - fee` and
+/// `h = 2 - fee` in basis points, `s = (sqrt(r * (h² * r + 4 * g * BPS * amount)) - h * r) / (2 * g)`.
+pub fn optimal_swap_amount_with_fee(amount: U256, reserve_in: U256, fee_bps: u64) -> U256 {
+    let g = U256::from(BPS - fee_bps);
+    let h = U256::from(2 * BPS - fee_bps);
+    let root = sqrt(reserve_in * (h * h * reserve_in + U256::from(4 * BPS) * g * amount));
+    (root - h * reserve_in) / (U256::from(2) * g)
+}
+
+/// `sqrt(reserve0 * reserve1)`, the liquidity a pair's LP supply represents.
+/// Swap fees stay in the reserves, so it only grows between mints and burns.
+pub fn sqrt_k(reserve0: U256, reserve1: U256) -> U256 {
+    sqrt(reserve0 * reserve1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimal_swap_leaves_the_rest_at_the_new_ratio() {
+        let (amount, reserve_in, reserve_out) = (U256::from(10u128.pow(21)), U256::from(10u128.pow(22)), U256::from(3 * 10u128.pow(22)));
+        let at_base_fee = optimal_swap_amount_with_fee(amount, reserve_in, 30);
+        assert!(at_base_fee.abs_diff(optimal_swap_amount(amount, reserve_in)) <= U256::from(1));
+
+        for fee_bps in [0, 30, 300, 1_000] {
+            let swapped = optimal_swap_amount_with_fee(amount, reserve_in, fee_bps);
+            let out = get_amount_out_with_fee(swapped, reserve_in, reserve_out, fee_bps);
+            // (amount - s) / (r_in + s) == out / (r_out - out), to rounding
+            let lhs = (amount - swapped) * (reserve_out - out);
+            let rhs = out * (reserve_in + swapped);
+            assert!(lhs.abs_diff(rhs) <= lhs / U256::from(10u64.pow(15)), "{fee_bps}");
+        }
+    }
+}
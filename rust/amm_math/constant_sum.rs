@@ -0,0 +1,77 @@
+// x + y = k pricing for pegged pairs (see notes/amm/1_amm_fundamental.md).
+// Trades are 1:1 minus the fee, which is as cheap as it gets, but nothing stops a
+// depegged asset from draining the other reserve. `quote` therefore switches to
+// x * y = k as soon as a reserve would fall below `threshold_bps` of the pool.
+//
+// Everything returns `None` on overflow (or a fee above 100%), for the caller
+// to turn into its own error.
+use alloy_primitives::U256;
+
+pub const BPS: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pricing {
+    ConstantSum,
+    ConstantProduct,
+}
+
+/// 1:1 output after a fee in basis points.
+pub fn get_amount_out(amount_in: U256, fee_bps: u64) -> Option<U256> {
+    Some(amount_in.checked_mul(U256::from(BPS.checked_sub(fee_bps)?))? / U256::from(BPS))
+}
+
+/// True when the smaller reserve holds less than `threshold_bps` of the total.
+pub fn is_depegged(reserve0: U256, reserve1: U256, threshold_bps: u64) -> Option<bool> {
+    let total = reserve0.checked_add(reserve1)?;
+    if total == U256::ZERO {
+        return Some(false);
+    }
+    Some(reserve0.min(reserve1).checked_mul(U256::from(BPS))? < total.checked_mul(U256::from(threshold_bps))?)
+}
+
+/// Output for selling `amount_in` and the curve that priced it. Constant sum
+/// is used only while the pool stays above the threshold after the trade;
+/// otherwise the whole trade is priced on x * y = k with the same fee.
+pub fn quote(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: u64, threshold_bps: u64) -> Option<(U256, Pricing)> {
+    let amount_out = get_amount_out(amount_in, fee_bps)?;
+    if amount_out < reserve_out
+        && !is_depegged(reserve_in, reserve_out, threshold_bps)?
+        && !is_depegged(reserve_in.checked_add(amount_in)?, reserve_out - amount_out, threshold_bps)?
+    {
+        return Some((amount_out, Pricing::ConstantSum));
+    }
+    // amount_out is amount_in after the fee
+    let denominator = reserve_in.checked_add(amount_out)?;
+    if denominator == U256::ZERO {
+        return Some((U256::ZERO, Pricing::ConstantProduct));
+    }
+    Some((amount_out.checked_mul(reserve_out)? / denominator, Pricing::ConstantProduct))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn quotes_switch_curves_at_the_threshold() {
+        let (reserve_in, reserve_out) = (U256::from(1_000 * E18), U256::from(1_000 * E18));
+        let small = quote(U256::from(100 * E18), reserve_in, reserve_out, 4, 2_000).unwrap();
+        assert_eq!(small, (U256::from(9_996 * E18 / 100), Pricing::ConstantSum));
+        // 1_700 / 300 would leave the out side under 20%
+        let after_fee = U256::from(700 * E18 / 10_000 * 9_996);
+        let expected = after_fee * reserve_out / (reserve_in + after_fee);
+        assert_eq!(quote(U256::from(700 * E18), reserve_in, reserve_out, 4, 2_000).unwrap(), (expected, Pricing::ConstantProduct));
+    }
+
+    #[test]
+    fn overflow_is_none() {
+        assert_eq!(get_amount_out(U256::MAX, 4), None);
+        assert_eq!(get_amount_out(U256::from(1), BPS + 1), None);
+        assert_eq!(is_depegged(U256::MAX, U256::from(1), 2_000), None);
+        assert_eq!(is_depegged(U256::MAX / U256::from(2), U256::MAX / U256::from(2), 2_000), None);
+        let huge = U256::MAX / U256::from(BPS);
+        assert_eq!(quote(huge, U256::from(E18), huge, 0, 2_000), None);
+    }
+}
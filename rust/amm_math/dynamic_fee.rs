@@ -0,0 +1,128 @@
+// Volatility-driven swap fee (notes/amm/2_constant_product_formula.md,
+// "Dynamic Fee Models"): fee = base + volatility * multiplier, capped at max.
+//
+// Volatility is an EWMA of squared log returns per block,
+//     var' = lambda^n * var + (1 - lambda^n) * r^2 / n,
+// where r is the log return over the n blocks since the last observation, so a
+// quiet gap decays the estimate instead of counting as a single big move.
+use crate::fixed_point::{ln, mul_down, powi, WAD};
+use crate::full_math::sqrt;
+use alloy_primitives::U256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// Fee with no measured volatility.
+    pub base_fee_bps: u64,
+    /// Extra bps per 1.0 (WAD) of per-block volatility; 10_000 adds 1 bp per 0.01% move.
+    pub volatility_multiplier: U256,
+    pub max_fee_bps: u64,
+}
+
+/// Spot price of token0 in token1, WAD.
+pub fn spot_price(reserve0: U256, reserve1: U256) -> Option<U256> {
+    if reserve0 == U256::ZERO {
+        return None;
+    }
+    Some(reserve1 * WAD / reserve0)
+}
+
+/// Folds the move from `previous_price` to `price` over `blocks` blocks into
+/// `variance` with per-block `decay` (lambda, WAD, e.g. 0.94e18).
+pub fn update_variance(variance: U256, previous_price: U256, price: U256, blocks: u64, decay: U256) -> Option<U256> {
+    if blocks == 0 || previous_price == U256::ZERO || price == U256::ZERO {
+        return Some(variance);
+    }
+    let log_return = ln(price * WAD / previous_price)?.unsigned_abs();
+    let squared_return = mul_down(log_return, log_return) / U256::from(blocks);
+    let weight = powi(decay, blocks);
+    Some(mul_down(weight, variance) + mul_down(WAD - weight, squared_return))
+}
+
+/// Per-block standard deviation of log returns, WAD.
+pub fn volatility(variance: U256) -> U256 {
+    sqrt(variance * WAD)
+}
+
+/// `base + volatility * multiplier`, capped at `max_fee_bps`. Saturates, so an
+/// extreme reading can't wrap around to a low fee.
+pub fn dynamic_fee(volatility: U256, config: &FeeConfig) -> u64 {
+    let extra = volatility.saturating_mul(config.volatility_multiplier) / WAD;
+    let fee = U256::from(config.base_fee_bps).saturating_add(extra);
+    fee.min(U256::from(config.max_fee_bps)).saturating_to::<u64>()
+}
+
+/// Annualised volatility from a per-block one, for reporting:
+/// `sigma * sqrt(blocks_per_year)`.
+pub fn annualized_volatility(volatility: U256, blocks_per_year: u64) -> U256 {
+    volatility * sqrt(U256::from(blocks_per_year))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: FeeConfig = FeeConfig { base_fee_bps: 5, volatility_multiplier: U256::from_limbs([100_000, 0, 0, 0]), max_fee_bps: 100 };
+
+    fn wad(value: f64) -> U256 {
+        U256::from((value * 1e18) as u128)
+    }
+
+    #[test]
+    fn fee_stays_between_base_and_max() {
+        assert_eq!(dynamic_fee(U256::ZERO, &CONFIG), 5);
+        // 0.01% per block adds 10 bps
+        assert_eq!(dynamic_fee(wad(0.0001), &CONFIG), 15);
+        assert_eq!(dynamic_fee(wad(0.001), &CONFIG), 100);
+        assert_eq!(dynamic_fee(WAD, &CONFIG), 100);
+        assert_eq!(dynamic_fee(U256::MAX, &CONFIG), 100);
+        let extreme = FeeConfig { volatility_multiplier: U256::MAX, ..CONFIG };
+        assert_eq!(dynamic_fee(U256::MAX, &extreme), 100);
+
+        let mut previous = 0;
+        for step in 0..200u64 {
+            let fee = dynamic_fee(U256::from(step) * wad(0.000_01), &CONFIG);
+            assert!((CONFIG.base_fee_bps..=CONFIG.max_fee_bps).contains(&fee), "{fee}");
+            assert!(fee >= previous);
+            previous = fee;
+        }
+    }
+
+    #[test]
+    fn variance_is_a_weighted_average() {
+        let decay = wad(0.94);
+        // a 1% move in one block: r^2 ~ 1e-4, weighted by 6%
+        let variance = update_variance(U256::ZERO, WAD, wad(1.01), 1, decay).unwrap();
+        let squared = 0.01f64.ln_1p().powi(2);
+        assert!((variance.to::<u128>() as f64 / 1e18 - 0.06 * squared).abs() < 1e-12, "{variance}");
+        // the down move has the same magnitude
+        assert_eq!(update_variance(U256::ZERO, wad(1.01), WAD, 1, decay), Some(variance));
+
+        // new estimates stay between the old one and the new squared return
+        let high = wad(0.01);
+        let updated = update_variance(high, WAD, wad(1.01), 1, decay).unwrap();
+        assert!(updated < high && updated > variance);
+    }
+
+    #[test]
+    fn quiet_blocks_decay_the_estimate() {
+        let decay = wad(0.94);
+        let variance = wad(0.0001);
+        assert_eq!(update_variance(variance, WAD, WAD, 0, decay), Some(variance));
+        assert_eq!(update_variance(variance, U256::ZERO, WAD, 5, decay), Some(variance));
+        let after_10 = update_variance(variance, WAD, WAD, 10, decay).unwrap();
+        assert_eq!(after_10, mul_down(powi(decay, 10), variance));
+        // spread over 1_000 blocks the same 1% move counts as r^2 / 1_000, not 6% of r^2
+        let spread = update_variance(U256::ZERO, WAD, wad(1.01), 1_000, decay).unwrap();
+        let single = update_variance(U256::ZERO, WAD, wad(1.01), 1, decay).unwrap();
+        assert!(spread * U256::from(50) < single);
+        assert!(dynamic_fee(volatility(spread), &CONFIG) < dynamic_fee(volatility(single), &CONFIG));
+    }
+
+    #[test]
+    fn volatility_is_the_square_root() {
+        assert_eq!(volatility(U256::ZERO), U256::ZERO);
+        assert_eq!(volatility(wad(0.0001)), wad(0.01));
+        assert_eq!(volatility(WAD), WAD);
+        assert_eq!(annualized_volatility(wad(0.01), 10_000), wad(1.0));
+    }
+}
@@ -0,0 +1,156 @@
+// 18-decimal fixed point ("WAD") arithmetic, including the ln / exp / pow the
+// weighted pool needs for non-integer exponents.
+use alloy_primitives::{I256, U256};
+
+/// 1.0
+pub const WAD: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+/// ln(2)
+pub const LN_2: U256 = U256::from_limbs([693_147_180_559_945_309, 0, 0, 0]);
+
+/// Relative error `pow` stays within; `pow_up`/`pow_down` widen results by this.
+pub const MAX_POW_RELATIVE_ERROR: U256 = U256::from_limbs([10_000, 0, 0, 0]);
+
+// exp overflows a U256 WAD a little past e^135 and is below 1 wei under e^-42
+const MAX_EXP_ARGUMENT: u64 = 130;
+const MIN_EXP_ARGUMENT: u64 = 42;
+
+pub fn mul_down(a: U256, b: U256) -> U256 {
+    a * b / WAD
+}
+
+pub fn mul_up(a: U256, b: U256) -> U256 {
+    let product = a * b;
+    if product == U256::ZERO {
+        return U256::ZERO;
+    }
+    (product - U256::from(1)) / WAD + U256::from(1)
+}
+
+pub fn div_down(a: U256, b: U256) -> U256 {
+    a * WAD / b
+}
+
+pub fn div_up(a: U256, b: U256) -> U256 {
+    if a == U256::ZERO {
+        return U256::ZERO;
+    }
+    (a * WAD - U256::from(1)) / b + U256::from(1)
+}
+
+/// `x^n` for a whole exponent, by repeated squaring.
+pub fn powi(x: U256, n: u64) -> U256 {
+    let (mut base, mut n, mut result) = (x, n, WAD);
+    while n > 0 {
+        if n & 1 == 1 {
+            result = mul_down(result, base);
+        }
+        base = mul_down(base, base);
+        n >>= 1;
+    }
+    result
+}
+
+/// `1 - x`, floored at zero.
+pub fn complement(x: U256) -> U256 {
+    WAD.saturating_sub(x)
+}
+
+// Non-negative U256 values below 2^255 as I256.
+fn signed(value: U256) -> I256 {
+    I256::from_raw(value)
+}
+
+/// Natural log of a WAD value. Splits `x = 2^k * m` with `m` in [1, 2), then
+/// `ln(m) = 2 * atanh((m - 1) / (m + 1))`, a series that gains about one
+/// decimal per term since the argument is at most 1/3.
+pub fn ln(x: U256) -> Option<I256> {
+    if x == U256::ZERO {
+        return None;
+    }
+    let two = WAD * U256::from(2);
+    let (mut m, mut k) = if x >= WAD {
+        let shift = x.bit_len() - WAD.bit_len();
+        (x >> shift, shift as i64)
+    } else {
+        let shift = WAD.bit_len() - x.bit_len();
+        (x << shift, -(shift as i64))
+    };
+    // the bit-length guess can be off by one
+    if m >= two {
+        m >>= 1;
+        k += 1;
+    } else if m < WAD {
+        m <<= 1;
+        k -= 1;
+    }
+
+    let z = (m - WAD) * WAD / (m + WAD);
+    let z_squared = z * z / WAD;
+    let (mut term, mut sum, mut n) = (z, U256::ZERO, 1u64);
+    while term > U256::ZERO {
+        sum += term / U256::from(n);
+        term = term * z_squared / WAD;
+        n += 2;
+    }
+    let ln_m = signed(sum * U256::from(2));
+    let k_ln_2 = signed(LN_2 * U256::from(k.unsigned_abs()));
+    Some(if k < 0 { ln_m - k_ln_2 } else { ln_m + k_ln_2 })
+}
+
+/// e^x for a signed WAD exponent. Reduces to `x = k * ln(2) + r` with
+/// |r| <= ln(2) / 2, sums the Taylor series for e^r and shifts by k.
+/// `None` above e^130; zero below e^-42.
+pub fn exp(x: I256) -> Option<U256> {
+    let magnitude = x.unsigned_abs();
+    if x.is_negative() && magnitude > U256::from(MIN_EXP_ARGUMENT) * WAD {
+        return Some(U256::ZERO);
+    }
+    if !x.is_negative() && magnitude > U256::from(MAX_EXP_ARGUMENT) * WAD {
+        return None;
+    }
+    // nearest k, so the remainder is small either side of zero
+    let k = (magnitude + LN_2 / U256::from(2)) / LN_2;
+    let k_ln_2 = signed(k * LN_2);
+    let r = if x.is_negative() { -signed(magnitude) + k_ln_2 } else { signed(magnitude) - k_ln_2 };
+
+    let wad = signed(WAD);
+    let (mut term, mut sum, mut n) = (wad, wad, 1u64);
+    while term != I256::ZERO {
+        term = term * r / (wad * signed(U256::from(n)));
+        sum += term;
+        n += 1;
+    }
+    let sum = sum.unsigned_abs();
+    let k = k.saturating_to::<usize>();
+    Some(if x.is_negative() { sum >> k } else { sum << k })
+}
+
+/// `x^y` for WAD values, as `exp(y * ln(x))`; exact for y = 1 and y = 2.
+pub fn pow(x: U256, y: U256) -> Option<U256> {
+    if y == U256::ZERO {
+        return Some(WAD);
+    }
+    if x == U256::ZERO {
+        return Some(U256::ZERO);
+    }
+    if y == WAD {
+        return Some(x);
+    }
+    if y == WAD * U256::from(2) {
+        return Some(mul_down(x, x));
+    }
+    exp(ln(x)? * signed(y) / signed(WAD))
+}
+
+/// `pow` rounded down past its error bound.
+pub fn pow_down(x: U256, y: U256) -> Option<U256> {
+    let raw = pow(x, y)?;
+    let max_error = mul_up(raw, MAX_POW_RELATIVE_ERROR) + U256::from(1);
+    Some(raw.saturating_sub(max_error))
+}
+
+/// `pow` rounded up past its error bound.
+pub fn pow_up(x: U256, y: U256) -> Option<U256> {
+    let raw = pow(x, y)?;
+    Some(raw + mul_up(raw, MAX_POW_RELATIVE_ERROR) + U256::from(1))
+}
@@ -0,0 +1,65 @@
+// a * b / d without losing the high bits of the product, as in Uniswap's FullMath.
+use alloy_primitives::{U256, U512};
+
+/// `floor(a * b / denominator)`; `None` on a zero denominator or a result above `U256::MAX`.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator == U256::ZERO {
+        return None;
+    }
+    let result = U512::from(a) * U512::from(b) / U512::from(denominator);
+    if result > U512::from(U256::MAX) {
+        return None;
+    }
+    Some(result.wrapping_to::<U256>())
+}
+
+/// `ceil(a * b / denominator)`.
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if a.mul_mod(b, denominator) > U256::ZERO {
+        return result.checked_add(U256::from(1));
+    }
+    Some(result)
+}
+
+/// `ceil(a / b)`.
+pub fn div_rounding_up(a: U256, b: U256) -> Option<U256> {
+    if b == U256::ZERO {
+        return None;
+    }
+    let (quotient, remainder) = a.div_rem(b);
+    Some(if remainder > U256::ZERO { quotient + U256::from(1) } else { quotient })
+}
+
+/// `floor(sqrt(x))`, by Newton's method from a power of two above the root.
+/// (ruint's `root` goes through floats and needs `std`.)
+pub fn sqrt(x: U256) -> U256 {
+    if x == U256::ZERO {
+        return U256::ZERO;
+    }
+    let mut root = U256::from(1) << x.bit_len().div_ceil(2);
+    loop {
+        let next = (root + x / root) >> 1;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_rounds_down() {
+        for x in 0u64..2_000 {
+            let root = sqrt(U256::from(x)).to::<u64>();
+            assert!(root * root <= x && (root + 1) * (root + 1) > x, "{x}");
+        }
+        let big = U256::from(u128::MAX);
+        assert_eq!(sqrt(big * big), big);
+        assert_eq!(sqrt(big * big - U256::from(1)), big - U256::from(1));
+        assert_eq!(sqrt(U256::MAX), big);
+    }
+}
@@ -0,0 +1,146 @@
+// Impermanent loss of a constant-product position (see notes/amm/1_amm_fundamental.md).
+//
+// For a price ratio `r = current_price / entry_price` the LP position is worth
+// `2 * sqrt(r) / (1 + r)` of simply holding the entry amounts, so
+// `IL = 1 - 2 * sqrt(r) / (1 + r)`. A few reference points:
+//
+//     r = 1.25 -> 0.62%    r = 2 -> 5.72%    r = 4 -> 20.0%    r = 0.5 -> 5.72%
+//
+// Prices are token0 priced in token1, WAD-scaled. Fees are ignored here; the
+// breakeven APR says how much fee yield would have to make up the gap.
+pub use crate::fixed_point::WAD;
+use crate::full_math::sqrt;
+use alloy_primitives::U256;
+
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// LP value over HODL value for a move from `entry_price` to `current_price`, in WAD.
+pub fn lp_to_hodl_ratio(entry_price: U256, current_price: U256) -> Option<U256> {
+    if entry_price == U256::ZERO || current_price == U256::ZERO {
+        return None;
+    }
+    let ratio = current_price * WAD / entry_price;
+    let sqrt_ratio = sqrt(ratio * WAD);
+    Some(U256::from(2) * sqrt_ratio * WAD / (WAD + ratio))
+}
+
+/// Impermanent loss as a WAD fraction of the HODL value; `5.72e16` for a 2x move.
+pub fn impermanent_loss(entry_price: U256, current_price: U256) -> Option<U256> {
+    lp_to_hodl_ratio(entry_price, current_price).map(|lp| WAD.saturating_sub(lp))
+}
+
+/// HODL and LP value of a position, both in token1 units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionValue {
+    pub hodl_value: U256,
+    pub lp_value: U256,
+    /// `hodl_value - lp_value`
+    pub loss: U256,
+}
+
+/// Values a position that entered with `amount0`/`amount1` (in the pool's ratio at
+/// entry) at `current_price`. The LP side keeps `amount0 * amount1` constant, so
+/// it holds `sqrt(k / p)` of token0 and `sqrt(k * p)` of token1, worth `2 * sqrt(k * p)`.
+pub fn position_value(amount0: U256, amount1: U256, current_price: U256) -> PositionValue {
+    let hodl_value = amount0 * current_price / WAD + amount1;
+    let lp_value = U256::from(2) * sqrt(amount0 * amount1 * current_price / WAD);
+    PositionValue { hodl_value, lp_value, loss: hodl_value.saturating_sub(lp_value) }
+}
+
+/// Annualised fee yield (on LP value, in WAD) that would have offset the loss
+/// from a price move that took `elapsed` seconds: `(hodl / lp - 1) * year / elapsed`.
+pub fn breakeven_fee_apr(entry_price: U256, current_price: U256, elapsed: u64) -> Option<U256> {
+    let lp = lp_to_hodl_ratio(entry_price, current_price)?;
+    if elapsed == 0 || lp == U256::ZERO {
+        return None;
+    }
+    let required_yield = (WAD * WAD / lp).saturating_sub(WAD);
+    Some(required_yield * U256::from(SECONDS_PER_YEAR) / U256::from(elapsed))
+}
+
+/// Expected loss over a horizon whose log-price variance is `variance` (WAD).
+/// `IL(x) = 1 - 2 * e^(x/2) / (1 + e^x)` is `x^2 / 8` to second order in the log
+/// move `x`, so for small moves `E[IL] ~= sigma^2 * t / 8`. Used to price cover.
+pub fn expected_impermanent_loss(variance: U256) -> U256 {
+    variance / U256::from(8)
+}
+
+/// f64 versions for off-chain reporting; they need `std` for `sqrt`.
+#[cfg(any(feature = "std", test))]
+pub mod float {
+    /// `1 - 2 * sqrt(r) / (1 + r)` for `r = current_price / entry_price`.
+    pub fn impermanent_loss(price_ratio: f64) -> f64 {
+        1.0 - 2.0 * price_ratio.sqrt() / (1.0 + price_ratio)
+    }
+
+    /// `(hodl_value, lp_value)` in token1 units.
+    pub fn position_value(amount0: f64, amount1: f64, current_price: f64) -> (f64, f64) {
+        (amount0 * current_price + amount1, 2.0 * (amount0 * amount1 * current_price).sqrt())
+    }
+
+    /// Fee APR needed to break even after a move that took `days`.
+    pub fn breakeven_fee_apr(price_ratio: f64, days: f64) -> f64 {
+        let il = impermanent_loss(price_ratio);
+        il / (1.0 - il) * 365.0 / days
+    }
+
+    /// A WAD-scaled value as a plain number.
+    pub fn from_wad(value: super::U256) -> f64 {
+        let (whole, frac) = value.div_rem(super::WAD);
+        let whole = u128::try_from(whole).unwrap_or(u128::MAX);
+        let frac = u64::try_from(frac).unwrap_or_default();
+        whole as f64 + frac as f64 / 1e18
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wad(value: f64) -> U256 {
+        U256::from((value * 1e18) as u128)
+    }
+
+    fn assert_close(value: U256, expected: f64) {
+        let value = float::from_wad(value);
+        assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    }
+
+    #[test]
+    fn matches_the_reference_points() {
+        // 1 - 2 * sqrt(2) / 3
+        let il_2x = 0.057_190_958_417_936_7;
+        assert_close(impermanent_loss(WAD, U256::from(2) * WAD).unwrap(), il_2x);
+        assert_close(impermanent_loss(U256::from(2) * WAD, WAD).unwrap(), il_2x);
+        assert_close(impermanent_loss(wad(1.0), wad(1.25)).unwrap(), 0.006_192_010_000_093_5);
+        assert_close(impermanent_loss(wad(3.0), wad(12.0)).unwrap(), 0.2);
+        assert_eq!(impermanent_loss(wad(7.0), wad(7.0)), Some(U256::ZERO));
+        assert_eq!(impermanent_loss(U256::ZERO, WAD), None);
+
+        assert!((float::impermanent_loss(2.0) - il_2x).abs() < 1e-15);
+        assert_eq!(format!("{:.2}%", float::impermanent_loss(2.0) * 100.0), "5.72%");
+    }
+
+    #[test]
+    fn position_value_matches_the_ratio() {
+        // 1 token0 and 100 token1 at a price of 100, which then doubles
+        let value = position_value(WAD, U256::from(100) * WAD, U256::from(200) * WAD);
+        assert_eq!(value.hodl_value, U256::from(300) * WAD);
+        assert_close(value.lp_value / U256::from(300), 1.0 - 0.057_190_958_417_936_7);
+        assert_eq!(value.loss, value.hodl_value - value.lp_value);
+
+        let (hodl, lp) = float::position_value(1.0, 100.0, 200.0);
+        assert!((hodl - 300.0).abs() < 1e-9 && (lp - 282.842_712_474_619).abs() < 1e-9);
+    }
+
+    #[test]
+    fn breakeven_apr_covers_the_loss() {
+        // a 2x move over a year needs IL / (1 - IL) of fee yield
+        let year = breakeven_fee_apr(WAD, U256::from(2) * WAD, SECONDS_PER_YEAR).unwrap();
+        assert_close(year, 0.060_660_171_779_821_3);
+        let month = breakeven_fee_apr(WAD, U256::from(2) * WAD, SECONDS_PER_YEAR / 12).unwrap();
+        assert_close(month / U256::from(12), 0.060_660_171_779_821_3);
+        assert!((float::breakeven_fee_apr(2.0, 365.0) - 0.060_660_171_779_821_3).abs() < 1e-12);
+        assert_eq!(breakeven_fee_apr(WAD, WAD, 0), None);
+    }
+}
@@ -0,0 +1,19 @@
+// AMM math shared by the Stylus contracts and off-chain tools.
+// Everything works on U256 so contracts and reference code round the same way.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+
+pub mod batch_auction;
+pub mod constant_product;
+pub mod constant_sum;
+pub mod dynamic_fee;
+pub mod fixed_point;
+pub mod full_math;
+pub mod impermanent_loss;
+pub mod sqrt_price_math;
+pub mod stable_swap;
+pub mod swap_math;
+pub mod tick_bitmap;
+pub mod tick_math;
+pub mod twamm;
+pub mod weighted_math;
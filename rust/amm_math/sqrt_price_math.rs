@@ -0,0 +1,124 @@
+// Token amounts and price moves within a single tick range, where the pool
+// behaves like x * y = L^2 with x = L / sqrt(P) and y = L * sqrt(P).
+// Rounding always favours the pool: amounts in round up, amounts out round down.
+use crate::full_math::{div_rounding_up, mul_div, mul_div_rounding_up};
+use crate::tick_math::Q96;
+use alloy_primitives::U256;
+
+/// Price after adding (`add`) or removing `amount` of token0:
+/// `sqrt_p' = L * sqrt_p / (L ± amount * sqrt_p)`, rounded up.
+pub fn get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96: U256, liquidity: u128, amount: U256, add: bool) -> Option<U256> {
+    if amount == U256::ZERO {
+        return Some(sqrt_price_x96);
+    }
+    let numerator: U256 = U256::from(liquidity) << 96;
+    if add {
+        if let Some(denominator) = amount.checked_mul(sqrt_price_x96).and_then(|product| numerator.checked_add(product)) {
+            return mul_div_rounding_up(numerator, sqrt_price_x96, denominator);
+        }
+        // same value, written to avoid the overflowing product
+        div_rounding_up(numerator, (numerator / sqrt_price_x96).checked_add(amount)?)
+    } else {
+        let product = amount.checked_mul(sqrt_price_x96)?;
+        if numerator <= product {
+            return None;
+        }
+        mul_div_rounding_up(numerator, sqrt_price_x96, numerator - product)
+    }
+}
+
+/// Price after adding or removing `amount` of token1:
+/// `sqrt_p' = sqrt_p ± amount / L`, rounded down.
+pub fn get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96: U256, liquidity: u128, amount: U256, add: bool) -> Option<U256> {
+    if liquidity == 0 {
+        return None;
+    }
+    if add {
+        let quotient = mul_div(amount, Q96, U256::from(liquidity))?;
+        sqrt_price_x96.checked_add(quotient)
+    } else {
+        let quotient = mul_div_rounding_up(amount, Q96, U256::from(liquidity))?;
+        sqrt_price_x96.checked_sub(quotient).filter(|price| *price > U256::ZERO)
+    }
+}
+
+/// Price after selling `amount_in`; token0 in moves the price down.
+pub fn get_next_sqrt_price_from_input(sqrt_price_x96: U256, liquidity: u128, amount_in: U256, zero_for_one: bool) -> Option<U256> {
+    if sqrt_price_x96 == U256::ZERO || liquidity == 0 {
+        return None;
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_in, true)
+    }
+}
+
+/// Price after buying `amount_out`; token1 out moves the price down.
+pub fn get_next_sqrt_price_from_output(sqrt_price_x96: U256, liquidity: u128, amount_out: U256, zero_for_one: bool) -> Option<U256> {
+    if sqrt_price_x96 == U256::ZERO || liquidity == 0 {
+        return None;
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_out, false)
+    }
+}
+
+/// Token0 between two prices: `L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)`.
+pub fn get_amount0_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let (lower, upper) =
+        if sqrt_ratio_a_x96 <= sqrt_ratio_b_x96 { (sqrt_ratio_a_x96, sqrt_ratio_b_x96) } else { (sqrt_ratio_b_x96, sqrt_ratio_a_x96) };
+    if lower == U256::ZERO {
+        return None;
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = upper - lower;
+    if round_up {
+        div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower)
+    } else {
+        Some(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// Token1 between two prices: `L * (sqrt_b - sqrt_a)`.
+pub fn get_amount1_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let (lower, upper) =
+        if sqrt_ratio_a_x96 <= sqrt_ratio_b_x96 { (sqrt_ratio_a_x96, sqrt_ratio_b_x96) } else { (sqrt_ratio_b_x96, sqrt_ratio_a_x96) };
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), upper - lower, Q96)
+    } else {
+        mul_div(U256::from(liquidity), upper - lower, Q96)
+    }
+}
+
+/// Both token amounts a position of `liquidity` in `[sqrt_lower, sqrt_upper]`
+/// holds at `sqrt_price`: all token0 below the range, all token1 above it.
+pub fn get_amounts_for_liquidity(
+    sqrt_price_x96: U256,
+    sqrt_lower_x96: U256,
+    sqrt_upper_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<(U256, U256)> {
+    if sqrt_price_x96 <= sqrt_lower_x96 {
+        Some((get_amount0_delta(sqrt_lower_x96, sqrt_upper_x96, liquidity, round_up)?, U256::ZERO))
+    } else if sqrt_price_x96 < sqrt_upper_x96 {
+        Some((
+            get_amount0_delta(sqrt_price_x96, sqrt_upper_x96, liquidity, round_up)?,
+            get_amount1_delta(sqrt_lower_x96, sqrt_price_x96, liquidity, round_up)?,
+        ))
+    } else {
+        Some((U256::ZERO, get_amount1_delta(sqrt_lower_x96, sqrt_upper_x96, liquidity, round_up)?))
+    }
+}
+
+/// Applies a signed liquidity change; `None` on underflow or overflow.
+pub fn add_delta(liquidity: u128, delta: i128) -> Option<u128> {
+    if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    }
+}
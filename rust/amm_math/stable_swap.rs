@@ -0,0 +1,290 @@
+// Curve's StableSwap invariant for n pegged coins (see notes/amm/1_amm_fundamental.md):
+//
+//     A * n^n * sum(x_i) + D = A * D * n^n + D^(n+1) / (n^n * prod(x_i))
+//
+// Near balance it trades like x + y = k; as the pool drifts it bends toward
+// x * y = k so it can't be drained. `amp` below is A * n^(n-1) * A_PRECISION,
+// as in Curve's contracts. Balances are normalized to 18 decimals by the caller.
+use alloc::vec::Vec;
+use alloy_primitives::U256;
+
+/// Amplification values carry two extra decimals so ramps move smoothly.
+pub const A_PRECISION: u64 = 100;
+pub const MAX_A: u64 = 1_000_000;
+/// A ramp can at most multiply or divide A by this.
+pub const MAX_A_CHANGE: u64 = 10;
+pub const MIN_RAMP_TIME: u64 = 86_400;
+/// Fees are a fraction of `FEE_DENOMINATOR`; 4_000_000 is 0.04%.
+pub const FEE_DENOMINATOR: u64 = 10_000_000_000;
+pub const MAX_FEE: u64 = 5_000_000_000;
+
+const MAX_ITERATIONS: usize = 255;
+
+fn converged(a: U256, b: U256) -> bool {
+    a.abs_diff(b) <= U256::from(1)
+}
+
+/// Invariant D for normalized balances, by Newton's method:
+/// `D' = (Ann * S + n * D_P) * D / ((Ann - 1) * D + (n + 1) * D_P)` with
+/// `D_P = D^(n+1) / (n^n * prod(x_i))`. `None` if it doesn't converge.
+pub fn get_d(xp: &[U256], amp: U256) -> Option<U256> {
+    let n = U256::from(xp.len());
+    let sum = xp.iter().fold(U256::ZERO, |acc, x| acc + *x);
+    if sum == U256::ZERO {
+        return Some(U256::ZERO);
+    }
+    if xp.contains(&U256::ZERO) {
+        return None;
+    }
+    let precision = U256::from(A_PRECISION);
+    let ann = amp * n;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for x in xp {
+            d_p = d_p * d / (*x * n);
+        }
+        let d_prev = d;
+        d = (ann * sum / precision + d_p * n) * d / ((ann - precision) * d / precision + (n + U256::from(1)) * d_p);
+        if converged(d, d_prev) {
+            return Some(d);
+        }
+    }
+    None
+}
+
+// Solves the invariant at `d` for coin `i`, given every other balance in `others`:
+// y^2 + (b - D) * y = c, iterated as y' = (y^2 + c) / (2y + b - D).
+fn solve_y(n: usize, others: &[U256], amp: U256, d: U256) -> Option<U256> {
+    let n = U256::from(n);
+    let precision = U256::from(A_PRECISION);
+    let ann = amp * n;
+    let mut c = d;
+    let mut sum = U256::ZERO;
+    for x in others {
+        if *x == U256::ZERO {
+            return None;
+        }
+        sum += *x;
+        c = c * d / (*x * n);
+    }
+    c = c * d * precision / (ann * n);
+    let b = sum + d * precision / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        y = (y * y + c) / (U256::from(2) * y + b).checked_sub(d)?;
+        if converged(y, y_prev) {
+            return Some(y);
+        }
+    }
+    None
+}
+
+/// New balance of coin `j` once coin `i`'s balance becomes `x`, keeping D fixed.
+pub fn get_y(i: usize, j: usize, x: U256, xp: &[U256], amp: U256) -> Option<U256> {
+    if i == j || i >= xp.len() || j >= xp.len() {
+        return None;
+    }
+    let d = get_d(xp, amp)?;
+    let others: Vec<U256> = (0..xp.len()).filter(|k| *k != j).map(|k| if k == i { x } else { xp[k] }).collect();
+    solve_y(xp.len(), &others, amp, d)
+}
+
+/// Balance of coin `i` that brings the invariant to `d`, others unchanged;
+/// used for single-coin withdrawals.
+pub fn get_y_d(i: usize, xp: &[U256], amp: U256, d: U256) -> Option<U256> {
+    if i >= xp.len() {
+        return None;
+    }
+    let others: Vec<U256> = (0..xp.len()).filter(|k| *k != i).map(|k| xp[k]).collect();
+    solve_y(xp.len(), &others, amp, d)
+}
+
+/// Fee charged on the imbalanced part of a deposit or withdrawal, chosen so an
+/// imbalanced add followed by a balanced remove costs about one swap fee.
+pub fn imbalance_fee(fee: U256, n_coins: usize) -> U256 {
+    let n = U256::from(n_coins);
+    fee * n / (U256::from(4) * (n - U256::from(1)))
+}
+
+/// A linearly ramped from `initial_a` at `initial_time` to `future_a` at `future_time`.
+pub fn ramped_a(initial_a: U256, initial_time: u64, future_a: U256, future_time: u64, now: u64) -> U256 {
+    if now >= future_time || future_time <= initial_time {
+        return future_a;
+    }
+    let elapsed = U256::from(now - initial_time);
+    let duration = U256::from(future_time - initial_time);
+    if future_a > initial_a {
+        initial_a + (future_a - initial_a) * elapsed / duration
+    } else {
+        initial_a - (initial_a - future_a) * elapsed / duration
+    }
+}
+
+/// An f64 reference for the invariant, solved by bisection rather than Newton's
+/// method, so the two implementations only agree if both are right.
+#[cfg(any(feature = "std", test))]
+pub mod float {
+    // A * n^n from the contracts' `amp`
+    fn ann(amp: f64, n: usize) -> f64 {
+        amp * n as f64 / super::A_PRECISION as f64
+    }
+
+    // Both sides of the invariant, left minus right; decreasing in D.
+    fn invariant_gap(xp: &[f64], ann: f64, d: f64) -> f64 {
+        let n = xp.len() as f64;
+        let sum: f64 = xp.iter().sum();
+        let d_p = xp.iter().fold(d, |d_p, x| d_p * d / (x * n));
+        ann * sum + d - ann * d - d_p
+    }
+
+    fn bisect(mut low: f64, mut high: f64, increasing: bool, f: impl Fn(f64) -> f64) -> f64 {
+        for _ in 0..200 {
+            let mid = (low + high) / 2.0;
+            if (f(mid) > 0.0) == increasing {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// D for balances `xp`; it lies between 0 and their sum.
+    pub fn get_d(xp: &[f64], amp: f64) -> f64 {
+        let ann = ann(amp, xp.len());
+        bisect(0.0, xp.iter().sum(), false, |d| invariant_gap(xp, ann, d))
+    }
+
+    /// Coin `j`'s balance once coin `i`'s becomes `x`, keeping D fixed.
+    pub fn get_y(i: usize, j: usize, x: f64, xp: &[f64], amp: f64) -> f64 {
+        let ann = ann(amp, xp.len());
+        let d = get_d(xp, amp);
+        let gap = |y: f64| {
+            let mut balances = xp.to_vec();
+            balances[i] = x;
+            balances[j] = y;
+            invariant_gap(&balances, ann, d)
+        };
+        // the gap rises with y; double the bracket until it holds the root
+        let mut high = d;
+        while gap(high) <= 0.0 {
+            high *= 2.0;
+        }
+        bisect(0.0, high, true, gap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xorshift64*, so the cases are random but the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+
+        // A 2-5 coin pool of 1e15-1e25 wei balances, up to 20:1 apart, and its amp.
+        fn pool(&mut self) -> (Vec<U256>, U256) {
+            let n = 2 + self.below(4) as usize;
+            let scale = 10_u128.pow(15 + self.below(11) as u32);
+            let xp = (0..n).map(|_| U256::from(scale / 20 * (1 + self.below(20) as u128))).collect();
+            let amp = U256::from((1 + self.below(5_000)) * A_PRECISION);
+            (xp, amp)
+        }
+    }
+
+    fn floats(xp: &[U256]) -> Vec<f64> {
+        xp.iter().map(|x| f64::from(*x)).collect()
+    }
+
+    fn assert_close(value: U256, expected: f64, context: &str) {
+        let value = f64::from(value);
+        assert!((value - expected).abs() <= expected * 1e-10, "{context}: {value} vs {expected}");
+    }
+
+    #[test]
+    fn get_d_matches_the_reference() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..500 {
+            let (xp, amp) = rng.pool();
+            let d = get_d(&xp, amp).unwrap();
+            assert_close(d, float::get_d(&floats(&xp), f64::from(amp)), &format!("{xp:?} {amp}"));
+            // D never exceeds the sum of the balances, and equals it when they're equal
+            let sum = xp.iter().fold(U256::ZERO, |acc, x| acc + *x);
+            assert!(d <= sum + U256::from(1));
+            let balanced = vec![xp[0]; xp.len()];
+            assert!(get_d(&balanced, amp).unwrap().abs_diff(xp[0] * U256::from(xp.len())) <= U256::from(xp.len()));
+        }
+    }
+
+    #[test]
+    fn get_y_matches_the_reference_and_keeps_d() {
+        let mut rng = Rng(0xca11);
+        for _ in 0..500 {
+            let (xp, amp) = rng.pool();
+            let (i, j) = (0, 1 + rng.below(xp.len() as u64 - 1) as usize);
+            let context = format!("{xp:?} {amp} {i} {j}");
+            let dx = xp[i] * U256::from(1 + rng.below(100)) / U256::from(100);
+            let x = xp[i] + dx;
+
+            let y = get_y(i, j, x, &xp, amp).unwrap();
+            assert_close(y, float::get_y(i, j, f64::from(x), &floats(&xp), f64::from(amp)), &context);
+            assert!(y < xp[j], "{context}");
+
+            // y is the root to within a couple of wei: D is on either side of it
+            let d = get_d(&xp, amp).unwrap();
+            let d_with = |y: U256| {
+                let mut after = xp.clone();
+                after[i] = x;
+                after[j] = y;
+                get_d(&after, amp).unwrap()
+            };
+            assert!(d_with(y - U256::from(2)) <= d && d <= d_with(y + U256::from(2)), "{context}");
+
+            // no trade, no change; a bigger trade gets a smaller balance
+            assert!(get_y(i, j, xp[i], &xp, amp).unwrap().abs_diff(xp[j]) <= U256::from(2), "{context}");
+            assert!(get_y(i, j, x + dx, &xp, amp).unwrap() < y, "{context}");
+        }
+    }
+
+    #[test]
+    fn get_y_d_solves_for_a_lower_invariant() {
+        let mut rng = Rng(0xd0d0);
+        for _ in 0..200 {
+            let (xp, amp) = rng.pool();
+            let i = rng.below(xp.len() as u64) as usize;
+            let d = get_d(&xp, amp).unwrap();
+            let target = d - d * U256::from(1 + rng.below(10)) / U256::from(100);
+            let y = get_y_d(i, &xp, amp, target).unwrap();
+            assert!(y < xp[i]);
+            let d_with = |y: U256| {
+                let mut after = xp.clone();
+                after[i] = y;
+                get_d(&after, amp).unwrap()
+            };
+            assert!(d_with(y - U256::from(2)) <= target && target <= d_with(y + U256::from(2)));
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert_eq!(get_d(&[U256::ZERO, U256::ZERO], U256::from(100 * A_PRECISION)), Some(U256::ZERO));
+        assert_eq!(get_d(&[U256::from(1), U256::ZERO], U256::from(100 * A_PRECISION)), None);
+        let xp = [U256::from(1_000), U256::from(1_000)];
+        assert_eq!(get_y(0, 0, U256::from(1), &xp, U256::from(100)), None);
+        assert_eq!(get_y(0, 2, U256::from(1), &xp, U256::from(100)), None);
+    }
+}
@@ -0,0 +1,101 @@
+// One step of a concentrated-liquidity swap: move from the current price toward
+// a target (the next initialized tick or the caller's limit) until either the
+// target is reached or the amount runs out.
+use crate::full_math::{mul_div, mul_div_rounding_up};
+use crate::sqrt_price_math::{get_amount0_delta, get_amount1_delta, get_next_sqrt_price_from_input, get_next_sqrt_price_from_output};
+use alloy_primitives::U256;
+
+/// Fees are in hundredths of a basis point; 3000 is 0.3%.
+pub const FEE_PIPS_DENOMINATOR: u32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// `amount_remaining` is input left to sell when `exact_input`, output left to
+/// buy otherwise. The direction follows from the target: below the current price
+/// means token0 in. `amount_in` excludes `fee_amount`.
+pub fn compute_swap_step(
+    sqrt_price_current_x96: U256,
+    sqrt_price_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    exact_input: bool,
+    fee_pips: u32,
+) -> Option<SwapStep> {
+    let zero_for_one = sqrt_price_current_x96 >= sqrt_price_target_x96;
+    let fee = U256::from(fee_pips);
+    let denominator = U256::from(FEE_PIPS_DENOMINATOR);
+
+    // amount needed to reach the target, on the side that is fixed
+    let (sqrt_price_next_x96, to_target) = if exact_input {
+        let remaining_less_fee = mul_div(amount_remaining, denominator - fee, denominator)?;
+        let amount_in = if zero_for_one {
+            get_amount0_delta(sqrt_price_target_x96, sqrt_price_current_x96, liquidity, true)?
+        } else {
+            get_amount1_delta(sqrt_price_current_x96, sqrt_price_target_x96, liquidity, true)?
+        };
+        let next = if remaining_less_fee >= amount_in {
+            sqrt_price_target_x96
+        } else {
+            get_next_sqrt_price_from_input(sqrt_price_current_x96, liquidity, remaining_less_fee, zero_for_one)?
+        };
+        (next, amount_in)
+    } else {
+        let amount_out = if zero_for_one {
+            get_amount1_delta(sqrt_price_target_x96, sqrt_price_current_x96, liquidity, false)?
+        } else {
+            get_amount0_delta(sqrt_price_current_x96, sqrt_price_target_x96, liquidity, false)?
+        };
+        let next = if amount_remaining >= amount_out {
+            sqrt_price_target_x96
+        } else {
+            get_next_sqrt_price_from_output(sqrt_price_current_x96, liquidity, amount_remaining, zero_for_one)?
+        };
+        (next, amount_out)
+    };
+
+    let reached_target = sqrt_price_next_x96 == sqrt_price_target_x96;
+    let (amount_in, mut amount_out) = if zero_for_one {
+        (
+            if reached_target && exact_input {
+                to_target
+            } else {
+                get_amount0_delta(sqrt_price_next_x96, sqrt_price_current_x96, liquidity, true)?
+            },
+            if reached_target && !exact_input {
+                to_target
+            } else {
+                get_amount1_delta(sqrt_price_next_x96, sqrt_price_current_x96, liquidity, false)?
+            },
+        )
+    } else {
+        (
+            if reached_target && exact_input {
+                to_target
+            } else {
+                get_amount1_delta(sqrt_price_current_x96, sqrt_price_next_x96, liquidity, true)?
+            },
+            if reached_target && !exact_input {
+                to_target
+            } else {
+                get_amount0_delta(sqrt_price_current_x96, sqrt_price_next_x96, liquidity, false)?
+            },
+        )
+    };
+
+    // rounding in the price can't pay out more than was asked for
+    if !exact_input && amount_out > amount_remaining {
+        amount_out = amount_remaining;
+    }
+
+    // when an exact input stops short of the target, everything left over is fee
+    let fee_amount =
+        if exact_input && !reached_target { amount_remaining - amount_in } else { mul_div_rounding_up(amount_in, fee, denominator - fee)? };
+
+    Some(SwapStep { sqrt_price_next_x96, amount_in, amount_out, fee_amount })
+}
@@ -0,0 +1,51 @@
+// Which ticks have liquidity referencing them, one bit per spaced tick packed
+// into 256-bit words keyed by `word_pos`. Storage lives in the pool; these are
+// the index calculations and the in-word search.
+use alloy_primitives::U256;
+
+/// Tick divided by the spacing, rounded toward -inf.
+pub fn compress(tick: i32, tick_spacing: i32) -> i32 {
+    tick.div_euclid(tick_spacing)
+}
+
+/// `(word_pos, bit_pos)` of a compressed tick.
+pub fn position(compressed: i32) -> (i16, u8) {
+    ((compressed >> 8) as i16, compressed.rem_euclid(256) as u8)
+}
+
+/// Word with the bit for `bit_pos` flipped.
+pub fn flip(word: U256, bit_pos: u8) -> U256 {
+    word ^ (U256::from(1) << bit_pos as usize)
+}
+
+/// Next initialized tick in the same word as `tick`, searching down (`lte`,
+/// including `tick`) or up (excluding it). If none is set, returns the last tick
+/// of the word and `false`, so a swap never walks more than 256 spaced ticks per
+/// storage read. `word` must be the one at `position` of the compressed start tick:
+/// `compress(tick)` when `lte`, `compress(tick) + 1` otherwise.
+pub fn next_initialized_tick_within_one_word(word: U256, tick: i32, tick_spacing: i32, lte: bool) -> (i32, bool) {
+    let compressed = compress(tick, tick_spacing);
+    if lte {
+        let (_, bit_pos) = position(compressed);
+        // all bits at or below bit_pos
+        let mask = (U256::from(1) << bit_pos as usize) - U256::from(1) + (U256::from(1) << bit_pos as usize);
+        let masked = word & mask;
+        if masked > U256::ZERO {
+            let msb = (masked.bit_len() - 1) as i32;
+            ((compressed - (bit_pos as i32 - msb)) * tick_spacing, true)
+        } else {
+            ((compressed - bit_pos as i32) * tick_spacing, false)
+        }
+    } else {
+        let (_, bit_pos) = position(compressed + 1);
+        // all bits at or above bit_pos
+        let mask = !((U256::from(1) << bit_pos as usize) - U256::from(1));
+        let masked = word & mask;
+        if masked > U256::ZERO {
+            let lsb = masked.trailing_zeros() as i32;
+            ((compressed + 1 + (lsb - bit_pos as i32)) * tick_spacing, true)
+        } else {
+            ((compressed + 1 + (255 - bit_pos as i32)) * tick_spacing, false)
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Ticks and Q64.96 square-root prices for concentrated liquidity, after Uniswap
+// v3's TickMath. Price at tick i is 1.0001^i, so sqrt_price_x96 = sqrt(1.0001^i) * 2^96.
+use alloy_primitives::U256;
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// `get_sqrt_ratio_at_tick(MIN_TICK)`
+pub const MIN_SQRT_RATIO: U256 = U256::from_limbs([4295128739, 0, 0, 0]);
+/// `get_sqrt_ratio_at_tick(MAX_TICK)`
+pub const MAX_SQRT_RATIO: U256 = U256::from_limbs([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// 2^96, the Q64.96 scale.
+pub const Q96: U256 = U256::from_limbs([0, 1 << 32, 0, 0]);
+/// 2^128, the scale of fee growth accumulators.
+pub const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+// 2^128 / sqrt(1.0001)^(2^i) for bit i of |tick|.
+const RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// `sqrt(1.0001^tick) * 2^96`, rounded up; `None` outside `[MIN_TICK, MAX_TICK]`.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Option<U256> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    let abs_tick = tick.unsigned_abs();
+    // Q128.128 value of 1 / sqrt(1.0001^|tick|), built one bit at a time
+    let mut ratio = if abs_tick & 1 != 0 { U256::from(RATIOS[0]) } else { Q128 };
+    for (bit, factor) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Q128.128 -> Q64.96, rounding up so the result is never below the true price
+    let rounding = if ratio & U256::from(u32::MAX) > U256::ZERO { 1 } else { 0 };
+    Some((ratio >> 32) + U256::from(rounding))
+}
+
+/// Greatest tick whose sqrt ratio is `<= sqrt_price_x96`; `None` outside
+/// `[MIN_SQRT_RATIO, MAX_SQRT_RATIO)`. A binary search over
+/// `get_sqrt_ratio_at_tick`, so the two functions can never disagree.
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> Option<i32> {
+    if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
+        return None;
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        // rounds toward +inf so `low = mid` always makes progress
+        let mid = low + (high - low + 1) / 2;
+        if get_sqrt_ratio_at_tick(mid)? <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Some(low)
+}
+
+/// Largest liquidity a single tick may reference, so the sum over every usable
+/// tick still fits in a u128.
+pub fn max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = MIN_TICK / tick_spacing * tick_spacing;
+    let max_tick = MAX_TICK / tick_spacing * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundaries_match_the_constants() {
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK), Some(MIN_SQRT_RATIO));
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK), Some(MAX_SQRT_RATIO));
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK - 1), None);
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK + 1), None);
+        assert_eq!(get_sqrt_ratio_at_tick(0), Some(Q96));
+        // Uniswap v3's TickMath test vectors
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK + 1), Some(U256::from(4295343490_u64)));
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK - 1), Some("1461373636630004318706518188784493106690254656249".parse().unwrap()));
+
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO), Some(MIN_TICK));
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - U256::from(1)), None);
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256::from(1)), Some(MAX_TICK - 1));
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO), None);
+    }
+
+    // Every tick a spacing of 1, 60 or 200 lands on near the ends and around
+    // zero, and a stride across the whole range in between.
+    fn sample_ticks() -> impl Iterator<Item = i32> {
+        let edges = (MIN_TICK..MIN_TICK + 2_000).chain(-2_000..2_000).chain(MAX_TICK - 2_000..=MAX_TICK);
+        edges.chain((MIN_TICK..=MAX_TICK).step_by(997))
+    }
+
+    #[test]
+    fn ticks_round_trip_through_sqrt_ratios() {
+        for tick in sample_ticks() {
+            let ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            if tick < MAX_TICK {
+                let next = get_sqrt_ratio_at_tick(tick + 1).unwrap();
+                assert!(ratio < next, "not increasing at {tick}");
+                assert_eq!(get_tick_at_sqrt_ratio(ratio), Some(tick));
+                // every price up to the next tick's rounds down to this tick
+                assert_eq!(get_tick_at_sqrt_ratio(next - U256::from(1)), Some(tick));
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_ratios_track_the_float_price() {
+        for tick in (MIN_TICK..=MAX_TICK).step_by(10_007) {
+            let ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            let expected = 1.0001_f64.powf(tick as f64 / 2.0) * 2f64.powi(96);
+            let actual = f64::from(ratio);
+            assert!((actual / expected - 1.0).abs() < 1e-9, "tick {tick}: {actual} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn max_liquidity_per_tick_fits_every_tick() {
+        for spacing in [1, 10, 60, 200] {
+            let per_tick = max_liquidity_per_tick(spacing);
+            let ticks = (MAX_TICK / spacing - MIN_TICK / spacing) as u128 + 1;
+            assert!(per_tick.checked_mul(ticks).is_some());
+            assert!((per_tick + 1).checked_mul(ticks).is_none());
+        }
+    }
+}
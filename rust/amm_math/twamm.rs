@@ -0,0 +1,151 @@
+// TWAMM virtual-order math (Paradigm's TWAMM paper). Long-term orders sell at a
+// constant rate per block. Between two interactions the pool trades
+// infinitesimally against both streams at once, which has a closed form: with
+// `x`, `y` the reserves, `k = x * y` and `x_in`, `y_in` the amounts sold into
+// the pool over the span,
+//
+//     c     = (sqrt(x * y_in) - sqrt(y * x_in)) / (sqrt(x * y_in) + sqrt(y * x_in))
+//     e     = exp(2 * sqrt(x_in * y_in / k))
+//     x_end = sqrt(k * x_in / y_in) * (e + c) / (e - c)
+//     y_end = k / x_end
+//
+// With only one stream the span is a plain x * y = k swap. Fees come off the
+// sold amounts first and stay in the pool, as in the pair's swaps; the ending
+// reserves round up so k never drops.
+use crate::batch_auction::after_fee;
+use crate::fixed_point::{exp, WAD};
+use crate::full_math::{div_rounding_up, mul_div, mul_div_rounding_up, sqrt};
+use alloy_primitives::{I256, U256};
+
+/// Past this exponent `c / e` is below 1e-17 and `(e + c) / (e - c)` is 1.
+const MAX_EXPONENT: u64 = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VirtualTrade {
+    pub reserve0: U256,
+    pub reserve1: U256,
+    /// Token0 bought by the token1 stream.
+    pub amount0_out: U256,
+    /// Token1 bought by the token0 stream.
+    pub amount1_out: U256,
+}
+
+/// Runs both streams over one span, `amount0_in` and `amount1_in` being what
+/// each sold in total. `None` on empty reserves or overflow.
+pub fn execute_virtual_orders(reserve0: U256, reserve1: U256, amount0_in: U256, amount1_in: U256, fee_bps: u64) -> Option<VirtualTrade> {
+    if reserve0 == U256::ZERO || reserve1 == U256::ZERO {
+        return None;
+    }
+    let k = reserve0.checked_mul(reserve1)?;
+    let (net0, net1) = (after_fee(amount0_in, fee_bps), after_fee(amount1_in, fee_bps));
+    let (end0, end1) = if net1 == U256::ZERO {
+        let end0 = reserve0 + net0;
+        (end0, div_rounding_up(k, end0)?)
+    } else if net0 == U256::ZERO {
+        let end1 = reserve1 + net1;
+        (div_rounding_up(k, end1)?, end1)
+    } else {
+        let end0 = ending_reserve0(reserve0, reserve1, net0, net1, k)?;
+        (end0, div_rounding_up(k, end0)?)
+    };
+    let amount0_out = (reserve0 + net0).saturating_sub(end0);
+    let amount1_out = (reserve1 + net1).saturating_sub(end1);
+    Some(VirtualTrade {
+        reserve0: reserve0 + amount0_in - amount0_out,
+        reserve1: reserve1 + amount1_in - amount1_out,
+        amount0_out,
+        amount1_out,
+    })
+}
+
+// x_end for two opposing streams, rounded up.
+fn ending_reserve0(reserve0: U256, reserve1: U256, net0: U256, net1: U256, k: U256) -> Option<U256> {
+    let wad = I256::try_from(WAD).ok()?;
+    let a = I256::try_from(sqrt(reserve0.checked_mul(net1)?)).ok()?;
+    let b = I256::try_from(sqrt(reserve1.checked_mul(net0)?)).ok()?;
+    let c = (a - b) * wad / (a + b);
+
+    let exponent = mul_div(U256::from(2) * sqrt(net0.checked_mul(net1)?), WAD, sqrt(k))?;
+    let ratio = if exponent > U256::from(MAX_EXPONENT) * WAD {
+        WAD
+    } else {
+        let e = I256::try_from(exp(I256::try_from(exponent).ok()?)?).ok()?;
+        ((e + c) * wad / (e - c)).unsigned_abs()
+    };
+    let base = sqrt(mul_div(k, net0, net1)?);
+    mul_div_rounding_up(base, ratio, WAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant_product::get_amount_out_with_fee;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn e18(amount: u128) -> U256 {
+        U256::from(amount * E18)
+    }
+
+    #[test]
+    fn one_stream_is_a_plain_swap() {
+        let (reserve0, reserve1) = (e18(1_000), e18(2_000));
+        let trade = execute_virtual_orders(reserve0, reserve1, e18(50), U256::ZERO, 30).unwrap();
+        let expected = get_amount_out_with_fee(e18(50), reserve0, reserve1, 30);
+        assert!(trade.amount1_out <= expected && expected - trade.amount1_out <= U256::from(1));
+        assert_eq!(trade.amount0_out, U256::ZERO);
+        assert_eq!((trade.reserve0, trade.reserve1), (reserve0 + e18(50), reserve1 - trade.amount1_out));
+
+        let trade = execute_virtual_orders(reserve0, reserve1, U256::ZERO, e18(50), 30).unwrap();
+        let expected = get_amount_out_with_fee(e18(50), reserve1, reserve0, 30);
+        assert!(trade.amount0_out <= expected && expected - trade.amount0_out <= U256::from(1));
+    }
+
+    #[test]
+    fn matched_streams_at_the_pool_price_cross_in_full() {
+        // c = 0 and x_end = sqrt(k), so each stream gets exactly the other's input
+        let trade = execute_virtual_orders(e18(1_000), e18(1_000), e18(10), e18(10), 0).unwrap();
+        assert_eq!((trade.amount0_out, trade.amount1_out), (e18(10), e18(10)));
+        assert_eq!((trade.reserve0, trade.reserve1), (e18(1_000), e18(1_000)));
+    }
+
+    // The closed form is the limit of alternating ever smaller swaps.
+    #[test]
+    fn closed_form_matches_many_small_swaps() {
+        let (reserve0, reserve1, amount0_in, amount1_in) = (e18(1_000), e18(2_000), e18(100), e18(50));
+        let trade = execute_virtual_orders(reserve0, reserve1, amount0_in, amount1_in, 0).unwrap();
+
+        let steps = 2_000u64;
+        let (mut x, mut y, mut out0, mut out1) = (reserve0, reserve1, U256::ZERO, U256::ZERO);
+        for _ in 0..steps {
+            let (in0, in1) = (amount0_in / U256::from(steps), amount1_in / U256::from(steps));
+            let bought1 = get_amount_out_with_fee(in0, x, y, 0);
+            (x, y, out1) = (x + in0, y - bought1, out1 + bought1);
+            let bought0 = get_amount_out_with_fee(in1, y, x, 0);
+            (y, x, out0) = (y + in1, x - bought0, out0 + bought0);
+        }
+        // within 0.01% of the stepwise result
+        let close = |a: U256, b: U256| a.abs_diff(b) * U256::from(10_000) < b;
+        assert!(close(trade.amount0_out, out0), "{} vs {}", trade.amount0_out, out0);
+        assert!(close(trade.amount1_out, out1), "{} vs {}", trade.amount1_out, out1);
+        // the opposing stream nets against it, so it beats selling alone
+        assert!(trade.amount1_out > get_amount_out_with_fee(amount0_in, reserve0, reserve1, 0));
+    }
+
+    #[test]
+    fn k_never_drops() {
+        let cases = [
+            (e18(1_000), e18(1_000), e18(1), e18(500)),
+            (e18(5), e18(7_000), e18(3), U256::from(1)),
+            (U256::from(1_000_001), U256::from(999_983), U256::from(12_345), U256::from(67_891)),
+            (e18(1_000_000), e18(1), e18(900_000), e18(2)),
+        ];
+        for (reserve0, reserve1, amount0_in, amount1_in) in cases {
+            let trade = execute_virtual_orders(reserve0, reserve1, amount0_in, amount1_in, 0).unwrap();
+            assert!(trade.reserve0 * trade.reserve1 >= reserve0 * reserve1);
+            assert_eq!(trade.reserve0 + trade.amount0_out, reserve0 + amount0_in);
+            assert_eq!(trade.reserve1 + trade.amount1_out, reserve1 + amount1_in);
+        }
+        assert_eq!(execute_virtual_orders(U256::ZERO, e18(1), e18(1), e18(1), 30), None);
+    }
+}
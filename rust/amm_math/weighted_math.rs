@@ -0,0 +1,75 @@
+// Balancer's constant-mean invariant, V = prod(B_i ^ W_i) with normalized weights
+// summing to 1 (see notes/amm/1_amm_fundamental.md). All values are WAD.
+// Rounding always favours the pool.
+use crate::fixed_point::{complement, div_down, div_up, mul_down, mul_up, pow, pow_down, pow_up, WAD};
+use alloy_primitives::U256;
+
+/// Swaps may take in or pay out at most 30% of a balance, where `pow` is accurate.
+pub const MAX_IN_RATIO: U256 = U256::from_limbs([300_000_000_000_000_000, 0, 0, 0]);
+pub const MAX_OUT_RATIO: U256 = U256::from_limbs([300_000_000_000_000_000, 0, 0, 0]);
+/// Smallest normalized weight, 1%.
+pub const MIN_WEIGHT: U256 = U256::from_limbs([10_000_000_000_000_000, 0, 0, 0]);
+
+/// Price of the out token in units of the in token: `(B_i / W_i) / (B_o / W_o)`.
+pub fn spot_price(balance_in: U256, weight_in: U256, balance_out: U256, weight_out: U256) -> U256 {
+    div_up(div_up(balance_in, weight_in), div_down(balance_out, weight_out))
+}
+
+/// `A_o = B_o * (1 - (B_i / (B_i + A_i)) ^ (W_i / W_o))`, with `amount_in` after fees.
+pub fn calc_out_given_in(balance_in: U256, weight_in: U256, balance_out: U256, weight_out: U256, amount_in: U256) -> Option<U256> {
+    if amount_in > mul_down(balance_in, MAX_IN_RATIO) {
+        return None;
+    }
+    let base = div_up(balance_in, balance_in + amount_in);
+    let exponent = div_down(weight_in, weight_out);
+    let power = pow_up(base, exponent)?;
+    Some(mul_down(balance_out, complement(power)))
+}
+
+/// `A_i = B_i * ((B_o / (B_o - A_o)) ^ (W_o / W_i) - 1)`, before fees.
+pub fn calc_in_given_out(balance_in: U256, weight_in: U256, balance_out: U256, weight_out: U256, amount_out: U256) -> Option<U256> {
+    if amount_out > mul_down(balance_out, MAX_OUT_RATIO) {
+        return None;
+    }
+    let base = div_up(balance_out, balance_out - amount_out);
+    let exponent = div_up(weight_out, weight_in);
+    let power = pow_up(base, exponent)?;
+    Some(mul_up(balance_in, power - WAD))
+}
+
+/// Pool tokens minted for a single-token join. Only the part of the deposit
+/// beyond this token's weight is effectively swapped, so only it pays the fee.
+pub fn calc_bpt_out_given_exact_token_in(balance: U256, weight: U256, amount_in: U256, total_supply: U256, swap_fee: U256) -> Option<U256> {
+    if amount_in > mul_down(balance, MAX_IN_RATIO) {
+        return None;
+    }
+    let taxable = mul_up(amount_in, complement(weight));
+    let amount_in_after_fee = amount_in - mul_up(taxable, swap_fee);
+    let balance_ratio = div_down(balance + amount_in_after_fee, balance);
+    let invariant_ratio = pow_down(balance_ratio, weight)?;
+    Some(mul_down(total_supply, invariant_ratio.saturating_sub(WAD)))
+}
+
+/// Tokens paid out for burning `bpt_in` into a single token, the mirror of the join above.
+pub fn calc_token_out_given_exact_bpt_in(balance: U256, weight: U256, bpt_in: U256, total_supply: U256, swap_fee: U256) -> Option<U256> {
+    if bpt_in >= total_supply {
+        return None;
+    }
+    let invariant_ratio = div_up(total_supply - bpt_in, total_supply);
+    let balance_ratio = pow_up(invariant_ratio, div_down(WAD, weight))?;
+    let amount_out_before_fee = mul_down(balance, complement(balance_ratio));
+    if amount_out_before_fee > mul_down(balance, MAX_OUT_RATIO) {
+        return None;
+    }
+    let taxable = mul_up(amount_out_before_fee, complement(weight));
+    Some(amount_out_before_fee - mul_up(taxable, swap_fee))
+}
+
+/// `prod(B_i ^ W_i)`; only grows between joins and exits, as fees accrue.
+pub fn invariant(balances: &[U256], weights: &[U256]) -> Option<U256> {
+    let mut value = WAD;
+    for (balance, weight) in balances.iter().zip(weights) {
+        value = mul_down(value, pow(*balance, *weight)?);
+    }
+    Some(value)
+}
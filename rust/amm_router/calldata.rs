@@ -0,0 +1,55 @@
+// Router calldata for a split plan. `IAmmRouter` is not implemented: there is no
+// router contract in this repo, so the output can't be executed as is and only
+// fixes the ABI a router would take. Each route carries its input and hops,
+// `kind` picks the swap adapter (see `Pool::kind`) and the router checks the
+// summed output against `amountOutMin`.
+use crate::pool::Pool;
+use crate::split::SplitPlan;
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, SolCall};
+use amm_math::constant_product::BPS;
+
+sol! {
+    interface IAmmRouter {
+        struct Hop {
+            address pool;
+            uint8 kind;
+            address tokenIn;
+            address tokenOut;
+        }
+
+        struct Route {
+            uint256 amountIn;
+            Hop[] hops;
+        }
+
+        function swapSplit(Route[] routes, uint256 amountOutMin, address to, uint256 deadline) external returns (uint256 amountOut);
+    }
+}
+
+/// `amount_out` less `slippage_bps`.
+pub fn min_amount_out(amount_out: U256, slippage_bps: u64) -> U256 {
+    amount_out * U256::from(BPS - slippage_bps.min(BPS)) / U256::from(BPS)
+}
+
+pub fn encode_swap_split(pools: &[Pool], plan: &SplitPlan, amount_out_min: U256, to: Address, deadline: U256) -> Vec<u8> {
+    let routes = plan
+        .allocations
+        .iter()
+        .map(|allocation| IAmmRouter::Route {
+            amountIn: allocation.amount_in,
+            hops: allocation
+                .route
+                .hops
+                .iter()
+                .map(|hop| IAmmRouter::Hop {
+                    pool: pools[hop.pool].address(),
+                    kind: pools[hop.pool].kind(),
+                    tokenIn: hop.token_in,
+                    tokenOut: hop.token_out,
+                })
+                .collect(),
+        })
+        .collect();
+    IAmmRouter::swapSplitCall { routes, amountOutMin: amount_out_min, to, deadline }.abi_encode()
+}
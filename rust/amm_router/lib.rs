@@ -0,0 +1,12 @@
+// Off-chain route optimizer. Given a snapshot of pool states it finds every path
+// of up to N hops between two tokens, splits the input across the best of them
+// and encodes the result as router calldata (see notes/amm/3_price_in_amm_models.md,
+// "DEX Aggregators" and "Route Optimization").
+//
+// Pools are priced with amm_math, so quotes round exactly like the contracts in
+// stylys_rust_sdk_sample. `main.rs` is a CLI over a JSON snapshot file.
+pub mod calldata;
+pub mod pool;
+pub mod route;
+pub mod snapshot;
+pub mod split;
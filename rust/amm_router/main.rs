@@ -0,0 +1,114 @@
+// amm_router <snapshot.json> <token_in> <token_out> <amount_in> [options]
+//
+//   --max-hops N       longest path considered (default 3)
+//   --max-routes N     routes kept for splitting, best first (default 8)
+//   --parts N          pieces the input is split into (default 20)
+//   --slippage-bps N   tolerance for amountOutMin (default 50)
+//   --to ADDRESS       recipient in the calldata (default zero address)
+//   --deadline N       unix deadline in the calldata (default 0)
+//
+// Prints the plan and the router calldata as JSON. The calldata is for
+// `IAmmRouter.swapSplit` (calldata.rs), an ABI no contract implements yet: there
+// is no router in stylys_rust_sdk_sample to send it to, so treat it as the
+// encoded plan rather than a transaction that can be executed.
+//
+// Against the example file, selling 1000 A for C:
+//
+//   amm_router example_snapshot.json 0x000000000000000000000000000000000000000a \
+//       0x000000000000000000000000000000000000000c 1000000000000000000000
+use alloy_primitives::{hex, Address, U256};
+use amm_router::calldata::{encode_swap_split, min_amount_out};
+use amm_router::route::find_routes;
+use amm_router::snapshot::Snapshot;
+use amm_router::split::{best_routes, optimize};
+use serde_json::json;
+use std::{env, fs, process};
+
+struct Options {
+    snapshot: String,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    max_hops: usize,
+    max_routes: usize,
+    parts: u32,
+    slippage_bps: u64,
+    to: Address,
+    deadline: U256,
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {name}"))?;
+    value.parse().map_err(|_| format!("invalid {name}: {value}"))
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let snapshot = args.next().ok_or("usage: amm_router <snapshot.json> <token_in> <token_out> <amount_in> [options]")?;
+    let mut options = Options {
+        snapshot,
+        token_in: parse("token_in", args.next())?,
+        token_out: parse("token_out", args.next())?,
+        amount_in: parse("amount_in", args.next())?,
+        max_hops: 3,
+        max_routes: 8,
+        parts: 20,
+        slippage_bps: 50,
+        to: Address::ZERO,
+        deadline: U256::ZERO,
+    };
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--max-hops" => options.max_hops = parse(&flag, args.next())?,
+            "--max-routes" => options.max_routes = parse(&flag, args.next())?,
+            "--parts" => options.parts = parse(&flag, args.next())?,
+            "--slippage-bps" => options.slippage_bps = parse(&flag, args.next())?,
+            "--to" => options.to = parse(&flag, args.next())?,
+            "--deadline" => options.deadline = parse(&flag, args.next())?,
+            _ => return Err(format!("unknown option {flag}")),
+        }
+    }
+    Ok(options)
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+    let json = fs::read_to_string(&options.snapshot).map_err(|e| format!("{}: {e}", options.snapshot))?;
+    let snapshot = Snapshot::from_json(&json).map_err(|e| format!("{}: {e}", options.snapshot))?;
+    let pools = &snapshot.pools;
+
+    let routes = find_routes(pools, options.token_in, options.token_out, options.max_hops);
+    // rank on the size of one piece, since that's what each route is first offered
+    let piece = (options.amount_in / U256::from(options.parts.max(1))).max(U256::from(1));
+    let routes = best_routes(pools, routes, piece, options.max_routes);
+    let plan = optimize(pools, &routes, options.amount_in, options.parts).ok_or("no route can fill the trade")?;
+
+    let amount_out_min = min_amount_out(plan.amount_out, options.slippage_bps);
+    let calldata = encode_swap_split(pools, &plan, amount_out_min, options.to, options.deadline);
+    let report = json!({
+        "token_in": options.token_in,
+        "token_out": options.token_out,
+        "amount_in": options.amount_in.to_string(),
+        "amount_out": plan.amount_out.to_string(),
+        "amount_out_min": amount_out_min.to_string(),
+        "routes": plan.allocations.iter().map(|allocation| json!({
+            "amount_in": allocation.amount_in.to_string(),
+            "amount_out": allocation.amount_out.to_string(),
+            "hops": allocation.route.hops.iter().map(|hop| json!({
+                "pool": pools[hop.pool].address(),
+                "token_in": hop.token_in,
+                "token_out": hop.token_out,
+            })).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "calldata": hex::encode_prefixed(calldata),
+    });
+    println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("amm_router: {message}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,235 @@
+// Pool states as read from chain, with exact-input swaps that round the way the
+// contracts do. `swap` moves the state, so two routes through the same pool are
+// priced against each other's impact; `quote` works on a copy.
+use alloy_primitives::{Address, U256};
+use amm_math::constant_product::get_amount_out_with_fee;
+use amm_math::fixed_point::{complement, mul_down};
+use amm_math::sqrt_price_math::add_delta;
+use amm_math::stable_swap::{get_y, FEE_DENOMINATOR};
+use amm_math::swap_math::compute_swap_step;
+use amm_math::tick_math::{get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK};
+use amm_math::weighted_math::calc_out_given_in;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pool {
+    ConstantProduct(ConstantProductPool),
+    Stable(StablePool),
+    Weighted(WeightedPool),
+    Concentrated(ConcentratedPool),
+}
+
+/// x * y = k pair (23_pair.rs); `fee_bps` is its current fee.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstantProductPool {
+    pub address: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub fee_bps: u64,
+}
+
+/// StableSwap pool (20_stable_swap_pool.rs). `amp` is `a_precise()`, `fee` is out
+/// of `FEE_DENOMINATOR` and `rates` scale each coin to 18 decimals.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StablePool {
+    pub address: Address,
+    pub coins: Vec<Address>,
+    pub balances: Vec<U256>,
+    pub rates: Vec<U256>,
+    pub amp: U256,
+    pub fee: U256,
+}
+
+/// Weighted pool (21_weighted_pool.rs); weights and `swap_fee` are WAD.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedPool {
+    pub address: Address,
+    pub tokens: Vec<Address>,
+    pub balances: Vec<U256>,
+    pub rates: Vec<U256>,
+    pub weights: Vec<U256>,
+    pub swap_fee: U256,
+}
+
+/// Concentrated-liquidity pool (19_concentrated_liquidity_pool.rs). `fee` is in
+/// pips and `ticks` lists the initialized ticks, sorted by index.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcentratedPool {
+    pub address: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: u32,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    #[serde(with = "crate::snapshot::amount::unsigned")]
+    pub liquidity: u128,
+    pub ticks: Vec<TickLiquidity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickLiquidity {
+    pub index: i32,
+    #[serde(with = "crate::snapshot::amount::signed")]
+    pub liquidity_net: i128,
+}
+
+impl Pool {
+    pub fn address(&self) -> Address {
+        match self {
+            Pool::ConstantProduct(pool) => pool.address,
+            Pool::Stable(pool) => pool.address,
+            Pool::Weighted(pool) => pool.address,
+            Pool::Concentrated(pool) => pool.address,
+        }
+    }
+
+    pub fn tokens(&self) -> Vec<Address> {
+        match self {
+            Pool::ConstantProduct(pool) => vec![pool.token0, pool.token1],
+            Pool::Stable(pool) => pool.coins.clone(),
+            Pool::Weighted(pool) => pool.tokens.clone(),
+            Pool::Concentrated(pool) => vec![pool.token0, pool.token1],
+        }
+    }
+
+    /// Tells the router which swap adapter to call.
+    pub fn kind(&self) -> u8 {
+        match self {
+            Pool::ConstantProduct(_) => 0,
+            Pool::Stable(_) => 1,
+            Pool::Weighted(_) => 2,
+            Pool::Concentrated(_) => 3,
+        }
+    }
+
+    pub fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        self.clone().swap(token_in, token_out, amount_in)
+    }
+
+    /// Sells `amount_in` of `token_in` and updates the state. `None` if the pool
+    /// doesn't trade the pair or can't fill the whole amount.
+    pub fn swap(&mut self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        if token_in == token_out || amount_in == U256::ZERO {
+            return None;
+        }
+        let amount_out = match self {
+            Pool::ConstantProduct(pool) => pool.swap(token_in, token_out, amount_in),
+            Pool::Stable(pool) => pool.swap(token_in, token_out, amount_in),
+            Pool::Weighted(pool) => pool.swap(token_in, token_out, amount_in),
+            Pool::Concentrated(pool) => pool.swap(token_in, token_out, amount_in),
+        }?;
+        (amount_out > U256::ZERO).then_some(amount_out)
+    }
+}
+
+// (i, j) for a swap between two of `tokens`.
+fn indices(tokens: &[Address], token_in: Address, token_out: Address) -> Option<(usize, usize)> {
+    let i = tokens.iter().position(|token| *token == token_in)?;
+    let j = tokens.iter().position(|token| *token == token_out)?;
+    Some((i, j))
+}
+
+impl ConstantProductPool {
+    fn swap(&mut self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        let (i, _) = indices(&[self.token0, self.token1], token_in, token_out)?;
+        let (reserve_in, reserve_out) = if i == 0 { (self.reserve0, self.reserve1) } else { (self.reserve1, self.reserve0) };
+        if reserve_in == U256::ZERO || reserve_out == U256::ZERO {
+            return None;
+        }
+        let amount_out = get_amount_out_with_fee(amount_in, reserve_in, reserve_out, self.fee_bps);
+        if amount_out >= reserve_out {
+            return None;
+        }
+        if i == 0 {
+            (self.reserve0, self.reserve1) = (reserve_in + amount_in, reserve_out - amount_out);
+        } else {
+            (self.reserve1, self.reserve0) = (reserve_in + amount_in, reserve_out - amount_out);
+        }
+        Some(amount_out)
+    }
+}
+
+impl StablePool {
+    // StableSwapPool::calc_exchange
+    fn swap(&mut self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        let (i, j) = indices(&self.coins, token_in, token_out)?;
+        let xp: Vec<U256> = self.balances.iter().zip(&self.rates).map(|(balance, rate)| *balance * *rate).collect();
+        let x = xp[i] + amount_in * self.rates[i];
+        let y = get_y(i, j, x, &xp, self.amp)?;
+        let dy = xp[j].checked_sub(y)?.saturating_sub(U256::from(1));
+        let fee = dy * self.fee / U256::from(FEE_DENOMINATOR);
+        let amount_out = (dy - fee) / self.rates[j];
+        if amount_out >= self.balances[j] {
+            return None;
+        }
+        self.balances[i] += amount_in;
+        self.balances[j] -= amount_out;
+        Some(amount_out)
+    }
+}
+
+impl WeightedPool {
+    // WeightedPool::get_amount_out
+    fn swap(&mut self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        let (i, j) = indices(&self.tokens, token_in, token_out)?;
+        let (balance_in, balance_out) = (self.balances[i] * self.rates[i], self.balances[j] * self.rates[j]);
+        let after_fee = mul_down(amount_in * self.rates[i], complement(self.swap_fee));
+        let out = calc_out_given_in(balance_in, self.weights[i], balance_out, self.weights[j], after_fee)?;
+        let amount_out = out / self.rates[j];
+        self.balances[i] += amount_in;
+        self.balances[j] -= amount_out;
+        Some(amount_out)
+    }
+}
+
+impl ConcentratedPool {
+    // Nearest initialized tick at or below `tick` going left, above it going right.
+    fn next_initialized_tick(&self, tick: i32, zero_for_one: bool) -> (i32, Option<i128>) {
+        let found =
+            if zero_for_one { self.ticks.iter().rev().find(|t| t.index <= tick) } else { self.ticks.iter().find(|t| t.index > tick) };
+        match found {
+            Some(t) => (t.index, Some(t.liquidity_net)),
+            None => (if zero_for_one { MIN_TICK } else { MAX_TICK }, None),
+        }
+    }
+
+    // The exact-input path of ConcentratedLiquiditySwap::swap, with no price limit.
+    fn swap(&mut self, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        let (i, _) = indices(&[self.token0, self.token1], token_in, token_out)?;
+        let zero_for_one = i == 0;
+        let limit = if zero_for_one { MIN_SQRT_RATIO + U256::from(1) } else { MAX_SQRT_RATIO - U256::from(1) };
+        let (mut sqrt_price, mut tick, mut liquidity) = (self.sqrt_price_x96, self.tick, self.liquidity);
+        let (mut remaining, mut amount_out) = (amount_in, U256::ZERO);
+
+        while remaining > U256::ZERO && sqrt_price != limit {
+            let (tick_next, liquidity_net) = self.next_initialized_tick(tick, zero_for_one);
+            let sqrt_price_next = get_sqrt_ratio_at_tick(tick_next)?;
+            let target = if zero_for_one { sqrt_price_next.max(limit) } else { sqrt_price_next.min(limit) };
+
+            let step = compute_swap_step(sqrt_price, target, liquidity, remaining, true, self.fee)?;
+            let step_start = sqrt_price;
+            sqrt_price = step.sqrt_price_next_x96;
+            remaining -= step.amount_in + step.fee_amount;
+            amount_out += step.amount_out;
+
+            if sqrt_price == sqrt_price_next {
+                if let Some(net) = liquidity_net {
+                    // moving left crosses the tick from above, so its net is reversed
+                    liquidity = add_delta(liquidity, if zero_for_one { -net } else { net })?;
+                }
+                tick = if zero_for_one { tick_next - 1 } else { tick_next };
+            } else if sqrt_price != step_start {
+                tick = get_tick_at_sqrt_ratio(sqrt_price)?;
+            }
+        }
+        // ran out of liquidity before the input was used up
+        if remaining > U256::ZERO {
+            return None;
+        }
+        (self.sqrt_price_x96, self.tick, self.liquidity) = (sqrt_price, tick, liquidity);
+        Some(amount_out)
+    }
+}
@@ -0,0 +1,77 @@
+// Candidate paths. A -> ETH -> B can beat A -> B when the direct pool is thin,
+// so every simple path of up to `max_hops` pools is a candidate: no pool and no
+// token appears twice in one path.
+use crate::pool::Pool;
+use alloy_primitives::{Address, U256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hop {
+    /// Index into the snapshot's pools.
+    pub pool: usize,
+    pub token_in: Address,
+    pub token_out: Address,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Route {
+    pub hops: Vec<Hop>,
+}
+
+impl Route {
+    /// Output of the whole route, moving each pool's state along the way.
+    pub fn swap(&self, pools: &mut [Pool], amount_in: U256) -> Option<U256> {
+        self.hops.iter().try_fold(amount_in, |amount, hop| pools[hop.pool].swap(hop.token_in, hop.token_out, amount))
+    }
+
+    /// Same as `swap` without touching `pools`; a route never reuses a pool, so
+    /// each hop can be quoted on its own.
+    pub fn quote(&self, pools: &[Pool], amount_in: U256) -> Option<U256> {
+        self.hops.iter().try_fold(amount_in, |amount, hop| pools[hop.pool].quote(hop.token_in, hop.token_out, amount))
+    }
+}
+
+pub fn find_routes(pools: &[Pool], token_in: Address, token_out: Address, max_hops: usize) -> Vec<Route> {
+    let mut routes = Vec::new();
+    let mut path = Vec::new();
+    let mut visited = vec![token_in];
+    extend(pools, token_in, token_out, max_hops, &mut path, &mut visited, &mut routes);
+    routes
+}
+
+// Depth-first from `current`, recording each path that reaches `token_out`.
+fn extend(
+    pools: &[Pool],
+    current: Address,
+    token_out: Address,
+    max_hops: usize,
+    path: &mut Vec<Hop>,
+    visited: &mut Vec<Address>,
+    routes: &mut Vec<Route>,
+) {
+    if path.len() == max_hops {
+        return;
+    }
+    for (index, pool) in pools.iter().enumerate() {
+        if path.iter().any(|hop| hop.pool == index) {
+            continue;
+        }
+        let tokens = pool.tokens();
+        if !tokens.contains(&current) {
+            continue;
+        }
+        for next in tokens {
+            if next == current || visited.contains(&next) {
+                continue;
+            }
+            path.push(Hop { pool: index, token_in: current, token_out: next });
+            if next == token_out {
+                routes.push(Route { hops: path.clone() });
+            } else {
+                visited.push(next);
+                extend(pools, next, token_out, max_hops, path, visited, routes);
+                visited.pop();
+            }
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// The JSON file the CLI reads: pools tagged by `type`, amounts as decimal or
+// 0x-hex strings. See example_snapshot.json.
+use crate::pool::Pool;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Block the states were read at, for the record.
+    #[serde(default)]
+    pub block: Option<u64>,
+    pub pools: Vec<Pool>,
+}
+
+impl Snapshot {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+// serde for `u128` and `i128` fields as strings, like `U256`. `Pool` is an
+// internally tagged enum, and serde buffers those through a value type that has
+// no 128-bit integers, so bare JSON numbers that wide can't be read at all.
+pub(crate) mod amount {
+    use alloy_primitives::U256;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+
+    // (negative, magnitude) of a decimal or 0x-hex string with an optional `-`.
+    fn parse<E: Error>(text: &str) -> Result<(bool, U256), E> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let magnitude = digits.parse::<U256>().map_err(|_| E::custom(format!("invalid amount {text}")))?;
+        Ok((negative, magnitude))
+    }
+
+    fn out_of_range<E: Error>(text: &str) -> E {
+        E::custom(format!("amount {text} out of range"))
+    }
+
+    pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub mod unsigned {
+        use super::*;
+
+        pub use super::serialize;
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+            let text = String::deserialize(deserializer)?;
+            match parse::<D::Error>(&text)? {
+                (false, magnitude) => u128::try_from(magnitude).map_err(|_| out_of_range(&text)),
+                (true, _) => Err(out_of_range(&text)),
+            }
+        }
+    }
+
+    pub mod signed {
+        use super::*;
+
+        pub use super::serialize;
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+            let text = String::deserialize(deserializer)?;
+            let (negative, magnitude) = parse::<D::Error>(&text)?;
+            // i128::MIN has no positive counterpart, so go through the magnitude as u128
+            let magnitude = u128::try_from(magnitude).map_err(|_| out_of_range(&text))?;
+            let value = if negative { 0i128.checked_sub_unsigned(magnitude) } else { i128::try_from(magnitude).ok() };
+            value.ok_or_else(|| out_of_range(&text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pool::TickLiquidity;
+
+    fn tick(json: &str) -> serde_json::Result<TickLiquidity> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn liquidity_net_as_string() {
+        assert_eq!(tick(r#"{"index":1,"liquidity_net":"-0x10"}"#).unwrap().liquidity_net, -16);
+        let min = format!(r#"{{"index":1,"liquidity_net":"{}"}}"#, i128::MIN);
+        assert_eq!(tick(&min).unwrap().liquidity_net, i128::MIN);
+        let over = format!(r#"{{"index":1,"liquidity_net":"{}"}}"#, i128::MAX as u128 + 1);
+        assert!(tick(&over).is_err());
+        assert!(tick(r#"{"index":1,"liquidity_net":16}"#).is_err());
+
+        let json = serde_json::to_string(&TickLiquidity { index: -5, liquidity_net: -7 }).unwrap();
+        assert_eq!(json, r#"{"index":-5,"liquidity_net":"-7"}"#);
+    }
+}
@@ -0,0 +1,71 @@
+// Splitting one trade across parallel routes. The input is cut into `parts`
+// pieces and each piece goes to whichever route pays most for it in the state
+// the earlier pieces left behind. Route outputs are concave in their input, so
+// this ends with the marginal prices of the routes used equal to within one
+// piece, which is the optimal split at that granularity.
+use crate::pool::Pool;
+use crate::route::Route;
+use alloy_primitives::U256;
+use std::cmp::Reverse;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub route: Route,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitPlan {
+    pub allocations: Vec<Allocation>,
+    pub amount_out: U256,
+}
+
+/// The `max_routes` routes that pay most for `amount_in`, best first.
+pub fn best_routes(pools: &[Pool], routes: Vec<Route>, amount_in: U256, max_routes: usize) -> Vec<Route> {
+    let mut quoted: Vec<(U256, Route)> =
+        routes.into_iter().filter_map(|route| route.quote(pools, amount_in).map(|out| (out, route))).collect();
+    quoted.sort_by_key(|(out, _)| Reverse(*out));
+    quoted.into_iter().take(max_routes).map(|(_, route)| route).collect()
+}
+
+/// Splits `amount_in` over `routes`. `None` if some piece can't be filled by any route.
+pub fn optimize(pools: &[Pool], routes: &[Route], amount_in: U256, parts: u32) -> Option<SplitPlan> {
+    if routes.is_empty() || parts == 0 || amount_in == U256::ZERO {
+        return None;
+    }
+    let piece = amount_in / U256::from(parts);
+    let mut state = pools.to_vec();
+    let mut amounts = vec![U256::ZERO; routes.len()];
+    let mut left = amount_in;
+    for part in 0..parts {
+        // the last piece takes the rounding remainder
+        let size = if part + 1 == parts { left } else { piece };
+        if size == U256::ZERO {
+            continue;
+        }
+        // ties go to the earlier (better ranked) route
+        let (best, _) = routes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, route)| route.quote(&state, size).map(|out| (index, out)))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+        routes[best].swap(&mut state, size)?;
+        amounts[best] += size;
+        left -= size;
+    }
+
+    // price the plan as it will execute: one swap per route, in order
+    let mut state = pools.to_vec();
+    let mut allocations = Vec::new();
+    let mut amount_out = U256::ZERO;
+    for (route, amount_in) in routes.iter().zip(amounts) {
+        if amount_in == U256::ZERO {
+            continue;
+        }
+        let out = route.swap(&mut state, amount_in)?;
+        amount_out += out;
+        allocations.push(Allocation { route: route.clone(), amount_in, amount_out: out });
+    }
+    Some(SplitPlan { allocations, amount_out })
+}
@@ -0,0 +1,80 @@
+// Runs the CLI over example_snapshot.json and checks the plan it prints against
+// the calldata and the snapshot.
+use alloy_primitives::{hex, Address, U256};
+use alloy_sol_types::SolCall;
+use amm_router::calldata::IAmmRouter;
+use amm_router::snapshot::Snapshot;
+use serde_json::Value;
+use std::process::Command;
+
+const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/example_snapshot.json");
+const TOKEN_A: &str = "0x000000000000000000000000000000000000000a";
+const TOKEN_C: &str = "0x000000000000000000000000000000000000000c";
+
+fn run(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_amm_router")).arg(SNAPSHOT).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn amount(value: &Value) -> U256 {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[test]
+fn example_snapshot_parses() {
+    let snapshot = Snapshot::from_json(&std::fs::read_to_string(SNAPSHOT).unwrap()).unwrap();
+    assert_eq!(snapshot.pools.len(), 6);
+    // round-trips, 128-bit liquidity included
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(Snapshot::from_json(&json).unwrap(), snapshot);
+}
+
+#[test]
+fn example_trade_splits_and_encodes() {
+    let amount_in = U256::from(1000u64) * U256::from(10u64).pow(U256::from(18u64));
+    let to = "0x00000000000000000000000000000000000000f0";
+    let report = run(&[TOKEN_A, TOKEN_C, &amount_in.to_string(), "--to", to, "--deadline", "1700000000"]);
+
+    let routes = report["routes"].as_array().unwrap();
+    assert!(routes.len() > 1, "a 1000 A trade should be split across routes");
+    let total_in: U256 = routes.iter().map(|route| amount(&route["amount_in"])).sum();
+    let total_out: U256 = routes.iter().map(|route| amount(&route["amount_out"])).sum();
+    assert_eq!(total_in, amount_in);
+    assert_eq!(total_out, amount(&report["amount_out"]));
+    assert!(amount(&report["amount_out_min"]) < total_out);
+    for route in routes {
+        let hops = route["hops"].as_array().unwrap();
+        assert_eq!(hops.first().unwrap()["token_in"], TOKEN_A);
+        assert_eq!(hops.last().unwrap()["token_out"], TOKEN_C);
+        for pair in hops.windows(2) {
+            assert_eq!(pair[0]["token_out"], pair[1]["token_in"]);
+        }
+    }
+
+    let calldata = hex::decode(report["calldata"].as_str().unwrap()).unwrap();
+    let call = IAmmRouter::swapSplitCall::abi_decode(&calldata, true).unwrap();
+    assert_eq!(call.amountOutMin, amount(&report["amount_out_min"]));
+    assert_eq!(call.to, to.parse::<Address>().unwrap());
+    assert_eq!(call.deadline, U256::from(1_700_000_000u64));
+    assert_eq!(call.routes.len(), routes.len());
+    for (encoded, route) in call.routes.iter().zip(routes) {
+        assert_eq!(encoded.amountIn, amount(&route["amount_in"]));
+        let hops = route["hops"].as_array().unwrap();
+        assert_eq!(encoded.hops.len(), hops.len());
+        for (hop, printed) in encoded.hops.iter().zip(hops) {
+            assert_eq!(hop.pool, printed["pool"].as_str().unwrap().parse::<Address>().unwrap());
+            assert_eq!(hop.tokenIn, printed["token_in"].as_str().unwrap().parse::<Address>().unwrap());
+        }
+    }
+}
+
+#[test]
+fn rejects_unfillable_trade() {
+    let output = Command::new(env!("CARGO_BIN_EXE_amm_router"))
+        .args([SNAPSHOT, TOKEN_A, "0x00000000000000000000000000000000000000ff", "1000"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no route can fill the trade"));
+}
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+# Builds every sample contract for wasm32 and fails if one is over the Stylus
+# limit of 24KB once compressed. Deploying compresses the program with brotli
+# at quality 11 and window 22, as `cargo stylus check` does, so we do the same.
+set -euo pipefail
+cd "$(dirname "$0")"
+
+LIMIT=24576
+packages=$(for manifest in stylys_rust_sdk_sample/crates/*/Cargo.toml; do sed -n '0,/^name = "\(.*\)"$/s//\1/p' "$manifest"; done)
+cargo build --release --target wasm32-unknown-unknown $(printf -- '-p %s ' $packages)
+
+status=0
+for package in $packages; do
+    wasm="target/wasm32-unknown-unknown/release/${package//-/_}.wasm"
+    size=$(brotli -c -q 11 -w 22 "$wasm" | wc -c)
+    if [ "$size" -gt "$LIMIT" ]; then
+        echo "$package: $size bytes compressed, over the $LIMIT limit"
+        status=1
+    else
+        echo "$package: $size bytes compressed"
+    fi
+done
+exit $status
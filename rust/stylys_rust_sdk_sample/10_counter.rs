@@ -0,0 +1,240 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod ownable;
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    block, evm, msg,
+    prelude::*,
+    storage::Erase,
+};
+
+sol! {
+    event CounterChanged(address indexed account, uint256 old_value, uint256 new_value);
+
+    error InvalidBounds(uint256 min, uint256 max);
+    error CounterOverflow(uint256 number);
+    error CounterUnderflow(uint256 number);
+    error OutOfBounds(uint256 value, uint256 min, uint256 max);
+}
+
+#[derive(SolidityError)]
+pub enum CounterError {
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+    InvalidBounds(InvalidBounds),
+    CounterOverflow(CounterOverflow),
+    CounterUnderflow(CounterUnderflow),
+    OutOfBounds(OutOfBounds),
+}
+
+impl From<OwnableError> for CounterError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => CounterError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => CounterError::InvalidOwner(e),
+        }
+    }
+}
+
+// Bounded counter: the canonical smoke-test contract for storage, errors and events.
+sol_storage! {
+    #[entrypoint]
+    pub struct Counter {
+        #[borrow]
+        Ownable ownable;
+        uint256 min;
+        uint256 max;
+        CounterValue value;
+        mapping(address => uint256) counters;
+    }
+
+    #[derive(Erase)]
+    pub struct CounterValue {
+        uint256 number;
+        address last_caller;
+        uint64 updated_at;
+    }
+}
+
+impl Counter {
+    fn check_bounds(&self, value: U256) -> Result<(), CounterError> {
+        let (min, max) = (self.min.get(), self.max.get());
+        if value < min || value > max {
+            return Err(CounterError::OutOfBounds(OutOfBounds { value, min, max }));
+        }
+        Ok(())
+    }
+
+    fn write_number(&mut self, new_number: U256) -> Result<(), CounterError> {
+        self.check_bounds(new_number)?;
+        let old_value = self.value.number.get();
+        self.value.number.set(new_number);
+        self.value.last_caller.set(msg::sender());
+        self.value.updated_at.set(U64::from(block::timestamp()));
+        evm::log(CounterChanged { account: Address::ZERO, old_value, new_value: new_number });
+        Ok(())
+    }
+
+    fn write_counter(&mut self, account: Address, new_value: U256) -> Result<(), CounterError> {
+        let max = self.max.get();
+        if new_value > max {
+            return Err(CounterError::OutOfBounds(OutOfBounds { value: new_value, min: U256::ZERO, max }));
+        }
+        let old_value = self.counters.get(account);
+        self.counters.insert(account, new_value);
+        evm::log(CounterChanged { account, old_value, new_value });
+        Ok(())
+    }
+
+    fn add_checked(number: U256, n: U256) -> Result<U256, CounterError> {
+        number.checked_add(n).ok_or(CounterError::CounterOverflow(CounterOverflow { number }))
+    }
+
+    fn sub_checked(number: U256, n: U256) -> Result<U256, CounterError> {
+        number.checked_sub(n).ok_or(CounterError::CounterUnderflow(CounterUnderflow { number }))
+    }
+}
+
+#[external]
+#[inherit(Ownable)]
+impl Counter {
+    // Sets the owner and the inclusive [min, max] range; the counter starts at `min`.
+    pub fn init(&mut self, min: U256, max: U256, deploy_nonce: u64) -> Result<(), CounterError> {
+        if min > max {
+            return Err(CounterError::InvalidBounds(InvalidBounds { min, max }));
+        }
+        self.ownable.initialize(deploy_nonce)?;
+        self.min.set(min);
+        self.max.set(max);
+        self.write_number(min)
+    }
+
+    pub fn bounds(&self) -> Result<(U256, U256), CounterError> {
+        Ok((self.min.get(), self.max.get()))
+    }
+
+    pub fn number(&self) -> Result<U256, CounterError> {
+        Ok(self.value.number.get())
+    }
+
+    pub fn last_update(&self) -> Result<(Address, u64), CounterError> {
+        Ok((self.value.last_caller.get(), self.value.updated_at.get().saturating_to::<u64>()))
+    }
+
+    pub fn set_number(&mut self, new_number: U256) -> Result<(), CounterError> {
+        self.ownable.only_owner()?;
+        self.write_number(new_number)
+    }
+
+    pub fn increment(&mut self) -> Result<(), CounterError> {
+        self.add(U256::from(1))
+    }
+
+    pub fn decrement(&mut self) -> Result<(), CounterError> {
+        let number = Self::sub_checked(self.value.number.get(), U256::from(1))?;
+        self.write_number(number)
+    }
+
+    pub fn add(&mut self, n: U256) -> Result<(), CounterError> {
+        let number = Self::add_checked(self.value.number.get(), n)?;
+        self.write_number(number)
+    }
+
+    // Clears the shared counter through `Erase`, then puts it back at `min`.
+    pub fn reset(&mut self) -> Result<(), CounterError> {
+        self.ownable.only_owner()?;
+        self.value.erase();
+        self.write_number(self.min.get())
+    }
+
+    // Per-address counters start at zero and are only capped by `max`.
+    pub fn counter_of(&self, account: Address) -> Result<U256, CounterError> {
+        Ok(self.counters.get(account))
+    }
+
+    pub fn increment_mine(&mut self) -> Result<(), CounterError> {
+        self.add_mine(U256::from(1))
+    }
+
+    pub fn decrement_mine(&mut self) -> Result<(), CounterError> {
+        let account = msg::sender();
+        let value = Self::sub_checked(self.counters.get(account), U256::from(1))?;
+        self.write_counter(account, value)
+    }
+
+    pub fn add_mine(&mut self, n: U256) -> Result<(), CounterError> {
+        let account = msg::sender();
+        let value = Self::add_checked(self.counters.get(account), n)?;
+        self.write_counter(account, value)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use test_vm::{deploy, events, UnwrapOk, BOB, SENDER};
+
+    fn counter(min: u64, max: u64) -> Counter {
+        let mut counter = deploy::<Counter>();
+        counter.init(U256::from(min), U256::from(max), 0).unwrap_ok();
+        counter
+    }
+
+    #[test]
+    fn init_starts_at_min() {
+        let counter = counter(5, 10);
+        assert_eq!(counter.number().unwrap_ok(), U256::from(5));
+        assert_eq!(counter.bounds().unwrap_ok(), (U256::from(5), U256::from(10)));
+        assert_eq!(counter.ownable.owner().unwrap_ok(), SENDER);
+        assert!(matches!(deploy::<Counter>().init(U256::from(2), U256::from(1), 0), Err(CounterError::InvalidBounds(_))));
+    }
+
+    #[test]
+    fn stays_inside_bounds() {
+        let mut counter = counter(0, 2);
+        counter.increment().unwrap_ok();
+        counter.add(U256::from(1)).unwrap_ok();
+        assert!(matches!(counter.increment(), Err(CounterError::OutOfBounds(_))));
+        assert!(matches!(counter.add(U256::MAX), Err(CounterError::CounterOverflow(_))));
+        counter.set_number(U256::ZERO).unwrap_ok();
+        assert!(matches!(counter.decrement(), Err(CounterError::CounterUnderflow(_))));
+        assert_eq!(counter.last_update().unwrap_ok().0, SENDER);
+    }
+
+    #[test]
+    fn per_address_counters_emit_events() {
+        let mut counter = counter(0, 3);
+        counter.add_mine(U256::from(3)).unwrap_ok();
+        counter.decrement_mine().unwrap_ok();
+        assert_eq!(counter.counter_of(SENDER).unwrap_ok(), U256::from(2));
+        assert_eq!(counter.counter_of(BOB).unwrap_ok(), U256::ZERO);
+        let changed = events::<CounterChanged>();
+        let last = changed.last().unwrap();
+        assert_eq!((last.account, last.old_value, last.new_value), (SENDER, U256::from(3), U256::from(2)));
+    }
+
+    #[test]
+    fn reset_and_set_number_are_owner_only() {
+        let mut counter = counter(1, 9);
+        counter.add(U256::from(4)).unwrap_ok();
+        counter.reset().unwrap_ok();
+        assert_eq!(counter.number().unwrap_ok(), U256::from(1));
+        counter.ownable.renounce_ownership().unwrap_ok();
+        assert!(matches!(counter.reset(), Err(CounterError::Unauthorized(_))));
+        assert!(matches!(counter.set_number(U256::from(2)), Err(CounterError::Unauthorized(_))));
+    }
+}
@@ -0,0 +1,490 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+use crate::deployer::is_deployer;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    block,
+    call::RawCall,
+    evm, msg,
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageU256, StorageVec},
+};
+
+// Weights are in basis points of the delegator's LP votes
+const MAX_WEIGHT: u64 = 10_000;
+
+sol! {
+    // The LP votes token from 13_erc20_votes.rs
+    interface IVotes {
+        function getVotes(address account) external view returns (uint256);
+        function getPastVotes(address account, uint64 block_number) external view returns (uint256);
+    }
+}
+
+sol! {
+    event DelegateAdded(address indexed delegator, address indexed delegatee, uint256 weight, uint64 expiry);
+    event DelegateUpdated(address indexed delegator, address indexed delegatee, uint256 weight, uint64 expiry);
+    event DelegateRemoved(address indexed delegator, address indexed delegatee);
+
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error InvalidToken(address token);
+    error TokenCallFailed(address token);
+    error ZeroDelegatee();
+    error ZeroWeight();
+    error SelfDelegation(address delegator);
+    error DelegateExists(address delegator, address delegatee);
+    error DelegateNotFound(address delegator, address delegatee);
+    error WeightTooHigh(uint256 total_weight, uint256 max_weight);
+    error ExpiryInPast(uint64 expiry, uint64 now);
+    error DelegateNotExpired(uint64 expiry, uint64 now);
+    error FutureLookup(uint64 block_number, uint64 current_block);
+}
+
+#[derive(SolidityError)]
+pub enum DelegationError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    InvalidToken(InvalidToken),
+    TokenCallFailed(TokenCallFailed),
+    ZeroDelegatee(ZeroDelegatee),
+    ZeroWeight(ZeroWeight),
+    SelfDelegation(SelfDelegation),
+    DelegateExists(DelegateExists),
+    DelegateNotFound(DelegateNotFound),
+    WeightTooHigh(WeightTooHigh),
+    ExpiryInPast(ExpiryInPast),
+    DelegateNotExpired(DelegateNotExpired),
+    FutureLookup(FutureLookup),
+}
+
+// Delegators hand out basis points of their LP votes. Nothing is copied into
+// this contract: a delegatee's power is summed at lookup time from the token's
+// checkpoints, so every LP token counts once per block no matter who moved it.
+// Expiries are block numbers, so past lookups can tell which delegations were
+// live at the block asked about.
+sol_storage! {
+    #[entrypoint]
+    pub struct DelegationRegistry {
+        #[borrow]
+        SubStruct sub_struct;
+    }
+
+    pub struct SubStruct {
+        address token;
+        // delegator => its delegates
+        mapping(address => Delegate[]) delegates;
+        // delegator => delegatee => index + 1 in delegates[delegator] (0 = none)
+        mapping(address => mapping(address => uint256)) positions;
+        // delegator => sum of its delegates' weights
+        mapping(address => uint256) total_weight;
+        // delegatee => delegators pointing at it, for the power lookups
+        mapping(address => address[]) delegators;
+        // delegatee => delegator => index + 1 in delegators[delegatee] (0 = none)
+        mapping(address => mapping(address => uint256)) delegator_positions;
+    }
+
+    #[derive(Erase)]
+    pub struct Delegate {
+        address delegatee;
+        uint256 weight;
+        // first block the current weight counts in
+        uint64 start;
+        // first block it no longer counts in
+        uint64 expiry;
+    }
+}
+
+// Swap-and-pop removal from an index + 1 keyed list.
+fn unlink(list: &mut StorageVec<StorageAddress>, positions: &mut StorageMap<Address, StorageU256>, item: Address) {
+    let index = positions.get(item).saturating_to::<usize>() - 1;
+    let last_index = list.len() - 1;
+    if index != last_index {
+        let last = list.get(last_index).unwrap();
+        list.setter(index).unwrap().set(last);
+        positions.insert(last, U256::from(index + 1));
+    }
+    list.erase_last();
+    positions.delete(item);
+}
+
+impl SubStruct {
+    fn static_call<C: SolCall>(&self, call: C) -> Result<C::Return, DelegationError> {
+        let token = self.token.get();
+        let failed = || DelegationError::TokenCallFailed(TokenCallFailed { token });
+        let output = RawCall::new_static().call(token, &call.abi_encode()).map_err(|_| failed())?;
+        C::abi_decode_returns(&output, true).map_err(|_| failed())
+    }
+
+    fn votes(&self, account: Address, block_number: u64, now: u64) -> Result<U256, DelegationError> {
+        if block_number == now {
+            return Ok(self.static_call(IVotes::getVotesCall { account })?._0);
+        }
+        Ok(self.static_call(IVotes::getPastVotesCall { account, block_number })?._0)
+    }
+
+    // Sums the live delegations to `delegatee` at `block_number`, which is
+    // either the current block or one before it.
+    pub fn power_at(&self, delegatee: Address, block_number: u64, now: u64) -> Result<U256, DelegationError> {
+        if block_number > now {
+            return Err(DelegationError::FutureLookup(FutureLookup { block_number, current_block: now }));
+        }
+        let delegators = self.delegators.get(delegatee);
+        let mut power = U256::ZERO;
+        for i in 0..delegators.len() {
+            let delegator = delegators.get(i).unwrap();
+            let index = self.positions.get(delegator).get(delegatee).saturating_to::<usize>() - 1;
+            let delegates = self.delegates.get(delegator);
+            let delegate = delegates.get(index).unwrap();
+            let (start, expiry) = (delegate.start.get().saturating_to::<u64>(), delegate.expiry.get().saturating_to::<u64>());
+            if start > block_number || expiry <= block_number {
+                continue;
+            }
+            let votes = self.votes(delegator, block_number, now)?;
+            power += votes * delegate.weight.get() / U256::from(MAX_WEIGHT);
+        }
+        Ok(power)
+    }
+
+    fn check_weight(&self, delegator: Address, removed: U256, added: U256) -> Result<(), DelegationError> {
+        if added == U256::ZERO {
+            return Err(DelegationError::ZeroWeight(ZeroWeight {}));
+        }
+        let total_weight = self.total_weight.get(delegator) - removed + added;
+        if total_weight > U256::from(MAX_WEIGHT) {
+            return Err(DelegationError::WeightTooHigh(WeightTooHigh { total_weight, max_weight: U256::from(MAX_WEIGHT) }));
+        }
+        Ok(())
+    }
+
+    fn check_expiry(expiry: u64, now: u64) -> Result<(), DelegationError> {
+        if expiry <= now {
+            return Err(DelegationError::ExpiryInPast(ExpiryInPast { expiry, now }));
+        }
+        Ok(())
+    }
+
+    fn position(&self, delegator: Address, delegatee: Address) -> Result<usize, DelegationError> {
+        let position = self.positions.get(delegator).get(delegatee);
+        if position == U256::ZERO {
+            return Err(DelegationError::DelegateNotFound(DelegateNotFound { delegator, delegatee }));
+        }
+        Ok(position.saturating_to::<usize>() - 1)
+    }
+
+    pub fn add_delegate(
+        &mut self,
+        delegator: Address,
+        delegatee: Address,
+        weight: U256,
+        expiry: u64,
+        now: u64,
+    ) -> Result<(), DelegationError> {
+        if delegatee == Address::ZERO {
+            return Err(DelegationError::ZeroDelegatee(ZeroDelegatee {}));
+        }
+        if delegatee == delegator {
+            return Err(DelegationError::SelfDelegation(SelfDelegation { delegator }));
+        }
+        if self.positions.get(delegator).get(delegatee) != U256::ZERO {
+            return Err(DelegationError::DelegateExists(DelegateExists { delegator, delegatee }));
+        }
+        self.check_weight(delegator, U256::ZERO, weight)?;
+        Self::check_expiry(expiry, now)?;
+
+        let mut delegates = self.delegates.setter(delegator);
+        let mut delegate = delegates.grow();
+        delegate.delegatee.set(delegatee);
+        delegate.weight.set(weight);
+        delegate.start.set(U64::from(now));
+        delegate.expiry.set(U64::from(expiry));
+        let len = delegates.len();
+        self.positions.setter(delegator).insert(delegatee, U256::from(len));
+        self.total_weight.insert(delegator, self.total_weight.get(delegator) + weight);
+
+        let mut delegators = self.delegators.setter(delegatee);
+        delegators.push(delegator);
+        let len = delegators.len();
+        self.delegator_positions.setter(delegatee).insert(delegator, U256::from(len));
+        evm::log(DelegateAdded { delegator, delegatee, weight, expiry });
+        Ok(())
+    }
+
+    // The new weight counts from `now` on; earlier blocks no longer see this
+    // delegation, so a weight can't be raised retroactively.
+    pub fn update_delegate(
+        &mut self,
+        delegator: Address,
+        delegatee: Address,
+        weight: U256,
+        expiry: u64,
+        now: u64,
+    ) -> Result<(), DelegationError> {
+        let index = self.position(delegator, delegatee)?;
+        let old_weight = self.delegates.get(delegator).get(index).unwrap().weight.get();
+        self.check_weight(delegator, old_weight, weight)?;
+        Self::check_expiry(expiry, now)?;
+
+        let mut delegates = self.delegates.setter(delegator);
+        let mut delegate = delegates.setter(index).unwrap();
+        delegate.weight.set(weight);
+        delegate.start.set(U64::from(now));
+        delegate.expiry.set(U64::from(expiry));
+        self.total_weight.insert(delegator, self.total_weight.get(delegator) - old_weight + weight);
+        evm::log(DelegateUpdated { delegator, delegatee, weight, expiry });
+        Ok(())
+    }
+
+    // Swap-and-pop: the last delegate takes the removed one's slot.
+    pub fn remove_delegate(&mut self, delegator: Address, delegatee: Address) -> Result<(), DelegationError> {
+        let index = self.position(delegator, delegatee)?;
+        let mut delegates = self.delegates.setter(delegator);
+        let last_index = delegates.len() - 1;
+        let weight = delegates.get(index).unwrap().weight.get();
+        if index != last_index {
+            let last = delegates.get(last_index).unwrap();
+            let (last_delegatee, last_weight, last_start, last_expiry) =
+                (last.delegatee.get(), last.weight.get(), last.start.get(), last.expiry.get());
+            let mut slot = delegates.setter(index).unwrap();
+            slot.delegatee.set(last_delegatee);
+            slot.weight.set(last_weight);
+            slot.start.set(last_start);
+            slot.expiry.set(last_expiry);
+            self.positions.setter(delegator).insert(last_delegatee, U256::from(index + 1));
+        }
+        self.delegates.setter(delegator).erase_last();
+        self.positions.setter(delegator).delete(delegatee);
+        self.total_weight.insert(delegator, self.total_weight.get(delegator) - weight);
+
+        let mut delegators = self.delegators.setter(delegatee);
+        let mut delegator_positions = self.delegator_positions.setter(delegatee);
+        unlink(&mut delegators, &mut delegator_positions, delegator);
+        evm::log(DelegateRemoved { delegator, delegatee });
+        Ok(())
+    }
+
+    pub fn remove_expired_delegate(&mut self, delegator: Address, delegatee: Address, now: u64) -> Result<(), DelegationError> {
+        let index = self.position(delegator, delegatee)?;
+        let expiry = self.delegates.get(delegator).get(index).unwrap().expiry.get().saturating_to::<u64>();
+        if expiry > now {
+            return Err(DelegationError::DelegateNotExpired(DelegateNotExpired { expiry, now }));
+        }
+        self.remove_delegate(delegator, delegatee)
+    }
+}
+
+#[external]
+impl SubStruct {
+    pub fn add(&mut self, delegatee: Address, weight: U256, expiry: u64) -> Result<(), DelegationError> {
+        self.add_delegate(msg::sender(), delegatee, weight, expiry, block::number())
+    }
+
+    pub fn update(&mut self, delegatee: Address, weight: U256, expiry: u64) -> Result<(), DelegationError> {
+        self.update_delegate(msg::sender(), delegatee, weight, expiry, block::number())
+    }
+
+    pub fn remove(&mut self, delegatee: Address) -> Result<(), DelegationError> {
+        self.remove_delegate(msg::sender(), delegatee)
+    }
+
+    // Anyone may drop an expired delegation to free the delegator's weight.
+    pub fn remove_expired(&mut self, delegator: Address, delegatee: Address) -> Result<(), DelegationError> {
+        self.remove_expired_delegate(delegator, delegatee, block::number())
+    }
+
+    pub fn delegates_of(&self, delegator: Address) -> Result<Vec<(Address, U256, u64)>, DelegationError> {
+        let delegates = self.delegates.get(delegator);
+        let mut result = Vec::new();
+        for i in 0..delegates.len() {
+            let delegate = delegates.get(i).unwrap();
+            result.push((delegate.delegatee.get(), delegate.weight.get(), delegate.expiry.get().saturating_to::<u64>()));
+        }
+        Ok(result)
+    }
+
+    pub fn delegators_of(&self, delegatee: Address) -> Result<Vec<Address>, DelegationError> {
+        let delegators = self.delegators.get(delegatee);
+        Ok((0..delegators.len()).map(|i| delegators.get(i).unwrap()).collect())
+    }
+
+    pub fn delegation(&self, delegator: Address, delegatee: Address) -> Result<(U256, u64), DelegationError> {
+        let index = self.position(delegator, delegatee)?;
+        let delegates = self.delegates.get(delegator);
+        let delegate = delegates.get(index).unwrap();
+        Ok((delegate.weight.get(), delegate.expiry.get().saturating_to::<u64>()))
+    }
+
+    pub fn total_weight_of(&self, delegator: Address) -> Result<U256, DelegationError> {
+        Ok(self.total_weight.get(delegator))
+    }
+
+    pub fn delegated_power(&self, delegatee: Address) -> Result<U256, DelegationError> {
+        let now = block::number();
+        self.power_at(delegatee, now, now)
+    }
+
+    // Power held at the end of `block_number`, from the token's checkpoints
+    // at that block.
+    pub fn delegated_power_at(&self, delegatee: Address, block_number: u64) -> Result<U256, DelegationError> {
+        let now = block::number();
+        if block_number >= now {
+            return Err(DelegationError::FutureLookup(FutureLookup { block_number, current_block: now }));
+        }
+        self.power_at(delegatee, block_number, now)
+    }
+}
+
+#[external]
+#[inherit(SubStruct)]
+impl DelegationRegistry {
+    // Only the account that deployed the registry, with `deploy_nonce`, can
+    // choose the LP votes token.
+    pub fn init(&mut self, token: Address, deploy_nonce: u64) -> Result<(), DelegationError> {
+        if self.sub_struct.token.get() != Address::ZERO {
+            return Err(DelegationError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(DelegationError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        if token == Address::ZERO {
+            return Err(DelegationError::InvalidToken(InvalidToken { token }));
+        }
+        self.sub_struct.token.set(token);
+        Ok(())
+    }
+
+    pub fn token(&self) -> Result<Address, DelegationError> {
+        Ok(self.sub_struct.token.get())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use test_vm::{deploy, mock_call, UnwrapOk, BLOCK_NUMBER, BOB, CAROL, SENDER};
+
+    const TOKEN: Address = Address::repeat_byte(0x70);
+    const DAVE: Address = Address::repeat_byte(0xda);
+
+    // `history[account]` lists (block, votes) checkpoints, oldest first.
+    fn registry(history: HashMap<Address, Vec<(u64, u64)>>) -> DelegationRegistry {
+        let mut registry = deploy::<DelegationRegistry>();
+        mock_call(TOKEN, move |call| {
+            let votes_at = |account: Address, block: u64| {
+                let checkpoints = history.get(&account).cloned().unwrap_or_default();
+                let votes = checkpoints.iter().rev().find(|(at, _)| *at <= block).map_or(0, |(_, votes)| *votes);
+                Ok(U256::from(votes).to_be_bytes::<32>().to_vec())
+            };
+            if let Ok(call) = IVotes::getVotesCall::abi_decode(&call.data, true) {
+                return votes_at(call.account, BLOCK_NUMBER);
+            }
+            let call = IVotes::getPastVotesCall::abi_decode(&call.data, true).map_err(|_| Vec::new())?;
+            votes_at(call.account, call.block_number)
+        });
+        registry.init(TOKEN, 0).unwrap_ok();
+        registry
+    }
+
+    fn bps(weight: u64) -> U256 {
+        U256::from(weight)
+    }
+
+    #[test]
+    fn init_is_gated_on_the_deployer() {
+        let mut registry = deploy::<DelegationRegistry>();
+        assert!(matches!(registry.init(TOKEN, 1), Err(DelegationError::NotDeployer(_))));
+        assert!(matches!(registry.init(Address::ZERO, 0), Err(DelegationError::InvalidToken(_))));
+        registry.init(TOKEN, 0).unwrap_ok();
+        assert!(matches!(registry.init(TOKEN, 0), Err(DelegationError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn power_follows_token_checkpoints() {
+        let mut registry = registry(HashMap::from([(SENDER, vec![(0, 1_000)]), (CAROL, vec![(0, 400), (BLOCK_NUMBER, 0)])]));
+        let registry = &mut registry.sub_struct;
+        registry.add_delegate(SENDER, BOB, bps(6_000), 2_000, 900).unwrap_ok();
+        registry.add_delegate(CAROL, BOB, bps(MAX_WEIGHT), 2_000, 900).unwrap_ok();
+
+        assert_eq!(registry.delegated_power_at(BOB, 950).unwrap_ok(), U256::from(600 + 400));
+        // CAROL's LP moved away in the current block and stops counting at once
+        assert_eq!(registry.delegated_power(BOB).unwrap_ok(), U256::from(600));
+        // nothing was live before the delegations were made
+        assert_eq!(registry.delegated_power_at(BOB, 899).unwrap_ok(), U256::ZERO);
+        assert!(matches!(registry.delegated_power_at(BOB, BLOCK_NUMBER), Err(DelegationError::FutureLookup(_))));
+    }
+
+    #[test]
+    fn moved_votes_are_not_counted_twice() {
+        // DAVE's 500 votes end up with CAROL at block 950
+        let mut registry = registry(HashMap::from([(DAVE, vec![(0, 500), (950, 0)]), (CAROL, vec![(950, 500)])]));
+        let registry = &mut registry.sub_struct;
+        registry.add_delegate(DAVE, BOB, bps(MAX_WEIGHT), 2_000, 900).unwrap_ok();
+        registry.add_delegate(CAROL, BOB, bps(MAX_WEIGHT), 2_000, 900).unwrap_ok();
+        assert_eq!(registry.delegated_power_at(BOB, 949).unwrap_ok(), U256::from(500));
+        assert_eq!(registry.delegated_power_at(BOB, 950).unwrap_ok(), U256::from(500));
+    }
+
+    #[test]
+    fn expired_delegations_stop_counting() {
+        let mut registry = registry(HashMap::from([(SENDER, vec![(0, 1_000)])]));
+        let registry = &mut registry.sub_struct;
+        registry.add_delegate(SENDER, BOB, bps(5_000), 960, 900).unwrap_ok();
+        assert_eq!(registry.delegated_power_at(BOB, 959).unwrap_ok(), U256::from(500));
+        assert_eq!(registry.delegated_power_at(BOB, 960).unwrap_ok(), U256::ZERO);
+        assert_eq!(registry.delegated_power(BOB).unwrap_ok(), U256::ZERO);
+
+        // the weight stays taken until someone clears the record
+        assert!(matches!(registry.add_delegate(SENDER, CAROL, bps(6_000), 2_000, BLOCK_NUMBER), Err(DelegationError::WeightTooHigh(_))));
+        assert!(matches!(registry.remove_expired_delegate(SENDER, BOB, 959), Err(DelegationError::DelegateNotExpired(_))));
+        registry.remove_expired(SENDER, BOB).unwrap_ok();
+        assert_eq!(registry.total_weight_of(SENDER).unwrap_ok(), U256::ZERO);
+        assert!(registry.delegators_of(BOB).unwrap_ok().is_empty());
+    }
+
+    #[test]
+    fn update_does_not_apply_retroactively() {
+        let mut registry = registry(HashMap::from([(SENDER, vec![(0, 1_000)])]));
+        let registry = &mut registry.sub_struct;
+        registry.add_delegate(SENDER, BOB, bps(1_000), 2_000, 900).unwrap_ok();
+        registry.update_delegate(SENDER, BOB, bps(9_000), 2_000, 950).unwrap_ok();
+        assert_eq!(registry.delegated_power_at(BOB, 949).unwrap_ok(), U256::ZERO);
+        assert_eq!(registry.delegated_power_at(BOB, 950).unwrap_ok(), U256::from(900));
+        assert!(matches!(registry.update_delegate(SENDER, BOB, U256::ZERO, 2_000, 950), Err(DelegationError::ZeroWeight(_))));
+    }
+
+    #[test]
+    fn remove_swaps_and_pops_both_indexes() {
+        let mut registry = registry(HashMap::new());
+        let registry = &mut registry.sub_struct;
+        registry.add_delegate(SENDER, BOB, bps(1_000), 2_000, 900).unwrap_ok();
+        registry.add_delegate(SENDER, CAROL, bps(2_000), 2_000, 900).unwrap_ok();
+        registry.add_delegate(SENDER, DAVE, bps(3_000), 2_000, 900).unwrap_ok();
+        registry.add_delegate(CAROL, DAVE, bps(3_000), 2_000, 900).unwrap_ok();
+
+        registry.remove_delegate(SENDER, BOB).unwrap_ok();
+        let delegates: Vec<Address> = registry.delegates_of(SENDER).unwrap_ok().into_iter().map(|d| d.0).collect();
+        assert_eq!(delegates, vec![DAVE, CAROL]);
+        assert_eq!(registry.delegation(SENDER, DAVE).unwrap_ok(), (bps(3_000), 2_000));
+        assert_eq!(registry.total_weight_of(SENDER).unwrap_ok(), bps(5_000));
+
+        registry.remove_delegate(SENDER, DAVE).unwrap_ok();
+        assert_eq!(registry.delegators_of(DAVE).unwrap_ok(), vec![CAROL]);
+        assert!(matches!(registry.remove_delegate(SENDER, DAVE), Err(DelegationError::DelegateNotFound(_))));
+        assert!(matches!(registry.add_delegate(SENDER, SENDER, bps(1), 2_000, 900), Err(DelegationError::SelfDelegation(_))));
+    }
+}
@@ -0,0 +1,191 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod enumerable;
+use crate::enumerable::AddressUintMap;
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event LiquidityRecorded(address indexed lp, uint256 amount);
+    event PoolDeprecated(uint256 lp_count);
+    event LpsErased(uint256 erased, uint256 remaining);
+
+    error NotOwner(address caller, address owner);
+    error AlreadyInitialized();
+    error PoolIsDeprecated();
+    error PoolNotDeprecated();
+}
+
+#[derive(SolidityError)]
+pub enum LpRegistryError {
+    NotOwner(NotOwner),
+    AlreadyInitialized(AlreadyInitialized),
+    PoolIsDeprecated(PoolIsDeprecated),
+    PoolNotDeprecated(PoolNotDeprecated),
+}
+
+// Use case: list every LP of a pool and wipe them all when the pool is deprecated.
+// Wiping is paged, so a pool with any number of LPs can still be cleared.
+sol_storage! {
+    #[entrypoint]
+    pub struct LpRegistry {
+        address owner;
+        bool deprecated;
+        AddressUintMap liquidity;
+    }
+}
+
+impl LpRegistry {
+    pub fn only_owner(&self) -> Result<(), LpRegistryError> {
+        let owner = self.owner.get();
+        if owner != msg::sender() {
+            return Err(LpRegistryError::NotOwner(NotOwner { caller: msg::sender(), owner }));
+        }
+        Ok(())
+    }
+}
+
+#[external]
+impl LpRegistry {
+    pub fn init(&mut self) -> Result<(), LpRegistryError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(LpRegistryError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.owner.set(msg::sender());
+        Ok(())
+    }
+
+    // An amount of zero removes the LP from the list.
+    pub fn record_liquidity(&mut self, lp: Address, amount: U256) -> Result<(), LpRegistryError> {
+        self.only_owner()?;
+        if self.deprecated.get() {
+            return Err(LpRegistryError::PoolIsDeprecated(PoolIsDeprecated {}));
+        }
+        if amount == U256::ZERO {
+            self.liquidity.remove(lp);
+        } else {
+            self.liquidity.insert(lp, amount);
+        }
+        evm::log(LiquidityRecorded { lp, amount });
+        Ok(())
+    }
+
+    pub fn liquidity_of(&self, lp: Address) -> Result<U256, LpRegistryError> {
+        Ok(self.liquidity.get(lp))
+    }
+
+    pub fn lp_count(&self) -> Result<U256, LpRegistryError> {
+        Ok(U256::from(self.liquidity.len()))
+    }
+
+    pub fn lps(&self, offset: U256, limit: U256) -> Result<(Vec<Address>, Vec<U256>), LpRegistryError> {
+        let page = self.liquidity.page(offset.saturating_to(), limit.saturating_to());
+        Ok(page.into_iter().unzip())
+    }
+
+    // Freezes the list; `erase_lps` then clears it in pages.
+    pub fn deprecate(&mut self) -> Result<(), LpRegistryError> {
+        self.only_owner()?;
+        if self.deprecated.get() {
+            return Err(LpRegistryError::PoolIsDeprecated(PoolIsDeprecated {}));
+        }
+        self.deprecated.set(true);
+        evm::log(PoolDeprecated { lp_count: U256::from(self.liquidity.len()) });
+        Ok(())
+    }
+
+    // Anyone may erase up to `limit` LPs of a deprecated pool; returns how many are left.
+    pub fn erase_lps(&mut self, limit: U256) -> Result<U256, LpRegistryError> {
+        if !self.deprecated.get() {
+            return Err(LpRegistryError::PoolNotDeprecated(PoolNotDeprecated {}));
+        }
+        let before = self.liquidity.len();
+        let remaining = U256::from(self.liquidity.erase_n(limit.saturating_to()));
+        evm::log(LpsErased { erased: U256::from(before) - remaining, remaining });
+        Ok(remaining)
+    }
+
+    pub fn deprecated(&self) -> Result<bool, LpRegistryError> {
+        Ok(self.deprecated.get())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::enumerable::UintSet;
+    use test_vm::{deploy, events, UnwrapOk, BOB, CAROL, SENDER};
+
+    #[test]
+    fn set_swaps_and_pops() {
+        let mut set = deploy::<UintSet>();
+        for value in 1..=4 {
+            assert!(set.add(U256::from(value)));
+        }
+        assert!(!set.add(U256::from(2)));
+        assert!(set.remove(U256::from(2)));
+        assert!(!set.remove(U256::from(2)));
+        assert_eq!(set.page(0, 10), vec![U256::from(1), U256::from(4), U256::from(3)]);
+        assert_eq!(set.page(2, 10), vec![U256::from(3)]);
+        assert!(set.contains(U256::from(4)));
+
+        assert_eq!(set.erase_n(2), 1);
+        assert!(!set.contains(U256::from(4)) && !set.contains(U256::from(3)));
+        assert_eq!(set.erase_n(5), 0);
+        assert!(set.is_empty() && !set.contains(U256::from(1)));
+        // erased positions don't leak into new entries
+        assert!(set.add(U256::from(3)));
+        assert_eq!(set.at(0), Some(U256::from(3)));
+    }
+
+    #[test]
+    fn records_and_lists_liquidity() {
+        let mut registry = deploy::<LpRegistry>();
+        registry.init().unwrap_ok();
+        registry.record_liquidity(SENDER, U256::from(10)).unwrap_ok();
+        registry.record_liquidity(BOB, U256::from(20)).unwrap_ok();
+        registry.record_liquidity(CAROL, U256::from(30)).unwrap_ok();
+        registry.record_liquidity(SENDER, U256::ZERO).unwrap_ok();
+        assert_eq!(registry.lp_count().unwrap_ok(), U256::from(2));
+        assert_eq!(registry.lps(U256::ZERO, U256::MAX).unwrap_ok(), (vec![CAROL, BOB], vec![U256::from(30), U256::from(20)]));
+        assert_eq!(registry.liquidity_of(SENDER).unwrap_ok(), U256::ZERO);
+    }
+
+    #[test]
+    fn deprecated_pools_are_erased_in_pages() {
+        let mut registry = deploy::<LpRegistry>();
+        registry.init().unwrap_ok();
+        assert!(matches!(registry.erase_lps(U256::from(1)), Err(LpRegistryError::PoolNotDeprecated(_))));
+        for (i, lp) in [SENDER, BOB, CAROL].into_iter().enumerate() {
+            registry.record_liquidity(lp, U256::from(i + 1)).unwrap_ok();
+        }
+        registry.deprecate().unwrap_ok();
+        assert_eq!(events::<PoolDeprecated>()[0].lp_count, U256::from(3));
+        assert!(matches!(registry.record_liquidity(BOB, U256::from(1)), Err(LpRegistryError::PoolIsDeprecated(_))));
+
+        assert_eq!(registry.erase_lps(U256::from(2)).unwrap_ok(), U256::from(1));
+        assert_eq!(registry.liquidity_of(CAROL).unwrap_ok(), U256::ZERO);
+        assert_eq!(registry.liquidity_of(SENDER).unwrap_ok(), U256::from(1));
+        assert_eq!(registry.erase_lps(U256::MAX).unwrap_ok(), U256::ZERO);
+        assert_eq!(registry.liquidity_of(SENDER).unwrap_ok(), U256::ZERO);
+        assert_eq!(registry.lp_count().unwrap_ok(), U256::ZERO);
+        let erased: Vec<U256> = events::<LpsErased>().iter().map(|e| e.erased).collect();
+        assert_eq!(erased, vec![U256::from(2), U256::from(1)]);
+    }
+}
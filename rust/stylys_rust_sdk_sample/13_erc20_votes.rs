@@ -0,0 +1,346 @@
+#![cfg_attr(not(feature = "export-abi"), no_main, no_std)]
+extern crate alloc;
+
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+use crate::deployer::is_deployer;
+use crate::erc20::{Erc20, Erc20Error, Erc20Params};
+use alloy_sol_types::{sol, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U64},
+    block, call::RawCall, contract, crypto, evm, msg,
+    prelude::*,
+    storage::StorageVec,
+};
+
+struct VotesTokenParams;
+
+impl Erc20Params for VotesTokenParams {
+    const NAME: &'static str = "AMM LP Votes";
+    const SYMBOL: &'static str = "vLP";
+    const DECIMALS: u8 = 18;
+}
+
+sol! {
+    event DelegateChanged(address indexed delegator, address indexed from_delegate, address indexed to_delegate);
+    event DelegateVotesChanged(address indexed delegate, uint256 previous_votes, uint256 new_votes);
+
+    error NotOwner(address caller, address owner);
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error InsufficientBalance(address from, uint256 have, uint256 want);
+    error InsufficientAllowance(address owner, address spender, uint256 have, uint256 want);
+    error FutureLookup(uint64 block_number, uint64 current_block);
+    error SignatureExpired(uint256 expiry);
+    error InvalidSignature();
+    error InvalidNonce(address signer, uint256 expected, uint256 got);
+}
+
+#[derive(SolidityError)]
+pub enum VotesError {
+    NotOwner(NotOwner),
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    InsufficientBalance(InsufficientBalance),
+    InsufficientAllowance(InsufficientAllowance),
+    FutureLookup(FutureLookup),
+    SignatureExpired(SignatureExpired),
+    InvalidSignature(InvalidSignature),
+    InvalidNonce(InvalidNonce),
+}
+
+impl From<Erc20Error> for VotesError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => VotesError::InsufficientBalance(InsufficientBalance {
+                from: e.from,
+                have: e.have,
+                want: e.want,
+            }),
+            Erc20Error::InsufficientAllowance(e) => VotesError::InsufficientAllowance(InsufficientAllowance {
+                owner: e.owner,
+                spender: e.spender,
+                have: e.have,
+                want: e.want,
+            }),
+        }
+    }
+}
+
+// ERC20Votes-style extension: every balance change moves voting power between
+// delegates and writes a (block, votes) checkpoint, so governance can read
+// balances as of a past block instead of the manipulable current one.
+sol_storage! {
+    #[entrypoint]
+    pub struct VotesToken {
+        address owner;
+        #[borrow]
+        Erc20<VotesTokenParams> erc20;
+        #[borrow]
+        Votes votes;
+    }
+
+    pub struct Votes {
+        mapping(address => address) delegates;
+        mapping(address => Checkpoint[]) checkpoints;
+        Checkpoint[] total_supply_checkpoints;
+        mapping(address => uint256) nonces;
+    }
+
+    pub struct Checkpoint {
+        uint64 block;
+        uint256 votes;
+    }
+}
+
+fn latest(checkpoints: &StorageVec<Checkpoint>) -> U256 {
+    match checkpoints.len() {
+        0 => U256::ZERO,
+        len => checkpoints.get(len - 1).unwrap().votes.get(),
+    }
+}
+
+// Upper-bound binary search: the last checkpoint at or before `block_number`.
+fn lookup(checkpoints: &StorageVec<Checkpoint>, block_number: u64) -> U256 {
+    let (mut low, mut high) = (0, checkpoints.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        if checkpoints.get(mid).unwrap().block.get().saturating_to::<u64>() > block_number {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    if high == 0 {
+        return U256::ZERO;
+    }
+    checkpoints.get(high - 1).unwrap().votes.get()
+}
+
+fn push(checkpoints: &mut StorageVec<Checkpoint>, votes: U256) {
+    let now = U64::from(block::number());
+    let len = checkpoints.len();
+    if len > 0 && checkpoints.get(len - 1).unwrap().block.get() == now {
+        checkpoints.setter(len - 1).unwrap().votes.set(votes);
+    } else {
+        let mut checkpoint = checkpoints.grow();
+        checkpoint.block.set(now);
+        checkpoint.votes.set(votes);
+    }
+}
+
+impl Votes {
+    fn write_votes(&mut self, delegate: Address, add: bool, amount: U256) {
+        let mut checkpoints = self.checkpoints.setter(delegate);
+        let previous_votes = latest(&checkpoints);
+        let new_votes = if add { previous_votes + amount } else { previous_votes - amount };
+        push(&mut checkpoints, new_votes);
+        evm::log(DelegateVotesChanged { delegate, previous_votes, new_votes });
+    }
+
+    pub fn move_voting_power(&mut self, from: Address, to: Address, amount: U256) {
+        if from == to || amount == U256::ZERO {
+            return;
+        }
+        if from != Address::ZERO {
+            self.write_votes(from, false, amount);
+        }
+        if to != Address::ZERO {
+            self.write_votes(to, true, amount);
+        }
+    }
+
+    pub fn move_total_supply(&mut self, add: bool, amount: U256) {
+        let supply = latest(&self.total_supply_checkpoints);
+        let supply = if add { supply + amount } else { supply - amount };
+        push(&mut self.total_supply_checkpoints, supply);
+    }
+
+    fn check_past(block_number: u64) -> Result<(), VotesError> {
+        let current_block = block::number();
+        if block_number >= current_block {
+            return Err(VotesError::FutureLookup(FutureLookup { block_number, current_block }));
+        }
+        Ok(())
+    }
+}
+
+#[external]
+impl Votes {
+    pub fn delegates(&self, account: Address) -> Result<Address, VotesError> {
+        Ok(self.delegates.get(account))
+    }
+
+    pub fn nonces(&self, account: Address) -> Result<U256, VotesError> {
+        Ok(self.nonces.get(account))
+    }
+
+    pub fn num_checkpoints(&self, account: Address) -> Result<u32, VotesError> {
+        Ok(self.checkpoints.get(account).len() as u32)
+    }
+
+    pub fn checkpoints(&self, account: Address, pos: u32) -> Result<(u64, U256), VotesError> {
+        let checkpoints = self.checkpoints.get(account);
+        Ok(match checkpoints.get(pos as usize) {
+            Some(c) => (c.block.get().saturating_to::<u64>(), c.votes.get()),
+            None => (0, U256::ZERO),
+        })
+    }
+
+    pub fn get_votes(&self, account: Address) -> Result<U256, VotesError> {
+        Ok(latest(&self.checkpoints.get(account)))
+    }
+
+    pub fn get_past_votes(&self, account: Address, block_number: u64) -> Result<U256, VotesError> {
+        Self::check_past(block_number)?;
+        Ok(lookup(&self.checkpoints.get(account), block_number))
+    }
+
+    pub fn get_past_total_supply(&self, block_number: u64) -> Result<U256, VotesError> {
+        Self::check_past(block_number)?;
+        Ok(lookup(&self.total_supply_checkpoints, block_number))
+    }
+}
+
+impl VotesToken {
+    pub fn only_owner(&self) -> Result<(), VotesError> {
+        let owner = self.owner.get();
+        if owner != msg::sender() {
+            return Err(VotesError::NotOwner(NotOwner { caller: msg::sender(), owner }));
+        }
+        Ok(())
+    }
+
+    fn delegate_impl(&mut self, delegator: Address, delegatee: Address) {
+        let from_delegate = self.votes.delegates.get(delegator);
+        self.votes.delegates.insert(delegator, delegatee);
+        evm::log(DelegateChanged { delegator, from_delegate, to_delegate: delegatee });
+        let balance = self.erc20.balances.get(delegator);
+        self.votes.move_voting_power(from_delegate, delegatee, balance);
+    }
+
+    fn after_transfer(&mut self, from: Address, to: Address, value: U256) {
+        let from_delegate = self.votes.delegates.get(from);
+        let to_delegate = self.votes.delegates.get(to);
+        self.votes.move_voting_power(from_delegate, to_delegate, value);
+    }
+
+    fn domain_separator() -> B256 {
+        let type_hash = crypto::keccak(
+            b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+        );
+        let encoded = (
+            type_hash,
+            crypto::keccak(VotesTokenParams::NAME.as_bytes()),
+            crypto::keccak(b"1"),
+            U256::from(block::chainid()),
+            contract::address(),
+        )
+            .abi_encode();
+        crypto::keccak(encoded)
+    }
+
+    // ecrecover through the precompile at 0x01; returns zero on a bad signature.
+    fn recover(digest: B256, v: u8, r: B256, s: B256) -> Address {
+        let input = (digest, U256::from(v), r, s).abi_encode();
+        match RawCall::new_static().call(Address::with_last_byte(1), &input) {
+            Ok(output) if output.len() == 32 => Address::from_slice(&output[12..]),
+            _ => Address::ZERO,
+        }
+    }
+}
+
+#[external]
+#[inherit(Erc20<VotesTokenParams>, Votes)]
+impl VotesToken {
+    // Only the deployer can claim ownership, and with it the right to mint.
+    pub fn init(&mut self, deploy_nonce: u64) -> Result<(), VotesError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(VotesError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(VotesError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        self.owner.set(msg::sender());
+        Ok(())
+    }
+
+    pub fn mint(&mut self, to: Address, amount: U256) -> Result<(), VotesError> {
+        self.only_owner()?;
+        self.erc20.mint(to, amount);
+        self.votes.move_total_supply(true, amount);
+        self.after_transfer(Address::ZERO, to, amount);
+        Ok(())
+    }
+
+    pub fn burn(&mut self, from: Address, amount: U256) -> Result<(), VotesError> {
+        self.only_owner()?;
+        self.erc20.burn(from, amount)?;
+        self.votes.move_total_supply(false, amount);
+        self.after_transfer(from, Address::ZERO, amount);
+        Ok(())
+    }
+
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<bool, VotesError> {
+        self.erc20.transfer_impl(msg::sender(), to, value)?;
+        self.after_transfer(msg::sender(), to, value);
+        Ok(true)
+    }
+
+    pub fn transfer_from(&mut self, from: Address, to: Address, value: U256) -> Result<bool, VotesError> {
+        self.erc20.transfer_from(from, to, value)?;
+        self.after_transfer(from, to, value);
+        Ok(true)
+    }
+
+    pub fn delegate(&mut self, delegatee: Address) -> Result<(), VotesError> {
+        self.delegate_impl(msg::sender(), delegatee);
+        Ok(())
+    }
+
+    // EIP-712 signed `Delegation(address delegatee,uint256 nonce,uint256 expiry)`.
+    pub fn delegate_by_sig(
+        &mut self,
+        delegatee: Address,
+        nonce: U256,
+        expiry: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), VotesError> {
+        if U256::from(block::timestamp()) > expiry {
+            return Err(VotesError::SignatureExpired(SignatureExpired { expiry }));
+        }
+        let type_hash = crypto::keccak(b"Delegation(address delegatee,uint256 nonce,uint256 expiry)");
+        let struct_hash = crypto::keccak((type_hash, delegatee, nonce, expiry).abi_encode());
+        let digest = crypto::keccak(
+            [&[0x19, 0x01][..], &Self::domain_separator()[..], &struct_hash[..]].concat(),
+        );
+        let signer = Self::recover(digest, v, r, s);
+        if signer == Address::ZERO {
+            return Err(VotesError::InvalidSignature(InvalidSignature {}));
+        }
+
+        let expected = self.votes.nonces.get(signer);
+        if nonce != expected {
+            return Err(VotesError::InvalidNonce(InvalidNonce { signer, expected, got: nonce }));
+        }
+        self.votes.nonces.insert(signer, expected + U256::from(1));
+        self.delegate_impl(signer, delegatee);
+        Ok(())
+    }
+
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn domain_separator_view(&self) -> Result<B256, VotesError> {
+        Ok(Self::domain_separator())
+    }
+}
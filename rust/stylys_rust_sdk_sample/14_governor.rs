@@ -0,0 +1,543 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+use crate::deployer::is_deployer;
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolValue};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{self, Address, B256, U256, U64},
+    block,
+    call::{call, Call},
+    contract, crypto, evm, msg,
+    prelude::*,
+};
+
+// Queued proposals that are not executed within this window expire
+const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+const QUORUM_DENOMINATOR: u64 = 10_000;
+
+// Vote types, as in OpenZeppelin's GovernorCountingSimple
+const AGAINST: u8 = 0;
+const FOR: u8 = 1;
+const ABSTAIN: u8 = 2;
+
+// Proposal states returned by `state`
+const PENDING: u8 = 0;
+const ACTIVE: u8 = 1;
+const CANCELED: u8 = 2;
+const DEFEATED: u8 = 3;
+const SUCCEEDED: u8 = 4;
+const QUEUED: u8 = 5;
+const EXPIRED: u8 = 6;
+const EXECUTED: u8 = 7;
+
+// The checkpointed token from 13_erc20_votes.rs. The pair's own LP token
+// (23_pair.rs) keeps no checkpoints, so it can't be `token`: votes read from live
+// balances could be borrowed for one block. LP holders vote through a VotesToken
+// minted against their staked LP by its owner; that staking contract isn't one
+// of these samples.
+sol_interface! {
+    interface IVotes {
+        function getPastVotes(address account, uint64 block_number) external view returns (uint256);
+        function getPastTotalSupply(uint64 block_number) external view returns (uint256);
+    }
+}
+
+sol! {
+    event ProposalCreated(bytes32 indexed proposal_id, address indexed proposer, address[] targets, uint256[] values, bytes[] calldatas, uint64 vote_start, uint64 vote_end, string description);
+    event VoteCast(address indexed voter, bytes32 indexed proposal_id, uint8 support, uint256 weight);
+    event ProposalQueued(bytes32 indexed proposal_id, uint64 eta);
+    event ProposalExecuted(bytes32 indexed proposal_id);
+    event ProposalCanceled(bytes32 indexed proposal_id);
+
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error InvalidQuorum(uint256 quorum_numerator);
+    error OnlyGovernance(address caller);
+    error InvalidProposalLength(uint256 targets, uint256 values, uint256 calldatas);
+    error EmptyProposal();
+    error ProposalExists(bytes32 proposal_id);
+    error UnknownProposal(bytes32 proposal_id);
+    error BelowProposalThreshold(address proposer, uint256 votes, uint256 threshold);
+    error UnexpectedProposalState(bytes32 proposal_id, uint8 state);
+    error AlreadyVoted(address voter);
+    error InvalidVoteType(uint8 support);
+    error NotReady(bytes32 proposal_id, uint64 eta);
+    error Unauthorized(address caller);
+    error VotesLookupFailed(address token);
+    error CallFailed(address target, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum GovernorError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    InvalidQuorum(InvalidQuorum),
+    OnlyGovernance(OnlyGovernance),
+    InvalidProposalLength(InvalidProposalLength),
+    EmptyProposal(EmptyProposal),
+    ProposalExists(ProposalExists),
+    UnknownProposal(UnknownProposal),
+    BelowProposalThreshold(BelowProposalThreshold),
+    UnexpectedProposalState(UnexpectedProposalState),
+    AlreadyVoted(AlreadyVoted),
+    InvalidVoteType(InvalidVoteType),
+    NotReady(NotReady),
+    Unauthorized(Unauthorized),
+    VotesLookupFailed(VotesLookupFailed),
+    CallFailed(CallFailed),
+}
+
+// Protocol parameters (fee tiers, `fee_to`, ...) are changed by proposals that
+// pass a token vote and then wait out the timelock delay before execution.
+sol_storage! {
+    #[entrypoint]
+    pub struct Governor {
+        address token;
+        // can cancel any proposal that has not been executed
+        address guardian;
+        uint64 voting_delay;
+        uint64 voting_period;
+        uint64 timelock_delay;
+        uint256 quorum_numerator;
+        uint256 proposal_threshold;
+        mapping(bytes32 => Proposal) proposals;
+    }
+
+    pub struct Proposal {
+        address proposer;
+        uint64 vote_start;
+        uint64 vote_end;
+        uint64 eta;
+        uint256 against_votes;
+        uint256 for_votes;
+        uint256 abstain_votes;
+        bool canceled;
+        bool executed;
+        mapping(address => bool) has_voted;
+    }
+}
+
+impl Governor {
+    fn only_governance(&self) -> Result<(), GovernorError> {
+        if msg::sender() != contract::address() {
+            return Err(GovernorError::OnlyGovernance(OnlyGovernance { caller: msg::sender() }));
+        }
+        Ok(())
+    }
+
+    fn votes_token(&self) -> IVotes {
+        IVotes::new(self.token.get())
+    }
+
+    // Token lookups are static calls, so the read-only paths can stay `&self`.
+    fn past_votes(&self, account: Address, block_number: u64) -> Result<U256, GovernorError> {
+        let token = self.token.get();
+        self.votes_token()
+            .get_past_votes(self, account, block_number)
+            .map_err(|_| GovernorError::VotesLookupFailed(VotesLookupFailed { token }))
+    }
+
+    fn set_quorum(&mut self, quorum_numerator: U256) -> Result<(), GovernorError> {
+        if quorum_numerator > U256::from(QUORUM_DENOMINATOR) {
+            return Err(GovernorError::InvalidQuorum(InvalidQuorum { quorum_numerator }));
+        }
+        self.quorum_numerator.set(quorum_numerator);
+        Ok(())
+    }
+
+    fn hash_proposal(targets: &[Address], values: &[U256], calldatas: &[Bytes], description_hash: B256) -> B256 {
+        let calldatas: Vec<alloy_primitives::Bytes> = calldatas.iter().map(|data| data.0.clone().into()).collect();
+        crypto::keccak((targets.to_vec(), values.to_vec(), calldatas, description_hash).abi_encode())
+    }
+
+    fn require_state(&self, proposal_id: B256, allowed: &[u8]) -> Result<u8, GovernorError> {
+        let state = self.state(proposal_id)?;
+        if !allowed.contains(&state) {
+            return Err(GovernorError::UnexpectedProposalState(UnexpectedProposalState { proposal_id, state }));
+        }
+        Ok(state)
+    }
+}
+
+#[external]
+impl Governor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        token: Address,
+        guardian: Address,
+        voting_delay: u64,
+        voting_period: u64,
+        timelock_delay: u64,
+        quorum_numerator: U256,
+        proposal_threshold: U256,
+        deploy_nonce: u64,
+    ) -> Result<(), GovernorError> {
+        if self.token.get() != Address::ZERO {
+            return Err(GovernorError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(GovernorError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        self.set_quorum(quorum_numerator)?;
+        self.token.set(token);
+        self.guardian.set(guardian);
+        self.voting_delay.set(U64::from(voting_delay));
+        self.voting_period.set(U64::from(voting_period));
+        self.timelock_delay.set(U64::from(timelock_delay));
+        self.proposal_threshold.set(proposal_threshold);
+        Ok(())
+    }
+
+    pub fn propose(
+        &mut self,
+        targets: Vec<Address>,
+        values: Vec<U256>,
+        calldatas: Vec<Bytes>,
+        description: String,
+    ) -> Result<B256, GovernorError> {
+        if targets.len() != values.len() || targets.len() != calldatas.len() {
+            return Err(GovernorError::InvalidProposalLength(InvalidProposalLength {
+                targets: U256::from(targets.len()),
+                values: U256::from(values.len()),
+                calldatas: U256::from(calldatas.len()),
+            }));
+        }
+        if targets.is_empty() {
+            return Err(GovernorError::EmptyProposal(EmptyProposal {}));
+        }
+
+        let proposer = msg::sender();
+        let threshold = self.proposal_threshold.get();
+        let votes = self.past_votes(proposer, block::number() - 1)?;
+        if votes < threshold {
+            return Err(GovernorError::BelowProposalThreshold(BelowProposalThreshold { proposer, votes, threshold }));
+        }
+
+        let description_hash = crypto::keccak(description.as_bytes());
+        let proposal_id = Self::hash_proposal(&targets, &values, &calldatas, description_hash);
+        if self.proposals.get(proposal_id).vote_start.get() != U64::ZERO {
+            return Err(GovernorError::ProposalExists(ProposalExists { proposal_id }));
+        }
+
+        let vote_start = block::number() + self.voting_delay.get().saturating_to::<u64>();
+        let vote_end = vote_start + self.voting_period.get().saturating_to::<u64>();
+        let mut proposal = self.proposals.setter(proposal_id);
+        proposal.proposer.set(proposer);
+        proposal.vote_start.set(U64::from(vote_start));
+        proposal.vote_end.set(U64::from(vote_end));
+
+        evm::log(ProposalCreated {
+            proposal_id,
+            proposer,
+            targets,
+            values,
+            calldatas: calldatas.into_iter().map(|data| data.0.into()).collect(),
+            vote_start,
+            vote_end,
+            description,
+        });
+        Ok(proposal_id)
+    }
+
+    // Voting power is read at `vote_start`, so tokens bought during the vote don't count.
+    pub fn cast_vote(&mut self, proposal_id: B256, support: u8) -> Result<U256, GovernorError> {
+        self.require_state(proposal_id, &[ACTIVE])?;
+        if support > ABSTAIN {
+            return Err(GovernorError::InvalidVoteType(InvalidVoteType { support }));
+        }
+        let voter = msg::sender();
+        if self.proposals.get(proposal_id).has_voted.get(voter) {
+            return Err(GovernorError::AlreadyVoted(AlreadyVoted { voter }));
+        }
+
+        let snapshot = self.proposals.get(proposal_id).vote_start.get().saturating_to::<u64>();
+        let weight = self.past_votes(voter, snapshot)?;
+        let mut proposal = self.proposals.setter(proposal_id);
+        proposal.has_voted.insert(voter, true);
+        match support {
+            AGAINST => {
+                let votes = proposal.against_votes.get() + weight;
+                proposal.against_votes.set(votes);
+            }
+            FOR => {
+                let votes = proposal.for_votes.get() + weight;
+                proposal.for_votes.set(votes);
+            }
+            _ => {
+                let votes = proposal.abstain_votes.get() + weight;
+                proposal.abstain_votes.set(votes);
+            }
+        }
+
+        evm::log(VoteCast { voter, proposal_id, support, weight });
+        Ok(weight)
+    }
+
+    // Starts the timelock delay for a successful proposal.
+    pub fn queue(&mut self, proposal_id: B256) -> Result<u64, GovernorError> {
+        self.require_state(proposal_id, &[SUCCEEDED])?;
+        let eta = block::timestamp() + self.timelock_delay.get().saturating_to::<u64>();
+        self.proposals.setter(proposal_id).eta.set(U64::from(eta));
+        evm::log(ProposalQueued { proposal_id, eta });
+        Ok(eta)
+    }
+
+    // Only the proposal's hash is stored, so the caller passes the proposal
+    // again (as in OpenZeppelin's Governor); anything else hashes to an
+    // unknown id.
+    #[payable]
+    pub fn execute(
+        &mut self,
+        targets: Vec<Address>,
+        values: Vec<U256>,
+        calldatas: Vec<Bytes>,
+        description_hash: B256,
+    ) -> Result<B256, GovernorError> {
+        let proposal_id = Self::hash_proposal(&targets, &values, &calldatas, description_hash);
+        self.require_state(proposal_id, &[QUEUED])?;
+        let eta = self.proposals.get(proposal_id).eta.get().saturating_to::<u64>();
+        if block::timestamp() < eta {
+            return Err(GovernorError::NotReady(NotReady { proposal_id, eta }));
+        }
+        // marked before the calls so a proposal can't re-execute itself
+        self.proposals.setter(proposal_id).executed.set(true);
+
+        for ((target, value), data) in targets.into_iter().zip(values).zip(calldatas) {
+            call(Call::new_in(self).value(value), target, &data.0).map_err(|e| {
+                GovernorError::CallFailed(CallFailed {
+                    target,
+                    reason: match e {
+                        stylus_sdk::call::Error::Revert(reason) => reason.into(),
+                        _ => Vec::new().into(),
+                    },
+                })
+            })?;
+        }
+        evm::log(ProposalExecuted { proposal_id });
+        Ok(proposal_id)
+    }
+
+    // The proposer may cancel before voting starts, the guardian any time before execution.
+    pub fn cancel(&mut self, proposal_id: B256) -> Result<(), GovernorError> {
+        let state = self.require_state(proposal_id, &[PENDING, ACTIVE, SUCCEEDED, QUEUED])?;
+        let caller = msg::sender();
+        let is_proposer = caller == self.proposals.get(proposal_id).proposer.get() && state == PENDING;
+        if !is_proposer && caller != self.guardian.get() {
+            return Err(GovernorError::Unauthorized(Unauthorized { caller }));
+        }
+        self.proposals.setter(proposal_id).canceled.set(true);
+        evm::log(ProposalCanceled { proposal_id });
+        Ok(())
+    }
+
+    pub fn state(&self, proposal_id: B256) -> Result<u8, GovernorError> {
+        let proposal = self.proposals.get(proposal_id);
+        let vote_start = proposal.vote_start.get().saturating_to::<u64>();
+        if vote_start == 0 {
+            return Err(GovernorError::UnknownProposal(UnknownProposal { proposal_id }));
+        }
+        if proposal.executed.get() {
+            return Ok(EXECUTED);
+        }
+        if proposal.canceled.get() {
+            return Ok(CANCELED);
+        }
+        let now = block::number();
+        if now <= vote_start {
+            return Ok(PENDING);
+        }
+        if now <= proposal.vote_end.get().saturating_to::<u64>() {
+            return Ok(ACTIVE);
+        }
+
+        let (for_votes, against_votes, abstain_votes) =
+            (proposal.for_votes.get(), proposal.against_votes.get(), proposal.abstain_votes.get());
+        let eta = proposal.eta.get().saturating_to::<u64>();
+        if for_votes + abstain_votes < self.quorum(vote_start)? || for_votes <= against_votes {
+            return Ok(DEFEATED);
+        }
+        if eta == 0 {
+            return Ok(SUCCEEDED);
+        }
+        if block::timestamp() >= eta + GRACE_PERIOD {
+            return Ok(EXPIRED);
+        }
+        Ok(QUEUED)
+    }
+
+    // Quorum is a fraction of the LP token supply at `block_number`.
+    pub fn quorum(&self, block_number: u64) -> Result<U256, GovernorError> {
+        let token = self.token.get();
+        let supply = self
+            .votes_token()
+            .get_past_total_supply(self, block_number)
+            .map_err(|_| GovernorError::VotesLookupFailed(VotesLookupFailed { token }))?;
+        Ok(supply * self.quorum_numerator.get() / U256::from(QUORUM_DENOMINATOR))
+    }
+
+    pub fn proposal_votes(&self, proposal_id: B256) -> Result<(U256, U256, U256), GovernorError> {
+        let proposal = self.proposals.get(proposal_id);
+        Ok((proposal.against_votes.get(), proposal.for_votes.get(), proposal.abstain_votes.get()))
+    }
+
+    pub fn proposal_eta(&self, proposal_id: B256) -> Result<u64, GovernorError> {
+        Ok(self.proposals.get(proposal_id).eta.get().saturating_to::<u64>())
+    }
+
+    pub fn has_voted(&self, proposal_id: B256, account: Address) -> Result<bool, GovernorError> {
+        Ok(self.proposals.get(proposal_id).has_voted.get(account))
+    }
+
+    pub fn settings(&self) -> Result<(Address, u64, u64, u64, U256, U256), GovernorError> {
+        Ok((
+            self.token.get(),
+            self.voting_delay.get().saturating_to::<u64>(),
+            self.voting_period.get().saturating_to::<u64>(),
+            self.timelock_delay.get().saturating_to::<u64>(),
+            self.quorum_numerator.get(),
+            self.proposal_threshold.get(),
+        ))
+    }
+
+    // Parameter setters can only be reached through a passed proposal.
+    pub fn set_voting_period(&mut self, voting_period: u64) -> Result<(), GovernorError> {
+        self.only_governance()?;
+        self.voting_period.set(U64::from(voting_period));
+        Ok(())
+    }
+
+    pub fn set_timelock_delay(&mut self, timelock_delay: u64) -> Result<(), GovernorError> {
+        self.only_governance()?;
+        self.timelock_delay.set(U64::from(timelock_delay));
+        Ok(())
+    }
+
+    pub fn set_quorum_numerator(&mut self, quorum_numerator: U256) -> Result<(), GovernorError> {
+        self.only_governance()?;
+        self.set_quorum(quorum_numerator)
+    }
+
+    pub fn set_proposal_threshold(&mut self, proposal_threshold: U256) -> Result<(), GovernorError> {
+        self.only_governance()?;
+        self.proposal_threshold.set(proposal_threshold);
+        Ok(())
+    }
+
+    pub fn set_guardian(&mut self, guardian: Address) -> Result<(), GovernorError> {
+        self.only_governance()?;
+        self.guardian.set(guardian);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{calls, deploy, mock_call, CallKind, UnwrapOk, BLOCK_NUMBER, BOB, SENDER, TIMESTAMP};
+
+    const TOKEN: Address = Address::repeat_byte(0x70);
+
+    sol! {
+        interface IVotesMock {
+            function getPastVotes(address account, uint64 block_number) external view returns (uint256);
+            function getPastTotalSupply(uint64 block_number) external view returns (uint256);
+        }
+    }
+
+    // Every account holds 100 votes out of a 1_000 supply.
+    fn governor(voting_delay: u64) -> Governor {
+        let mut governor = deploy::<Governor>();
+        mock_call(TOKEN, |call| {
+            let amount = if IVotesMock::getPastVotesCall::abi_decode(&call.data, true).is_ok() { 100 } else { 1_000 };
+            Ok(U256::from(amount).to_be_bytes::<32>().to_vec())
+        });
+        governor.init(TOKEN, SENDER, voting_delay, 10, 60, U256::from(400), U256::from(50), 0).unwrap_ok();
+        governor
+    }
+
+    fn propose(governor: &mut Governor) -> Result<B256, GovernorError> {
+        governor.propose(vec![BOB], vec![U256::ZERO], vec![Bytes(vec![1, 2, 3])], "fee tier".into())
+    }
+
+    #[test]
+    fn init_checks_the_caller_and_quorum() {
+        let mut governor = deploy::<Governor>();
+        let init = |governor: &mut Governor, quorum_numerator: u64, deploy_nonce: u64| {
+            governor.init(TOKEN, BOB, 5, 10, 60, U256::from(quorum_numerator), U256::from(50), deploy_nonce)
+        };
+        assert!(matches!(init(&mut governor, 400, 1), Err(GovernorError::NotDeployer(_))));
+        assert!(matches!(init(&mut governor, QUORUM_DENOMINATOR + 1, 0), Err(GovernorError::InvalidQuorum(_))));
+        init(&mut governor, QUORUM_DENOMINATOR, 0).unwrap_ok();
+        assert!(matches!(init(&mut governor, 400, 0), Err(GovernorError::AlreadyInitialized(_))));
+        assert_eq!(governor.guardian.get(), BOB);
+        assert!(matches!(governor.set_quorum(U256::from(QUORUM_DENOMINATOR + 1)), Err(GovernorError::InvalidQuorum(_))));
+    }
+
+    #[test]
+    fn state_and_quorum_only_read() {
+        let mut governor = governor(5);
+        let proposal_id = propose(&mut governor).unwrap_ok();
+        let governor = &governor;
+        assert_eq!(governor.state(proposal_id).unwrap_ok(), PENDING);
+        assert_eq!(governor.quorum(BLOCK_NUMBER - 1).unwrap_ok(), U256::from(40));
+        assert!(calls().iter().all(|call| call.kind == CallKind::Static && call.to == TOKEN));
+        assert!(matches!(governor.state(B256::ZERO), Err(GovernorError::UnknownProposal(_))));
+    }
+
+    #[test]
+    fn propose_checks_threshold_and_shape() {
+        let mut governor = governor(5);
+        let proposal_id = propose(&mut governor).unwrap_ok();
+        assert!(matches!(propose(&mut governor), Err(GovernorError::ProposalExists(_))));
+        assert!(matches!(governor.propose(vec![BOB], vec![], vec![], "bad".into()), Err(GovernorError::InvalidProposalLength(_))));
+        assert!(matches!(governor.cast_vote(proposal_id, FOR), Err(GovernorError::UnexpectedProposalState(_))));
+
+        governor.cancel(proposal_id).unwrap_ok();
+        assert_eq!(governor.state(proposal_id).unwrap_ok(), CANCELED);
+
+        let mut governor = self::governor(5);
+        governor.proposal_threshold.set(U256::from(101));
+        assert!(matches!(propose(&mut governor), Err(GovernorError::BelowProposalThreshold(_))));
+        assert_eq!(governor.settings().unwrap_ok().0, TOKEN);
+        assert!(matches!(governor.set_guardian(SENDER), Err(GovernorError::OnlyGovernance(_))));
+    }
+
+    #[test]
+    fn execute_takes_the_proposal_back() {
+        let mut governor = governor(5);
+        let proposal_id = propose(&mut governor).unwrap_ok();
+        // passed, queued and past its delay
+        let mut proposal = governor.proposals.setter(proposal_id);
+        proposal.vote_start.set(U64::from(BLOCK_NUMBER - 20));
+        proposal.vote_end.set(U64::from(BLOCK_NUMBER - 10));
+        proposal.for_votes.set(U256::from(500));
+        proposal.eta.set(U64::from(TIMESTAMP));
+        assert_eq!(governor.state(proposal_id).unwrap_ok(), QUEUED);
+        mock_call(BOB, |_| Ok(Vec::new()));
+
+        let description_hash = crypto::keccak("fee tier".as_bytes());
+        let execute =
+            |governor: &mut Governor, data: Vec<u8>| governor.execute(vec![BOB], vec![U256::ZERO], vec![Bytes(data)], description_hash);
+        assert!(matches!(execute(&mut governor, vec![1, 2]), Err(GovernorError::UnknownProposal(_))));
+        assert_eq!(execute(&mut governor, vec![1, 2, 3]).unwrap_ok(), proposal_id);
+        assert_eq!(calls().last().unwrap().to, BOB);
+        assert_eq!(calls().last().unwrap().data, vec![1, 2, 3]);
+        assert_eq!(governor.state(proposal_id).unwrap_ok(), EXECUTED);
+        assert!(matches!(execute(&mut governor, vec![1, 2, 3]), Err(GovernorError::UnexpectedProposalState(_))));
+    }
+}
@@ -0,0 +1,147 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod ownable;
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::Address,
+    call::{self, delegate_call},
+    evm,
+    prelude::*,
+};
+
+sol! {
+    event Upgraded(address indexed implementation);
+
+    error ImplementationNotSet();
+    error DelegateCallFailed(address implementation, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum ProxyError {
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+    ImplementationNotSet(ImplementationNotSet),
+    DelegateCallFailed(DelegateCallFailed),
+}
+
+impl From<OwnableError> for ProxyError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => ProxyError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => ProxyError::InvalidOwner(e),
+        }
+    }
+}
+
+// The Proxy from 4_contract_call.rs and 5_evm_affordances.rs, with its owner
+// kept in the shared two-step Ownable.
+sol_storage! {
+    #[entrypoint]
+    pub struct Proxy {
+        #[borrow]
+        Ownable ownable;
+        address implementation;
+    }
+}
+
+impl Proxy {
+    pub fn get_implementation(&self) -> Result<Address, ProxyError> {
+        let implementation = self.implementation.get();
+        if implementation == Address::ZERO {
+            return Err(ProxyError::ImplementationNotSet(ImplementationNotSet {}));
+        }
+        Ok(implementation)
+    }
+}
+
+#[external]
+#[inherit(Ownable)]
+impl Proxy {
+    pub fn init(&mut self, implementation: Address, deploy_nonce: u64) -> Result<(), ProxyError> {
+        self.ownable.initialize(deploy_nonce)?;
+        self.implementation.set(implementation);
+        evm::log(Upgraded { implementation });
+        Ok(())
+    }
+
+    pub fn implementation(&self) -> Result<Address, ProxyError> {
+        Ok(self.implementation.get())
+    }
+
+    pub fn set_implementation(&mut self, implementation: Address) -> Result<(), ProxyError> {
+        self.ownable.only_owner()?;
+        self.implementation.set(implementation);
+        evm::log(Upgraded { implementation });
+        Ok(())
+    }
+
+    #[payable]
+    pub fn relay_to_implementation(&mut self, data: Vec<u8>) -> Result<Vec<u8>, ProxyError> {
+        let implementation_address = self.get_implementation()?;
+        let res;
+        unsafe { res = delegate_call(self, implementation_address, &data[..]) };
+
+        match res {
+            Ok(res) => Ok(res),
+            Err(e) => Err(ProxyError::DelegateCallFailed(DelegateCallFailed {
+                implementation: implementation_address,
+                reason: match e {
+                    call::Error::Revert(reason) => reason.into(),
+                    _ => Vec::new().into(),
+                },
+            })),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use test_vm::{calls, deploy, events, mock_call, CallKind, UnwrapOk, BOB, CAROL};
+
+    #[test]
+    fn relays_through_delegate_call() {
+        let mut proxy = deploy::<Proxy>();
+        assert!(matches!(proxy.relay_to_implementation(vec![1]), Err(ProxyError::ImplementationNotSet(_))));
+        proxy.init(BOB, 0).unwrap_ok();
+        mock_call(BOB, |call| Ok(call.data.iter().rev().copied().collect()));
+        assert_eq!(proxy.relay_to_implementation(vec![1, 2, 3]).unwrap_ok(), vec![3, 2, 1]);
+        assert_eq!(calls()[0].kind, CallKind::Delegate);
+    }
+
+    #[test]
+    fn forwards_revert_reason() {
+        let mut proxy = deploy::<Proxy>();
+        proxy.init(BOB, 0).unwrap_ok();
+        mock_call(BOB, |_| Err(b"nope".to_vec()));
+        match proxy.relay_to_implementation(Vec::new()) {
+            Err(ProxyError::DelegateCallFailed(e)) => assert_eq!(e.reason.to_vec(), b"nope"),
+            _ => panic!("expected DelegateCallFailed"),
+        }
+    }
+
+    #[test]
+    fn upgrade_is_owner_only() {
+        let mut proxy = deploy::<Proxy>();
+        proxy.init(BOB, 0).unwrap_ok();
+        proxy.set_implementation(CAROL).unwrap_ok();
+        assert_eq!(proxy.implementation().unwrap_ok(), CAROL);
+        assert_eq!(events::<Upgraded>().len(), 2);
+        proxy.ownable.renounce_ownership().unwrap_ok();
+        assert!(matches!(proxy.set_implementation(BOB), Err(ProxyError::Unauthorized(_))));
+    }
+}
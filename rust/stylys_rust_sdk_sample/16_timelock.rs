@@ -0,0 +1,501 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+use crate::deployer::is_deployer;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolValue};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{self, Address, B256, U256},
+    block,
+    call::{call, Call},
+    contract, crypto, evm, msg,
+    prelude::*,
+};
+
+// Timestamp stored for executed operations (any real eta is far above it)
+const DONE_TIMESTAMP: u64 = 1;
+
+fn admin_role() -> B256 {
+    crypto::keccak(b"TIMELOCK_ADMIN_ROLE")
+}
+
+fn proposer_role() -> B256 {
+    crypto::keccak(b"PROPOSER_ROLE")
+}
+
+fn executor_role() -> B256 {
+    crypto::keccak(b"EXECUTOR_ROLE")
+}
+
+fn canceller_role() -> B256 {
+    crypto::keccak(b"CANCELLER_ROLE")
+}
+
+sol! {
+    event CallScheduled(bytes32 indexed id, uint256 indexed index, address target, uint256 value, bytes data, bytes32 predecessor, uint256 delay);
+    event CallExecuted(bytes32 indexed id, uint256 indexed index, address target, uint256 value, bytes data);
+    event CallSalt(bytes32 indexed id, bytes32 salt);
+    event Cancelled(bytes32 indexed id);
+    event MinDelayChange(uint256 old_duration, uint256 new_duration);
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error MissingRole(address account, bytes32 role);
+    error InvalidOperationLength(uint256 targets, uint256 values, uint256 payloads);
+    error InsufficientDelay(uint256 delay, uint256 min_delay);
+    error DelayOverflow(uint256 delay);
+    error UnexpectedOperationState(bytes32 id, uint8 expected);
+    error UnexecutedPredecessor(bytes32 predecessor);
+    error UnauthorizedCaller(address caller);
+    error CallFailed(address target, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum TimelockError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    MissingRole(MissingRole),
+    InvalidOperationLength(InvalidOperationLength),
+    InsufficientDelay(InsufficientDelay),
+    DelayOverflow(DelayOverflow),
+    UnexpectedOperationState(UnexpectedOperationState),
+    UnexecutedPredecessor(UnexecutedPredecessor),
+    UnauthorizedCaller(UnauthorizedCaller),
+    CallFailed(CallFailed),
+}
+
+// Operation states used in UnexpectedOperationState
+const UNSET: u8 = 0;
+const WAITING: u8 = 1;
+const READY: u8 = 2;
+const DONE: u8 = 3;
+
+// Admin actions (Proxy `set_implementation`, Vault `setAsset`, ...) go through
+// here once the timelock owns those contracts, so users get `min_delay` to react.
+sol_storage! {
+    #[entrypoint]
+    pub struct TimelockController {
+        bool initialized;
+        uint256 min_delay;
+        // operation id => ready timestamp (0 = unset, 1 = done)
+        mapping(bytes32 => uint256) timestamps;
+        mapping(bytes32 => mapping(address => bool)) roles;
+    }
+}
+
+impl TimelockController {
+    fn has_role_impl(&self, role: B256, account: Address) -> bool {
+        self.roles.get(role).get(account)
+    }
+
+    fn check_role(&self, role: B256) -> Result<(), TimelockError> {
+        let account = msg::sender();
+        if !self.has_role_impl(role, account) {
+            return Err(TimelockError::MissingRole(MissingRole { account, role }));
+        }
+        Ok(())
+    }
+
+    // A role granted to the zero address is open to everyone.
+    fn check_open_role(&self, role: B256) -> Result<(), TimelockError> {
+        if self.has_role_impl(role, Address::ZERO) {
+            return Ok(());
+        }
+        self.check_role(role)
+    }
+
+    fn grant(&mut self, role: B256, account: Address) {
+        if !self.has_role_impl(role, account) {
+            self.roles.setter(role).insert(account, true);
+            evm::log(RoleGranted { role, account, sender: msg::sender() });
+        }
+    }
+
+    fn revoke(&mut self, role: B256, account: Address) {
+        if self.has_role_impl(role, account) {
+            self.roles.setter(role).insert(account, false);
+            evm::log(RoleRevoked { role, account, sender: msg::sender() });
+        }
+    }
+
+    fn state(&self, id: B256) -> u8 {
+        let timestamp = self.timestamps.get(id);
+        if timestamp == U256::ZERO {
+            UNSET
+        } else if timestamp == U256::from(DONE_TIMESTAMP) {
+            DONE
+        } else if timestamp > U256::from(block::timestamp()) {
+            WAITING
+        } else {
+            READY
+        }
+    }
+
+    fn expect_state(&self, id: B256, expected: u8) -> Result<(), TimelockError> {
+        if self.state(id) != expected {
+            return Err(TimelockError::UnexpectedOperationState(UnexpectedOperationState { id, expected }));
+        }
+        Ok(())
+    }
+
+    fn schedule_id(&mut self, id: B256, delay: U256) -> Result<(), TimelockError> {
+        self.expect_state(id, UNSET)?;
+        let min_delay = self.min_delay.get();
+        if delay < min_delay {
+            return Err(TimelockError::InsufficientDelay(InsufficientDelay { delay, min_delay }));
+        }
+        // a wrapped sum could land on 0 or 1 (unset / done) or in the past
+        let ready_at = U256::from(block::timestamp()).checked_add(delay).ok_or(TimelockError::DelayOverflow(DelayOverflow { delay }))?;
+        self.timestamps.insert(id, ready_at);
+        Ok(())
+    }
+
+    fn before_call(&self, id: B256, predecessor: B256) -> Result<(), TimelockError> {
+        self.expect_state(id, READY)?;
+        if predecessor != B256::ZERO && self.state(predecessor) != DONE {
+            return Err(TimelockError::UnexecutedPredecessor(UnexecutedPredecessor { predecessor }));
+        }
+        Ok(())
+    }
+
+    fn after_call(&mut self, id: B256) -> Result<(), TimelockError> {
+        self.expect_state(id, READY)?;
+        self.timestamps.insert(id, U256::from(DONE_TIMESTAMP));
+        Ok(())
+    }
+
+    fn execute_call(&mut self, target: Address, value: U256, data: &[u8]) -> Result<(), TimelockError> {
+        call(Call::new_in(self).value(value), target, data).map_err(|e| {
+            TimelockError::CallFailed(CallFailed {
+                target,
+                reason: match e {
+                    stylus_sdk::call::Error::Revert(reason) => reason.into(),
+                    _ => Vec::new().into(),
+                },
+            })
+        })?;
+        Ok(())
+    }
+
+    fn check_lengths(targets: &[Address], values: &[U256], payloads: &[Bytes]) -> Result<(), TimelockError> {
+        if targets.len() != values.len() || targets.len() != payloads.len() {
+            return Err(TimelockError::InvalidOperationLength(InvalidOperationLength {
+                targets: U256::from(targets.len()),
+                values: U256::from(values.len()),
+                payloads: U256::from(payloads.len()),
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[external]
+impl TimelockController {
+    // The timelock administers itself; `admin` is an optional bootstrap admin
+    // that should renounce once roles are set up.
+    pub fn init(
+        &mut self,
+        min_delay: U256,
+        proposers: Vec<Address>,
+        executors: Vec<Address>,
+        admin: Address,
+        deploy_nonce: u64,
+    ) -> Result<(), TimelockError> {
+        if self.initialized.get() {
+            return Err(TimelockError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(TimelockError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        self.initialized.set(true);
+        self.grant(admin_role(), contract::address());
+        if admin != Address::ZERO {
+            self.grant(admin_role(), admin);
+        }
+        for proposer in proposers {
+            self.grant(proposer_role(), proposer);
+            self.grant(canceller_role(), proposer);
+        }
+        for executor in executors {
+            self.grant(executor_role(), executor);
+        }
+        self.min_delay.set(min_delay);
+        evm::log(MinDelayChange { old_duration: U256::ZERO, new_duration: min_delay });
+        Ok(())
+    }
+
+    pub fn hash_operation(&self, target: Address, value: U256, data: Bytes, predecessor: B256, salt: B256) -> Result<B256, TimelockError> {
+        let data = alloy_primitives::Bytes::from(data.0);
+        Ok(crypto::keccak((target, value, data, predecessor, salt).abi_encode()))
+    }
+
+    pub fn hash_operation_batch(
+        &self,
+        targets: Vec<Address>,
+        values: Vec<U256>,
+        payloads: Vec<Bytes>,
+        predecessor: B256,
+        salt: B256,
+    ) -> Result<B256, TimelockError> {
+        let payloads: Vec<alloy_primitives::Bytes> = payloads.into_iter().map(|data| data.0.into()).collect();
+        Ok(crypto::keccak((targets, values, payloads, predecessor, salt).abi_encode()))
+    }
+
+    pub fn schedule(
+        &mut self,
+        target: Address,
+        value: U256,
+        data: Bytes,
+        predecessor: B256,
+        salt: B256,
+        delay: U256,
+    ) -> Result<B256, TimelockError> {
+        self.check_role(proposer_role())?;
+        let id = self.hash_operation(target, value, data.clone(), predecessor, salt)?;
+        self.schedule_id(id, delay)?;
+        evm::log(CallScheduled { id, index: U256::ZERO, target, value, data: data.0.into(), predecessor, delay });
+        if salt != B256::ZERO {
+            evm::log(CallSalt { id, salt });
+        }
+        Ok(id)
+    }
+
+    pub fn schedule_batch(
+        &mut self,
+        targets: Vec<Address>,
+        values: Vec<U256>,
+        payloads: Vec<Bytes>,
+        predecessor: B256,
+        salt: B256,
+        delay: U256,
+    ) -> Result<B256, TimelockError> {
+        self.check_role(proposer_role())?;
+        Self::check_lengths(&targets, &values, &payloads)?;
+        let id = self.hash_operation_batch(targets.clone(), values.clone(), payloads.clone(), predecessor, salt)?;
+        self.schedule_id(id, delay)?;
+        for (i, data) in payloads.into_iter().enumerate() {
+            evm::log(CallScheduled {
+                id,
+                index: U256::from(i),
+                target: targets[i],
+                value: values[i],
+                data: data.0.into(),
+                predecessor,
+                delay,
+            });
+        }
+        if salt != B256::ZERO {
+            evm::log(CallSalt { id, salt });
+        }
+        Ok(id)
+    }
+
+    #[payable]
+    pub fn execute(&mut self, target: Address, value: U256, payload: Bytes, predecessor: B256, salt: B256) -> Result<(), TimelockError> {
+        self.check_open_role(executor_role())?;
+        let id = self.hash_operation(target, value, payload.clone(), predecessor, salt)?;
+        self.before_call(id, predecessor)?;
+        self.execute_call(target, value, &payload.0)?;
+        evm::log(CallExecuted { id, index: U256::ZERO, target, value, data: payload.0.into() });
+        self.after_call(id)
+    }
+
+    #[payable]
+    pub fn execute_batch(
+        &mut self,
+        targets: Vec<Address>,
+        values: Vec<U256>,
+        payloads: Vec<Bytes>,
+        predecessor: B256,
+        salt: B256,
+    ) -> Result<(), TimelockError> {
+        self.check_open_role(executor_role())?;
+        Self::check_lengths(&targets, &values, &payloads)?;
+        let id = self.hash_operation_batch(targets.clone(), values.clone(), payloads.clone(), predecessor, salt)?;
+        self.before_call(id, predecessor)?;
+        for (i, payload) in payloads.into_iter().enumerate() {
+            self.execute_call(targets[i], values[i], &payload.0)?;
+            evm::log(CallExecuted { id, index: U256::from(i), target: targets[i], value: values[i], data: payload.0.into() });
+        }
+        self.after_call(id)
+    }
+
+    pub fn cancel(&mut self, id: B256) -> Result<(), TimelockError> {
+        self.check_role(canceller_role())?;
+        let state = self.state(id);
+        if state != WAITING && state != READY {
+            return Err(TimelockError::UnexpectedOperationState(UnexpectedOperationState { id, expected: WAITING }));
+        }
+        self.timestamps.delete(id);
+        evm::log(Cancelled { id });
+        Ok(())
+    }
+
+    // Only reachable through a scheduled operation targeting the timelock itself.
+    pub fn update_delay(&mut self, new_delay: U256) -> Result<(), TimelockError> {
+        let caller = msg::sender();
+        if caller != contract::address() {
+            return Err(TimelockError::UnauthorizedCaller(UnauthorizedCaller { caller }));
+        }
+        evm::log(MinDelayChange { old_duration: self.min_delay.get(), new_duration: new_delay });
+        self.min_delay.set(new_delay);
+        Ok(())
+    }
+
+    pub fn get_min_delay(&self) -> Result<U256, TimelockError> {
+        Ok(self.min_delay.get())
+    }
+
+    pub fn get_timestamp(&self, id: B256) -> Result<U256, TimelockError> {
+        Ok(self.timestamps.get(id))
+    }
+
+    pub fn get_operation_state(&self, id: B256) -> Result<u8, TimelockError> {
+        Ok(self.state(id))
+    }
+
+    pub fn is_operation(&self, id: B256) -> Result<bool, TimelockError> {
+        Ok(self.state(id) != UNSET)
+    }
+
+    pub fn is_operation_pending(&self, id: B256) -> Result<bool, TimelockError> {
+        let state = self.state(id);
+        Ok(state == WAITING || state == READY)
+    }
+
+    pub fn is_operation_ready(&self, id: B256) -> Result<bool, TimelockError> {
+        Ok(self.state(id) == READY)
+    }
+
+    pub fn is_operation_done(&self, id: B256) -> Result<bool, TimelockError> {
+        Ok(self.state(id) == DONE)
+    }
+
+    pub fn has_role(&self, role: B256, account: Address) -> Result<bool, TimelockError> {
+        Ok(self.has_role_impl(role, account))
+    }
+
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), TimelockError> {
+        self.check_role(admin_role())?;
+        self.grant(role, account);
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), TimelockError> {
+        self.check_role(admin_role())?;
+        self.revoke(role, account);
+        Ok(())
+    }
+
+    pub fn renounce_role(&mut self, role: B256) -> Result<(), TimelockError> {
+        self.revoke(role, msg::sender());
+        Ok(())
+    }
+
+    #[selector(name = "PROPOSER_ROLE")]
+    pub fn proposer_role(&self) -> Result<B256, TimelockError> {
+        Ok(proposer_role())
+    }
+
+    #[selector(name = "EXECUTOR_ROLE")]
+    pub fn executor_role(&self) -> Result<B256, TimelockError> {
+        Ok(executor_role())
+    }
+
+    #[selector(name = "CANCELLER_ROLE")]
+    pub fn canceller_role(&self) -> Result<B256, TimelockError> {
+        Ok(canceller_role())
+    }
+
+    #[selector(name = "TIMELOCK_ADMIN_ROLE")]
+    pub fn admin_role(&self) -> Result<B256, TimelockError> {
+        Ok(admin_role())
+    }
+
+    // Accepts ETH so scheduled calls can forward value.
+    #[payable]
+    pub fn receive_eth(&mut self) -> Result<(), TimelockError> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use test_vm::{calls, deploy, UnwrapOk, BOB, SENDER, TIMESTAMP};
+
+    fn timelock(min_delay: u64) -> TimelockController {
+        let mut timelock = deploy::<TimelockController>();
+        timelock.init(U256::from(min_delay), vec![SENDER], vec![Address::ZERO], Address::ZERO, 0).unwrap_ok();
+        timelock
+    }
+
+    fn payload() -> Bytes {
+        Bytes(vec![0xde, 0xad])
+    }
+
+    #[test]
+    fn init_checks_the_caller() {
+        let mut timelock = deploy::<TimelockController>();
+        assert!(matches!(timelock.init(U256::from(60), vec![BOB], vec![BOB], BOB, 1), Err(TimelockError::NotDeployer(_))));
+        let mut timelock = self::timelock(60);
+        assert!(matches!(timelock.init(U256::from(60), vec![BOB], vec![BOB], BOB, 0), Err(TimelockError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn schedule_rejects_overflowing_delays() {
+        let mut timelock = timelock(60);
+        assert!(matches!(
+            timelock.schedule(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO, U256::MAX),
+            Err(TimelockError::DelayOverflow(_))
+        ));
+        let id = timelock.hash_operation(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO).unwrap_ok();
+        assert!(!timelock.is_operation(id).unwrap_ok());
+        assert!(matches!(
+            timelock.schedule(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO, U256::from(59)),
+            Err(TimelockError::InsufficientDelay(_))
+        ));
+    }
+
+    #[test]
+    fn waits_out_the_delay_and_cancels() {
+        let mut timelock = timelock(60);
+        let id = timelock.schedule(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO, U256::from(60)).unwrap_ok();
+        assert_eq!(timelock.get_timestamp(id).unwrap_ok(), U256::from(TIMESTAMP + 60));
+        assert_eq!(timelock.get_operation_state(id).unwrap_ok(), WAITING);
+        assert!(matches!(
+            timelock.execute(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO),
+            Err(TimelockError::UnexpectedOperationState(_))
+        ));
+        timelock.cancel(id).unwrap_ok();
+        assert!(!timelock.is_operation(id).unwrap_ok());
+    }
+
+    #[test]
+    fn executes_ready_operations_once() {
+        let mut timelock = timelock(0);
+        let id = timelock.schedule(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO, U256::ZERO).unwrap_ok();
+        assert!(timelock.is_operation_ready(id).unwrap_ok());
+        timelock.execute(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO).unwrap_ok();
+        assert_eq!(calls().last().unwrap().data, payload().0);
+        assert!(timelock.is_operation_done(id).unwrap_ok());
+        assert!(matches!(
+            timelock.execute(BOB, U256::ZERO, payload(), B256::ZERO, B256::ZERO),
+            Err(TimelockError::UnexpectedOperationState(_))
+        ));
+    }
+}
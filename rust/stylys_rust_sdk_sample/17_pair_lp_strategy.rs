@@ -0,0 +1,586 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+use crate::deployer::is_deployer;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use amm_math::{
+    constant_product::{get_amount_out_with_fee, optimal_swap_amount_with_fee, sqrt_k, BPS},
+    fixed_point::WAD,
+};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    block,
+    call::{Call, RawCall},
+    contract, msg,
+    prelude::*,
+};
+
+// Shortest window the TWAP is averaged over
+const TWAP_PERIOD: u64 = 30 * 60;
+
+sol_interface! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+    }
+}
+
+sol_interface! {
+    interface IPair {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function getReserves() external view returns (uint256, uint256, uint32);
+        function currentFee() external view returns (uint64);
+        function price0CumulativeLast() external view returns (uint256);
+        function price1CumulativeLast() external view returns (uint256);
+        function totalSupply() external view returns (uint256);
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function mint(address to) external returns (uint256);
+        function burn(address to) external returns (uint256, uint256);
+    }
+}
+
+// sol_interface! encodes a call with dynamic arguments as one tuple, offset and
+// all, so calls that take `bytes` are encoded here and sent with RawCall.
+sol! {
+    interface IPairSwap {
+        function swap(uint256 amount0_out, uint256 amount1_out, address to, bytes data) external;
+    }
+}
+
+sol! {
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error OnlyVault(address caller);
+    error AssetNotInPair(address asset, address pair);
+    error InvalidSlippage(uint256 max_slippage_bps);
+    error ExternalCallFailed(address target);
+    error PriceNotReady(uint64 ready_at);
+    error SlippageExceeded(uint256 amount_out, uint256 min_out);
+}
+
+#[derive(SolidityError)]
+pub enum StrategyError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    OnlyVault(OnlyVault),
+    AssetNotInPair(AssetNotInPair),
+    InvalidSlippage(InvalidSlippage),
+    ExternalCallFailed(ExternalCallFailed),
+    PriceNotReady(PriceNotReady),
+    SlippageExceeded(SlippageExceeded),
+}
+
+// Reference IStrategy for the Vault: LPs the vault's asset into one of our
+// x * y = k pairs. Swap fees stay in the pair's reserves, so they compound into
+// the value of the LP tokens; `harvest` reinvests any loose tokens.
+//
+// Spot reserves can be pushed around within a block, so the LP tokens are valued
+// and every swap is bounded by the pair's TWAP instead (see update_price).
+sol_storage! {
+    #[entrypoint]
+    pub struct PairLpStrategy {
+        address vault;
+        address asset;
+        address pair;
+        bool asset_is_token0;
+        // a swap may return this much less than the TWAP says, pair fee included
+        uint256 max_slippage_bps;
+        // the pair's cumulative price of the other token in the asset at the last update
+        uint256 price_cumulative_last;
+        uint64 price_timestamp_last;
+        // asset per other token, WAD; zero until a full period has been observed
+        uint256 twap;
+    }
+}
+
+impl PairLpStrategy {
+    fn only_vault(&self) -> Result<(), StrategyError> {
+        let caller = msg::sender();
+        if caller != self.vault.get() {
+            return Err(StrategyError::OnlyVault(OnlyVault { caller }));
+        }
+        Ok(())
+    }
+
+    fn failed(target: Address) -> StrategyError {
+        StrategyError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    fn other_token(&self) -> Result<Address, StrategyError> {
+        let pair = self.pair.get();
+        let token = if self.asset_is_token0.get() { IPair::new(pair).token_1(self) } else { IPair::new(pair).token_0(self) };
+        token.map_err(|_| Self::failed(pair))
+    }
+
+    fn balance_of(&self, token: Address) -> Result<U256, StrategyError> {
+        IERC20::new(token).balance_of(self, contract::address()).map_err(|_| Self::failed(token))
+    }
+
+    // (asset reserve, other reserve, pair timestamp)
+    fn reserves(&self) -> Result<(U256, U256, u32), StrategyError> {
+        let pair = self.pair.get();
+        let (r0, r1, timestamp) = IPair::new(pair).get_reserves(self).map_err(|_| Self::failed(pair))?;
+        Ok(if self.asset_is_token0.get() { (r0, r1, timestamp) } else { (r1, r0, timestamp) })
+    }
+
+    // The pair's dynamic fee in bps for a swap in this block.
+    fn fee(&self) -> Result<u64, StrategyError> {
+        let pair = self.pair.get();
+        IPair::new(pair).current_fee(self).map_err(|_| Self::failed(pair))
+    }
+
+    // (our LP balance, LP supply)
+    fn lp_position(&self) -> Result<(U256, U256), StrategyError> {
+        let pair = self.pair.get();
+        let balance = IPair::new(pair).balance_of(self, contract::address()).map_err(|_| Self::failed(pair))?;
+        let supply = IPair::new(pair).total_supply(self).map_err(|_| Self::failed(pair))?;
+        Ok((balance, supply))
+    }
+
+    // The pair's cumulative price of the other token at `now`, counting the
+    // seconds since its last update at the current reserves, like V2's oracle library.
+    fn price_cumulative(&self, now: u64) -> Result<U256, StrategyError> {
+        let pair = self.pair.get();
+        let cumulative = if self.asset_is_token0.get() {
+            IPair::new(pair).price_1_cumulative_last(self)
+        } else {
+            IPair::new(pair).price_0_cumulative_last(self)
+        }
+        .map_err(|_| Self::failed(pair))?;
+        let (r_asset, r_other, timestamp) = self.reserves()?;
+        let elapsed = (now as u32).wrapping_sub(timestamp);
+        if elapsed == 0 || r_asset == U256::ZERO || r_other == U256::ZERO {
+            return Ok(cumulative);
+        }
+        Ok(cumulative.wrapping_add(r_asset * WAD / r_other * U256::from(elapsed)))
+    }
+
+    // Averages the pair's price over the time since the last update once at least
+    // TWAP_PERIOD has passed, and returns the current TWAP.
+    fn update_price_at(&mut self, now: u64) -> Result<U256, StrategyError> {
+        let last = self.price_timestamp_last.get().saturating_to::<u64>();
+        let elapsed = now.saturating_sub(last);
+        if elapsed >= TWAP_PERIOD {
+            let cumulative = self.price_cumulative(now)?;
+            let twap = cumulative.wrapping_sub(self.price_cumulative_last.get()) / U256::from(elapsed);
+            self.twap.set(twap);
+            self.price_cumulative_last.set(cumulative);
+            self.price_timestamp_last.set(U64::from(now));
+        }
+        self.price()
+    }
+
+    fn price(&self) -> Result<U256, StrategyError> {
+        let twap = self.twap.get();
+        if twap == U256::ZERO {
+            let ready_at = self.price_timestamp_last.get().saturating_to::<u64>() + TWAP_PERIOD;
+            return Err(StrategyError::PriceNotReady(PriceNotReady { ready_at }));
+        }
+        Ok(twap)
+    }
+
+    // What `lp` LP tokens are worth in the asset at `price`: the reserves are moved
+    // along x * y = k to where the pair's price would equal `price`, which gives
+    // 2 * sqrt(k * price). Swapping against the pair moves the spot price but not k.
+    fn lp_value(&self, lp: U256, lp_supply: U256, price: U256) -> Result<U256, StrategyError> {
+        if lp == U256::ZERO || lp_supply == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let (r_asset, r_other, _) = self.reserves()?;
+        Ok(U256::from(2) * sqrt_k(r_asset, r_other * price / WAD) * lp / lp_supply)
+    }
+
+    fn transfer(&mut self, token: Address, to: Address, amount: U256) -> Result<(), StrategyError> {
+        let ok = IERC20::new(token).transfer(Call::new_in(self), to, amount).map_err(|_| Self::failed(token))?;
+        if !ok {
+            return Err(Self::failed(token));
+        }
+        Ok(())
+    }
+
+    // Sends `amount_in` of `token_in` to the pair and takes the output back,
+    // reverting if it's more than max_slippage_bps short of the TWAP quote.
+    fn swap(&mut self, token_in: Address, amount_in: U256, price: U256) -> Result<(), StrategyError> {
+        let selling_asset = token_in == self.asset.get();
+        let (r_asset, r_other, _) = self.reserves()?;
+        let fee_bps = self.fee()?;
+        let (amount_out, fair_out) = if selling_asset {
+            (get_amount_out_with_fee(amount_in, r_asset, r_other, fee_bps), amount_in * WAD / price)
+        } else {
+            (get_amount_out_with_fee(amount_in, r_other, r_asset, fee_bps), amount_in * price / WAD)
+        };
+        let min_out = fair_out * (U256::from(BPS) - self.max_slippage_bps.get()) / U256::from(BPS);
+        if amount_out < min_out {
+            return Err(StrategyError::SlippageExceeded(SlippageExceeded { amount_out, min_out }));
+        }
+        if amount_out == U256::ZERO {
+            return Ok(());
+        }
+        let pair = self.pair.get();
+        self.transfer(token_in, pair, amount_in)?;
+        let in_is_token0 = selling_asset == self.asset_is_token0.get();
+        let (amount0_out, amount1_out) = if in_is_token0 { (U256::ZERO, amount_out) } else { (amount_out, U256::ZERO) };
+        let call = IPairSwap::swapCall { amount0_out, amount1_out, to: contract::address(), data: Vec::new().into() };
+        RawCall::new().call(pair, &call.abi_encode()).map_err(|_| Self::failed(pair))?;
+        Ok(())
+    }
+
+    // Zaps every loose asset token into LP.
+    fn invest(&mut self, price: U256) -> Result<(), StrategyError> {
+        let (asset, other, pair) = (self.asset.get(), self.other_token()?, self.pair.get());
+        let amount = self.balance_of(asset)?;
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let (r_asset, _, _) = self.reserves()?;
+        let swap_amount = optimal_swap_amount_with_fee(amount, r_asset, self.fee()?);
+        self.swap(asset, swap_amount, price)?;
+
+        let (asset_amount, other_amount) = (self.balance_of(asset)?, self.balance_of(other)?);
+        if asset_amount == U256::ZERO || other_amount == U256::ZERO {
+            return Ok(());
+        }
+        self.transfer(asset, pair, asset_amount)?;
+        self.transfer(other, pair, other_amount)?;
+        IPair::new(pair).mint(Call::new_in(self), contract::address()).map_err(|_| Self::failed(pair))?;
+        Ok(())
+    }
+
+    // Swaps any loose other-side token back into the asset.
+    fn sell_other(&mut self, price: U256) -> Result<(), StrategyError> {
+        let other = self.other_token()?;
+        let amount = self.balance_of(other)?;
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        self.swap(other, amount, price)
+    }
+
+    // Burns enough LP to free about `amount` of asset (valued at the TWAP).
+    fn divest(&mut self, amount: U256, price: U256) -> Result<(), StrategyError> {
+        let pair = self.pair.get();
+        let (lp_balance, lp_supply) = self.lp_position()?;
+        let value = self.lp_value(lp_balance, lp_supply, price)?;
+        if value == U256::ZERO {
+            return Ok(());
+        }
+        let lp = ((amount * lp_balance + value - U256::from(1)) / value).min(lp_balance);
+
+        let ok = IPair::new(pair).transfer(Call::new_in(self), pair, lp).map_err(|_| Self::failed(pair))?;
+        if !ok {
+            return Err(Self::failed(pair));
+        }
+        IPair::new(pair).burn(Call::new_in(self), contract::address()).map_err(|_| Self::failed(pair))?;
+        self.sell_other(price)
+    }
+
+    fn withdraw_at(&mut self, amount: U256, now: u64) -> Result<U256, StrategyError> {
+        self.only_vault()?;
+        let price = self.update_price_at(now)?;
+        let asset = self.asset.get();
+        let before = self.total_assets()?;
+        let idle = self.balance_of(asset)?;
+        if idle < amount {
+            self.divest(amount - idle, price)?;
+        }
+        let sent = amount.min(self.balance_of(asset)?);
+        self.transfer(asset, self.vault.get(), sent)?;
+
+        let after = self.total_assets()?;
+        Ok((before - after.min(before)).saturating_sub(sent))
+    }
+}
+
+#[external]
+impl PairLpStrategy {
+    // Starts the TWAP; the strategy can't take deposits until TWAP_PERIOD has passed.
+    pub fn init(
+        &mut self,
+        vault: Address,
+        asset: Address,
+        pair: Address,
+        max_slippage_bps: U256,
+        deploy_nonce: u64,
+    ) -> Result<(), StrategyError> {
+        if self.vault.get() != Address::ZERO {
+            return Err(StrategyError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(StrategyError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        if max_slippage_bps >= U256::from(BPS) {
+            return Err(StrategyError::InvalidSlippage(InvalidSlippage { max_slippage_bps }));
+        }
+        let token0 = IPair::new(pair).token_0(&*self).map_err(|_| Self::failed(pair))?;
+        let token1 = IPair::new(pair).token_1(&*self).map_err(|_| Self::failed(pair))?;
+        if asset != token0 && asset != token1 {
+            return Err(StrategyError::AssetNotInPair(AssetNotInPair { asset, pair }));
+        }
+        self.vault.set(vault);
+        self.asset.set(asset);
+        self.pair.set(pair);
+        self.asset_is_token0.set(asset == token0);
+        self.max_slippage_bps.set(max_slippage_bps);
+
+        let now = block::timestamp();
+        let cumulative = self.price_cumulative(now)?;
+        self.price_cumulative_last.set(cumulative);
+        self.price_timestamp_last.set(U64::from(now));
+        Ok(())
+    }
+
+    pub fn asset(&self) -> Result<Address, StrategyError> {
+        Ok(self.asset.get())
+    }
+
+    pub fn vault(&self) -> Result<Address, StrategyError> {
+        Ok(self.vault.get())
+    }
+
+    pub fn pair(&self) -> Result<Address, StrategyError> {
+        Ok(self.pair.get())
+    }
+
+    // Anyone can roll the TWAP forward; deposits, withdrawals and harvests do too.
+    pub fn update_price(&mut self) -> Result<U256, StrategyError> {
+        self.update_price_at(block::timestamp())
+    }
+
+    // The vault has already transferred `amount` in.
+    pub fn deposit(&mut self, _amount: U256) -> Result<(), StrategyError> {
+        self.only_vault()?;
+        let price = self.update_price_at(block::timestamp())?;
+        self.invest(price)
+    }
+
+    // Returns the value lost to swap fees and rounding while unwinding.
+    pub fn withdraw(&mut self, amount: U256) -> Result<U256, StrategyError> {
+        self.withdraw_at(amount, block::timestamp())
+    }
+
+    // LP value at the TWAP plus loose tokens, in units of the asset.
+    pub fn total_assets(&self) -> Result<U256, StrategyError> {
+        let mut assets = self.balance_of(self.asset.get())?;
+        let (lp_balance, lp_supply) = self.lp_position()?;
+        let other = self.balance_of(self.other_token()?)?;
+        if lp_balance == U256::ZERO && other == U256::ZERO {
+            return Ok(assets);
+        }
+        let price = self.price()?;
+        assets += self.lp_value(lp_balance, lp_supply, price)?;
+        assets += other * price / WAD;
+        Ok(assets)
+    }
+
+    pub fn harvest(&mut self) -> Result<(), StrategyError> {
+        self.only_vault()?;
+        let price = self.update_price_at(block::timestamp())?;
+        self.sell_other(price)?;
+        self.invest(price)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use stylus_sdk::{abi::Bytes, function_selector};
+    use test_vm::{
+        contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, transfer_erc20, UnwrapOk, BOB, CAROL, SENDER, TIMESTAMP,
+    };
+
+    const ASSET: Address = Address::repeat_byte(0xa5);
+    const OTHER: Address = Address::repeat_byte(0x07);
+    const PAIR: Address = Address::repeat_byte(0x9a);
+
+    // Just enough of 23_pair.rs, without its fee and k checks. The oracle never
+    // ticks, so the TWAP is whatever the reserves were when it's read.
+    #[derive(Default)]
+    struct Pair {
+        reserves: (U256, U256),
+        lp: HashMap<Address, U256>,
+        supply: U256,
+    }
+
+    impl Pair {
+        fn sync(&mut self) {
+            self.reserves = (erc20_balance(ASSET, PAIR), erc20_balance(OTHER, PAIR));
+        }
+
+        fn move_lp(&mut self, from: Address, to: Address, amount: U256) {
+            *self.lp.get_mut(&from).unwrap() -= amount;
+            *self.lp.entry(to).or_default() += amount;
+        }
+    }
+
+    fn word(value: U256) -> Vec<u8> {
+        value.to_be_bytes::<32>().to_vec()
+    }
+
+    fn mock_pair(pair: Rc<RefCell<Pair>>) {
+        mock_call(PAIR, move |call| {
+            let mut pair = pair.borrow_mut();
+            let arg = |i: usize| U256::from_be_slice(&call.data[4 + 32 * i..36 + 32 * i]);
+            let account = |i: usize| Address::from_slice(&call.data[16 + 32 * i..36 + 32 * i]);
+            let selector: [u8; 4] = call.data[..4].try_into().unwrap();
+            let (r0, r1) = pair.reserves;
+            Ok(match selector {
+                s if s == function_selector!("token0") => ASSET.into_word().to_vec(),
+                s if s == function_selector!("token1") => OTHER.into_word().to_vec(),
+                s if s == function_selector!("getReserves") => [word(r0), word(r1), word(U256::from(TIMESTAMP))].concat(),
+                s if s == function_selector!("currentFee") => word(U256::from(30)),
+                s if s == function_selector!("price0CumulativeLast") => word(U256::ZERO),
+                s if s == function_selector!("price1CumulativeLast") => word(U256::ZERO),
+                s if s == function_selector!("totalSupply") => word(pair.supply),
+                s if s == function_selector!("balanceOf", Address) => word(pair.lp.get(&account(0)).copied().unwrap_or_default()),
+                s if s == function_selector!("transfer", Address, U256) => {
+                    pair.move_lp(contract(), account(0), arg(1));
+                    word(U256::from(1))
+                }
+                s if s == function_selector!("swap", U256, U256, Address, Bytes) => {
+                    transfer_erc20(ASSET, PAIR, account(2), arg(0));
+                    transfer_erc20(OTHER, PAIR, account(2), arg(1));
+                    pair.sync();
+                    Vec::new()
+                }
+                s if s == function_selector!("mint", Address) => {
+                    let amount0 = erc20_balance(ASSET, PAIR) - r0;
+                    let amount1 = erc20_balance(OTHER, PAIR) - r1;
+                    let liquidity = (amount0 * pair.supply / r0).min(amount1 * pair.supply / r1);
+                    *pair.lp.entry(account(0)).or_default() += liquidity;
+                    pair.supply += liquidity;
+                    pair.sync();
+                    word(liquidity)
+                }
+                s if s == function_selector!("burn", Address) => {
+                    let liquidity = pair.lp.remove(&PAIR).unwrap_or_default();
+                    let amount0 = liquidity * r0 / pair.supply;
+                    let amount1 = liquidity * r1 / pair.supply;
+                    pair.supply -= liquidity;
+                    transfer_erc20(ASSET, PAIR, account(0), amount0);
+                    transfer_erc20(OTHER, PAIR, account(0), amount1);
+                    pair.sync();
+                    [word(amount0), word(amount1)].concat()
+                }
+                _ => return Err(Vec::new()),
+            })
+        });
+    }
+
+    // A 1M asset / 2M other pair, so the other token is worth half an asset.
+    // The test itself plays the vault.
+    fn strategy() -> (PairLpStrategy, Rc<RefCell<Pair>>) {
+        let mut strategy = deploy::<PairLpStrategy>();
+        mock_erc20(ASSET);
+        mock_erc20(OTHER);
+        mint_erc20(ASSET, PAIR, U256::from(1_000_000));
+        mint_erc20(OTHER, PAIR, U256::from(2_000_000));
+        let pair = Rc::new(RefCell::new(Pair::default()));
+        pair.borrow_mut().sync();
+        pair.borrow_mut().lp.insert(CAROL, U256::from(1_414_213));
+        pair.borrow_mut().supply = U256::from(1_414_213);
+        mock_pair(pair.clone());
+        strategy.init(SENDER, ASSET, PAIR, U256::from(100), 0).unwrap_ok();
+        (strategy, pair)
+    }
+
+    fn deposit(strategy: &mut PairLpStrategy, amount: u64) -> Result<(), StrategyError> {
+        mint_erc20(ASSET, contract(), U256::from(amount));
+        strategy.deposit(U256::from(amount))
+    }
+
+    // Someone trades the pair along x * y = k until it holds `asset` of the asset.
+    fn move_reserves(pair: &Rc<RefCell<Pair>>, asset: u64) {
+        let (r_asset, r_other) = pair.borrow().reserves;
+        let (asset, other) = (U256::from(asset), r_asset * r_other / U256::from(asset));
+        for (token, target) in [(ASSET, asset), (OTHER, other)] {
+            let balance = erc20_balance(token, PAIR);
+            if target > balance {
+                mint_erc20(token, PAIR, target - balance);
+            } else {
+                transfer_erc20(token, PAIR, BOB, balance - target);
+            }
+        }
+        pair.borrow_mut().sync();
+    }
+
+    fn invested(amount: u64) -> (PairLpStrategy, Rc<RefCell<Pair>>) {
+        let (mut strategy, pair) = strategy();
+        strategy.update_price_at(TIMESTAMP + TWAP_PERIOD).unwrap_ok();
+        deposit(&mut strategy, amount).unwrap_ok();
+        (strategy, pair)
+    }
+
+    #[test]
+    fn init_checks_the_caller_and_slippage() {
+        let mut strategy = deploy::<PairLpStrategy>();
+        assert!(matches!(strategy.init(SENDER, ASSET, PAIR, U256::from(100), 1), Err(StrategyError::NotDeployer(_))));
+        assert!(matches!(strategy.init(SENDER, ASSET, PAIR, U256::from(BPS), 0), Err(StrategyError::InvalidSlippage(_))));
+        let (mut strategy, _) = self::strategy();
+        assert!(matches!(strategy.init(SENDER, ASSET, PAIR, U256::from(100), 0), Err(StrategyError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn waits_a_full_period_for_the_twap() {
+        let (mut strategy, _) = strategy();
+        assert!(matches!(strategy.deposit(U256::from(1_000)), Err(StrategyError::PriceNotReady(_))));
+        assert!(matches!(strategy.update_price_at(TIMESTAMP + TWAP_PERIOD - 1), Err(StrategyError::PriceNotReady(_))));
+        assert_eq!(strategy.update_price_at(TIMESTAMP + TWAP_PERIOD).unwrap_ok(), WAD / U256::from(2));
+        deposit(&mut strategy, 1_000).unwrap_ok();
+        let assets = strategy.total_assets().unwrap_ok();
+        assert!(assets >= U256::from(990) && assets <= U256::from(1_000), "{assets}");
+    }
+
+    #[test]
+    fn moving_the_spot_price_does_not_move_total_assets() {
+        let (strategy, pair) = invested(10_000);
+        let before = strategy.total_assets().unwrap_ok();
+        // at spot, our share of the asset reserve alone would be worth 4x as much
+        move_reserves(&pair, 4_000_000);
+        assert!(strategy.total_assets().unwrap_ok().abs_diff(before) <= U256::from(1));
+        move_reserves(&pair, 250_000);
+        assert!(strategy.total_assets().unwrap_ok().abs_diff(before) <= U256::from(1));
+    }
+
+    #[test]
+    fn swaps_revert_when_spot_leaves_the_twap() {
+        // the asset is cheap at spot, so zapping it in would sell low
+        let (mut strategy, pair) = invested(10_000);
+        move_reserves(&pair, 4_000_000);
+        assert!(matches!(deposit(&mut strategy, 1_000), Err(StrategyError::SlippageExceeded(_))));
+
+        // the other token is cheap at spot, so selling it after the burn would too
+        let (mut strategy, pair) = invested(10_000);
+        move_reserves(&pair, 250_000);
+        assert!(matches!(strategy.withdraw(U256::from(5_000)), Err(StrategyError::SlippageExceeded(_))));
+
+        // a move inside max_slippage_bps still goes through
+        let (mut strategy, pair) = invested(10_000);
+        move_reserves(&pair, 1_002_000);
+        deposit(&mut strategy, 1_000).unwrap_ok();
+    }
+
+    #[test]
+    fn withdraw_unwinds_lp_into_the_asset() {
+        let (mut strategy, _) = invested(10_000);
+        let loss = strategy.withdraw(U256::from(5_000)).unwrap_ok();
+        let received = erc20_balance(ASSET, SENDER);
+        assert!(received > U256::from(4_980) && received <= U256::from(5_000), "{received}");
+        assert!(loss <= U256::from(20), "{loss}");
+        assert_eq!(erc20_balance(OTHER, contract()), U256::ZERO);
+    }
+}
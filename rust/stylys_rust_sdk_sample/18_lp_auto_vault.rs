@@ -0,0 +1,497 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+use crate::deployer::is_deployer;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use amm_math::constant_product::{get_amount_out_with_fee, optimal_swap_amount_with_fee, sqrt_k};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    call::{Call, RawCall},
+    contract, evm, msg,
+    prelude::*,
+};
+
+sol_interface! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+sol_interface! {
+    interface IPair {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function getReserves() external view returns (uint256, uint256, uint32);
+        function currentFee() external view returns (uint64);
+        function totalSupply() external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+        function mint(address to) external returns (uint256);
+    }
+}
+
+// `swap` takes `bytes`, which sol_interface! mis-encodes; see 17_pair_lp_strategy.rs.
+sol! {
+    interface IPairSwap {
+        function swap(uint256 amount0_out, uint256 amount1_out, address to, bytes data) external;
+    }
+}
+
+sol! {
+    event Deposited(address indexed account, uint256 lp, uint256 amount0, uint256 amount1);
+    event Zapped(address indexed account, address indexed token_in, uint256 amount_in, uint256 swapped, uint256 lp);
+    event Withdrawn(address indexed account, uint256 lp);
+
+    error AlreadyInitialized();
+    error NotDeployer(address caller);
+    error InvalidToken(address token);
+    error ZeroAmount();
+    error InsufficientLiquidityMinted(uint256 lp, uint256 min_lp);
+    error InsufficientPosition(uint256 have, uint256 want);
+    error ExternalCallFailed(address target);
+}
+
+#[derive(SolidityError)]
+pub enum AutoVaultError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotDeployer(NotDeployer),
+    InvalidToken(InvalidToken),
+    ZeroAmount(ZeroAmount),
+    InsufficientLiquidityMinted(InsufficientLiquidityMinted),
+    InsufficientPosition(InsufficientPosition),
+    ExternalCallFailed(ExternalCallFailed),
+}
+
+// Holds pair LP tokens for depositors. Swap fees already compound inside the
+// pair's reserves; this contract remembers what each position looked like on
+// entry, so it can split today's value into fee earnings and impermanent loss.
+//
+// With L = sqrt(reserve0 * reserve1) / lp_supply, fees are the only thing that
+// grows L, so for a position of `lp` tokens:
+//     value without fees = value now * entry_liquidity / (lp * L now)
+//     fee earnings       = value now - value without fees
+//     impermanent loss   = HODL value of the entry amounts - value without fees
+sol_storage! {
+    #[entrypoint]
+    pub struct LpAutoVault {
+        address pair;
+        address token0;
+        address token1;
+        uint256 total_lp;
+        mapping(address => Position) positions;
+    }
+
+    pub struct Position {
+        uint256 lp;
+        // sqrt(k) share at entry: sum of lp * sqrt(k) / supply over every deposit
+        uint256 entry_liquidity;
+        // token amounts the LP stood for when deposited
+        uint256 entry_amount0;
+        uint256 entry_amount1;
+    }
+}
+
+impl LpAutoVault {
+    fn failed(target: Address) -> AutoVaultError {
+        AutoVaultError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    // (reserve0, reserve1, lp supply)
+    fn pool_state(&self) -> Result<(U256, U256, U256), AutoVaultError> {
+        let pair = self.pair.get();
+        let (r0, r1, _) = IPair::new(pair).get_reserves(self).map_err(|_| Self::failed(pair))?;
+        let supply = IPair::new(pair).total_supply(self).map_err(|_| Self::failed(pair))?;
+        Ok((r0, r1, supply))
+    }
+
+    // The pair's dynamic fee in bps for a swap in this block.
+    fn fee(&self) -> Result<u64, AutoVaultError> {
+        let pair = self.pair.get();
+        IPair::new(pair).current_fee(self).map_err(|_| Self::failed(pair))
+    }
+
+    fn balance_of(&self, token: Address) -> Result<U256, AutoVaultError> {
+        IERC20::new(token).balance_of(self, contract::address()).map_err(|_| Self::failed(token))
+    }
+
+    fn transfer(&mut self, token: Address, to: Address, amount: U256) -> Result<(), AutoVaultError> {
+        let ok = IERC20::new(token).transfer(Call::new_in(self), to, amount).map_err(|_| Self::failed(token))?;
+        if !ok {
+            return Err(Self::failed(token));
+        }
+        Ok(())
+    }
+
+    fn pull(&mut self, token: Address, from: Address, amount: U256) -> Result<(), AutoVaultError> {
+        let ok =
+            IERC20::new(token).transfer_from(Call::new_in(self), from, contract::address(), amount).map_err(|_| Self::failed(token))?;
+        if !ok {
+            return Err(Self::failed(token));
+        }
+        Ok(())
+    }
+
+    fn record_deposit(&mut self, account: Address, lp: U256) -> Result<(), AutoVaultError> {
+        let (r0, r1, supply) = self.pool_state()?;
+        let amount0 = lp * r0 / supply;
+        let amount1 = lp * r1 / supply;
+        let liquidity = lp * sqrt_k(r0, r1) / supply;
+
+        let mut position = self.positions.setter(account);
+        let new_lp = position.lp.get() + lp;
+        let new_liquidity = position.entry_liquidity.get() + liquidity;
+        let new_amount0 = position.entry_amount0.get() + amount0;
+        let new_amount1 = position.entry_amount1.get() + amount1;
+        position.lp.set(new_lp);
+        position.entry_liquidity.set(new_liquidity);
+        position.entry_amount0.set(new_amount0);
+        position.entry_amount1.set(new_amount1);
+        self.total_lp.set(self.total_lp.get() + lp);
+        evm::log(Deposited { account, lp, amount0, amount1 });
+        Ok(())
+    }
+}
+
+#[external]
+impl LpAutoVault {
+    pub fn init(&mut self, pair: Address, deploy_nonce: u64) -> Result<(), AutoVaultError> {
+        if self.pair.get() != Address::ZERO {
+            return Err(AutoVaultError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(AutoVaultError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        let token0 = IPair::new(pair).token_0(&*self).map_err(|_| Self::failed(pair))?;
+        let token1 = IPair::new(pair).token_1(&*self).map_err(|_| Self::failed(pair))?;
+        self.pair.set(pair);
+        self.token0.set(token0);
+        self.token1.set(token1);
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, lp: U256) -> Result<(), AutoVaultError> {
+        if lp == U256::ZERO {
+            return Err(AutoVaultError::ZeroAmount(ZeroAmount {}));
+        }
+        let pair = self.pair.get();
+        self.pull(pair, msg::sender(), lp)?;
+        self.record_deposit(msg::sender(), lp)
+    }
+
+    // Swaps the optimal part of `amount_in` into the other token, adds both
+    // sides to the pair at its new ratio and credits the minted LP. Whatever
+    // doesn't fit the ratio is refunded.
+    pub fn zap_in(&mut self, token_in: Address, amount_in: U256, min_lp: U256) -> Result<U256, AutoVaultError> {
+        let (pair, token0, token1) = (self.pair.get(), self.token0.get(), self.token1.get());
+        if token_in != token0 && token_in != token1 {
+            return Err(AutoVaultError::InvalidToken(InvalidToken { token: token_in }));
+        }
+        if amount_in == U256::ZERO {
+            return Err(AutoVaultError::ZeroAmount(ZeroAmount {}));
+        }
+        let account = msg::sender();
+        self.pull(token_in, account, amount_in)?;
+
+        let (r0, r1, _) = self.pool_state()?;
+        let (r_in, r_out, token_out) = if token_in == token0 { (r0, r1, token1) } else { (r1, r0, token0) };
+        let fee_bps = self.fee()?;
+        let swapped = optimal_swap_amount_with_fee(amount_in, r_in, fee_bps);
+        let amount_out = get_amount_out_with_fee(swapped, r_in, r_out, fee_bps);
+        self.transfer(token_in, pair, swapped)?;
+        let (amount0_out, amount1_out) = if token_in == token0 { (U256::ZERO, amount_out) } else { (amount_out, U256::ZERO) };
+        let call = IPairSwap::swapCall { amount0_out, amount1_out, to: contract::address(), data: Vec::new().into() };
+        RawCall::new().call(pair, &call.abi_encode()).map_err(|_| Self::failed(pair))?;
+
+        // the pair mints by the smaller side, so only send what matches its reserves
+        let (r_in, r_out) = (r_in + swapped, r_out - amount_out);
+        let rest = amount_in - swapped;
+        let (add_in, add_out) =
+            if rest * r_out <= amount_out * r_in { (rest, rest * r_out / r_in) } else { (amount_out * r_in / r_out, amount_out) };
+        self.transfer(token_in, pair, add_in)?;
+        self.transfer(token_out, pair, add_out)?;
+        let lp = IPair::new(pair).mint(Call::new_in(self), contract::address()).map_err(|_| Self::failed(pair))?;
+        if lp < min_lp {
+            return Err(AutoVaultError::InsufficientLiquidityMinted(InsufficientLiquidityMinted { lp, min_lp }));
+        }
+        self.record_deposit(account, lp)?;
+
+        // the vault only holds LP between calls, so any loose token is this zap's dust
+        for token in [token0, token1] {
+            let dust = self.balance_of(token)?;
+            if dust > U256::ZERO {
+                self.transfer(token, account, dust)?;
+            }
+        }
+        evm::log(Zapped { account, token_in, amount_in, swapped, lp });
+        Ok(lp)
+    }
+
+    // Entry amounts and liquidity shrink with the position, so the report stays per-LP.
+    pub fn withdraw(&mut self, lp: U256) -> Result<(), AutoVaultError> {
+        let account = msg::sender();
+        let position = self.positions.get(account);
+        let have = position.lp.get();
+        if lp > have || lp == U256::ZERO {
+            return Err(AutoVaultError::InsufficientPosition(InsufficientPosition { have, want: lp }));
+        }
+        let scale = |value: U256| value - value * lp / have;
+        let (liquidity, amount0, amount1) =
+            (scale(position.entry_liquidity.get()), scale(position.entry_amount0.get()), scale(position.entry_amount1.get()));
+
+        let mut position = self.positions.setter(account);
+        position.lp.set(have - lp);
+        position.entry_liquidity.set(liquidity);
+        position.entry_amount0.set(amount0);
+        position.entry_amount1.set(amount1);
+        self.total_lp.set(self.total_lp.get() - lp);
+
+        let pair = self.pair.get();
+        self.transfer(pair, account, lp)?;
+        evm::log(Withdrawn { account, lp });
+        Ok(())
+    }
+
+    pub fn position(&self, account: Address) -> Result<(U256, U256, U256, U256), AutoVaultError> {
+        let position = self.positions.get(account);
+        Ok((position.lp.get(), position.entry_liquidity.get(), position.entry_amount0.get(), position.entry_amount1.get()))
+    }
+
+    // "How much did I earn": (value now, HODL value, fee earnings, impermanent loss),
+    // all in units of token1 at the current pair price.
+    pub fn position_report(&self, account: Address) -> Result<(U256, U256, U256, U256), AutoVaultError> {
+        let position = self.positions.get(account);
+        let lp = position.lp.get();
+        let (r0, r1, supply) = self.pool_state()?;
+        if lp == U256::ZERO || r0 == U256::ZERO || supply == U256::ZERO {
+            return Ok((U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
+        }
+
+        // both sides are worth the same at the pair price
+        let value_now = U256::from(2) * lp * r1 / supply;
+        let liquidity_now = lp * sqrt_k(r0, r1) / supply;
+        let value_without_fees = value_now * position.entry_liquidity.get() / liquidity_now;
+        let fee_earnings = value_now.saturating_sub(value_without_fees);
+        let hodl_value = position.entry_amount1.get() + position.entry_amount0.get() * r1 / r0;
+        let impermanent_loss = hodl_value.saturating_sub(value_without_fees);
+        Ok((value_now, hodl_value, fee_earnings, impermanent_loss))
+    }
+
+    // (amount swapped, LP expected) for a zap at current reserves.
+    pub fn quote_zap_in(&self, token_in: Address, amount_in: U256) -> Result<(U256, U256), AutoVaultError> {
+        let (r0, r1, supply) = self.pool_state()?;
+        let (r_in, r_out) = if token_in == self.token0.get() { (r0, r1) } else { (r1, r0) };
+        let fee_bps = self.fee()?;
+        let swapped = optimal_swap_amount_with_fee(amount_in, r_in, fee_bps);
+        let amount_out = get_amount_out_with_fee(swapped, r_in, r_out, fee_bps);
+        let lp = ((amount_in - swapped) * supply / (r_in + swapped)).min(amount_out * supply / (r_out - amount_out));
+        Ok((swapped, lp))
+    }
+
+    pub fn total_lp(&self) -> Result<U256, AutoVaultError> {
+        Ok(self.total_lp.get())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::constant_product::BPS;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use stylus_sdk::{abi::Bytes, function_selector};
+    use test_vm::{contract, deploy, erc20_balance, events, mint_erc20, mock_call, mock_erc20, transfer_erc20, UnwrapOk, BOB, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x70);
+    const TOKEN1: Address = Address::repeat_byte(0x71);
+    const PAIR: Address = Address::repeat_byte(0x9a);
+
+    // Just enough of 23_pair.rs: LP accounting, mint by reserves, and swaps
+    // checked against x * y = k after the current fee.
+    #[derive(Default)]
+    struct Pair {
+        fee_bps: u64,
+        reserves: (U256, U256),
+        lp: HashMap<Address, U256>,
+        supply: U256,
+    }
+
+    impl Pair {
+        fn sync(&mut self) {
+            self.reserves = (erc20_balance(TOKEN0, PAIR), erc20_balance(TOKEN1, PAIR));
+        }
+
+        fn move_lp(&mut self, from: Address, to: Address, amount: U256) -> Result<Vec<u8>, Vec<u8>> {
+            let have = self.lp.get(&from).copied().unwrap_or_default();
+            if have < amount {
+                return Err(Vec::new());
+            }
+            self.lp.insert(from, have - amount);
+            *self.lp.entry(to).or_default() += amount;
+            Ok(word(U256::from(1)))
+        }
+    }
+
+    fn word(value: U256) -> Vec<u8> {
+        value.to_be_bytes::<32>().to_vec()
+    }
+
+    fn mock_pair(pair: Rc<RefCell<Pair>>) {
+        mock_call(PAIR, move |call| {
+            let mut pair = pair.borrow_mut();
+            let arg = |i: usize| U256::from_be_slice(&call.data[4 + 32 * i..36 + 32 * i]);
+            let account = |i: usize| Address::from_slice(&call.data[16 + 32 * i..36 + 32 * i]);
+            let selector: [u8; 4] = call.data[..4].try_into().unwrap();
+            let (r0, r1) = pair.reserves;
+            Ok(match selector {
+                s if s == function_selector!("token0") => TOKEN0.into_word().to_vec(),
+                s if s == function_selector!("token1") => TOKEN1.into_word().to_vec(),
+                s if s == function_selector!("getReserves") => [word(r0), word(r1), word(U256::ZERO)].concat(),
+                s if s == function_selector!("currentFee") => word(U256::from(pair.fee_bps)),
+                s if s == function_selector!("totalSupply") => word(pair.supply),
+                s if s == function_selector!("transfer", Address, U256) => pair.move_lp(contract(), account(0), arg(1))?,
+                s if s == function_selector!("transferFrom", Address, Address, U256) => pair.move_lp(account(0), account(1), arg(2))?,
+                s if s == function_selector!("swap", U256, U256, Address, Bytes) => {
+                    transfer_erc20(TOKEN0, PAIR, account(2), arg(0));
+                    transfer_erc20(TOKEN1, PAIR, account(2), arg(1));
+                    let (b0, b1) = (erc20_balance(TOKEN0, PAIR), erc20_balance(TOKEN1, PAIR));
+                    let in0 = (b0 + arg(0)).saturating_sub(r0);
+                    let in1 = (b1 + arg(1)).saturating_sub(r1);
+                    let (bps, fee) = (U256::from(BPS), U256::from(pair.fee_bps));
+                    let adjusted = |balance: U256, amount_in: U256| balance * bps - amount_in * fee;
+                    if adjusted(b0, in0) * adjusted(b1, in1) < r0 * r1 * bps * bps {
+                        return Err(b"K".to_vec());
+                    }
+                    pair.sync();
+                    Vec::new()
+                }
+                s if s == function_selector!("mint", Address) => {
+                    let amount0 = erc20_balance(TOKEN0, PAIR) - r0;
+                    let amount1 = erc20_balance(TOKEN1, PAIR) - r1;
+                    let liquidity = (amount0 * pair.supply / r0).min(amount1 * pair.supply / r1);
+                    *pair.lp.entry(account(0)).or_default() += liquidity;
+                    pair.supply += liquidity;
+                    pair.sync();
+                    word(liquidity)
+                }
+                _ => return Err(Vec::new()),
+            })
+        });
+    }
+
+    // A 1M / 1M pair; SENDER holds 100k of its LP and BOB the rest.
+    fn vault() -> (LpAutoVault, Rc<RefCell<Pair>>) {
+        let mut vault = deploy::<LpAutoVault>();
+        mock_erc20(TOKEN0);
+        mock_erc20(TOKEN1);
+        mint_erc20(TOKEN0, PAIR, U256::from(1_000_000));
+        mint_erc20(TOKEN1, PAIR, U256::from(1_000_000));
+        let pair = Rc::new(RefCell::new(Pair { fee_bps: 30, ..Pair::default() }));
+        pair.borrow_mut().sync();
+        pair.borrow_mut().lp.insert(SENDER, U256::from(100_000));
+        pair.borrow_mut().lp.insert(BOB, U256::from(900_000));
+        pair.borrow_mut().supply = U256::from(1_000_000);
+        mock_pair(pair.clone());
+        vault.init(PAIR, 0).unwrap_ok();
+        (vault, pair)
+    }
+
+    #[test]
+    fn init_checks_the_caller() {
+        let mut vault = deploy::<LpAutoVault>();
+        assert!(matches!(vault.init(PAIR, 1), Err(AutoVaultError::NotDeployer(_))));
+        let (mut vault, _) = self::vault();
+        assert!(matches!(vault.init(PAIR, 0), Err(AutoVaultError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn zap_in_mints_lp_and_refunds_the_rest() {
+        let (mut vault, pair) = vault();
+        mint_erc20(TOKEN0, SENDER, U256::from(10_000));
+        let (swapped, quoted) = vault.quote_zap_in(TOKEN0, U256::from(10_000)).unwrap_ok();
+        let lp = vault.zap_in(TOKEN0, U256::from(10_000), quoted).unwrap_ok();
+        assert_eq!(lp, quoted);
+        assert_eq!(pair.borrow().lp[&contract()], lp);
+        assert_eq!(vault.total_lp().unwrap_ok(), lp);
+        assert_eq!(vault.position(SENDER).unwrap_ok().0, lp);
+        // a few wei of the input may come back, and nothing stays in the vault
+        let refund = erc20_balance(TOKEN0, SENDER) + erc20_balance(TOKEN1, SENDER);
+        assert!(refund <= U256::from(2), "{refund}");
+        assert_eq!(erc20_balance(TOKEN0, contract()) + erc20_balance(TOKEN1, contract()), U256::ZERO);
+        let zapped = events::<Zapped>();
+        assert_eq!((zapped[0].swapped, zapped[0].lp), (swapped, lp));
+    }
+
+    #[test]
+    fn zap_in_quotes_at_the_pair_fee() {
+        // a volatile pair charges the 10% max, which a 0.3% quote would overpay by far
+        let (mut vault, pair) = vault();
+        pair.borrow_mut().fee_bps = 1_000;
+        mint_erc20(TOKEN1, SENDER, U256::from(100_000));
+        let (_, quoted) = vault.quote_zap_in(TOKEN1, U256::from(100_000)).unwrap_ok();
+        assert_eq!(vault.zap_in(TOKEN1, U256::from(100_000), quoted).unwrap_ok(), quoted);
+        let refund = erc20_balance(TOKEN0, SENDER) + erc20_balance(TOKEN1, SENDER);
+        assert!(refund <= U256::from(2), "{refund}");
+    }
+
+    #[test]
+    fn zap_in_checks_its_inputs() {
+        let (mut vault, _) = vault();
+        assert!(matches!(vault.zap_in(BOB, U256::from(1), U256::ZERO), Err(AutoVaultError::InvalidToken(_))));
+        assert!(matches!(vault.zap_in(TOKEN1, U256::ZERO, U256::ZERO), Err(AutoVaultError::ZeroAmount(_))));
+        mint_erc20(TOKEN1, SENDER, U256::from(10_000));
+        let (_, quoted) = vault.quote_zap_in(TOKEN1, U256::from(10_000)).unwrap_ok();
+        let min_lp = quoted + U256::from(1);
+        assert!(matches!(vault.zap_in(TOKEN1, U256::from(10_000), min_lp), Err(AutoVaultError::InsufficientLiquidityMinted(_))));
+    }
+
+    #[test]
+    fn withdraw_returns_lp_and_shrinks_the_position() {
+        let (mut vault, pair) = vault();
+        vault.deposit(U256::from(40_000)).unwrap_ok();
+        let (_, liquidity, amount0, amount1) = vault.position(SENDER).unwrap_ok();
+        assert_eq!((liquidity, amount0, amount1), (U256::from(40_000), U256::from(40_000), U256::from(40_000)));
+
+        assert!(matches!(vault.withdraw(U256::from(40_001)), Err(AutoVaultError::InsufficientPosition(_))));
+        vault.withdraw(U256::from(10_000)).unwrap_ok();
+        assert_eq!(pair.borrow().lp[&SENDER], U256::from(70_000));
+        assert_eq!(vault.position(SENDER).unwrap_ok(), (U256::from(30_000), U256::from(30_000), U256::from(30_000), U256::from(30_000)));
+        assert_eq!(vault.total_lp().unwrap_ok(), U256::from(30_000));
+    }
+
+    #[test]
+    fn position_report_splits_fees_from_impermanent_loss() {
+        let (mut vault, pair) = vault();
+        vault.deposit(U256::from(100_000)).unwrap_ok();
+        let (value, hodl, fees, loss) = vault.position_report(SENDER).unwrap_ok();
+        assert_eq!((value, hodl, fees, loss), (U256::from(200_000), U256::from(200_000), U256::ZERO, U256::ZERO));
+
+        // fees: the reserves grow 1% with no new LP, at the same price
+        mint_erc20(TOKEN0, PAIR, U256::from(10_000));
+        mint_erc20(TOKEN1, PAIR, U256::from(10_000));
+        pair.borrow_mut().sync();
+        let (value, hodl, fees, loss) = vault.position_report(SENDER).unwrap_ok();
+        assert_eq!((value, hodl, fees, loss), (U256::from(202_000), U256::from(200_000), U256::from(2_000), U256::ZERO));
+
+        // impermanent loss: the price of token0 quadruples along the same k
+        transfer_erc20(TOKEN0, PAIR, BOB, U256::from(505_000));
+        mint_erc20(TOKEN1, PAIR, U256::from(1_010_000));
+        pair.borrow_mut().sync();
+        let (value, hodl, fees, loss) = vault.position_report(SENDER).unwrap_ok();
+        // HODL: 100k * 4 + 100k = 500k; LP: 2 * 202k = 404k of which 4k are fees
+        assert_eq!((value, hodl, fees), (U256::from(404_000), U256::from(500_000), U256::from(4_000)));
+        assert_eq!(loss, U256::from(100_000));
+    }
+}
@@ -0,0 +1,218 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod concentrated_liquidity_state;
+use crate::concentrated_liquidity_state::*;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{U256, U8},
+    evm,
+    prelude::*,
+};
+
+sol! {
+    event LimitOrderClaimed(uint256 indexed order_id, address indexed owner, uint256 amount0, uint256 amount1);
+    event LimitEpochFilled(uint256 indexed epoch, int24 tick_lower, bool zero_for_one, uint256 amount0, uint256 amount1);
+}
+
+// Limit order fills for the concentrated liquidity pool in
+// 19_concentrated_liquidity_pool.rs. The pool delegate_calls this facet for the
+// `IConcentratedLiquidityLimitFills` selectors, and the swap facet for
+// `fillLimitEpochs` with the ticks a swap crossed, so it runs on the pool's
+// storage (see concentrated_liquidity_state.rs for the layout). Orders are
+// placed and cancelled in 19_concentrated_liquidity_limit_orders.rs.
+sol_storage! {
+    #[entrypoint]
+    pub struct ConcentratedLiquidityLimitFills {
+        #[borrow]
+        ConcentratedLiquidityState state;
+    }
+}
+
+impl ConcentratedLiquidityLimitFills {
+    // Pulls the epoch waiting on the far side of a tick the price crossed out
+    // of the pool. Rising through `tick` completes a token0 sale on the range
+    // below it; falling through it completes a token1 sale on the range above.
+    fn fill_limit_epoch(&mut self, tick: i32, zero_for_one: bool) -> Result<(), PoolError> {
+        let spacing = self.state.spacing();
+        let (tick_lower, sells_token0) = if zero_for_one { (tick, false) } else { (tick - spacing, true) };
+        let key = ConcentratedLiquidityState::epoch_key(tick_lower, sells_token0);
+        let epoch = self.state.open_limit_epochs.get(key);
+        if epoch == U256::ZERO {
+            return Ok(());
+        }
+        self.state.open_limit_epochs.insert(key, U256::ZERO);
+
+        let liquidity = self.state.limit_epochs.get(epoch).liquidity.get().saturating_to::<u128>();
+        let owner = ConcentratedLiquidityState::epoch_owner(epoch);
+        let tick_upper = tick_lower + spacing;
+        let delta = i128::try_from(liquidity).map_err(|_| overflow())?;
+        let (amount0, amount1) = self.state.modify_position(owner, tick_lower, tick_upper, -delta)?;
+
+        let fees = self.state.take_owed(owner, tick_lower, tick_upper);
+        let (amount0, amount1) = (amount0 + fees.0, amount1 + fees.1);
+
+        let mut entry = self.state.limit_epochs.setter(epoch);
+        entry.filled.set(true);
+        entry.amount0.set(amount0);
+        entry.amount1.set(amount1);
+        evm::log(LimitEpochFilled { epoch, tick_lower, zero_for_one: sells_token0, amount0, amount1 });
+        Ok(())
+    }
+}
+
+#[external]
+impl ConcentratedLiquidityLimitFills {
+    // Pays a filled order to its owner. Anyone may call it, so no keeper is needed.
+    pub fn claim_limit_order(&mut self, order_id: U256) -> Result<(U256, U256), PoolError> {
+        let (owner, _, _, filled) = self.state.open_limit_order(order_id)?;
+        if !filled {
+            return Err(PoolError::LimitOrderNotFilled(LimitOrderNotFilled { order_id }));
+        }
+        let (amount0, amount1) = self.state.limit_order_proceeds(order_id)?;
+        self.state.limit_orders.setter(order_id).status.set(U8::from(ORDER_CLAIMED));
+
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        self.transfer(token0, owner, amount0)?;
+        self.transfer(token1, owner, amount1)?;
+        evm::log(LimitOrderClaimed { order_id, owner, amount0, amount1 });
+        Ok((amount0, amount1))
+    }
+
+    // Fills the epochs behind the initialized ticks a swap in the direction
+    // `zero_for_one` just crossed. A tick the price isn't past is skipped.
+    pub fn fill_limit_epochs(&mut self, ticks: Vec<i32>, zero_for_one: bool) -> Result<(), PoolError> {
+        let current = self.state.current_tick();
+        for tick in ticks {
+            let past = if zero_for_one { current < tick } else { current >= tick };
+            if past {
+                self.fill_limit_epoch(tick, zero_for_one)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::tick_math::{get_sqrt_ratio_at_tick, max_liquidity_per_tick, Q96};
+    use stylus_sdk::{
+        abi::Router,
+        alloy_primitives::{Address, U128, U32},
+    };
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // Price 1, 0.3% fee, spacing 60, with 1e21 liquidity over [-6000, 6000)
+    // and orders 1 and 2 of 1e18 and 3e18 selling token0 over [60, 120) in
+    // epoch 1, as init, a first mint and placing the orders leave it.
+    fn pool() -> ConcentratedLiquidityLimitFills {
+        let mut pool = deploy::<ConcentratedLiquidityLimitFills>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000_000 * E18));
+        }
+        let state = &mut pool.state;
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.fee.set(U32::from(3_000));
+        state.tick_spacing.set(U32::from(60));
+        state.max_liquidity_per_tick.set(U128::from(max_liquidity_per_tick(60)));
+        state.sqrt_price_x96.set(Q96);
+        state.modify_position(SENDER, -6_000, 6_000, (1_000 * E18) as i128).unwrap_ok();
+
+        let epoch = U256::from(1);
+        state.modify_position(ConcentratedLiquidityState::epoch_owner(epoch), 60, 120, (4 * E18) as i128).unwrap_ok();
+        state.open_limit_epochs.insert(ConcentratedLiquidityState::epoch_key(60, true), epoch);
+        let mut entry = state.limit_epochs.setter(epoch);
+        entry.tick_lower.set(tick_key(60));
+        entry.zero_for_one.set(true);
+        entry.liquidity.set(U128::from(4 * E18));
+        for (order_id, liquidity) in [(U256::from(1), E18), (U256::from(2), 3 * E18)] {
+            let mut order = state.limit_orders.setter(order_id);
+            order.owner.set(SENDER);
+            order.epoch.set(epoch);
+            order.liquidity.set(U128::from(liquidity));
+            order.status.set(U8::from(ORDER_OPEN));
+        }
+        pool
+    }
+
+    // Puts the price at `tick` with `liquidity` active, as a fee-free swap there leaves it.
+    fn move_price(pool: &mut ConcentratedLiquidityLimitFills, tick: i32, liquidity: u128) {
+        let state = &mut pool.state;
+        state.sqrt_price_x96.set(get_sqrt_ratio_at_tick(tick).unwrap());
+        state.tick.set(tick_key(tick));
+        state.liquidity.set(U128::from(liquidity));
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_and_the_swap_facet_send() {
+        let mut pool = pool();
+        let selectors = IConcentratedLiquidityLimitFills::IConcentratedLiquidityLimitFillsCalls::SELECTORS
+            .iter()
+            .chain(ILimitEpochFills::ILimitEpochFillsCalls::SELECTORS);
+        for selector in selectors {
+            let routed = <ConcentratedLiquidityLimitFills as Router<ConcentratedLiquidityLimitFills>>::route(
+                &mut pool,
+                u32::from_be_bytes(*selector),
+                &[],
+            );
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn limit_orders_fill_once_and_claim_pro_rata() {
+        let mut pool = pool();
+        let (epoch, small, large) = (U256::from(1), U256::from(1), U256::from(2));
+        assert!(matches!(pool.claim_limit_order(small), Err(PoolError::LimitOrderNotFilled(_))));
+
+        // a swap only partway into the range fills nothing
+        move_price(&mut pool, 90, 1_004 * E18);
+        pool.fill_limit_epochs(vec![120], false).unwrap_ok();
+        assert!(!pool.state.limit_epochs.get(epoch).filled.get());
+
+        // once the price is past 120 the whole epoch leaves the pool as token1
+        move_price(&mut pool, 150, 1_000 * E18);
+        pool.fill_limit_epochs(vec![60, 120], false).unwrap_ok();
+        let entry = pool.state.limit_epochs.get(epoch);
+        assert!(entry.filled.get());
+        assert_eq!(entry.amount0.get(), U256::ZERO);
+        // the range's liquidity times the rise in sqrt(P) across it, rounded down
+        let amount1 = entry.amount1.get();
+        let worth = U256::from(4 * E18) * (get_sqrt_ratio_at_tick(120).unwrap() - get_sqrt_ratio_at_tick(60).unwrap()) / Q96;
+        assert!(amount1 <= worth && worth - amount1 <= U256::from(1), "{amount1}");
+        assert_eq!(pool.state.open_limit_epochs.get(ConcentratedLiquidityState::epoch_key(60, true)), U256::ZERO);
+        let key = ConcentratedLiquidityState::position_key(ConcentratedLiquidityState::epoch_owner(epoch), 60, 120);
+        assert_eq!(pool.state.positions.get(key).liquidity.get(), U128::ZERO);
+        // crossing it again finds no open epoch there
+        pool.fill_limit_epochs(vec![120], false).unwrap_ok();
+        assert_eq!(pool.state.limit_epochs.get(epoch).amount1.get(), amount1);
+
+        let share = pool.claim_limit_order(small).unwrap_ok();
+        assert_eq!(share, (U256::ZERO, amount1 / U256::from(4)));
+        assert_eq!(erc20_balance(TOKEN1, SENDER), share.1);
+        assert_eq!(pool.state.limit_orders.get(small).status.get(), U8::from(ORDER_CLAIMED));
+        assert!(matches!(pool.claim_limit_order(small), Err(PoolError::LimitOrderClosed(_))));
+        let (_, rest) = pool.claim_limit_order(large).unwrap_ok();
+        assert_eq!(rest, amount1 * U256::from(3) / U256::from(4));
+        assert!(matches!(pool.claim_limit_order(U256::from(99)), Err(PoolError::UnknownLimitOrder(_))));
+    }
+}
@@ -0,0 +1,268 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod concentrated_liquidity_state;
+use crate::concentrated_liquidity_state::*;
+use alloy_sol_types::sol;
+use amm_math::full_math::mul_div;
+use stylus_sdk::{
+    alloy_primitives::{U128, U256, U8},
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event LimitOrderPlaced(uint256 indexed order_id, address indexed owner, uint256 indexed epoch, int24 tick_lower, bool zero_for_one, uint128 liquidity, uint256 amount0, uint256 amount1);
+    event LimitOrderCancelled(uint256 indexed order_id, address indexed owner, uint256 amount0, uint256 amount1);
+}
+
+// Limit orders for the concentrated liquidity pool in
+// 19_concentrated_liquidity_pool.rs. The pool delegate_calls this facet for the
+// `IConcentratedLiquidityLimitOrders` selectors, so it runs on the pool's
+// storage (see concentrated_liquidity_state.rs for the layout). It places and
+// cancels orders; filling and claiming them is up to
+// 19_concentrated_liquidity_limit_fills.rs.
+sol_storage! {
+    #[entrypoint]
+    pub struct ConcentratedLiquidityLimitOrders {
+        #[borrow]
+        ConcentratedLiquidityState state;
+    }
+}
+
+#[external]
+impl ConcentratedLiquidityLimitOrders {
+    // Sells token0 (`zero_for_one`) or token1 over [tick_lower, tick_lower +
+    // spacing), which must lie wholly above or below the price respectively. The
+    // caller pays and owns the order; it fills once the price crosses the far tick.
+    pub fn place_limit_order(&mut self, tick_lower: i32, zero_for_one: bool, liquidity: u128) -> Result<U256, PoolError> {
+        if self.state.sqrt_price_x96.get() == U256::ZERO {
+            return Err(PoolError::NotInitialized(NotInitialized {}));
+        }
+        if liquidity == 0 {
+            return Err(PoolError::ZeroLiquidity(ZeroLiquidity {}));
+        }
+        let tick_upper = tick_lower.checked_add(self.state.spacing()).ok_or_else(overflow)?;
+        let tick = self.state.current_tick();
+        let outside = if zero_for_one { tick < tick_lower } else { tick >= tick_upper };
+        if !outside {
+            return Err(PoolError::LimitOrderInRange(LimitOrderInRange { tick_lower, tick }));
+        }
+
+        let key = ConcentratedLiquidityState::epoch_key(tick_lower, zero_for_one);
+        let mut epoch = self.state.open_limit_epochs.get(key);
+        if epoch == U256::ZERO {
+            epoch = self.state.next_limit_epoch.get() + U256::from(1);
+            self.state.next_limit_epoch.set(epoch);
+            self.state.open_limit_epochs.insert(key, epoch);
+            let mut entry = self.state.limit_epochs.setter(epoch);
+            entry.tick_lower.set(tick_key(tick_lower));
+            entry.zero_for_one.set(zero_for_one);
+        }
+        let delta = i128::try_from(liquidity).map_err(|_| overflow())?;
+        let (amount0, amount1) =
+            self.state.modify_position(ConcentratedLiquidityState::epoch_owner(epoch), tick_lower, tick_upper, delta)?;
+        {
+            let mut entry = self.state.limit_epochs.setter(epoch);
+            let total = entry.liquidity.get().saturating_to::<u128>().checked_add(liquidity).ok_or_else(overflow)?;
+            entry.liquidity.set(U128::from(total));
+        }
+
+        let owner = msg::sender();
+        let order_id = self.state.next_limit_order_id.get() + U256::from(1);
+        self.state.next_limit_order_id.set(order_id);
+        {
+            let mut order = self.state.limit_orders.setter(order_id);
+            order.owner.set(owner);
+            order.epoch.set(epoch);
+            order.liquidity.set(U128::from(liquidity));
+            order.status.set(U8::from(ORDER_OPEN));
+        }
+
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        self.pull(token0, owner, amount0)?;
+        self.pull(token1, owner, amount1)?;
+        evm::log(LimitOrderPlaced { order_id, owner, epoch, tick_lower, zero_for_one, liquidity, amount0, amount1 });
+        Ok(order_id)
+    }
+
+    // Withdraws an unfilled order, partly converted or not, with its share of
+    // the epoch's fees.
+    pub fn cancel_limit_order(&mut self, order_id: U256) -> Result<(U256, U256), PoolError> {
+        let (owner, epoch, liquidity, filled) = self.state.open_limit_order(order_id)?;
+        if owner != msg::sender() {
+            return Err(PoolError::NotLimitOrderOwner(NotLimitOrderOwner { order_id, caller: msg::sender() }));
+        }
+        if filled {
+            return Err(PoolError::LimitOrderFilled(LimitOrderFilled { order_id }));
+        }
+        let entry = self.state.limit_epochs.get(epoch);
+        let tick_lower = i32::try_from(entry.tick_lower.get()).unwrap();
+        let zero_for_one = entry.zero_for_one.get();
+        let total = entry.liquidity.get().saturating_to::<u128>();
+        let tick_upper = tick_lower + self.state.spacing();
+
+        // fees are credited to the epoch position before its liquidity changes
+        let position_owner = ConcentratedLiquidityState::epoch_owner(epoch);
+        let delta = i128::try_from(liquidity).map_err(|_| overflow())?;
+        let (principal0, principal1) = self.state.modify_position(position_owner, tick_lower, tick_upper, -delta)?;
+        let (fees0, fees1) = {
+            let mut position =
+                self.state.positions.setter(ConcentratedLiquidityState::position_key(position_owner, tick_lower, tick_upper));
+            let (owed0, owed1) = (position.tokens_owed0.get(), position.tokens_owed1.get());
+            let fees0 = mul_div(owed0, U256::from(liquidity), U256::from(total)).ok_or_else(overflow)?;
+            let fees1 = mul_div(owed1, U256::from(liquidity), U256::from(total)).ok_or_else(overflow)?;
+            position.tokens_owed0.set(owed0 - fees0);
+            position.tokens_owed1.set(owed1 - fees1);
+            (fees0, fees1)
+        };
+
+        self.state.limit_epochs.setter(epoch).liquidity.set(U128::from(total - liquidity));
+        if total == liquidity {
+            self.state.open_limit_epochs.insert(ConcentratedLiquidityState::epoch_key(tick_lower, zero_for_one), U256::ZERO);
+        }
+        self.state.limit_orders.setter(order_id).status.set(U8::from(ORDER_CANCELLED));
+
+        let (amount0, amount1) = (principal0 + fees0, principal1 + fees1);
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        self.transfer(token0, owner, amount0)?;
+        self.transfer(token1, owner, amount1)?;
+        evm::log(LimitOrderCancelled { order_id, owner, amount0, amount1 });
+        Ok((amount0, amount1))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::tick_math::{get_sqrt_ratio_at_tick, max_liquidity_per_tick, Q96};
+    use stylus_sdk::{
+        abi::Router,
+        alloy_primitives::{Address, U32},
+    };
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // Price 1, 0.3% fee, spacing 60, with 1e21 liquidity over [-6000, 6000),
+    // as init and a first mint leave it.
+    fn pool() -> ConcentratedLiquidityLimitOrders {
+        let mut pool = deploy::<ConcentratedLiquidityLimitOrders>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+            mint_erc20(token, contract(), U256::from(1_000_000 * E18));
+        }
+        let state = &mut pool.state;
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.fee.set(U32::from(3_000));
+        state.tick_spacing.set(U32::from(60));
+        state.max_liquidity_per_tick.set(U128::from(max_liquidity_per_tick(60)));
+        state.sqrt_price_x96.set(Q96);
+        state.modify_position(SENDER, -6_000, 6_000, (1_000 * E18) as i128).unwrap_ok();
+        pool
+    }
+
+    fn held() -> (U256, U256) {
+        (erc20_balance(TOKEN0, SENDER), erc20_balance(TOKEN1, SENDER))
+    }
+
+    fn open_epoch(pool: &ConcentratedLiquidityLimitOrders, tick_lower: i32, zero_for_one: bool) -> U256 {
+        pool.state.open_limit_epochs.get(ConcentratedLiquidityState::epoch_key(tick_lower, zero_for_one))
+    }
+
+    fn status(pool: &ConcentratedLiquidityLimitOrders, order_id: U256) -> u8 {
+        pool.state.limit_orders.get(order_id).status.get().saturating_to::<u8>()
+    }
+
+    // Puts the price at `tick` with `liquidity` active, as a fee-free swap there leaves it.
+    fn move_price(pool: &mut ConcentratedLiquidityLimitOrders, tick: i32, liquidity: u128) {
+        let state = &mut pool.state;
+        state.sqrt_price_x96.set(get_sqrt_ratio_at_tick(tick).unwrap());
+        state.tick.set(tick_key(tick));
+        state.liquidity.set(U128::from(liquidity));
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IConcentratedLiquidityLimitOrders::IConcentratedLiquidityLimitOrdersCalls::SELECTORS {
+            let routed = <ConcentratedLiquidityLimitOrders as Router<ConcentratedLiquidityLimitOrders>>::route(
+                &mut pool,
+                u32::from_be_bytes(*selector),
+                &[],
+            );
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn orders_at_a_range_and_side_share_an_epoch() {
+        let mut pool = pool();
+        assert!(matches!(pool.place_limit_order(0, true, E18), Err(PoolError::LimitOrderInRange(_))));
+        assert!(matches!(pool.place_limit_order(-60, true, E18), Err(PoolError::LimitOrderInRange(_))));
+
+        // two orders selling token0 over [60, 120) pay token0 only
+        let before = held();
+        let small = pool.place_limit_order(60, true, E18).unwrap_ok();
+        let large = pool.place_limit_order(60, true, 3 * E18).unwrap_ok();
+        assert_eq!(held().1, before.1);
+        let epoch = open_epoch(&pool, 60, true);
+        assert_eq!(pool.state.limit_orders.get(small).epoch.get(), epoch);
+        assert_eq!(pool.state.limit_orders.get(large).epoch.get(), epoch);
+        assert_eq!(status(&pool, large), ORDER_OPEN);
+        assert_eq!(pool.state.limit_epochs.get(epoch).liquidity.get(), U128::from(4 * E18));
+        let key = ConcentratedLiquidityState::position_key(ConcentratedLiquidityState::epoch_owner(epoch), 60, 120);
+        assert_eq!(pool.state.positions.get(key).liquidity.get(), U128::from(4 * E18));
+        // once filled, an order can only be claimed
+        pool.state.limit_epochs.setter(epoch).filled.set(true);
+        assert!(matches!(pool.cancel_limit_order(small), Err(PoolError::LimitOrderFilled(_))));
+    }
+
+    #[test]
+    fn cancelled_limit_orders_return_what_is_left() {
+        let mut pool = pool();
+        // two orders selling token1 over [-120, -60)
+        let before = held();
+        let first = pool.place_limit_order(-120, false, E18).unwrap_ok();
+        let paid1 = before.1 - held().1;
+        assert_eq!(held().0, before.0);
+        let second = pool.place_limit_order(-120, false, E18).unwrap_ok();
+        let epoch = open_epoch(&pool, -120, false);
+        assert!(matches!(pool.cancel_limit_order(U256::from(99)), Err(PoolError::UnknownLimitOrder(_))));
+
+        // untouched, the first gets its token1 back less rounding
+        let (amount0, amount1) = pool.cancel_limit_order(first).unwrap_ok();
+        assert_eq!(amount0, U256::ZERO);
+        assert!(amount1 <= paid1 && paid1 - amount1 <= U256::from(1));
+        assert_eq!(status(&pool, first), ORDER_CANCELLED);
+        assert!(matches!(pool.cancel_limit_order(first), Err(PoolError::LimitOrderClosed(_))));
+        assert_eq!(pool.state.limit_epochs.get(epoch).liquidity.get(), U128::from(E18));
+        assert_eq!(open_epoch(&pool, -120, false), epoch);
+
+        // halfway through the range the second is part token0, part token1
+        move_price(&mut pool, -90, 1_001 * E18);
+        let before = held();
+        let (amount0, amount1) = pool.cancel_limit_order(second).unwrap_ok();
+        assert!(amount0 > U256::ZERO && amount1 > U256::ZERO && amount1 < paid1);
+        assert_eq!(held(), (before.0 + amount0, before.1 + amount1));
+        // the emptied epoch closes, so the range no longer fills anything
+        assert_eq!(open_epoch(&pool, -120, false), U256::ZERO);
+        assert_eq!(pool.state.liquidity.get(), U128::from(1_000 * E18));
+        let key = ConcentratedLiquidityState::position_key(ConcentratedLiquidityState::epoch_owner(epoch), -120, -60);
+        assert_eq!(pool.state.positions.get(key).liquidity.get(), U128::ZERO);
+    }
+}
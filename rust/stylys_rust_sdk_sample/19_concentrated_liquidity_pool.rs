@@ -0,0 +1,363 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod concentrated_liquidity_state;
+use crate::concentrated_liquidity_state::*;
+use alloy_sol_types::{sol, SolInterface};
+use amm_math::{
+    swap_math::FEE_PIPS_DENOMINATOR,
+    tick_math::{get_tick_at_sqrt_ratio, max_liquidity_per_tick, MAX_TICK, MIN_TICK},
+};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, I16, U128, U256, U32},
+    call::{self, delegate_call},
+    evm,
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+sol! {
+    event Initialize(uint256 sqrt_price_x96, int24 tick);
+}
+
+// Concentrated liquidity (notes/amm/3_price_in_amm_models.md): each position
+// only backs trades while the price is inside its [tick_lower, tick_upper),
+// so a stablecoin LP in 0.99-1.01 gives the depth of a far larger x * y = k pool.
+//
+// Price is kept as sqrt(P) in Q64.96. Crossing an initialized tick adds or
+// removes that tick's `liquidity_net` from the active liquidity. Fees accrue per
+// unit of liquidity in `fee_growth_global`; each tick remembers the growth on
+// its far side, which is enough to work out the growth inside any range.
+//
+// Math lives in the amm_math crate (tick_math, sqrt_price_math, swap_math).
+// Tokens are pulled with transferFrom, so callers approve the pool first.
+//
+// Limit orders are one-spacing ranges that sit entirely on one side of the
+// price: selling token0 above it, token1 below. Orders at the same range and
+// side pool into an epoch, held as a single position under an address derived
+// from the epoch id. When a swap crosses the range's far tick the whole epoch
+// is pulled out of the pool, so the order can't convert back if the price
+// returns, and each owner claims their pro-rata share whenever they like.
+// An order the price is only partway through stays open and can be cancelled.
+//
+// The pool keeps setup and the views. Positions
+// (19_concentrated_liquidity_positions.rs), swaps
+// (19_concentrated_liquidity_swap.rs) and limit orders
+// (19_concentrated_liquidity_limit_orders.rs for placing and cancelling them,
+// 19_concentrated_liquidity_limit_fills.rs for filling and claiming) live in facets, which the pool
+// delegate_calls for their selectors, so callers use the pool's address for
+// everything (see concentrated_liquidity_state.rs for the layout).
+sol_storage! {
+    pub struct ConcentratedLiquidityPool {
+        #[borrow]
+        ConcentratedLiquidityState state;
+    }
+}
+
+// `#[entrypoint]` goes on `dispatch` below, which adds the facet fallback.
+unsafe impl TopLevelStorage for ConcentratedLiquidityPool {}
+
+// Runs the pool's own methods, then hands any selector a facet serves to that
+// facet, on the pool's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut pool = unsafe { ConcentratedLiquidityPool::new(U256::ZERO, 0) };
+    if let Some(result) =
+        <ConcentratedLiquidityPool as Router<ConcentratedLiquidityPool>>::route(&mut pool, u32::from_be_bytes(selector), &input[4..])
+    {
+        return result;
+    }
+    let Some(facet) = pool.facet(selector) else {
+        return Err(Vec::new());
+    };
+    unsafe { delegate_call(&mut pool, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
+
+impl ConcentratedLiquidityPool {
+    fn facet(&self, selector: [u8; 4]) -> Option<Address> {
+        if IConcentratedLiquidityPositions::IConcentratedLiquidityPositionsCalls::valid_selector(selector) {
+            Some(self.state.positions_facet.get())
+        } else if IConcentratedLiquiditySwap::IConcentratedLiquiditySwapCalls::valid_selector(selector) {
+            Some(self.state.swap_facet.get())
+        } else if IConcentratedLiquidityLimitOrders::IConcentratedLiquidityLimitOrdersCalls::valid_selector(selector) {
+            Some(self.state.limit_order_facet.get())
+        } else if IConcentratedLiquidityLimitFills::IConcentratedLiquidityLimitFillsCalls::valid_selector(selector) {
+            Some(self.state.limit_fill_facet.get())
+        } else {
+            None
+        }
+    }
+}
+
+#[external]
+impl ConcentratedLiquidityPool {
+    // Tick spacing 1 with a 0.01% fee suits stablecoins; 60 with 0.3% suits volatile pairs.
+    // The facets are fixed here for good: swapping one would change the pool's code.
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        fee: u32,
+        tick_spacing: u32,
+        sqrt_price_x96: U256,
+        positions_facet: Address,
+        swap_facet: Address,
+        limit_order_facet: Address,
+        limit_fill_facet: Address,
+    ) -> Result<(), PoolError> {
+        if self.state.sqrt_price_x96.get() != U256::ZERO {
+            return Err(PoolError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if tick_spacing == 0 || tick_spacing > 16384 {
+            return Err(PoolError::InvalidTickSpacing(InvalidTickSpacing { tick_spacing }));
+        }
+        // the fee is in pips of the input; a 100% fee would leave nothing to swap
+        if fee >= FEE_PIPS_DENOMINATOR {
+            return Err(PoolError::InvalidFee(InvalidFee { fee }));
+        }
+        let tick = get_tick_at_sqrt_ratio(sqrt_price_x96).ok_or(PoolError::InvalidPrice(InvalidPrice { sqrt_price_x96 }))?;
+        self.state.token0.set(token0);
+        self.state.token1.set(token1);
+        self.state.fee.set(U32::from(fee));
+        self.state.tick_spacing.set(U32::from(tick_spacing));
+        self.state.max_liquidity_per_tick.set(U128::from(max_liquidity_per_tick(tick_spacing as i32)));
+        self.state.sqrt_price_x96.set(sqrt_price_x96);
+        self.state.tick.set(tick_key(tick));
+        self.state.positions_facet.set(positions_facet);
+        self.state.swap_facet.set(swap_facet);
+        self.state.limit_order_facet.set(limit_order_facet);
+        self.state.limit_fill_facet.set(limit_fill_facet);
+        evm::log(Initialize { sqrt_price_x96, tick });
+        Ok(())
+    }
+
+    // (positions_facet, swap_facet, limit_order_facet, limit_fill_facet)
+    pub fn facets(&self) -> Result<(Address, Address, Address, Address), PoolError> {
+        let state = &self.state;
+        Ok((state.positions_facet.get(), state.swap_facet.get(), state.limit_order_facet.get(), state.limit_fill_facet.get()))
+    }
+
+    // (owner, epoch, tick_lower, zero_for_one, liquidity, status); see ORDER_*.
+    pub fn limit_order(&self, order_id: U256) -> Result<(Address, U256, i32, bool, u128, u8), PoolError> {
+        let order = self.state.limit_orders.get(order_id);
+        let epoch = self.state.limit_epochs.get(order.epoch.get());
+        let mut status = order.status.get().saturating_to::<u8>();
+        if status == ORDER_OPEN && epoch.filled.get() {
+            status = ORDER_FILLED;
+        }
+        Ok((
+            order.owner.get(),
+            order.epoch.get(),
+            i32::try_from(epoch.tick_lower.get()).unwrap(),
+            epoch.zero_for_one.get(),
+            order.liquidity.get().saturating_to::<u128>(),
+            status,
+        ))
+    }
+
+    // What `claim_limit_order` would pay now; zero until the order fills.
+    pub fn limit_order_claimable(&self, order_id: U256) -> Result<(U256, U256), PoolError> {
+        match self.state.open_limit_order(order_id) {
+            Ok((_, _, _, true)) => self.state.limit_order_proceeds(order_id),
+            _ => Ok((U256::ZERO, U256::ZERO)),
+        }
+    }
+
+    // (tick_lower, zero_for_one, liquidity, filled, amount0, amount1)
+    pub fn limit_epoch(&self, epoch: U256) -> Result<(i32, bool, u128, bool, U256, U256), PoolError> {
+        let entry = self.state.limit_epochs.get(epoch);
+        Ok((
+            i32::try_from(entry.tick_lower.get()).unwrap(),
+            entry.zero_for_one.get(),
+            entry.liquidity.get().saturating_to::<u128>(),
+            entry.filled.get(),
+            entry.amount0.get(),
+            entry.amount1.get(),
+        ))
+    }
+
+    // The epoch new orders at this range and side join, 0 if none is open.
+    pub fn open_limit_epoch(&self, tick_lower: i32, zero_for_one: bool) -> Result<U256, PoolError> {
+        Ok(self.state.open_limit_epochs.get(ConcentratedLiquidityState::epoch_key(tick_lower, zero_for_one)))
+    }
+
+    pub fn slot0(&self) -> Result<(U256, i32), PoolError> {
+        Ok((self.state.sqrt_price_x96.get(), self.state.current_tick()))
+    }
+
+    pub fn liquidity(&self) -> Result<u128, PoolError> {
+        Ok(self.state.active_liquidity())
+    }
+
+    pub fn fee_growth_global(&self) -> Result<(U256, U256), PoolError> {
+        Ok((self.state.fee_growth_global0_x128.get(), self.state.fee_growth_global1_x128.get()))
+    }
+
+    pub fn tokens(&self) -> Result<(Address, Address), PoolError> {
+        Ok((self.state.token0.get(), self.state.token1.get()))
+    }
+
+    pub fn fee(&self) -> Result<u32, PoolError> {
+        Ok(self.state.fee.get().saturating_to::<u32>())
+    }
+
+    pub fn tick_spacing(&self) -> Result<u32, PoolError> {
+        Ok(self.state.tick_spacing.get().saturating_to::<u32>())
+    }
+
+    // (liquidity_gross, liquidity_net, fee_growth_outside0, fee_growth_outside1)
+    pub fn ticks(&self, tick: i32) -> Result<(u128, i128, U256, U256), PoolError> {
+        if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+            return Ok((0, 0, U256::ZERO, U256::ZERO));
+        }
+        let info = self.state.ticks.get(tick_key(tick));
+        Ok((
+            info.liquidity_gross.get().saturating_to::<u128>(),
+            to_i128(info.liquidity_net.get()),
+            info.fee_growth_outside0_x128.get(),
+            info.fee_growth_outside1_x128.get(),
+        ))
+    }
+
+    // (liquidity, tokens_owed0, tokens_owed1); owed fees only update on burn/mint.
+    pub fn position(&self, owner: Address, tick_lower: i32, tick_upper: i32) -> Result<(u128, U256, U256), PoolError> {
+        let position = self.state.positions.get(ConcentratedLiquidityState::position_key(owner, tick_lower, tick_upper));
+        Ok((position.liquidity.get().saturating_to::<u128>(), position.tokens_owed0.get(), position.tokens_owed1.get()))
+    }
+
+    pub fn tick_bitmap(&self, word_pos: i16) -> Result<U256, PoolError> {
+        Ok(self.state.tick_bitmap.get(I16::try_from(word_pos).unwrap()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use amm_math::tick_math::{MAX_SQRT_RATIO, Q96};
+    use stylus_sdk::alloy_primitives::U8;
+    use test_vm::{calls, deploy, mock_call, CallKind, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const POSITIONS: Address = Address::repeat_byte(0x20);
+    const SWAP: Address = Address::repeat_byte(0x21);
+    const LIMIT_ORDERS: Address = Address::repeat_byte(0x22);
+    const LIMIT_FILLS: Address = Address::repeat_byte(0x23);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn init(pool: &mut ConcentratedLiquidityPool, fee: u32, tick_spacing: u32, sqrt_price_x96: U256) -> Result<(), PoolError> {
+        pool.init(TOKEN0, TOKEN1, fee, tick_spacing, sqrt_price_x96, POSITIONS, SWAP, LIMIT_ORDERS, LIMIT_FILLS)
+    }
+
+    #[test]
+    fn init_rejects_a_full_fee() {
+        let mut pool = deploy::<ConcentratedLiquidityPool>();
+        assert!(matches!(init(&mut pool, FEE_PIPS_DENOMINATOR, 60, Q96), Err(PoolError::InvalidFee(_))));
+        assert!(matches!(init(&mut pool, 3_000, 0, Q96), Err(PoolError::InvalidTickSpacing(_))));
+        assert!(matches!(init(&mut pool, 3_000, 60, MAX_SQRT_RATIO), Err(PoolError::InvalidPrice(_))));
+        init(&mut pool, FEE_PIPS_DENOMINATOR - 1, 60, Q96).unwrap_ok();
+        assert_eq!(pool.fee().unwrap_ok(), FEE_PIPS_DENOMINATOR - 1);
+        assert_eq!(pool.facets().unwrap_ok(), (POSITIONS, SWAP, LIMIT_ORDERS, LIMIT_FILLS));
+        assert!(matches!(init(&mut pool, 3_000, 60, Q96), Err(PoolError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn limit_order_views_follow_the_epoch() {
+        let mut pool = deploy::<ConcentratedLiquidityPool>();
+        init(&mut pool, 3_000, 60, Q96).unwrap_ok();
+        assert_eq!(pool.limit_order(U256::from(1)).unwrap_ok().5, ORDER_NONE);
+        assert_eq!(pool.limit_order_claimable(U256::from(1)).unwrap_ok(), (U256::ZERO, U256::ZERO));
+
+        // orders of 1e18 and 3e18 selling token0 over [60, 120), as placing them leaves it
+        let (epoch, small) = (U256::from(1), U256::from(1));
+        let state = &mut pool.state;
+        state.open_limit_epochs.insert(ConcentratedLiquidityState::epoch_key(60, true), epoch);
+        let mut entry = state.limit_epochs.setter(epoch);
+        entry.tick_lower.set(tick_key(60));
+        entry.zero_for_one.set(true);
+        entry.liquidity.set(U128::from(4 * E18));
+        for (order_id, liquidity) in [(small, E18), (U256::from(2), 3 * E18)] {
+            let mut order = state.limit_orders.setter(order_id);
+            order.owner.set(SENDER);
+            order.epoch.set(epoch);
+            order.liquidity.set(U128::from(liquidity));
+            order.status.set(U8::from(ORDER_OPEN));
+        }
+        assert_eq!(pool.open_limit_epoch(60, true).unwrap_ok(), epoch);
+        assert_eq!(pool.limit_order(small).unwrap_ok(), (SENDER, epoch, 60, true, E18, ORDER_OPEN));
+        assert_eq!(pool.limit_order_claimable(small).unwrap_ok(), (U256::ZERO, U256::ZERO));
+
+        // filled for 401 token1, an open order reads as filled and a quarter is claimable
+        let amount1 = U256::from(401 * E18);
+        let mut entry = pool.state.limit_epochs.setter(epoch);
+        entry.filled.set(true);
+        entry.amount1.set(amount1);
+        assert_eq!(pool.limit_epoch(epoch).unwrap_ok(), (60, true, 4 * E18, true, U256::ZERO, amount1));
+        assert_eq!(pool.limit_order(small).unwrap_ok().5, ORDER_FILLED);
+        assert_eq!(pool.limit_order_claimable(small).unwrap_ok(), (U256::ZERO, amount1 / U256::from(4)));
+        pool.state.limit_orders.setter(small).status.set(U8::from(ORDER_CLAIMED));
+        assert_eq!(pool.limit_order(small).unwrap_ok().5, ORDER_CLAIMED);
+        assert_eq!(pool.limit_order_claimable(small).unwrap_ok(), (U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn facet_selectors_run_on_the_facets() {
+        let mut pool = deploy::<ConcentratedLiquidityPool>();
+        init(&mut pool, 3_000, 60, Q96).unwrap_ok();
+        mock_call(SWAP, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        mock_call(LIMIT_ORDERS, |_| Err(b"limit orders say no".to_vec()));
+
+        let data = IConcentratedLiquiditySwap::swapCall {
+            recipient: SENDER,
+            zero_for_one: true,
+            amount: U256::from(E18),
+            exact_input: true,
+            sqrt_price_limit_x96: U256::from(1),
+        }
+        .abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+        mock_call(POSITIONS, |_| Ok(Vec::new()));
+        let data = IConcentratedLiquidityPositions::burnCall { tick_lower: -60, tick_upper: 60, amount: 0 }.abi_encode();
+        dispatch(data).unwrap_ok();
+        assert_eq!(calls().last().unwrap().to, POSITIONS);
+        // facet reverts come back as they are
+        let data = IConcentratedLiquidityLimitOrders::cancelLimitOrderCall { order_id: U256::from(1) }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"limit orders say no".to_vec());
+        assert_eq!(calls().last().unwrap().to, LIMIT_ORDERS);
+        mock_call(LIMIT_FILLS, |_| Ok(Vec::new()));
+        dispatch(IConcentratedLiquidityLimitFills::claimLimitOrderCall { order_id: U256::from(1) }.abi_encode()).unwrap_ok();
+        assert_eq!(calls().last().unwrap().to, LIMIT_FILLS);
+        // the pool's own methods don't leave it
+        assert_eq!(pool.facet(stylus_sdk::function_selector!("slot0")), None);
+        let fills = ILimitEpochFills::fillLimitEpochsCall { ticks: vec![60], zero_for_one: true }.abi_encode();
+        assert_eq!(dispatch(fills).err().unwrap(), Vec::<u8>::new());
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+    }
+}
@@ -0,0 +1,168 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod concentrated_liquidity_state;
+use crate::concentrated_liquidity_state::*;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U128, U256},
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event Mint(address indexed owner, int24 indexed tick_lower, int24 indexed tick_upper, uint128 liquidity, uint256 amount0, uint256 amount1);
+    event Burn(address indexed owner, int24 indexed tick_lower, int24 indexed tick_upper, uint128 liquidity, uint256 amount0, uint256 amount1);
+    event Collect(address indexed owner, address recipient, int24 indexed tick_lower, int24 indexed tick_upper, uint256 amount0, uint256 amount1);
+}
+
+// Liquidity positions for the concentrated liquidity pool in
+// 19_concentrated_liquidity_pool.rs. The pool delegate_calls this facet for the
+// `IConcentratedLiquidityPositions` selectors, so it runs on the pool's storage
+// and holds the pool's tokens (see concentrated_liquidity_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct ConcentratedLiquidityPositions {
+        #[borrow]
+        ConcentratedLiquidityState state;
+    }
+}
+
+#[external]
+impl ConcentratedLiquidityPositions {
+    // Adds `amount` of liquidity to `recipient`'s range; the caller pays.
+    pub fn mint(&mut self, recipient: Address, tick_lower: i32, tick_upper: i32, amount: u128) -> Result<(U256, U256), PoolError> {
+        if amount == 0 {
+            return Err(PoolError::ZeroLiquidity(ZeroLiquidity {}));
+        }
+        let delta = i128::try_from(amount).map_err(|_| overflow())?;
+        let (amount0, amount1) = self.state.modify_position(recipient, tick_lower, tick_upper, delta)?;
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        self.pull(token0, msg::sender(), amount0)?;
+        self.pull(token1, msg::sender(), amount1)?;
+        evm::log(Mint { owner: recipient, tick_lower, tick_upper, liquidity: amount, amount0, amount1 });
+        Ok((amount0, amount1))
+    }
+
+    // Removes liquidity from the caller's range; the tokens are added to what the
+    // position is owed and paid out by `collect`. Burning 0 just updates fees.
+    pub fn burn(&mut self, tick_lower: i32, tick_upper: i32, amount: u128) -> Result<(U256, U256), PoolError> {
+        let owner = msg::sender();
+        let delta = i128::try_from(amount).map_err(|_| overflow())?;
+        if amount == 0
+            && self.state.positions.get(ConcentratedLiquidityState::position_key(owner, tick_lower, tick_upper)).liquidity.get()
+                == U128::ZERO
+        {
+            return Err(PoolError::ZeroLiquidity(ZeroLiquidity {}));
+        }
+        let (amount0, amount1) = self.state.modify_position(owner, tick_lower, tick_upper, -delta)?;
+
+        let mut position = self.state.positions.setter(ConcentratedLiquidityState::position_key(owner, tick_lower, tick_upper));
+        let tokens_owed0 = position.tokens_owed0.get() + amount0;
+        let tokens_owed1 = position.tokens_owed1.get() + amount1;
+        position.tokens_owed0.set(tokens_owed0);
+        position.tokens_owed1.set(tokens_owed1);
+        evm::log(Burn { owner, tick_lower, tick_upper, liquidity: amount, amount0, amount1 });
+        Ok((amount0, amount1))
+    }
+
+    pub fn collect(
+        &mut self,
+        recipient: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0_requested: U256,
+        amount1_requested: U256,
+    ) -> Result<(U256, U256), PoolError> {
+        let owner = msg::sender();
+        let mut position = self.state.positions.setter(ConcentratedLiquidityState::position_key(owner, tick_lower, tick_upper));
+        let (owed0, owed1) = (position.tokens_owed0.get(), position.tokens_owed1.get());
+        let amount0 = amount0_requested.min(owed0);
+        let amount1 = amount1_requested.min(owed1);
+        position.tokens_owed0.set(owed0 - amount0);
+        position.tokens_owed1.set(owed1 - amount1);
+
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        self.transfer(token0, recipient, amount0)?;
+        self.transfer(token1, recipient, amount1)?;
+        evm::log(Collect { owner, recipient, tick_lower, tick_upper, amount0, amount1 });
+        Ok((amount0, amount1))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::tick_math::{max_liquidity_per_tick, Q96};
+    use stylus_sdk::{abi::Router, alloy_primitives::U32};
+    use test_vm::{deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // Price 1, 0.3% fee, spacing 60, as init leaves it.
+    fn pool() -> ConcentratedLiquidityPositions {
+        let mut pool = deploy::<ConcentratedLiquidityPositions>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+        }
+        let state = &mut pool.state;
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.fee.set(U32::from(3_000));
+        state.tick_spacing.set(U32::from(60));
+        state.max_liquidity_per_tick.set(U128::from(max_liquidity_per_tick(60)));
+        state.sqrt_price_x96.set(Q96);
+        pool
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IConcentratedLiquidityPositions::IConcentratedLiquidityPositionsCalls::SELECTORS {
+            let routed = <ConcentratedLiquidityPositions as Router<ConcentratedLiquidityPositions>>::route(
+                &mut pool,
+                u32::from_be_bytes(*selector),
+                &[],
+            );
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn positions_mint_burn_and_collect() {
+        let mut pool = pool();
+        assert!(matches!(pool.mint(SENDER, -6_000, 6_001, E18), Err(PoolError::InvalidTickRange(_))));
+        assert!(matches!(pool.burn(-6_000, 6_000, 0), Err(PoolError::ZeroLiquidity(_))));
+
+        // at price 1 a symmetric range takes both tokens equally
+        let (paid0, paid1) = pool.mint(SENDER, -6_000, 6_000, 1_000 * E18).unwrap_ok();
+        assert_eq!(paid0, paid1);
+        assert_eq!(pool.state.active_liquidity(), 1_000 * E18);
+        assert_eq!(to_i128(pool.state.ticks.get(tick_key(-6_000)).liquidity_net.get()), (1_000 * E18) as i128);
+
+        let (out0, out1) = pool.burn(-6_000, 6_000, 1_000 * E18).unwrap_ok();
+        assert!(paid0 - out0 <= U256::from(1) && paid1 - out1 <= U256::from(1));
+        let key = ConcentratedLiquidityState::position_key(SENDER, -6_000, 6_000);
+        assert_eq!(pool.state.positions.get(key).tokens_owed0.get(), out0);
+        assert_eq!(pool.state.active_liquidity(), 0);
+        // the emptied ticks are cleared
+        assert_eq!(pool.state.ticks.get(tick_key(-6_000)).liquidity_gross.get(), U128::ZERO);
+
+        assert_eq!(pool.collect(SENDER, -6_000, 6_000, U256::MAX, U256::MAX).unwrap_ok(), (out0, out1));
+        assert_eq!(erc20_balance(TOKEN0, SENDER), U256::from(1_000_000 * E18) - paid0 + out0);
+        assert_eq!(pool.state.positions.get(key).tokens_owed1.get(), U256::ZERO);
+    }
+}
@@ -0,0 +1,257 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod concentrated_liquidity_state;
+use crate::concentrated_liquidity_state::*;
+use alloy_sol_types::{sol, SolCall};
+use amm_math::{
+    full_math::mul_div,
+    sqrt_price_math::add_delta,
+    swap_math::compute_swap_step,
+    tick_math::{get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK, Q128},
+};
+use stylus_sdk::{
+    alloy_primitives::{Address, U128, U256},
+    call::delegate_call,
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event Swap(address indexed sender, address indexed recipient, bool zero_for_one, uint256 amount_in, uint256 amount_out, uint256 sqrt_price_x96, uint128 liquidity, int24 tick);
+}
+
+// Swaps for the concentrated liquidity pool in 19_concentrated_liquidity_pool.rs.
+// The pool delegate_calls this facet for the `IConcentratedLiquiditySwap`
+// selectors, so it runs on the pool's storage (see
+// concentrated_liquidity_state.rs for the layout). A swap that crosses the far
+// tick of a limit-order range also fills the epoch waiting there, through the
+// limit fill facet.
+sol_storage! {
+    #[entrypoint]
+    pub struct ConcentratedLiquiditySwap {
+        #[borrow]
+        ConcentratedLiquidityState state;
+    }
+}
+
+#[external]
+impl ConcentratedLiquiditySwap {
+    // Sells token0 for token1 (`zero_for_one`) or the reverse. `amount` is the
+    // input when `exact_input`, the output otherwise. Stops early at
+    // `sqrt_price_limit_x96`. Returns (amount in incl. fee, amount out).
+    pub fn swap(
+        &mut self,
+        recipient: Address,
+        zero_for_one: bool,
+        amount: U256,
+        exact_input: bool,
+        sqrt_price_limit_x96: U256,
+    ) -> Result<(U256, U256), PoolError> {
+        let mut sqrt_price = self.state.sqrt_price_x96.get();
+        if sqrt_price == U256::ZERO {
+            return Err(PoolError::NotInitialized(NotInitialized {}));
+        }
+        let limit_ok = if zero_for_one {
+            sqrt_price_limit_x96 < sqrt_price && sqrt_price_limit_x96 > MIN_SQRT_RATIO
+        } else {
+            sqrt_price_limit_x96 > sqrt_price && sqrt_price_limit_x96 < MAX_SQRT_RATIO
+        };
+        if !limit_ok {
+            return Err(PoolError::InvalidPriceLimit(InvalidPriceLimit { sqrt_price_limit_x96 }));
+        }
+
+        let fee = self.state.fee.get().saturating_to::<u32>();
+        let mut tick = self.state.current_tick();
+        let mut liquidity = self.state.active_liquidity();
+        // only the input token earns fees
+        let (mut fee_growth_in, fee_growth_other) = if zero_for_one {
+            (self.state.fee_growth_global0_x128.get(), self.state.fee_growth_global1_x128.get())
+        } else {
+            (self.state.fee_growth_global1_x128.get(), self.state.fee_growth_global0_x128.get())
+        };
+        let (mut remaining, mut amount_in, mut amount_out) = (amount, U256::ZERO, U256::ZERO);
+        // limit epochs are filled once the pool state is written back
+        let mut crossed = Vec::new();
+
+        // one step per initialized tick (or bitmap word) until the amount or the limit runs out
+        while remaining > U256::ZERO && sqrt_price != sqrt_price_limit_x96 {
+            let (tick_next, initialized) = self.state.next_initialized_tick(tick, zero_for_one);
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next = sqrt_ratio_at(tick_next)?;
+            let target = if zero_for_one { sqrt_price_next.max(sqrt_price_limit_x96) } else { sqrt_price_next.min(sqrt_price_limit_x96) };
+
+            let step = compute_swap_step(sqrt_price, target, liquidity, remaining, exact_input, fee).ok_or_else(overflow)?;
+            let step_start = sqrt_price;
+            sqrt_price = step.sqrt_price_next_x96;
+            let step_in = step.amount_in + step.fee_amount;
+            remaining -= if exact_input { step_in } else { step.amount_out };
+            amount_in += step_in;
+            amount_out += step.amount_out;
+            if liquidity > 0 {
+                fee_growth_in += mul_div(step.fee_amount, Q128, U256::from(liquidity)).ok_or_else(overflow)?;
+            }
+
+            if sqrt_price == sqrt_price_next {
+                if initialized {
+                    let (global0, global1) =
+                        if zero_for_one { (fee_growth_in, fee_growth_other) } else { (fee_growth_other, fee_growth_in) };
+                    let net = self.state.cross_tick(tick_next, global0, global1);
+                    // moving left crosses the tick from above, so its net is reversed
+                    let net = if zero_for_one { -net } else { net };
+                    liquidity = add_delta(liquidity, net).ok_or_else(overflow)?;
+                    crossed.push(tick_next);
+                }
+                tick = if zero_for_one { tick_next - 1 } else { tick_next };
+            } else if sqrt_price != step_start {
+                tick = get_tick_at_sqrt_ratio(sqrt_price).ok_or_else(overflow)?;
+            }
+        }
+
+        self.state.sqrt_price_x96.set(sqrt_price);
+        self.state.tick.set(tick_key(tick));
+        self.state.liquidity.set(U128::from(liquidity));
+        if zero_for_one {
+            self.state.fee_growth_global0_x128.set(fee_growth_in);
+        } else {
+            self.state.fee_growth_global1_x128.set(fee_growth_in);
+        }
+        if !crossed.is_empty() {
+            let facet = self.state.limit_fill_facet.get();
+            let data = ILimitEpochFills::fillLimitEpochsCall { ticks: crossed, zero_for_one }.abi_encode();
+            unsafe { delegate_call(&mut *self, facet, &data) }.map_err(|_| ConcentratedLiquidityState::failed(facet))?;
+        }
+
+        let (token_in, token_out) = if zero_for_one {
+            (self.state.token0.get(), self.state.token1.get())
+        } else {
+            (self.state.token1.get(), self.state.token0.get())
+        };
+        self.pull(token_in, msg::sender(), amount_in)?;
+        self.transfer(token_out, recipient, amount_out)?;
+        evm::log(Swap {
+            sender: msg::sender(),
+            recipient,
+            zero_for_one,
+            amount_in,
+            amount_out,
+            sqrt_price_x96: sqrt_price,
+            liquidity,
+            tick,
+        });
+        Ok((amount_in, amount_out))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::tick_math::{get_sqrt_ratio_at_tick, max_liquidity_per_tick, Q96};
+    use stylus_sdk::{abi::Router, alloy_primitives::U32};
+    use test_vm::{calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, CallKind, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const LIMIT_FILLS: Address = Address::repeat_byte(0x22);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // Price 1, 0.3% fee, spacing 60, with 1e21 liquidity over [-6000, 6000),
+    // as init and a first mint leave it.
+    fn pool() -> ConcentratedLiquiditySwap {
+        let mut pool = deploy::<ConcentratedLiquiditySwap>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+            mint_erc20(token, contract(), U256::from(1_000_000 * E18));
+        }
+        let state = &mut pool.state;
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.fee.set(U32::from(3_000));
+        state.tick_spacing.set(U32::from(60));
+        state.max_liquidity_per_tick.set(U128::from(max_liquidity_per_tick(60)));
+        state.sqrt_price_x96.set(Q96);
+        state.modify_position(SENDER, -6_000, 6_000, (1_000 * E18) as i128).unwrap_ok();
+        pool
+    }
+
+    // Moves the price to `tick` with an oversized exact-input swap.
+    fn swap_to(pool: &mut ConcentratedLiquiditySwap, tick: i32) {
+        let zero_for_one = tick < pool.state.current_tick();
+        pool.swap(SENDER, zero_for_one, U256::from(1_000_000 * E18), true, get_sqrt_ratio_at_tick(tick).unwrap()).unwrap_ok();
+        assert_eq!(pool.state.current_tick(), tick);
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IConcentratedLiquiditySwap::IConcentratedLiquiditySwapCalls::SELECTORS {
+            let routed =
+                <ConcentratedLiquiditySwap as Router<ConcentratedLiquiditySwap>>::route(&mut pool, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn swaps_pay_the_fee_and_respect_the_limit() {
+        let mut empty = deploy::<ConcentratedLiquiditySwap>();
+        assert!(matches!(
+            empty.swap(SENDER, true, U256::from(E18), true, MIN_SQRT_RATIO + U256::from(1)),
+            Err(PoolError::NotInitialized(_))
+        ));
+
+        let mut pool = pool();
+        assert!(matches!(pool.swap(SENDER, true, U256::from(E18), true, Q96 + U256::from(1)), Err(PoolError::InvalidPriceLimit(_))));
+        let before = erc20_balance(TOKEN1, SENDER);
+        let (amount_in, amount_out) = pool.swap(SENDER, true, U256::from(E18), true, MIN_SQRT_RATIO + U256::from(1)).unwrap_ok();
+        assert_eq!(amount_in, U256::from(E18));
+        // 1 token0 buys a bit under 0.997 token1 after the fee and price impact
+        assert!(amount_out < U256::from(E18 * 997 / 1000) && amount_out > U256::from(E18 * 996 / 1000), "{amount_out}");
+        assert_eq!(erc20_balance(TOKEN1, SENDER) - before, amount_out);
+        assert!(pool.state.fee_growth_global0_x128.get() > U256::ZERO);
+        assert!(pool.state.sqrt_price_x96.get() < Q96);
+    }
+
+    #[test]
+    fn crossing_initialized_ticks_fills_their_limit_epochs() {
+        let mut pool = pool();
+        pool.state.limit_fill_facet.set(LIMIT_FILLS);
+        // a range over [60, 120), as an epoch of limit orders leaves it
+        let owner = ConcentratedLiquidityState::epoch_owner(U256::from(1));
+        pool.state.modify_position(owner, 60, 120, (4 * E18) as i128).unwrap_ok();
+
+        // nothing crossed, nothing to fill
+        swap_to(&mut pool, 30);
+        assert!(calls().iter().all(|call| call.to != LIMIT_FILLS));
+
+        // rising to 150 crosses both of its ticks
+        mock_call(LIMIT_FILLS, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(Vec::new())
+        });
+        swap_to(&mut pool, 150);
+        let data = ILimitEpochFills::fillLimitEpochsCall { ticks: vec![60, 120], zero_for_one: false }.abi_encode();
+        let fills: Vec<_> = calls().into_iter().filter(|call| call.to == LIMIT_FILLS).collect();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].data, data);
+
+        // a failed fill reverts the swap
+        mock_call(LIMIT_FILLS, |_| Err(Vec::new()));
+        assert!(matches!(
+            pool.swap(SENDER, true, U256::from(1_000_000 * E18), true, get_sqrt_ratio_at_tick(-30).unwrap()),
+            Err(PoolError::ExternalCallFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod stable_swap_state;
+use crate::erc20::Erc20;
+use crate::stable_swap_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use amm_math::stable_swap::{get_d, get_y, get_y_d, imbalance_fee, FEE_DENOMINATOR};
+use stylus_sdk::{alloy_primitives::U256, evm, msg, prelude::*};
+
+sol! {
+    event TokenExchange(address indexed buyer, uint256 sold_id, uint256 tokens_sold, uint256 bought_id, uint256 tokens_bought);
+    event RemoveLiquidityOne(address indexed provider, uint256 token_amount, uint256 coin_index, uint256 coin_amount);
+}
+
+// Swaps and single-coin withdrawals for the StableSwap pool in
+// 20_stable_swap_pool.rs. The pool delegate_calls this facet for the
+// `IStableSwapExchange` selectors, so it runs on the pool's storage and burns
+// the pool's own LP token, which follows `StableSwapState` (see
+// stable_swap_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct StableSwapExchange {
+        #[borrow]
+        StableSwapState state;
+        Erc20<StableSwapParams> erc20;
+    }
+}
+
+impl StableSwapExchange {
+    // (dy after fee, fee), both in raw units of coin j.
+    fn calc_exchange(&self, i: usize, j: usize, dx: U256) -> Result<(U256, U256), StableSwapError> {
+        let state = &self.state;
+        let xp = state.xp(&state.stored_balances());
+        let (rate_i, rate_j) = (state.rates.get(i).unwrap(), state.rates.get(j).unwrap());
+        let x = xp[i] + dx * rate_i;
+        let y = get_y(i, j, x, &xp, state.amp()).ok_or_else(StableSwapState::no_convergence)?;
+        // -1 so rounding inside get_y can never pay out more than the curve allows
+        let dy = xp[j].saturating_sub(y).saturating_sub(U256::from(1));
+        let fee = dy * state.fee.get() / U256::from(FEE_DENOMINATOR);
+        Ok(((dy - fee) / rate_j, fee / rate_j))
+    }
+
+    // (amount of coin i, fee) for burning `token_amount` LP into a single coin.
+    fn withdraw_one_coin_amounts(&self, token_amount: U256, i: usize) -> Result<(U256, U256), StableSwapError> {
+        let supply = StableSwapState::supply_covering(self.erc20.total_supply.get(), token_amount)?;
+        let state = &self.state;
+        let amp = state.amp();
+        let xp = state.xp(&state.stored_balances());
+        let d0 = get_d(&xp, amp).ok_or_else(StableSwapState::no_convergence)?;
+        let d1 = d0 - token_amount * d0 / supply;
+        let new_y = get_y_d(i, &xp, amp, d1).ok_or_else(StableSwapState::no_convergence)?;
+
+        // charge the fee on how far each coin moves away from a proportional withdrawal
+        let fee = imbalance_fee(state.fee.get(), state.n_coins());
+        let xp_reduced: Vec<U256> = xp
+            .iter()
+            .enumerate()
+            .map(|(j, x)| {
+                let expected = if j == i { *x * d1 / d0 - new_y } else { *x - *x * d1 / d0 };
+                *x - fee * expected / U256::from(FEE_DENOMINATOR)
+            })
+            .collect();
+        let rate = state.rates.get(i).unwrap();
+        let y_reduced = get_y_d(i, &xp_reduced, amp, d1).ok_or_else(StableSwapState::no_convergence)?;
+        let dy = (xp_reduced[i] - y_reduced).saturating_sub(U256::from(1)) / rate;
+        let dy_without_fee = (xp[i] - new_y) / rate;
+        Ok((dy, dy_without_fee.saturating_sub(dy)))
+    }
+}
+
+#[external]
+impl StableSwapExchange {
+    pub fn get_dy(&self, i: U256, j: U256, dx: U256) -> Result<U256, StableSwapError> {
+        let (i, j) = (self.state.check_index(i)?, self.state.check_index(j)?);
+        Ok(self.calc_exchange(i, j, dx)?.0)
+    }
+
+    pub fn exchange(&mut self, i: U256, j: U256, dx: U256, min_dy: U256) -> Result<U256, StableSwapError> {
+        let (i, j) = (self.state.check_index(i)?, self.state.check_index(j)?);
+        let (dy, _) = self.calc_exchange(i, j, dx)?;
+        if dy < min_dy {
+            return Err(StableSwapError::Slippage(Slippage { amount: dy, limit: min_dy }));
+        }
+        self.pull(i, dx)?;
+
+        let mut balances = self.state.stored_balances();
+        balances[i] += dx;
+        // the fee is left in balances[j], where it accrues to LPs
+        balances[j] -= dy;
+        self.state.set_balances(&balances);
+        self.send(j, msg::sender(), dy)?;
+        evm::log(TokenExchange {
+            buyer: msg::sender(),
+            sold_id: U256::from(i),
+            tokens_sold: dx,
+            bought_id: U256::from(j),
+            tokens_bought: dy,
+        });
+        Ok(dy)
+    }
+
+    pub fn calc_withdraw_one_coin(&self, token_amount: U256, i: U256) -> Result<U256, StableSwapError> {
+        let i = self.state.check_index(i)?;
+        Ok(self.withdraw_one_coin_amounts(token_amount, i)?.0)
+    }
+
+    pub fn remove_liquidity_one_coin(&mut self, token_amount: U256, i: U256, min_amount: U256) -> Result<U256, StableSwapError> {
+        let index = self.state.check_index(i)?;
+        let (dy, _) = self.withdraw_one_coin_amounts(token_amount, index)?;
+        if dy < min_amount {
+            return Err(StableSwapError::Slippage(Slippage { amount: dy, limit: min_amount }));
+        }
+        self.erc20.burn(msg::sender(), token_amount)?;
+        let mut balances = self.state.stored_balances();
+        balances[index] -= dy;
+        self.state.set_balances(&balances);
+        self.send(index, msg::sender(), dy)?;
+        evm::log(RemoveLiquidityOne { provider: msg::sender(), token_amount, coin_index: i, coin_amount: dy });
+        Ok(dy)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::stable_swap::A_PRECISION;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const DAI: Address = Address::repeat_byte(0xda);
+    const USDC: Address = Address::repeat_byte(0xc0);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // DAI/USDC at A = 200 and a 0.04% fee, as init and a first deposit of
+    // 500k of each leave it, with the 1M LP minted to SENDER.
+    fn pool() -> StableSwapExchange {
+        let mut pool = deploy::<StableSwapExchange>();
+        let state = &mut pool.state;
+        for coin in [DAI, USDC] {
+            mock_erc20(coin);
+            mint_erc20(coin, SENDER, U256::from(1_000_000 * E18));
+            mint_erc20(coin, contract(), U256::from(500_000 * E18));
+            state.coins.push(coin);
+            state.balances.push(U256::from(500_000 * E18));
+            state.rates.push(U256::from(1));
+        }
+        state.initial_a.set(U256::from(200 * A_PRECISION));
+        state.future_a.set(U256::from(200 * A_PRECISION));
+        state.fee.set(U256::from(4_000_000));
+        pool.erc20.mint(SENDER, U256::from(1_000_000 * E18));
+        pool
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IStableSwapExchange::IStableSwapExchangeCalls::SELECTORS {
+            let routed = <StableSwapExchange as Router<StableSwapExchange>>::route(&mut pool, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn one_coin_withdrawals_need_the_supply_to_cover_them() {
+        let mut pool = pool();
+        let too_much = U256::from(1_000_000 * E18 + 1);
+        assert!(matches!(pool.calc_withdraw_one_coin(too_much, U256::ZERO), Err(StableSwapError::InsufficientShares(_))));
+        assert!(matches!(pool.calc_withdraw_one_coin(U256::from(1), U256::from(2)), Err(StableSwapError::InvalidCoinIndex(_))));
+
+        pool.erc20.burn(SENDER, U256::from(1_000_000 * E18)).unwrap_ok();
+        assert!(matches!(pool.calc_withdraw_one_coin(U256::from(1), U256::ZERO), Err(StableSwapError::EmptyPool(_))));
+        assert!(matches!(pool.remove_liquidity_one_coin(U256::from(1), U256::ZERO, U256::ZERO), Err(StableSwapError::EmptyPool(_))));
+    }
+
+    #[test]
+    fn swap_and_withdraw_one_coin() {
+        let mut pool = pool();
+        // near balance 1 DAI buys 1 USDC less the 0.04% fee
+        let quoted = pool.get_dy(U256::ZERO, U256::from(1), U256::from(E18)).unwrap_ok();
+        let dy = pool.exchange(U256::ZERO, U256::from(1), U256::from(E18), U256::ZERO).unwrap_ok();
+        assert_eq!(dy, quoted);
+        assert!(dy > U256::from(E18 * 9_995 / 10_000) && dy < U256::from(E18 * 9_996 / 10_000), "{dy}");
+        assert_eq!(erc20_balance(USDC, contract()), U256::from(500_000 * E18) - dy);
+        assert!(matches!(pool.exchange(U256::ZERO, U256::from(1), U256::from(E18), U256::from(E18)), Err(StableSwapError::Slippage(_))));
+
+        // 1 LP is worth a bit over 1 USDC after the fee accrued, less the withdrawal fee
+        let quoted = pool.calc_withdraw_one_coin(U256::from(E18), U256::from(1)).unwrap_ok();
+        let out = pool.remove_liquidity_one_coin(U256::from(E18), U256::from(1), U256::ZERO).unwrap_ok();
+        assert_eq!(out, quoted);
+        assert!(out > U256::from(E18 * 999 / 1_000) && out < U256::from(E18), "{out}");
+        assert_eq!(pool.erc20.balance_of(SENDER).unwrap_ok(), U256::from(999_999 * E18));
+        assert_eq!(erc20_balance(USDC, contract()), pool.state.balances.get(1).unwrap());
+    }
+}
@@ -0,0 +1,274 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod stable_swap_state;
+use crate::erc20::Erc20;
+use crate::stable_swap_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use amm_math::stable_swap::{imbalance_fee, FEE_DENOMINATOR};
+use stylus_sdk::{alloy_primitives::U256, evm, msg, prelude::*};
+
+sol! {
+    event AddLiquidity(address indexed provider, uint256[] token_amounts, uint256[] fees, uint256 invariant, uint256 token_supply);
+    event RemoveLiquidity(address indexed provider, uint256[] token_amounts, uint256 token_supply);
+    event RemoveLiquidityImbalance(address indexed provider, uint256[] token_amounts, uint256[] fees, uint256 invariant, uint256 token_supply);
+}
+
+// Adds and multi-coin removes for the StableSwap pool in 20_stable_swap_pool.rs.
+// The pool delegate_calls this facet for the `IStableSwapLiquidity` selectors,
+// so it runs on the pool's storage and mints and burns the pool's own LP
+// token, which follows `StableSwapState` (see stable_swap_state.rs for the
+// layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct StableSwapLiquidity {
+        #[borrow]
+        StableSwapState state;
+        Erc20<StableSwapParams> erc20;
+    }
+}
+
+impl StableSwapLiquidity {
+    // Imbalanced adds and removes pay the swap fee on the part of each coin that
+    // differs from a proportional deposit. Returns (fees per coin, D after fees).
+    fn imbalance_fees(&self, old: &[U256], new: &[U256], d0: U256, d1: U256, amp: U256) -> Result<(Vec<U256>, U256), StableSwapError> {
+        let fee = imbalance_fee(self.state.fee.get(), self.state.n_coins());
+        let mut fees = Vec::with_capacity(old.len());
+        let mut after_fees = Vec::with_capacity(old.len());
+        for (old_balance, new_balance) in old.iter().zip(new) {
+            let ideal = d1 * *old_balance / d0;
+            let charged = fee * ideal.abs_diff(*new_balance) / U256::from(FEE_DENOMINATOR);
+            fees.push(charged);
+            after_fees.push(*new_balance - charged);
+        }
+        let d2 = self.state.d(&after_fees, amp)?;
+        Ok((fees, d2))
+    }
+}
+
+#[external]
+impl StableSwapLiquidity {
+    // Value of one LP token in the pegged unit, 1e18 = 1.0; only fees move it up.
+    pub fn get_virtual_price(&self) -> Result<U256, StableSwapError> {
+        let supply = self.erc20.total_supply.get();
+        if supply == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let d = self.state.d(&self.state.stored_balances(), self.state.amp())?;
+        Ok(d * U256::from(10).pow(U256::from(PRECISION_DECIMALS)) / supply)
+    }
+
+    // LP minted (or burned, when !is_deposit) for these amounts, before fees.
+    pub fn calc_token_amount(&self, amounts: Vec<U256>, is_deposit: bool) -> Result<U256, StableSwapError> {
+        let state = &self.state;
+        state.check_amounts(&amounts)?;
+        let amp = state.amp();
+        let old = state.stored_balances();
+        let d0 = state.d(&old, amp)?;
+        let new: Vec<U256> = old.iter().zip(&amounts).map(|(b, a)| if is_deposit { *b + *a } else { b.saturating_sub(*a) }).collect();
+        let d1 = state.d(&new, amp)?;
+        let supply = self.erc20.total_supply.get();
+        if d0 == U256::ZERO {
+            return Ok(d1);
+        }
+        Ok(d0.abs_diff(d1) * supply / d0)
+    }
+
+    // Any mix of coins; deposits away from the pool's ratio pay the imbalance fee.
+    pub fn add_liquidity(&mut self, amounts: Vec<U256>, min_mint_amount: U256) -> Result<U256, StableSwapError> {
+        let state = &self.state;
+        state.check_amounts(&amounts)?;
+        let amp = state.amp();
+        let supply = self.erc20.total_supply.get();
+        let old = state.stored_balances();
+        let d0 = state.d(&old, amp)?;
+        let new: Vec<U256> = old.iter().zip(&amounts).map(|(b, a)| *b + *a).collect();
+        if supply == U256::ZERO && new.contains(&U256::ZERO) {
+            // the first deposit sets the ratio and needs every coin
+            return Err(StableSwapError::InvalidAmounts(InvalidAmounts { expected: U256::from(new.len()), got: U256::ZERO }));
+        }
+        let d1 = state.d(&new, amp)?;
+        if d1 <= d0 {
+            return Err(StableSwapError::InvariantDecreased(InvariantDecreased { before: d0, after: d1 }));
+        }
+
+        let (fees, mint_amount) = if supply > U256::ZERO {
+            let (fees, d2) = self.imbalance_fees(&old, &new, d0, d1, amp)?;
+            (fees, supply * (d2 - d0) / d0)
+        } else {
+            (amounts.iter().map(|_| U256::ZERO).collect(), d1)
+        };
+        if mint_amount < min_mint_amount {
+            return Err(StableSwapError::Slippage(Slippage { amount: mint_amount, limit: min_mint_amount }));
+        }
+
+        for (i, amount) in amounts.iter().enumerate() {
+            self.pull(i, *amount)?;
+        }
+        self.state.set_balances(&new);
+        self.erc20.mint(msg::sender(), mint_amount);
+        evm::log(AddLiquidity { provider: msg::sender(), token_amounts: amounts, fees, invariant: d1, token_supply: supply + mint_amount });
+        Ok(mint_amount)
+    }
+
+    // Proportional withdrawal; no fee since it leaves the pool's ratio unchanged.
+    pub fn remove_liquidity(&mut self, amount: U256, min_amounts: Vec<U256>) -> Result<Vec<U256>, StableSwapError> {
+        self.state.check_amounts(&min_amounts)?;
+        let supply = StableSwapState::supply_covering(self.erc20.total_supply.get(), amount)?;
+        let mut balances = self.state.stored_balances();
+        let mut amounts = Vec::with_capacity(balances.len());
+        for (i, balance) in balances.iter_mut().enumerate() {
+            let value = *balance * amount / supply;
+            if value < min_amounts[i] {
+                return Err(StableSwapError::Slippage(Slippage { amount: value, limit: min_amounts[i] }));
+            }
+            *balance -= value;
+            amounts.push(value);
+        }
+        self.erc20.burn(msg::sender(), amount)?;
+        self.state.set_balances(&balances);
+        for (i, value) in amounts.iter().enumerate() {
+            self.send(i, msg::sender(), *value)?;
+        }
+        evm::log(RemoveLiquidity { provider: msg::sender(), token_amounts: amounts.clone(), token_supply: supply - amount });
+        Ok(amounts)
+    }
+
+    // Withdraws exact amounts, burning at most `max_burn_amount` LP.
+    pub fn remove_liquidity_imbalance(&mut self, amounts: Vec<U256>, max_burn_amount: U256) -> Result<U256, StableSwapError> {
+        let state = &self.state;
+        state.check_amounts(&amounts)?;
+        let amp = state.amp();
+        let supply = StableSwapState::supply_covering(self.erc20.total_supply.get(), U256::ZERO)?;
+        let old = state.stored_balances();
+        let d0 = state.d(&old, amp)?;
+        let mut new = Vec::with_capacity(old.len());
+        for (i, (balance, amount)) in old.iter().zip(&amounts).enumerate() {
+            if amount > balance {
+                return Err(StableSwapError::InsufficientBalance(InsufficientBalance {
+                    index: U256::from(i),
+                    have: *balance,
+                    want: *amount,
+                }));
+            }
+            new.push(*balance - *amount);
+        }
+        let d1 = state.d(&new, amp)?;
+        let (fees, d2) = self.imbalance_fees(&old, &new, d0, d1, amp)?;
+        // rounds against the withdrawer
+        let burn_amount = (d0 - d2) * supply / d0 + U256::from(1);
+        if burn_amount > max_burn_amount {
+            return Err(StableSwapError::Slippage(Slippage { amount: burn_amount, limit: max_burn_amount }));
+        }
+
+        self.erc20.burn(msg::sender(), burn_amount)?;
+        self.state.set_balances(&new);
+        for (i, amount) in amounts.iter().enumerate() {
+            self.send(i, msg::sender(), *amount)?;
+        }
+        evm::log(RemoveLiquidityImbalance {
+            provider: msg::sender(),
+            token_amounts: amounts,
+            fees,
+            invariant: d1,
+            token_supply: supply - burn_amount,
+        });
+        Ok(burn_amount)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::stable_swap::A_PRECISION;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const DAI: Address = Address::repeat_byte(0xda);
+    const USDC: Address = Address::repeat_byte(0xc0);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // DAI/USDC at A = 200 and a 0.04% fee, as init leaves it.
+    fn pool() -> StableSwapLiquidity {
+        let mut pool = deploy::<StableSwapLiquidity>();
+        let state = &mut pool.state;
+        for coin in [DAI, USDC] {
+            mock_erc20(coin);
+            mint_erc20(coin, SENDER, U256::from(1_000_000 * E18));
+            state.coins.push(coin);
+            state.balances.push(U256::ZERO);
+            state.rates.push(U256::from(1));
+        }
+        state.initial_a.set(U256::from(200 * A_PRECISION));
+        state.future_a.set(U256::from(200 * A_PRECISION));
+        state.fee.set(U256::from(4_000_000));
+        pool
+    }
+
+    fn amounts(a: u128, b: u128) -> Vec<U256> {
+        vec![U256::from(a), U256::from(b)]
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IStableSwapLiquidity::IStableSwapLiquidityCalls::SELECTORS {
+            let routed = <StableSwapLiquidity as Router<StableSwapLiquidity>>::route(&mut pool, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn withdrawals_from_an_empty_pool_revert() {
+        let mut pool = pool();
+        assert!(matches!(pool.remove_liquidity(U256::ZERO, amounts(0, 0)), Err(StableSwapError::EmptyPool(_))));
+        assert!(matches!(pool.remove_liquidity_imbalance(amounts(0, 0), U256::ZERO), Err(StableSwapError::EmptyPool(_))));
+        assert!(matches!(pool.add_liquidity(amounts(E18, 0), U256::ZERO), Err(StableSwapError::InvalidAmounts(_))));
+        assert_eq!(pool.get_virtual_price().unwrap_ok(), U256::ZERO);
+    }
+
+    #[test]
+    fn burning_more_than_the_supply_reverts() {
+        let mut pool = pool();
+        let minted = pool.add_liquidity(amounts(1_000 * E18, 1_000 * E18), U256::ZERO).unwrap_ok();
+        let too_much = minted + U256::from(1);
+        assert!(matches!(pool.remove_liquidity(too_much, amounts(0, 0)), Err(StableSwapError::InsufficientShares(_))));
+        assert!(matches!(pool.remove_liquidity_imbalance(amounts(E18, E18), U256::from(E18)), Err(StableSwapError::Slippage(_))));
+    }
+
+    #[test]
+    fn add_and_remove_round_trip() {
+        let mut pool = pool();
+        let minted = pool.add_liquidity(amounts(1_000_000 * E18 / 2, 1_000_000 * E18 / 2), U256::ZERO).unwrap_ok();
+        assert_eq!(minted, U256::from(1_000_000 * E18));
+
+        // a one-sided add pays the imbalance fee, so it mints less than its value
+        let quoted = pool.calc_token_amount(amounts(1_000 * E18, 0), true).unwrap_ok();
+        let one_sided = pool.add_liquidity(amounts(1_000 * E18, 0), U256::ZERO).unwrap_ok();
+        assert!(one_sided < quoted && quoted < U256::from(1_000 * E18), "{one_sided} {quoted}");
+        let burned = pool.remove_liquidity_imbalance(amounts(1_000 * E18, 0), U256::MAX).unwrap_ok();
+        assert!(burned > one_sided, "{burned}");
+        // the fees stay with the remaining LPs
+        assert!(pool.get_virtual_price().unwrap_ok() > U256::from(E18));
+
+        let supply = pool.erc20.total_supply.get();
+        let out = pool.remove_liquidity(supply, amounts(0, 0)).unwrap_ok();
+        assert_eq!(out, amounts(500_000 * E18, 500_000 * E18));
+        assert_eq!(erc20_balance(DAI, contract()), U256::ZERO);
+        assert_eq!(erc20_balance(USDC, contract()), U256::ZERO);
+    }
+}
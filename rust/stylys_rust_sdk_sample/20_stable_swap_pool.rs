@@ -0,0 +1,318 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod stable_swap_state;
+use crate::erc20::Erc20;
+use crate::ownable::Ownable;
+use crate::stable_swap_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolInterface};
+use amm_math::stable_swap::{A_PRECISION, MAX_A, MAX_A_CHANGE, MAX_FEE, MIN_RAMP_TIME};
+use core::borrow::{Borrow, BorrowMut};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256},
+    block,
+    call::{self, delegate_call},
+    evm,
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+sol! {
+    event RampA(uint256 old_a, uint256 new_a, uint256 initial_time, uint256 future_time);
+    event StopRampA(uint256 a, uint256 t);
+    event FeeUpdated(uint256 fee);
+}
+
+const MAX_COINS: usize = 8;
+
+// n-coin StableSwap pool (amm_math::stable_swap has the invariant). Swap fees
+// stay in the pool and raise the virtual price of the LP token.
+//
+// A is changed by ramping it linearly between two points in time; a sudden
+// jump would let whoever trades first capture the repricing.
+//
+// The pool keeps its setup, the LP token, A and the fee. Swaps and
+// single-coin withdrawals (20_stable_swap_exchange.rs) and the other adds and
+// removes (20_stable_swap_liquidity.rs) live in facets, which the pool
+// delegate_calls for their selectors, so callers use the pool's address for
+// everything (see stable_swap_state.rs for the layout).
+sol_storage! {
+    pub struct StableSwapPool {
+        #[borrow]
+        StableSwapState state;
+        #[borrow]
+        Erc20<StableSwapParams> erc20;
+    }
+}
+
+// `#[entrypoint]` goes on `dispatch` below, which adds the facet fallback.
+unsafe impl TopLevelStorage for StableSwapPool {}
+
+impl Borrow<Ownable> for StableSwapPool {
+    fn borrow(&self) -> &Ownable {
+        &self.state.ownable
+    }
+}
+
+impl BorrowMut<Ownable> for StableSwapPool {
+    fn borrow_mut(&mut self) -> &mut Ownable {
+        &mut self.state.ownable
+    }
+}
+
+// Runs the pool's own methods, then hands any selector a facet serves to that
+// facet, on the pool's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut pool = unsafe { StableSwapPool::new(U256::ZERO, 0) };
+    if let Some(result) = <StableSwapPool as Router<StableSwapPool>>::route(&mut pool, u32::from_be_bytes(selector), &input[4..]) {
+        return result;
+    }
+    let Some(facet) = pool.facet(selector) else {
+        return Err(Vec::new());
+    };
+    unsafe { delegate_call(&mut pool, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
+
+impl StableSwapPool {
+    fn facet(&self, selector: [u8; 4]) -> Option<Address> {
+        if IStableSwapExchange::IStableSwapExchangeCalls::valid_selector(selector) {
+            Some(self.state.exchange_facet.get())
+        } else if IStableSwapLiquidity::IStableSwapLiquidityCalls::valid_selector(selector) {
+            Some(self.state.liquidity_facet.get())
+        } else {
+            None
+        }
+    }
+}
+
+#[external]
+#[inherit(Erc20<StableSwapParams>, Ownable)]
+impl StableSwapPool {
+    // `a` is the plain amplification coefficient (e.g. 200 for DAI/USDC).
+    // The facets are fixed here for good: swapping one would change the pool's code.
+    pub fn init(
+        &mut self,
+        coins: Vec<Address>,
+        a: U256,
+        fee: U256,
+        exchange_facet: Address,
+        liquidity_facet: Address,
+        deploy_nonce: u64,
+    ) -> Result<(), StableSwapError> {
+        if !self.state.coins.is_empty() {
+            return Err(StableSwapError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if coins.len() < 2 || coins.len() > MAX_COINS {
+            return Err(StableSwapError::InvalidCoins(InvalidCoins { n_coins: U256::from(coins.len()) }));
+        }
+        if a == U256::ZERO || a > U256::from(MAX_A) {
+            return Err(StableSwapError::InvalidA(InvalidA { a }));
+        }
+        if fee > U256::from(MAX_FEE) {
+            return Err(StableSwapError::FeeTooHigh(FeeTooHigh { fee }));
+        }
+        self.state.ownable.initialize(deploy_nonce)?;
+        for coin in coins {
+            let decimals = IERC20::new(coin).decimals(&*self).map_err(|_| StableSwapState::failed(coin))?;
+            let state = &mut self.state;
+            if decimals > PRECISION_DECIMALS {
+                return Err(StableSwapError::InvalidCoins(InvalidCoins { n_coins: U256::from(state.coins.len()) }));
+            }
+            state.coins.push(coin);
+            state.balances.push(U256::ZERO);
+            state.rates.push(U256::from(10).pow(U256::from(PRECISION_DECIMALS - decimals)));
+        }
+        let state = &mut self.state;
+        let a_precise = a * U256::from(A_PRECISION);
+        state.initial_a.set(a_precise);
+        state.future_a.set(a_precise);
+        state.fee.set(fee);
+        state.exchange_facet.set(exchange_facet);
+        state.liquidity_facet.set(liquidity_facet);
+        Ok(())
+    }
+
+    pub fn coins(&self) -> Result<Vec<Address>, StableSwapError> {
+        let state = &self.state;
+        Ok((0..state.n_coins()).map(|i| state.coins.get(i).unwrap()).collect())
+    }
+
+    pub fn balances(&self) -> Result<Vec<U256>, StableSwapError> {
+        Ok(self.state.stored_balances())
+    }
+
+    pub fn a(&self) -> Result<U256, StableSwapError> {
+        Ok(self.state.amp() / U256::from(A_PRECISION))
+    }
+
+    pub fn a_precise(&self) -> Result<U256, StableSwapError> {
+        Ok(self.state.amp())
+    }
+
+    pub fn fee(&self) -> Result<U256, StableSwapError> {
+        Ok(self.state.fee.get())
+    }
+
+    // (exchange_facet, liquidity_facet)
+    pub fn facets(&self) -> Result<(Address, Address), StableSwapError> {
+        Ok((self.state.exchange_facet.get(), self.state.liquidity_facet.get()))
+    }
+
+    // Moves A linearly to `future_a` by `future_time`: at most 10x either way,
+    // over at least a day, and no sooner than a day after the last ramp started.
+    pub fn ramp_a(&mut self, future_a: U256, future_time: U256) -> Result<(), StableSwapError> {
+        let state = &mut self.state;
+        state.ownable.only_owner()?;
+        let now = U256::from(block::timestamp());
+        let allowed_at = state.initial_a_time.get() + U256::from(MIN_RAMP_TIME);
+        if now < allowed_at {
+            return Err(StableSwapError::RampTooSoon(RampTooSoon { now, allowed_at }));
+        }
+        if future_time < now + U256::from(MIN_RAMP_TIME) {
+            return Err(StableSwapError::RampTooSoon(RampTooSoon { now: future_time, allowed_at: now + U256::from(MIN_RAMP_TIME) }));
+        }
+        let initial_a = state.amp();
+        let future_a_precise = future_a * U256::from(A_PRECISION);
+        let max_change = U256::from(MAX_A_CHANGE);
+        if future_a == U256::ZERO
+            || future_a > U256::from(MAX_A)
+            || future_a_precise > initial_a * max_change
+            || future_a_precise * max_change < initial_a
+        {
+            return Err(StableSwapError::InvalidA(InvalidA { a: future_a }));
+        }
+
+        state.initial_a.set(initial_a);
+        state.future_a.set(future_a_precise);
+        state.initial_a_time.set(now);
+        state.future_a_time.set(future_time);
+        evm::log(RampA { old_a: initial_a, new_a: future_a_precise, initial_time: now, future_time });
+        Ok(())
+    }
+
+    // Freezes A at its current value.
+    pub fn stop_ramp_a(&mut self) -> Result<(), StableSwapError> {
+        let state = &mut self.state;
+        state.ownable.only_owner()?;
+        let current = state.amp();
+        let now = U256::from(block::timestamp());
+        state.initial_a.set(current);
+        state.future_a.set(current);
+        state.initial_a_time.set(now);
+        state.future_a_time.set(now);
+        evm::log(StopRampA { a: current, t: now });
+        Ok(())
+    }
+
+    pub fn set_fee(&mut self, fee: U256) -> Result<(), StableSwapError> {
+        self.state.ownable.only_owner()?;
+        if fee > U256::from(MAX_FEE) {
+            return Err(StableSwapError::FeeTooHigh(FeeTooHigh { fee }));
+        }
+        self.state.fee.set(fee);
+        evm::log(FeeUpdated { fee });
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{calls, deploy, mock_call, mock_erc20, CallKind, UnwrapOk, TIMESTAMP};
+
+    const DAI: Address = Address::repeat_byte(0xda);
+    const USDC: Address = Address::repeat_byte(0xc0);
+    const EXCHANGE: Address = Address::repeat_byte(0x21);
+    const LIQUIDITY: Address = Address::repeat_byte(0x20);
+    const DAY: u64 = 86_400;
+
+    fn pool() -> StableSwapPool {
+        let mut pool = deploy::<StableSwapPool>();
+        mock_erc20(DAI);
+        mock_erc20(USDC);
+        pool.init(vec![DAI, USDC], U256::from(200), U256::from(4_000_000), EXCHANGE, LIQUIDITY, 0).unwrap_ok();
+        pool
+    }
+
+    #[test]
+    fn init_checks_the_config() {
+        let mut pool = deploy::<StableSwapPool>();
+        mock_erc20(DAI);
+        let mut init = |a: u64, fee: u64| pool.init(vec![DAI, DAI], U256::from(a), U256::from(fee), EXCHANGE, LIQUIDITY, 0);
+        assert!(matches!(init(0, 0), Err(StableSwapError::InvalidA(_))));
+        assert!(matches!(init(200, MAX_FEE + 1), Err(StableSwapError::FeeTooHigh(_))));
+        init(200, 0).unwrap_ok();
+        assert!(matches!(init(200, 0), Err(StableSwapError::AlreadyInitialized(_))));
+
+        let pool = self::pool();
+        assert_eq!(pool.coins().unwrap_ok(), vec![DAI, USDC]);
+        assert_eq!(pool.facets().unwrap_ok(), (EXCHANGE, LIQUIDITY));
+        assert_eq!(pool.a().unwrap_ok(), U256::from(200));
+    }
+
+    #[test]
+    fn a_ramps_linearly_and_stops_where_it_is() {
+        let mut pool = pool();
+        // a ramp may only start a day after the last one
+        pool.state.initial_a_time.set(U256::from(TIMESTAMP - DAY));
+        let future_time = U256::from(TIMESTAMP + 2 * DAY);
+        assert!(matches!(pool.ramp_a(U256::from(2_001), future_time), Err(StableSwapError::InvalidA(_))));
+        assert!(matches!(pool.ramp_a(U256::from(400), U256::from(TIMESTAMP + 1)), Err(StableSwapError::RampTooSoon(_))));
+        pool.ramp_a(U256::from(400), future_time).unwrap_ok();
+        assert_eq!(pool.a().unwrap_ok(), U256::from(200));
+
+        // halfway through the ramp
+        pool.state.initial_a_time.set(U256::from(TIMESTAMP - DAY));
+        pool.state.future_a_time.set(U256::from(TIMESTAMP + DAY));
+        assert_eq!(pool.a().unwrap_ok(), U256::from(300));
+        pool.stop_ramp_a().unwrap_ok();
+        assert_eq!(pool.state.future_a.get(), U256::from(300 * A_PRECISION));
+        assert!(matches!(pool.set_fee(U256::from(MAX_FEE + 1)), Err(StableSwapError::FeeTooHigh(_))));
+    }
+
+    #[test]
+    fn facet_selectors_run_on_the_facets() {
+        let pool = pool();
+        mock_call(EXCHANGE, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        mock_call(LIQUIDITY, |_| Err(b"liquidity says no".to_vec()));
+
+        let data = IStableSwapExchange::getDyCall { i: U256::ZERO, j: U256::from(1), dx: U256::from(1) }.abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+        // facet reverts come back as they are
+        let data = IStableSwapLiquidity::removeLiquidityCall { amount: U256::from(1), min_amounts: vec![] }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"liquidity says no".to_vec());
+        assert_eq!(calls().last().unwrap().to, LIQUIDITY);
+        // the pool's own methods don't leave it
+        assert_eq!(pool.facet(stylus_sdk::function_selector!("coins")), None);
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+    }
+}
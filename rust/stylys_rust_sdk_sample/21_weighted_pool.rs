@@ -0,0 +1,253 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod weighted_pool_state;
+use crate::erc20::Erc20;
+use crate::ownable::Ownable;
+use crate::weighted_pool_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolInterface};
+use amm_math::fixed_point::WAD;
+use amm_math::weighted_math::MIN_WEIGHT;
+use core::borrow::{Borrow, BorrowMut};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256},
+    call::{self, delegate_call},
+    evm,
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+sol! {
+    event SwapFeeUpdated(uint256 swap_fee);
+}
+
+const MIN_TOKENS: usize = 2;
+const MAX_TOKENS: usize = 8;
+const PRECISION_DECIMALS: u8 = 18;
+// 10%
+const MAX_SWAP_FEE: U256 = U256::from_limbs([100_000_000_000_000_000, 0, 0, 0]);
+
+// Balancer-style weighted pool (amm_math::weighted_math). Each token keeps a
+// fixed share of the pool's value, so an 80/20 GOV/WETH pool gives holders
+// exposure mostly to GOV while still providing liquidity. Fees stay in the pool
+// and accrue to pool token holders.
+//
+// The pool keeps its setup, the pool token and the swap fee. Swaps
+// (21_weighted_pool_swap.rs) and joins and exits (21_weighted_pool_liquidity.rs)
+// live in facets, which the pool delegate_calls for their selectors, so callers
+// use the pool's address for everything (see weighted_pool_state.rs for the
+// layout).
+sol_storage! {
+    pub struct WeightedPool {
+        #[borrow]
+        WeightedPoolState state;
+        #[borrow]
+        Erc20<WeightedPoolParams> erc20;
+    }
+}
+
+// `#[entrypoint]` goes on `dispatch` below, which adds the facet fallback.
+unsafe impl TopLevelStorage for WeightedPool {}
+
+impl Borrow<Ownable> for WeightedPool {
+    fn borrow(&self) -> &Ownable {
+        &self.state.ownable
+    }
+}
+
+impl BorrowMut<Ownable> for WeightedPool {
+    fn borrow_mut(&mut self) -> &mut Ownable {
+        &mut self.state.ownable
+    }
+}
+
+// Runs the pool's own methods, then hands any selector a facet serves to that
+// facet, on the pool's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut pool = unsafe { WeightedPool::new(U256::ZERO, 0) };
+    if let Some(result) = <WeightedPool as Router<WeightedPool>>::route(&mut pool, u32::from_be_bytes(selector), &input[4..]) {
+        return result;
+    }
+    let Some(facet) = pool.facet(selector) else {
+        return Err(Vec::new());
+    };
+    unsafe { delegate_call(&mut pool, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
+
+impl WeightedPool {
+    fn facet(&self, selector: [u8; 4]) -> Option<Address> {
+        if IWeightedPoolSwap::IWeightedPoolSwapCalls::valid_selector(selector) {
+            Some(self.state.swap_facet.get())
+        } else if IWeightedPoolLiquidity::IWeightedPoolLiquidityCalls::valid_selector(selector) {
+            Some(self.state.liquidity_facet.get())
+        } else {
+            None
+        }
+    }
+}
+
+#[external]
+#[inherit(Erc20<WeightedPoolParams>, Ownable)]
+impl WeightedPool {
+    // `weights` are WAD and must sum to 1e18, e.g. [0.8e18, 0.2e18] for an 80/20 pool.
+    // The facets are fixed here for good: swapping one would change the pool's code.
+    pub fn init(
+        &mut self,
+        tokens: Vec<Address>,
+        weights: Vec<U256>,
+        swap_fee: U256,
+        swap_facet: Address,
+        liquidity_facet: Address,
+        deploy_nonce: u64,
+    ) -> Result<(), WeightedPoolError> {
+        let state = &mut self.state;
+        if !state.tokens.is_empty() {
+            return Err(WeightedPoolError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if tokens.len() < MIN_TOKENS || tokens.len() > MAX_TOKENS || tokens.len() != weights.len() {
+            return Err(WeightedPoolError::InvalidTokens(InvalidTokens { n_tokens: U256::from(tokens.len()) }));
+        }
+        let total = weights.iter().fold(U256::ZERO, |acc, w| acc + *w);
+        if total != WAD || weights.iter().any(|w| *w < MIN_WEIGHT) {
+            return Err(WeightedPoolError::InvalidWeights(InvalidWeights { total }));
+        }
+        if swap_fee > MAX_SWAP_FEE {
+            return Err(WeightedPoolError::SwapFeeTooHigh(SwapFeeTooHigh { swap_fee }));
+        }
+        state.ownable.initialize(deploy_nonce)?;
+        for (token, weight) in tokens.into_iter().zip(weights) {
+            let decimals = IERC20::new(token).decimals(&*self).map_err(|_| WeightedPoolState::failed(token))?;
+            let state = &mut self.state;
+            if decimals > PRECISION_DECIMALS {
+                return Err(WeightedPoolError::InvalidTokens(InvalidTokens { n_tokens: U256::from(state.tokens.len()) }));
+            }
+            state.tokens.push(token);
+            state.balances.push(U256::ZERO);
+            state.weights.push(weight);
+            state.rates.push(U256::from(10).pow(U256::from(PRECISION_DECIMALS - decimals)));
+        }
+        let state = &mut self.state;
+        state.swap_fee.set(swap_fee);
+        state.swap_facet.set(swap_facet);
+        state.liquidity_facet.set(liquidity_facet);
+        Ok(())
+    }
+
+    pub fn get_tokens(&self) -> Result<Vec<Address>, WeightedPoolError> {
+        let state = &self.state;
+        Ok((0..state.n_tokens()).map(|i| state.tokens.get(i).unwrap()).collect())
+    }
+
+    pub fn get_balances(&self) -> Result<Vec<U256>, WeightedPoolError> {
+        let state = &self.state;
+        Ok((0..state.n_tokens()).map(|i| state.balances.get(i).unwrap()).collect())
+    }
+
+    pub fn get_normalized_weights(&self) -> Result<Vec<U256>, WeightedPoolError> {
+        let state = &self.state;
+        Ok((0..state.n_tokens()).map(|i| state.weights.get(i).unwrap()).collect())
+    }
+
+    pub fn get_swap_fee(&self) -> Result<U256, WeightedPoolError> {
+        Ok(self.state.swap_fee.get())
+    }
+
+    // (swap_facet, liquidity_facet)
+    pub fn facets(&self) -> Result<(Address, Address), WeightedPoolError> {
+        Ok((self.state.swap_facet.get(), self.state.liquidity_facet.get()))
+    }
+
+    pub fn set_swap_fee(&mut self, swap_fee: U256) -> Result<(), WeightedPoolError> {
+        self.state.ownable.only_owner()?;
+        if swap_fee > MAX_SWAP_FEE {
+            return Err(WeightedPoolError::SwapFeeTooHigh(SwapFeeTooHigh { swap_fee }));
+        }
+        self.state.swap_fee.set(swap_fee);
+        evm::log(SwapFeeUpdated { swap_fee });
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{calls, deploy, mock_call, mock_erc20, CallKind, UnwrapOk};
+
+    const GOV: Address = Address::repeat_byte(0x60);
+    const WETH: Address = Address::repeat_byte(0xee);
+    const SWAP: Address = Address::repeat_byte(0x21);
+    const LIQUIDITY: Address = Address::repeat_byte(0x20);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn init(pool: &mut WeightedPool, weights: [u128; 2], swap_fee: u128) -> Result<(), WeightedPoolError> {
+        let weights = weights.into_iter().map(U256::from).collect();
+        pool.init(vec![GOV, WETH], weights, U256::from(swap_fee), SWAP, LIQUIDITY, 0)
+    }
+
+    #[test]
+    fn init_checks_the_weights_and_fee() {
+        let mut pool = deploy::<WeightedPool>();
+        mock_erc20(GOV);
+        mock_erc20(WETH);
+        assert!(matches!(init(&mut pool, [8 * E18 / 10, E18 / 10], 0), Err(WeightedPoolError::InvalidWeights(_))));
+        assert!(matches!(init(&mut pool, [8 * E18 / 10, 2 * E18 / 10], E18 / 5), Err(WeightedPoolError::SwapFeeTooHigh(_))));
+        init(&mut pool, [8 * E18 / 10, 2 * E18 / 10], 3 * E18 / 1000).unwrap_ok();
+        assert_eq!(pool.get_tokens().unwrap_ok(), vec![GOV, WETH]);
+        assert_eq!(pool.get_balances().unwrap_ok(), vec![U256::ZERO; 2]);
+        assert_eq!(pool.facets().unwrap_ok(), (SWAP, LIQUIDITY));
+        assert!(matches!(init(&mut pool, [8 * E18 / 10, 2 * E18 / 10], 0), Err(WeightedPoolError::AlreadyInitialized(_))));
+
+        assert!(matches!(pool.set_swap_fee(U256::from(E18 / 5)), Err(WeightedPoolError::SwapFeeTooHigh(_))));
+        pool.set_swap_fee(U256::from(E18 / 100)).unwrap_ok();
+        assert_eq!(pool.get_swap_fee().unwrap_ok(), U256::from(E18 / 100));
+    }
+
+    #[test]
+    fn facet_selectors_run_on_the_facets() {
+        let mut pool = deploy::<WeightedPool>();
+        mock_erc20(GOV);
+        mock_erc20(WETH);
+        init(&mut pool, [E18 / 2, E18 / 2], 0).unwrap_ok();
+        mock_call(SWAP, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        mock_call(LIQUIDITY, |_| Err(b"liquidity says no".to_vec()));
+
+        let data = IWeightedPoolSwap::getAmountOutCall { i: U256::ZERO, o: U256::from(1), amount_in: U256::from(E18) }.abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+        // facet reverts come back as they are
+        let data = IWeightedPoolLiquidity::exitPoolCall { bpt_in: U256::from(1), min_amounts_out: vec![] }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"liquidity says no".to_vec());
+        assert_eq!(calls().last().unwrap().to, LIQUIDITY);
+        // the pool's own methods don't leave it
+        assert_eq!(pool.facet(stylus_sdk::function_selector!("getTokens")), None);
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+    }
+}
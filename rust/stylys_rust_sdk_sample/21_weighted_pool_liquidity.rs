@@ -0,0 +1,227 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod weighted_pool_state;
+use crate::erc20::Erc20;
+use crate::weighted_pool_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use amm_math::fixed_point::{div_down, div_up, mul_down, mul_up};
+use amm_math::weighted_math::{calc_bpt_out_given_exact_token_in, calc_token_out_given_exact_bpt_in, invariant};
+use stylus_sdk::{alloy_primitives::U256, evm, msg, prelude::*};
+
+sol! {
+    event Join(address indexed caller, uint256[] amounts_in, uint256 bpt_out);
+    event Exit(address indexed caller, uint256[] amounts_out, uint256 bpt_in);
+}
+
+// Joins and exits for the weighted pool in 21_weighted_pool.rs. The pool
+// delegate_calls this facet for the `IWeightedPoolLiquidity` selectors, so it
+// runs on the pool's storage and mints and burns the pool's own token, which
+// follows `WeightedPoolState` (see weighted_pool_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct WeightedPoolLiquidity {
+        #[borrow]
+        WeightedPoolState state;
+        Erc20<WeightedPoolParams> erc20;
+    }
+}
+
+impl WeightedPoolLiquidity {
+    fn check_amounts(&self, amounts: &[U256]) -> Result<(), WeightedPoolError> {
+        let n_tokens = self.state.n_tokens();
+        if amounts.len() != n_tokens {
+            return Err(WeightedPoolError::InvalidAmounts(InvalidAmounts {
+                expected: U256::from(n_tokens),
+                got: U256::from(amounts.len()),
+            }));
+        }
+        Ok(())
+    }
+
+    fn supply(&self) -> Result<U256, WeightedPoolError> {
+        let supply = self.erc20.total_supply.get();
+        if supply == U256::ZERO {
+            return Err(WeightedPoolError::NotInitialized(NotInitialized {}));
+        }
+        Ok(supply)
+    }
+}
+
+#[external]
+impl WeightedPoolLiquidity {
+    // prod(B_i ^ W_i) over scaled balances.
+    pub fn get_invariant(&self) -> Result<U256, WeightedPoolError> {
+        let state = &self.state;
+        let (balances, weights): (Vec<U256>, Vec<U256>) = (0..state.n_tokens()).map(|i| state.scaled(i)).unzip();
+        invariant(&balances, &weights).ok_or_else(WeightedPoolState::ratio_too_large)
+    }
+
+    // Seeds the pool; the amounts set the starting prices together with the weights.
+    // Mints invariant * n pool tokens, which prices each one near the value of a
+    // balanced basket.
+    pub fn initial_join(&mut self, amounts_in: Vec<U256>) -> Result<U256, WeightedPoolError> {
+        self.check_amounts(&amounts_in)?;
+        if self.erc20.total_supply.get() != U256::ZERO {
+            return Err(WeightedPoolError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if amounts_in.contains(&U256::ZERO) {
+            return Err(WeightedPoolError::InvalidAmounts(InvalidAmounts { expected: U256::from(amounts_in.len()), got: U256::ZERO }));
+        }
+        for (i, amount) in amounts_in.iter().enumerate() {
+            self.pull(i, *amount)?;
+        }
+        let bpt_out = self.get_invariant()? * U256::from(self.state.n_tokens());
+        self.erc20.mint(msg::sender(), bpt_out);
+        evm::log(Join { caller: msg::sender(), amounts_in, bpt_out });
+        Ok(bpt_out)
+    }
+
+    // Proportional join for exactly `bpt_out`; no fee since prices don't move.
+    pub fn join_pool(&mut self, bpt_out: U256, max_amounts_in: Vec<U256>) -> Result<Vec<U256>, WeightedPoolError> {
+        self.check_amounts(&max_amounts_in)?;
+        let ratio = div_up(bpt_out, self.supply()?);
+        let mut amounts_in = Vec::with_capacity(max_amounts_in.len());
+        for (i, max_amount) in max_amounts_in.iter().enumerate() {
+            let amount = mul_up(self.state.balances.get(i).unwrap(), ratio);
+            if amount > *max_amount {
+                return Err(WeightedPoolError::Slippage(Slippage { amount, limit: *max_amount }));
+            }
+            self.pull(i, amount)?;
+            amounts_in.push(amount);
+        }
+        self.erc20.mint(msg::sender(), bpt_out);
+        evm::log(Join { caller: msg::sender(), amounts_in: amounts_in.clone(), bpt_out });
+        Ok(amounts_in)
+    }
+
+    // Single-token join; the part that is effectively swapped into the other tokens pays the fee.
+    pub fn join_swap_extern_amount_in(&mut self, index: U256, amount_in: U256, min_bpt_out: U256) -> Result<U256, WeightedPoolError> {
+        let state = &self.state;
+        let i = state.check_index(index)?;
+        let (balance, weight) = state.scaled(i);
+        let bpt_out = calc_bpt_out_given_exact_token_in(balance, weight, state.upscale(i, amount_in), self.supply()?, state.swap_fee.get())
+            .ok_or_else(WeightedPoolState::ratio_too_large)?;
+        if bpt_out < min_bpt_out {
+            return Err(WeightedPoolError::Slippage(Slippage { amount: bpt_out, limit: min_bpt_out }));
+        }
+        self.pull(i, amount_in)?;
+        self.erc20.mint(msg::sender(), bpt_out);
+        let mut amounts_in = alloc::vec![U256::ZERO; self.state.n_tokens()];
+        amounts_in[i] = amount_in;
+        evm::log(Join { caller: msg::sender(), amounts_in, bpt_out });
+        Ok(bpt_out)
+    }
+
+    pub fn exit_pool(&mut self, bpt_in: U256, min_amounts_out: Vec<U256>) -> Result<Vec<U256>, WeightedPoolError> {
+        self.check_amounts(&min_amounts_out)?;
+        let ratio = div_down(bpt_in, self.supply()?);
+        self.erc20.burn(msg::sender(), bpt_in)?;
+        let mut amounts_out = Vec::with_capacity(min_amounts_out.len());
+        for (i, min_amount) in min_amounts_out.iter().enumerate() {
+            let amount = mul_down(self.state.balances.get(i).unwrap(), ratio);
+            if amount < *min_amount {
+                return Err(WeightedPoolError::Slippage(Slippage { amount, limit: *min_amount }));
+            }
+            self.send(i, amount)?;
+            amounts_out.push(amount);
+        }
+        evm::log(Exit { caller: msg::sender(), amounts_out: amounts_out.clone(), bpt_in });
+        Ok(amounts_out)
+    }
+
+    pub fn exit_swap_pool_amount_in(&mut self, index: U256, bpt_in: U256, min_amount_out: U256) -> Result<U256, WeightedPoolError> {
+        let state = &self.state;
+        let i = state.check_index(index)?;
+        let (balance, weight) = state.scaled(i);
+        let amount_out = calc_token_out_given_exact_bpt_in(balance, weight, bpt_in, self.supply()?, state.swap_fee.get())
+            .ok_or_else(WeightedPoolState::ratio_too_large)?;
+        let amount_out = state.downscale(i, amount_out, false);
+        if amount_out < min_amount_out {
+            return Err(WeightedPoolError::Slippage(Slippage { amount: amount_out, limit: min_amount_out }));
+        }
+        self.erc20.burn(msg::sender(), bpt_in)?;
+        self.send(i, amount_out)?;
+        let mut amounts_out = alloc::vec![U256::ZERO; self.state.n_tokens()];
+        amounts_out[i] = amount_out;
+        evm::log(Exit { caller: msg::sender(), amounts_out, bpt_in });
+        Ok(amount_out)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const GOV: Address = Address::repeat_byte(0x60);
+    const WETH: Address = Address::repeat_byte(0xee);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // 80/20 GOV/WETH with a 0.3% fee, as init leaves it.
+    fn pool() -> WeightedPoolLiquidity {
+        let mut pool = deploy::<WeightedPoolLiquidity>();
+        let state = &mut pool.state;
+        for (token, weight) in [(GOV, 8 * E18 / 10), (WETH, 2 * E18 / 10)] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+            state.tokens.push(token);
+            state.balances.push(U256::ZERO);
+            state.weights.push(U256::from(weight));
+            state.rates.push(U256::from(1));
+        }
+        state.swap_fee.set(U256::from(3 * E18 / 1000));
+        pool
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IWeightedPoolLiquidity::IWeightedPoolLiquidityCalls::SELECTORS {
+            let routed = <WeightedPoolLiquidity as Router<WeightedPoolLiquidity>>::route(&mut pool, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn joins_and_exits_move_balances_in_proportion() {
+        let mut pool = pool();
+        assert!(matches!(pool.join_pool(U256::from(E18), vec![U256::MAX; 2]), Err(WeightedPoolError::NotInitialized(_))));
+        assert!(matches!(pool.initial_join(vec![U256::from(E18)]), Err(WeightedPoolError::InvalidAmounts(_))));
+        let supply = pool.initial_join(vec![U256::from(800 * E18), U256::from(50 * E18)]).unwrap_ok();
+        assert_eq!(pool.erc20.balance_of(SENDER).unwrap_ok(), supply);
+
+        // a tenth of the supply takes a tenth of each balance
+        let amounts_in = pool.join_pool(supply / U256::from(10), vec![U256::MAX; 2]).unwrap_ok();
+        assert_eq!(amounts_in, vec![U256::from(80 * E18), U256::from(5 * E18)]);
+        let amounts_out = pool.exit_pool(supply / U256::from(10), vec![U256::ZERO; 2]).unwrap_ok();
+        assert!(amounts_out[0] <= amounts_in[0] && amounts_out[1] <= amounts_in[1]);
+        assert_eq!(erc20_balance(GOV, contract()), pool.state.balances.get(0).unwrap());
+    }
+
+    #[test]
+    fn single_token_joins_and_exits_pay_the_fee() {
+        let mut pool = pool();
+        pool.initial_join(vec![U256::from(800 * E18), U256::from(50 * E18)]).unwrap_ok();
+        let bpt = pool.join_swap_extern_amount_in(U256::from(1), U256::from(E18), U256::ZERO).unwrap_ok();
+        let out = pool.exit_swap_pool_amount_in(U256::from(1), bpt, U256::ZERO).unwrap_ok();
+        assert!(out < U256::from(E18) && out > U256::from(E18 * 99 / 100), "{out}");
+        assert!(matches!(pool.exit_swap_pool_amount_in(U256::from(2), bpt, U256::ZERO), Err(WeightedPoolError::InvalidTokenIndex(_))));
+        assert_eq!(erc20_balance(WETH, contract()), pool.state.balances.get(1).unwrap());
+    }
+}
@@ -0,0 +1,183 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod weighted_pool_state;
+use crate::weighted_pool_state::*;
+use alloy_sol_types::sol;
+use amm_math::fixed_point::{complement, div_up, mul_down};
+use amm_math::weighted_math::{calc_in_given_out, calc_out_given_in, spot_price};
+use stylus_sdk::{alloy_primitives::U256, evm, msg, prelude::*};
+
+sol! {
+    event Swap(address indexed caller, address indexed token_in, address indexed token_out, uint256 amount_in, uint256 amount_out);
+}
+
+// Swaps for the weighted pool in 21_weighted_pool.rs. The pool delegate_calls
+// this facet for the `IWeightedPoolSwap` selectors, so it runs on the pool's
+// storage (see weighted_pool_state.rs for the layout). The fee comes off the
+// input and stays in the pool.
+sol_storage! {
+    #[entrypoint]
+    pub struct WeightedPoolSwap {
+        #[borrow]
+        WeightedPoolState state;
+    }
+}
+
+impl WeightedPoolSwap {
+    // Raw amount out for an exact raw amount in; the fee comes off the input.
+    fn amount_out(&self, i: usize, o: usize, amount_in: U256) -> Result<U256, WeightedPoolError> {
+        let state = &self.state;
+        let (balance_in, weight_in) = state.scaled(i);
+        let (balance_out, weight_out) = state.scaled(o);
+        let after_fee = mul_down(state.upscale(i, amount_in), complement(state.swap_fee.get()));
+        let out =
+            calc_out_given_in(balance_in, weight_in, balance_out, weight_out, after_fee).ok_or_else(WeightedPoolState::ratio_too_large)?;
+        Ok(state.downscale(o, out, false))
+    }
+
+    // Raw amount in, fee included, for an exact raw amount out.
+    fn amount_in(&self, i: usize, o: usize, amount_out: U256) -> Result<U256, WeightedPoolError> {
+        let state = &self.state;
+        let (balance_in, weight_in) = state.scaled(i);
+        let (balance_out, weight_out) = state.scaled(o);
+        let before_fee = calc_in_given_out(balance_in, weight_in, balance_out, weight_out, state.upscale(o, amount_out))
+            .ok_or_else(WeightedPoolState::ratio_too_large)?;
+        let amount = div_up(before_fee, complement(state.swap_fee.get()));
+        Ok(state.downscale(i, amount, true))
+    }
+
+    fn settle(&mut self, i: usize, amount_in: U256, o: usize, amount_out: U256) -> Result<(), WeightedPoolError> {
+        self.pull(i, amount_in)?;
+        self.send(o, amount_out)?;
+        evm::log(Swap {
+            caller: msg::sender(),
+            token_in: self.state.tokens.get(i).unwrap(),
+            token_out: self.state.tokens.get(o).unwrap(),
+            amount_in,
+            amount_out,
+        });
+        Ok(())
+    }
+}
+
+#[external]
+impl WeightedPoolSwap {
+    // Price of token `o` in units of token `i`, (B_i / W_i) / (B_o / W_o), before fees.
+    pub fn get_spot_price(&self, i: U256, o: U256) -> Result<U256, WeightedPoolError> {
+        let (i, o) = self.state.check_pair(i, o)?;
+        let (balance_in, weight_in) = self.state.scaled(i);
+        let (balance_out, weight_out) = self.state.scaled(o);
+        Ok(spot_price(balance_in, weight_in, balance_out, weight_out))
+    }
+
+    pub fn get_amount_out(&self, i: U256, o: U256, amount_in: U256) -> Result<U256, WeightedPoolError> {
+        let (i, o) = self.state.check_pair(i, o)?;
+        self.amount_out(i, o, amount_in)
+    }
+
+    pub fn get_amount_in(&self, i: U256, o: U256, amount_out: U256) -> Result<U256, WeightedPoolError> {
+        let (i, o) = self.state.check_pair(i, o)?;
+        self.amount_in(i, o, amount_out)
+    }
+
+    pub fn swap_exact_amount_in(&mut self, i: U256, amount_in: U256, o: U256, min_amount_out: U256) -> Result<U256, WeightedPoolError> {
+        let (i, o) = self.state.check_pair(i, o)?;
+        let amount_out = self.amount_out(i, o, amount_in)?;
+        if amount_out < min_amount_out {
+            return Err(WeightedPoolError::Slippage(Slippage { amount: amount_out, limit: min_amount_out }));
+        }
+        self.settle(i, amount_in, o, amount_out)?;
+        Ok(amount_out)
+    }
+
+    pub fn swap_exact_amount_out(&mut self, i: U256, max_amount_in: U256, o: U256, amount_out: U256) -> Result<U256, WeightedPoolError> {
+        let (i, o) = self.state.check_pair(i, o)?;
+        let amount_in = self.amount_in(i, o, amount_out)?;
+        if amount_in > max_amount_in {
+            return Err(WeightedPoolError::Slippage(Slippage { amount: amount_in, limit: max_amount_in }));
+        }
+        self.settle(i, amount_in, o, amount_out)?;
+        Ok(amount_in)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const GOV: Address = Address::repeat_byte(0x60);
+    const WETH: Address = Address::repeat_byte(0xee);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // 80/20 GOV/WETH with a 0.3% fee, as init and a first join of 800 GOV /
+    // 50 WETH leave it (1 WETH = 4 GOV).
+    fn pool() -> WeightedPoolSwap {
+        let mut pool = deploy::<WeightedPoolSwap>();
+        let state = &mut pool.state;
+        for (token, weight, balance) in [(GOV, 8 * E18 / 10, 800 * E18), (WETH, 2 * E18 / 10, 50 * E18)] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+            mint_erc20(token, contract(), U256::from(balance));
+            state.tokens.push(token);
+            state.balances.push(U256::from(balance));
+            state.weights.push(U256::from(weight));
+            state.rates.push(U256::from(1));
+        }
+        state.swap_fee.set(U256::from(3 * E18 / 1000));
+        pool
+    }
+
+    #[test]
+    fn serves_every_selector_the_pool_forwards() {
+        let mut pool = pool();
+        for selector in IWeightedPoolSwap::IWeightedPoolSwapCalls::SELECTORS {
+            let routed = <WeightedPoolSwap as Router<WeightedPoolSwap>>::route(&mut pool, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn swaps_between_a_token_and_itself_revert() {
+        let mut pool = pool();
+        let (one, zero) = (U256::from(E18), U256::ZERO);
+        assert!(matches!(pool.swap_exact_amount_in(zero, one, zero, zero), Err(WeightedPoolError::SameToken(_))));
+        assert!(matches!(pool.swap_exact_amount_out(zero, U256::MAX, zero, one), Err(WeightedPoolError::SameToken(_))));
+        assert!(matches!(pool.get_amount_out(U256::from(1), U256::from(1), one), Err(WeightedPoolError::SameToken(_))));
+        assert!(matches!(pool.get_spot_price(zero, zero), Err(WeightedPoolError::SameToken(_))));
+        assert_eq!(pool.state.balances.get(0).unwrap(), U256::from(800 * E18));
+    }
+
+    #[test]
+    fn swaps_follow_the_weighted_price() {
+        let mut pool = pool();
+        assert_eq!(pool.get_spot_price(U256::ZERO, U256::from(1)).unwrap_ok(), U256::from(4 * E18));
+        assert_eq!(pool.get_spot_price(U256::from(1), U256::ZERO).unwrap_ok(), U256::from(E18 / 4));
+
+        // 1 GOV buys a bit under 0.25 WETH after the fee and price impact
+        let out = pool.swap_exact_amount_in(U256::ZERO, U256::from(E18), U256::from(1), U256::ZERO).unwrap_ok();
+        assert!(out > U256::from(E18 / 4 * 99 / 100) && out < U256::from(E18 / 4 * 997 / 1000), "{out}");
+        assert_eq!(erc20_balance(WETH, contract()), U256::from(50 * E18) - out);
+        assert_eq!(erc20_balance(GOV, contract()), U256::from(801 * E18));
+
+        // buying it back costs more than it paid out
+        let paid = pool.swap_exact_amount_out(U256::from(1), U256::MAX, U256::ZERO, U256::from(E18)).unwrap_ok();
+        assert!(paid > out, "{paid}");
+        assert_eq!(erc20_balance(GOV, contract()), U256::from(800 * E18));
+    }
+}
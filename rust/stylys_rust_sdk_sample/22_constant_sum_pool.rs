@@ -0,0 +1,427 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+use crate::erc20::{Erc20, Erc20Error, Erc20Params};
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloy_sol_types::sol;
+use amm_math::constant_sum::{is_depegged, quote, Pricing};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U8},
+    call::Call,
+    contract, evm, msg,
+    prelude::*,
+};
+
+struct ConstantSumParams;
+
+impl Erc20Params for ConstantSumParams {
+    const NAME: &'static str = "Constant Sum LP";
+    const SYMBOL: &'static str = "CSLP";
+    const DECIMALS: u8 = 18;
+}
+
+sol_interface! {
+    interface IERC20 {
+        function decimals() external view returns (uint8);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+sol! {
+    event Swap(address indexed sender, bool zero_for_one, uint256 amount_in, uint256 amount_out, bool constant_product);
+    event Mint(address indexed provider, uint256 amount0, uint256 amount1, uint256 shares);
+    event Burn(address indexed provider, uint256 amount0, uint256 amount1, uint256 shares);
+    event DepegDetected(uint256 reserve0, uint256 reserve1, uint256 threshold_bps, bool halted);
+    event PegRestored(uint256 reserve0, uint256 reserve1);
+    event ParametersUpdated(uint256 fee_bps, uint256 threshold_bps, uint8 fallback_mode);
+
+    error AlreadyInitialized();
+    error DecimalsMismatch(uint8 decimals0, uint8 decimals1);
+    error InvalidParameters(uint256 fee_bps, uint256 threshold_bps, uint8 fallback_mode);
+    error PoolHalted();
+    error ExceedsThreshold(uint256 amount_in, uint256 threshold_bps);
+    error InsufficientLiquidity(uint256 reserve, uint256 amount);
+    error Slippage(uint256 amount, uint256 limit);
+    error ZeroShares();
+    error InsufficientShares(uint256 have, uint256 want);
+    error ExternalCallFailed(address target);
+    error MathOverflow();
+}
+
+#[derive(SolidityError)]
+pub enum ConstantSumError {
+    AlreadyInitialized(AlreadyInitialized),
+    DecimalsMismatch(DecimalsMismatch),
+    InvalidParameters(InvalidParameters),
+    PoolHalted(PoolHalted),
+    ExceedsThreshold(ExceedsThreshold),
+    InsufficientLiquidity(InsufficientLiquidity),
+    Slippage(Slippage),
+    ZeroShares(ZeroShares),
+    InsufficientShares(InsufficientShares),
+    ExternalCallFailed(ExternalCallFailed),
+    MathOverflow(MathOverflow),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for ConstantSumError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => ConstantSumError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => ConstantSumError::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<Erc20Error> for ConstantSumError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => ConstantSumError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+            Erc20Error::InsufficientAllowance(e) => ConstantSumError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+        }
+    }
+}
+
+// What the pool does once a reserve falls below the threshold.
+const FALLBACK_HALT: u8 = 0;
+const FALLBACK_CONSTANT_PRODUCT: u8 = 1;
+const MAX_FEE_BPS: u64 = 100;
+// a threshold of 50% or more would flag a perfectly balanced pool
+const MAX_THRESHOLD_BPS: u64 = 5_000;
+
+// x + y = k pool for wrapped or bridged versions of the same asset, which
+// should never trade far from 1:1. Both tokens must share decimals.
+//
+// The circuit breaker watches for either reserve under `threshold_bps` of the
+// pool. In halt mode a trade that would cross the threshold reverts, and a swap
+// against reserves that are already past it stops the pool until the owner
+// resumes it; in constant-product mode such trades (and every trade while
+// depegged) are priced on x * y = k instead.
+sol_storage! {
+    #[entrypoint]
+    pub struct ConstantSumPool {
+        address token0;
+        address token1;
+        uint256 reserve0;
+        uint256 reserve1;
+        uint256 fee_bps;
+        uint256 threshold_bps;
+        uint8 fallback_mode;
+        bool halted;
+        // last depeg state seen, so DepegDetected fires once per episode
+        bool depegged;
+        #[borrow]
+        Erc20<ConstantSumParams> erc20;
+        #[borrow]
+        Ownable ownable;
+    }
+}
+
+impl ConstantSumPool {
+    fn failed(target: Address) -> ConstantSumError {
+        ConstantSumError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    fn overflow() -> ConstantSumError {
+        ConstantSumError::MathOverflow(MathOverflow {})
+    }
+
+    fn store_parameters(&mut self, fee_bps: u64, threshold_bps: u64, fallback_mode: u8) -> Result<(), ConstantSumError> {
+        if fee_bps > MAX_FEE_BPS || threshold_bps > MAX_THRESHOLD_BPS || fallback_mode > FALLBACK_CONSTANT_PRODUCT {
+            return Err(ConstantSumError::InvalidParameters(InvalidParameters {
+                fee_bps: U256::from(fee_bps),
+                threshold_bps: U256::from(threshold_bps),
+                fallback_mode,
+            }));
+        }
+        self.fee_bps.set(U256::from(fee_bps));
+        self.threshold_bps.set(U256::from(threshold_bps));
+        self.fallback_mode.set(U8::from(fallback_mode));
+        evm::log(ParametersUpdated { fee_bps: U256::from(fee_bps), threshold_bps: U256::from(threshold_bps), fallback_mode });
+        Ok(())
+    }
+
+    fn pull(&mut self, token: Address, amount: U256) -> Result<(), ConstantSumError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let ok = IERC20::new(token)
+            .transfer_from(Call::new_in(self), msg::sender(), contract::address(), amount)
+            .map_err(|_| Self::failed(token))?;
+        if !ok {
+            return Err(Self::failed(token));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, token: Address, amount: U256) -> Result<(), ConstantSumError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let ok = IERC20::new(token).transfer(Call::new_in(self), msg::sender(), amount).map_err(|_| Self::failed(token))?;
+        if !ok {
+            return Err(Self::failed(token));
+        }
+        Ok(())
+    }
+
+    // Keeps the depeg flag in step with the reserves and logs the transitions.
+    fn update_peg_state(&mut self, reserve0: U256, reserve1: U256) -> Result<(), ConstantSumError> {
+        let threshold = self.threshold_bps.get();
+        let depegged = is_depegged(reserve0, reserve1, threshold.saturating_to::<u64>()).ok_or_else(Self::overflow)?;
+        if depegged && !self.depegged.get() {
+            evm::log(DepegDetected { reserve0, reserve1, threshold_bps: threshold, halted: self.halted.get() });
+        } else if !depegged && self.depegged.get() {
+            evm::log(PegRestored { reserve0, reserve1 });
+        }
+        self.depegged.set(depegged);
+        Ok(())
+    }
+
+    // (amount out, priced on x * y = k) at current reserves.
+    fn price_swap(&self, zero_for_one: bool, amount_in: U256) -> Result<(U256, bool), ConstantSumError> {
+        let (reserve0, reserve1) = (self.reserve0.get(), self.reserve1.get());
+        let (reserve_in, reserve_out) = if zero_for_one { (reserve0, reserve1) } else { (reserve1, reserve0) };
+        let (amount_out, pricing) = quote(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            self.fee_bps.get().saturating_to::<u64>(),
+            self.threshold_bps.get().saturating_to::<u64>(),
+        )
+        .ok_or_else(Self::overflow)?;
+        Ok((amount_out, pricing == Pricing::ConstantProduct))
+    }
+}
+
+#[external]
+#[inherit(Erc20<ConstantSumParams>, Ownable)]
+impl ConstantSumPool {
+    // `fallback_mode`: 0 halts swaps on a depeg, 1 falls back to x * y = k pricing.
+    pub fn init(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        fee_bps: u64,
+        threshold_bps: u64,
+        fallback_mode: u8,
+        deploy_nonce: u64,
+    ) -> Result<(), ConstantSumError> {
+        if self.token0.get() != Address::ZERO {
+            return Err(ConstantSumError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        let decimals0 = IERC20::new(token0).decimals(&*self).map_err(|_| Self::failed(token0))?;
+        let decimals1 = IERC20::new(token1).decimals(&*self).map_err(|_| Self::failed(token1))?;
+        if decimals0 != decimals1 {
+            return Err(ConstantSumError::DecimalsMismatch(DecimalsMismatch { decimals0, decimals1 }));
+        }
+        self.ownable.initialize(deploy_nonce)?;
+        self.token0.set(token0);
+        self.token1.set(token1);
+        self.store_parameters(fee_bps, threshold_bps, fallback_mode)
+    }
+
+    pub fn get_reserves(&self) -> Result<(U256, U256), ConstantSumError> {
+        Ok((self.reserve0.get(), self.reserve1.get()))
+    }
+
+    // (fee_bps, threshold_bps, fallback_mode, halted, depegged)
+    pub fn parameters(&self) -> Result<(U256, U256, u8, bool, bool), ConstantSumError> {
+        Ok((
+            self.fee_bps.get(),
+            self.threshold_bps.get(),
+            self.fallback_mode.get().saturating_to::<u8>(),
+            self.halted.get(),
+            self.depegged.get(),
+        ))
+    }
+
+    // (amount out, whether x * y = k pricing applies) for a swap at current reserves.
+    pub fn get_amount_out(&self, zero_for_one: bool, amount_in: U256) -> Result<(U256, bool), ConstantSumError> {
+        self.price_swap(zero_for_one, amount_in)
+    }
+
+    // In halt mode a trade that would depeg the pool reverts with nothing stored,
+    // so an oversized amount can't be used to halt a healthy pool. Only when the
+    // reserves themselves are past the threshold (e.g. after a lopsided deposit)
+    // does the swap halt the pool, returning 0 without moving any tokens; the
+    // call succeeds so that the halt is recorded.
+    pub fn swap(&mut self, zero_for_one: bool, amount_in: U256, min_amount_out: U256) -> Result<U256, ConstantSumError> {
+        if self.halted.get() {
+            return Err(ConstantSumError::PoolHalted(PoolHalted {}));
+        }
+        let (amount_out, constant_product) = self.price_swap(zero_for_one, amount_in)?;
+        if constant_product && self.fallback_mode.get().saturating_to::<u8>() == FALLBACK_HALT {
+            let (reserve0, reserve1) = (self.reserve0.get(), self.reserve1.get());
+            let threshold_bps = self.threshold_bps.get();
+            if !is_depegged(reserve0, reserve1, threshold_bps.saturating_to::<u64>()).ok_or_else(Self::overflow)? {
+                return Err(ConstantSumError::ExceedsThreshold(ExceedsThreshold { amount_in, threshold_bps }));
+            }
+            self.halted.set(true);
+            evm::log(DepegDetected { reserve0, reserve1, threshold_bps, halted: true });
+            return Ok(U256::ZERO);
+        }
+        if amount_out < min_amount_out {
+            return Err(ConstantSumError::Slippage(Slippage { amount: amount_out, limit: min_amount_out }));
+        }
+
+        let (token0, token1) = (self.token0.get(), self.token1.get());
+        let (mut reserve0, mut reserve1) = (self.reserve0.get(), self.reserve1.get());
+        let (token_in, token_out) = if zero_for_one {
+            if amount_out >= reserve1 {
+                return Err(ConstantSumError::InsufficientLiquidity(InsufficientLiquidity { reserve: reserve1, amount: amount_out }));
+            }
+            reserve0 += amount_in;
+            reserve1 -= amount_out;
+            (token0, token1)
+        } else {
+            if amount_out >= reserve0 {
+                return Err(ConstantSumError::InsufficientLiquidity(InsufficientLiquidity { reserve: reserve0, amount: amount_out }));
+            }
+            reserve1 += amount_in;
+            reserve0 -= amount_out;
+            (token1, token0)
+        };
+        self.reserve0.set(reserve0);
+        self.reserve1.set(reserve1);
+        self.update_peg_state(reserve0, reserve1)?;
+
+        self.pull(token_in, amount_in)?;
+        self.send(token_out, amount_out)?;
+        evm::log(Swap { sender: msg::sender(), zero_for_one, amount_in, amount_out, constant_product });
+        Ok(amount_out)
+    }
+
+    // Both tokens count 1:1, so any mix is accepted and can rebalance a depegged pool.
+    pub fn add_liquidity(&mut self, amount0: U256, amount1: U256, min_shares: U256) -> Result<U256, ConstantSumError> {
+        let (reserve0, reserve1) = (self.reserve0.get(), self.reserve1.get());
+        let supply = self.erc20.total_supply.get();
+        let value = amount0 + amount1;
+        let shares = if supply == U256::ZERO { value } else { value * supply / (reserve0 + reserve1) };
+        if shares == U256::ZERO {
+            return Err(ConstantSumError::ZeroShares(ZeroShares {}));
+        }
+        if shares < min_shares {
+            return Err(ConstantSumError::Slippage(Slippage { amount: shares, limit: min_shares }));
+        }
+        let (token0, token1) = (self.token0.get(), self.token1.get());
+        self.pull(token0, amount0)?;
+        self.pull(token1, amount1)?;
+        self.reserve0.set(reserve0 + amount0);
+        self.reserve1.set(reserve1 + amount1);
+        self.update_peg_state(reserve0 + amount0, reserve1 + amount1)?;
+        self.erc20.mint(msg::sender(), shares);
+        evm::log(Mint { provider: msg::sender(), amount0, amount1, shares });
+        Ok(shares)
+    }
+
+    // Proportional, so it never moves the pool toward a depeg; allowed while halted.
+    pub fn remove_liquidity(&mut self, shares: U256) -> Result<(U256, U256), ConstantSumError> {
+        let supply = self.erc20.total_supply.get();
+        let (reserve0, reserve1) = (self.reserve0.get(), self.reserve1.get());
+        self.erc20.burn(msg::sender(), shares)?;
+        let amount0 = reserve0 * shares / supply;
+        let amount1 = reserve1 * shares / supply;
+        self.reserve0.set(reserve0 - amount0);
+        self.reserve1.set(reserve1 - amount1);
+        let (token0, token1) = (self.token0.get(), self.token1.get());
+        self.send(token0, amount0)?;
+        self.send(token1, amount1)?;
+        evm::log(Burn { provider: msg::sender(), amount0, amount1, shares });
+        Ok((amount0, amount1))
+    }
+
+    pub fn set_parameters(&mut self, fee_bps: u64, threshold_bps: u64, fallback_mode: u8) -> Result<(), ConstantSumError> {
+        self.ownable.only_owner()?;
+        self.store_parameters(fee_bps, threshold_bps, fallback_mode)
+    }
+
+    // Reopens swapping after a halt, e.g. once the peg is restored or liquidity rebalanced.
+    pub fn resume(&mut self) -> Result<(), ConstantSumError> {
+        self.ownable.only_owner()?;
+        self.halted.set(false);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, SENDER};
+
+    const USDC: Address = Address::repeat_byte(0xc0);
+    const USDCE: Address = Address::repeat_byte(0xce);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // 1_000 / 1_000 with a 4 bps fee and a 20% threshold.
+    fn pool(fallback_mode: u8) -> ConstantSumPool {
+        let mut pool = deploy::<ConstantSumPool>();
+        for token in [USDC, USDCE] {
+            mock_erc20(token);
+            mint_erc20(token, SENDER, U256::from(1_000_000 * E18));
+        }
+        pool.init(USDC, USDCE, 4, 2_000, fallback_mode, 0).unwrap_ok();
+        pool.add_liquidity(U256::from(1_000 * E18), U256::from(1_000 * E18), U256::ZERO).unwrap_ok();
+        pool
+    }
+
+    #[test]
+    fn an_oversized_trade_reverts_without_halting() {
+        let mut pool = pool(FALLBACK_HALT);
+        let result = pool.swap(true, U256::from(1_000_000 * E18), U256::ZERO);
+        assert!(matches!(result, Err(ConstantSumError::ExceedsThreshold(_))));
+        assert!(!pool.parameters().unwrap_ok().3);
+
+        let out = pool.swap(true, U256::from(100 * E18), U256::ZERO).unwrap_ok();
+        assert_eq!(out, U256::from(100 * E18 * 9_996 / 10_000));
+        assert_eq!(erc20_balance(USDCE, contract()), U256::from(1_000 * E18) - out);
+    }
+
+    #[test]
+    fn depegged_reserves_halt_the_pool() {
+        let mut pool = pool(FALLBACK_HALT);
+        // one-sided deposit leaves USDC.e at 1_000 / 6_000 of the pool, under 20%
+        pool.add_liquidity(U256::from(5_000 * E18), U256::ZERO, U256::ZERO).unwrap_ok();
+        assert!(pool.parameters().unwrap_ok().4);
+
+        assert_eq!(pool.swap(true, U256::from(E18), U256::ZERO).unwrap_ok(), U256::ZERO);
+        assert!(pool.parameters().unwrap_ok().3);
+        assert_eq!(erc20_balance(USDC, contract()), U256::from(6_000 * E18));
+        assert!(matches!(pool.swap(false, U256::from(E18), U256::ZERO), Err(ConstantSumError::PoolHalted(_))));
+
+        pool.resume().unwrap_ok();
+        assert!(!pool.parameters().unwrap_ok().3);
+    }
+
+    #[test]
+    fn constant_product_fallback_prices_large_trades() {
+        let mut pool = pool(FALLBACK_CONSTANT_PRODUCT);
+        let amount_in = U256::from(1_500 * E18);
+        let (quoted, constant_product) = pool.get_amount_out(true, amount_in).unwrap_ok();
+        assert!(constant_product);
+        // x * y = k: 1_499.4 * 1_000 / 2_499.4, about 600
+        let after_fee = amount_in * U256::from(9_996) / U256::from(10_000);
+        assert_eq!(quoted, after_fee * U256::from(1_000 * E18) / (U256::from(1_000 * E18) + after_fee));
+        assert_eq!(pool.swap(true, amount_in, quoted).unwrap_ok(), quoted);
+        // the remaining ~400 USDC.e is under 20% of the pool
+        assert!(pool.parameters().unwrap_ok().4);
+        // amounts the math can't hold revert instead of wrapping
+        assert!(matches!(pool.get_amount_out(false, U256::MAX), Err(ConstantSumError::MathOverflow(_))));
+        assert!(matches!(pool.swap(false, U256::MAX, U256::ZERO), Err(ConstantSumError::MathOverflow(_))));
+    }
+}
@@ -0,0 +1,270 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod pair_state;
+use crate::erc20::Erc20;
+use crate::ownable::Ownable;
+use crate::pair_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::SolInterface;
+use core::borrow::{Borrow, BorrowMut};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256},
+    call::{self, delegate_call},
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+// The x * y = k pair from 4_contract_call.rs (3.1) as a full contract: an LP
+// token over two reserves, with a V2 price oracle and a fee that follows the
+// pair's own volatility.
+//
+// All of it in one program is far over the 24KB limit, so the pair itself only
+// keeps its setup, the LP token and the reserves. Mint and burn
+// (23_pair_liquidity.rs), swaps (23_pair_swap.rs), long-term orders
+// (23_pair_twamm.rs), batch auctions (23_pair_batch.rs) and the payouts of
+// both kinds of order (23_pair_claims.rs) live in facets. The pair forwards their selectors with
+// delegate_call, so callers use the pair's address for everything. Long-term
+// orders run lazily: every state-changing call first has the TWAMM facet trade
+// the elapsed blocks.
+sol_storage! {
+    pub struct Pair {
+        #[borrow]
+        PairState state;
+        #[borrow]
+        Erc20<PairParams> erc20;
+    }
+}
+
+// `#[entrypoint]` goes on `dispatch` below, which adds the facet fallback.
+unsafe impl TopLevelStorage for Pair {}
+
+impl Borrow<Ownable> for Pair {
+    fn borrow(&self) -> &Ownable {
+        &self.state.ownable
+    }
+}
+
+impl BorrowMut<Ownable> for Pair {
+    fn borrow_mut(&mut self) -> &mut Ownable {
+        &mut self.state.ownable
+    }
+}
+
+// Runs the pair's own methods, then hands any selector a facet serves to that
+// facet, on the pair's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut pair = unsafe { Pair::new(U256::ZERO, 0) };
+    if let Some(result) = <Pair as Router<Pair>>::route(&mut pair, u32::from_be_bytes(selector), &input[4..]) {
+        return result;
+    }
+    let Some(facet) = pair.facet(selector) else {
+        return Err(Vec::new());
+    };
+    unsafe { delegate_call(&mut pair, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
+
+impl Pair {
+    fn facet(&self, selector: [u8; 4]) -> Option<Address> {
+        if IPairLiquidity::IPairLiquidityCalls::valid_selector(selector) {
+            Some(self.state.liquidity_facet.get())
+        } else if IPairSwap::IPairSwapCalls::valid_selector(selector) {
+            Some(self.state.swap_facet.get())
+        } else if IPairTwamm::IPairTwammCalls::valid_selector(selector) {
+            Some(self.state.twamm_facet.get())
+        } else if IPairBatch::IPairBatchCalls::valid_selector(selector) {
+            Some(self.state.batch_facet.get())
+        } else if IPairClaims::IPairClaimsCalls::valid_selector(selector) {
+            Some(self.state.claims_facet.get())
+        } else {
+            None
+        }
+    }
+}
+
+#[external]
+#[inherit(Erc20<PairParams>, Ownable)]
+impl Pair {
+    // e.g. base 5 bps, multiplier 10_000 (1 bp per 0.01% per-block move), max 100 bps, decay 0.94e18.
+    // The facets are fixed here for good: swapping one would change the pair's code.
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        base_fee_bps: u64,
+        volatility_multiplier: U256,
+        max_fee_bps: u64,
+        decay: U256,
+        liquidity_facet: Address,
+        swap_facet: Address,
+        twamm_facet: Address,
+        batch_facet: Address,
+        claims_facet: Address,
+        deploy_nonce: u64,
+    ) -> Result<(), PairError> {
+        if self.state.token0.get() != Address::ZERO {
+            return Err(PairError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.state.set_fee_config(base_fee_bps, volatility_multiplier, max_fee_bps, decay)?;
+        self.state.ownable.initialize(deploy_nonce)?;
+        self.state.token0.set(token0);
+        self.state.token1.set(token1);
+        self.state.liquidity_facet.set(liquidity_facet);
+        self.state.swap_facet.set(swap_facet);
+        self.state.twamm_facet.set(twamm_facet);
+        self.state.batch_facet.set(batch_facet);
+        self.state.claims_facet.set(claims_facet);
+        Ok(())
+    }
+
+    pub fn token0(&self) -> Result<Address, PairError> {
+        Ok(self.state.token0.get())
+    }
+
+    pub fn token1(&self) -> Result<Address, PairError> {
+        Ok(self.state.token1.get())
+    }
+
+    // (liquidity_facet, swap_facet, twamm_facet, batch_facet, claims_facet)
+    pub fn facets(&self) -> Result<(Address, Address, Address, Address, Address), PairError> {
+        let state = &self.state;
+        Ok((
+            state.liquidity_facet.get(),
+            state.swap_facet.get(),
+            state.twamm_facet.get(),
+            state.batch_facet.get(),
+            state.claims_facet.get(),
+        ))
+    }
+
+    pub fn get_reserves(&self) -> Result<(U256, U256, u32), PairError> {
+        let state = &self.state;
+        Ok((state.reserve0.get(), state.reserve1.get(), state.block_timestamp_last.get().saturating_to::<u32>()))
+    }
+
+    // Token0 priced in token1, accumulated per second (WAD).
+    pub fn price0_cumulative_last(&self) -> Result<U256, PairError> {
+        Ok(self.state.price0_cumulative_last.get())
+    }
+
+    pub fn price1_cumulative_last(&self) -> Result<U256, PairError> {
+        Ok(self.state.price1_cumulative_last.get())
+    }
+
+    // Matches reserves to balances, e.g. after a direct transfer.
+    pub fn sync(&mut self) -> Result<(), PairError> {
+        self.execute_long_term_orders()?;
+        let (balance0, balance1) = self.pool_balances()?;
+        self.state.update(balance0, balance1);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, transfer_erc20, CallKind, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const LIQUIDITY: Address = Address::repeat_byte(0x20);
+    const SWAP: Address = Address::repeat_byte(0x21);
+    const TWAMM: Address = Address::repeat_byte(0x22);
+    const BATCH: Address = Address::repeat_byte(0x23);
+    const CLAIMS: Address = Address::repeat_byte(0x24);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // 1_000 / 1_000 with a flat 0.3%.
+    fn pair() -> Pair {
+        let mut pair = deploy::<Pair>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+            mint_erc20(token, SENDER, U256::from(1_000 * E18));
+        }
+        pair.init(TOKEN0, TOKEN1, 30, U256::ZERO, 30, U256::from(94 * E18 / 100), LIQUIDITY, SWAP, TWAMM, BATCH, CLAIMS, 0).unwrap_ok();
+        pair.sync().unwrap_ok();
+        pair
+    }
+
+    #[test]
+    fn init_checks_the_fee_config() {
+        let mut pair = deploy::<Pair>();
+        let init = |pair: &mut Pair, max_fee_bps| {
+            pair.init(TOKEN0, TOKEN1, 30, U256::ZERO, max_fee_bps, U256::ZERO, LIQUIDITY, SWAP, TWAMM, BATCH, CLAIMS, 0)
+        };
+        assert!(matches!(init(&mut pair, MAX_FEE_BPS + 1), Err(PairError::InvalidFeeConfig(_))));
+        init(&mut pair, MAX_FEE_BPS).unwrap_ok();
+        assert_eq!(pair.facets().unwrap_ok(), (LIQUIDITY, SWAP, TWAMM, BATCH, CLAIMS));
+        assert!(matches!(init(&mut pair, MAX_FEE_BPS), Err(PairError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn facet_selectors_run_on_the_facets() {
+        let pair = pair();
+        mock_call(LIQUIDITY, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        mock_call(SWAP, |_| Ok(Vec::new()));
+        mock_call(CLAIMS, |_| Err(b"claims says no".to_vec()));
+
+        let data = IPairLiquidity::mintCall { to: SENDER }.abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+        let data =
+            IPairSwap::swapCall { amount0_out: U256::ZERO, amount1_out: U256::from(1), to: SENDER, data: Default::default() }.abi_encode();
+        assert_eq!(dispatch(data).unwrap_ok(), Vec::<u8>::new());
+        assert_eq!(calls().last().unwrap().to, SWAP);
+        // facet reverts come back as they are
+        let data = IPairClaims::claimCall { order_id: U256::from(1) }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"claims says no".to_vec());
+        // the pair's own methods don't leave it
+        assert_eq!(pair.facet(stylus_sdk::function_selector!("token0")), None);
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn sync_catches_up_long_term_orders_first() {
+        let mut pair = pair();
+        let catch_up = IPairTwamm::executeVirtualOrdersCall {}.abi_encode();
+        let hook_calls = || calls().iter().filter(|call| call.to == TWAMM && call.data == catch_up).count();
+        // off until the TWAMM facet enables them
+        assert_eq!(hook_calls(), 0);
+
+        pair.state.order_block_interval.set(U256::from(100));
+        transfer_erc20(TOKEN0, SENDER, contract(), U256::from(E18));
+        pair.sync().unwrap_ok();
+        assert_eq!(hook_calls(), 1);
+        assert!(calls().iter().filter(|call| call.to == TWAMM).all(|call| call.kind == CallKind::Delegate));
+        assert_eq!(pair.get_reserves().unwrap_ok().0, erc20_balance(TOKEN0, contract()));
+
+        mock_call(TWAMM, |_| Err(Vec::new()));
+        assert!(matches!(pair.sync(), Err(PairError::FacetCallFailed(_))));
+    }
+}
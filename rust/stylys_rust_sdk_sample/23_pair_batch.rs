@@ -0,0 +1,273 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod namespaced;
+mod ownable;
+mod pair_orders;
+mod pair_state;
+use crate::namespaced::Namespaced;
+use crate::pair_orders::*;
+use crate::pair_state::*;
+use alloy_sol_types::sol;
+use amm_math::batch_auction::settle;
+use stylus_sdk::{alloy_primitives::U256, block, evm, msg, prelude::*};
+
+sol! {
+    event BatchModeUpdated(bool enabled, uint256 epoch_blocks);
+    event OrderCommitted(uint256 indexed order_id, address indexed owner, uint256 indexed batch_id, bool zero_for_one, uint256 amount_in);
+    event BatchSettled(uint256 indexed batch_id, uint256 amount0_in, uint256 amount1_in, uint256 price_numerator, uint256 price_denominator, uint256 fee_bps);
+    event BatchCancelled(uint256 indexed batch_id, uint256 amount0_in, uint256 amount1_in);
+}
+
+// Batch auctions (amm_math::batch_auction) for the pair in 23_pair.rs, which
+// delegate_calls this facet for the `IPairBatch` selectors and runs on the
+// pair's storage (see pair_state.rs for the layout).
+//
+// In batch mode the pair's `swap` is off. Orders are committed into the
+// epoch's batch, the whole batch clears at one FM-AMM price once the epoch is
+// over, and each order's output waits in escrow until claimed through
+// 23_pair_claims.rs.
+sol_storage! {
+    #[entrypoint]
+    pub struct PairBatch {
+        #[borrow]
+        PairState state;
+        Namespaced<BatchState> batches;
+    }
+}
+
+impl PairBatch {
+    fn commit_order_at(&mut self, zero_for_one: bool, amount_in: U256, number: U256) -> Result<U256, PairError> {
+        if !self.state.batch_mode.get() {
+            return Err(PairError::BatchModeDisabled(BatchModeDisabled {}));
+        }
+        if amount_in == U256::ZERO {
+            return Err(PairError::InsufficientInputAmount(InsufficientInputAmount {}));
+        }
+        let owner = msg::sender();
+        self.pull(self.state.token(zero_for_one), owner, amount_in)?;
+        self.state.rebalance_escrow(zero_for_one, U256::ZERO, amount_in)?;
+
+        let mut batch_id = self.batches.open_batch.get();
+        if number >= self.batches.batches.get(batch_id).end_block.get() {
+            let epoch = self.batches.epoch_blocks.get();
+            batch_id += U256::from(1);
+            self.batches.open_batch.set(batch_id);
+            self.batches.batches.setter(batch_id).end_block.set((number / epoch + U256::from(1)) * epoch);
+        }
+        let mut batch = self.batches.batches.setter(batch_id);
+        let total = if zero_for_one { &mut batch.amount0_in } else { &mut batch.amount1_in };
+        total.set(total.get() + amount_in);
+
+        let order_id = self.batches.next_order_id.get() + U256::from(1);
+        self.batches.next_order_id.set(order_id);
+        let mut order = self.batches.orders.setter(order_id);
+        order.owner.set(owner);
+        order.batch_id.set(batch_id);
+        order.zero_for_one.set(zero_for_one);
+        order.amount_in.set(amount_in);
+        evm::log(OrderCommitted { order_id, owner, batch_id, zero_for_one, amount_in });
+        Ok(order_id)
+    }
+
+    fn settle_batch_at(&mut self, batch_id: U256, number: U256) -> Result<(), PairError> {
+        let (end_block, amount0_in, amount1_in, _, _, settled, _) = self.batch(batch_id)?;
+        if end_block == U256::ZERO {
+            return Err(PairError::UnknownBatch(UnknownBatch { batch_id }));
+        }
+        if settled {
+            return Err(PairError::BatchAlreadySettled(BatchAlreadySettled { batch_id }));
+        }
+        if number < end_block {
+            return Err(PairError::BatchNotEnded(BatchNotEnded { batch_id, end_block }));
+        }
+        self.execute_long_term_orders()?;
+        self.state.observe(number);
+        let fee_bps = self.state.fee_bps();
+        let (reserve0, reserve1) = (self.state.reserve0.get(), self.state.reserve1.get());
+        let mut batch = self.batches.batches.setter(batch_id);
+        // a batch that can't clear (an empty pool, or inputs too big for the
+        // price math) would hold its orders' escrow forever, so it closes and
+        // refunds them instead
+        let Some(settlement) = settle(reserve0, reserve1, amount0_in, amount1_in, fee_bps) else {
+            batch.settled.set(true);
+            batch.cancelled.set(true);
+            evm::log(BatchCancelled { batch_id, amount0_in, amount1_in });
+            return Ok(());
+        };
+        batch.price_numerator.set(settlement.price.numerator);
+        batch.price_denominator.set(settlement.price.denominator);
+        batch.fee_bps.set(U256::from(fee_bps));
+        batch.settled.set(true);
+        self.state.rebalance_escrow(true, amount0_in, settlement.amount0_out)?;
+        self.state.rebalance_escrow(false, amount1_in, settlement.amount1_out)?;
+        self.state.update(settlement.reserve0, settlement.reserve1);
+        evm::log(BatchSettled {
+            batch_id,
+            amount0_in,
+            amount1_in,
+            price_numerator: settlement.price.numerator,
+            price_denominator: settlement.price.denominator,
+            fee_bps: U256::from(fee_bps),
+        });
+        Ok(())
+    }
+}
+
+#[external]
+impl PairBatch {
+    // Switching off leaves open batches settleable and claimable.
+    pub fn set_batch_mode(&mut self, enabled: bool, epoch_blocks: u64) -> Result<(), PairError> {
+        self.state.ownable.only_owner()?;
+        if epoch_blocks == 0 {
+            return Err(PairError::InvalidEpoch(InvalidEpoch {}));
+        }
+        self.state.batch_mode.set(enabled);
+        self.batches.epoch_blocks.set(U256::from(epoch_blocks));
+        evm::log(BatchModeUpdated { enabled, epoch_blocks: U256::from(epoch_blocks) });
+        Ok(())
+    }
+
+    // Pulls `amount_in` (approve the pair first) into the open batch, starting a
+    // new one if the last has ended.
+    pub fn commit_order(&mut self, zero_for_one: bool, amount_in: U256) -> Result<U256, PairError> {
+        self.commit_order_at(zero_for_one, amount_in, U256::from(block::number()))
+    }
+
+    // Clears an ended batch against the reserves at the current dynamic fee, or
+    // cancels it if it can't clear. Anyone can call it.
+    pub fn settle_batch(&mut self, batch_id: U256) -> Result<(), PairError> {
+        self.settle_batch_at(batch_id, U256::from(block::number()))
+    }
+
+    pub fn open_batch(&self) -> Result<U256, PairError> {
+        Ok(self.batches.open_batch.get())
+    }
+
+    // (end_block, amount0_in, amount1_in, price_numerator, price_denominator, settled, cancelled)
+    #[allow(clippy::type_complexity)]
+    pub fn batch(&self, batch_id: U256) -> Result<(U256, U256, U256, U256, U256, bool, bool), PairError> {
+        let batch = self.batches.batches.get(batch_id);
+        Ok((
+            batch.end_block.get(),
+            batch.amount0_in.get(),
+            batch.amount1_in.get(),
+            batch.price_numerator.get(),
+            batch.price_denominator.get(),
+            batch.settled.get(),
+            batch.cancelled.get(),
+        ))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, BLOCK_NUMBER, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // The pair's state as its init and first mint leave it: 1_000 / 1_000 with
+    // a flat 0.3% fee, in batch mode with 10-block epochs.
+    fn pair() -> PairBatch {
+        let mut pair = deploy::<PairBatch>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+            mint_erc20(token, SENDER, U256::from(1_000 * E18));
+        }
+        let state = &mut pair.state;
+        state.ownable.initialize(0).unwrap_ok();
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.base_fee_bps.set(U256::from(30));
+        state.max_fee_bps.set(U256::from(30));
+        state.decay.set(U256::from(94 * E18 / 100));
+        state.update(U256::from(1_000 * E18), U256::from(1_000 * E18));
+        pair.set_batch_mode(true, 10).unwrap_ok();
+        pair
+    }
+
+    // Whatever the pair holds is either reserves or escrow.
+    fn assert_balanced(pair: &PairBatch) {
+        let state = &pair.state;
+        assert_eq!(erc20_balance(TOKEN0, contract()), state.reserve0.get() + state.escrow0.get());
+        assert_eq!(erc20_balance(TOKEN1, contract()), state.reserve1.get() + state.escrow1.get());
+    }
+
+    #[test]
+    fn serves_every_selector_the_pair_forwards() {
+        let mut pair = pair();
+        for selector in IPairBatch::IPairBatchCalls::SELECTORS {
+            let routed = <PairBatch as Router<PairBatch>>::route(&mut pair, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn settling_moves_the_batch_through_the_escrow() {
+        let mut pair = pair();
+        let start = U256::from(BLOCK_NUMBER);
+        let orders = [(true, 10 * E18), (true, E18 / 3 + 7), (false, 4 * E18), (false, 1), (true, 999)];
+        for (zero_for_one, amount) in orders {
+            pair.commit_order_at(zero_for_one, U256::from(amount), start).unwrap_ok();
+        }
+        let (end_block, amount0_in, amount1_in, _, _, _, _) = pair.batch(U256::from(1)).unwrap_ok();
+        assert_eq!(end_block, start + U256::from(10));
+        assert_eq!((amount0_in, amount1_in), (U256::from(10 * E18 + E18 / 3 + 7 + 999), U256::from(4 * E18 + 1)));
+        assert_eq!((pair.state.escrow0.get(), pair.state.escrow1.get()), (amount0_in, amount1_in));
+        assert_balanced(&pair);
+
+        assert!(matches!(pair.settle_batch_at(U256::from(1), end_block - U256::from(1)), Err(PairError::BatchNotEnded(_))));
+        assert!(matches!(pair.settle_batch_at(U256::from(2), end_block), Err(PairError::UnknownBatch(_))));
+
+        // the inputs go into the reserves and the outputs wait in escrow
+        let (reserve0, reserve1) = (pair.state.reserve0.get(), pair.state.reserve1.get());
+        let expected = settle(reserve0, reserve1, amount0_in, amount1_in, 30).unwrap();
+        pair.settle_batch_at(U256::from(1), end_block).unwrap_ok();
+        assert_eq!((pair.state.reserve0.get(), pair.state.reserve1.get()), (expected.reserve0, expected.reserve1));
+        assert_eq!((pair.state.escrow0.get(), pair.state.escrow1.get()), (expected.amount0_out, expected.amount1_out));
+        let (_, _, _, numerator, denominator, settled, cancelled) = pair.batch(U256::from(1)).unwrap_ok();
+        assert_eq!((numerator, denominator, settled, cancelled), (expected.price.numerator, expected.price.denominator, true, false));
+        assert_balanced(&pair);
+        assert!(matches!(pair.settle_batch_at(U256::from(1), end_block), Err(PairError::BatchAlreadySettled(_))));
+
+        // the next commit opens the next batch
+        let id = pair.commit_order_at(true, U256::from(E18), end_block).unwrap_ok();
+        assert_eq!(pair.batches.orders.get(id).batch_id.get(), U256::from(2));
+        assert_eq!(pair.open_batch().unwrap_ok(), U256::from(2));
+    }
+
+    #[test]
+    fn a_batch_that_cannot_clear_is_cancelled() {
+        let mut pair = pair();
+        let start = U256::from(BLOCK_NUMBER);
+        pair.commit_order_at(true, U256::from(E18), start).unwrap_ok();
+        pair.commit_order_at(false, U256::from(2 * E18), start).unwrap_ok();
+        // an emptied pool has no price to clear at
+        pair.state.update(U256::ZERO, U256::from(1_000 * E18));
+
+        pair.settle_batch_at(U256::from(1), start + U256::from(10)).unwrap_ok();
+        let (_, _, _, numerator, denominator, settled, cancelled) = pair.batch(U256::from(1)).unwrap_ok();
+        assert_eq!((numerator, denominator, settled, cancelled), (U256::ZERO, U256::ZERO, true, true));
+        // the inputs stay in escrow for the refunds
+        assert_eq!((pair.state.escrow0.get(), pair.state.escrow1.get()), (U256::from(E18), U256::from(2 * E18)));
+        assert_eq!((pair.state.reserve0.get(), pair.state.reserve1.get()), (U256::ZERO, U256::from(1_000 * E18)));
+        assert!(matches!(pair.settle_batch_at(U256::from(1), start + U256::from(10)), Err(PairError::BatchAlreadySettled(_))));
+    }
+}
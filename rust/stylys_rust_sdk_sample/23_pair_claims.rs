@@ -0,0 +1,418 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod namespaced;
+mod ownable;
+mod pair_orders;
+mod pair_state;
+use crate::namespaced::Namespaced;
+use crate::ownable::Unauthorized;
+use crate::pair_orders::*;
+use crate::pair_state::*;
+use alloy_sol_types::sol;
+use amm_math::batch_auction::{after_fee, amount0_out, amount1_out, ClearingPrice};
+use amm_math::tick_math::Q128;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block, evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event ProceedsWithdrawn(uint256 indexed order_id, address indexed owner, uint256 proceeds);
+    event LongTermOrderCancelled(uint256 indexed order_id, address indexed owner, uint256 refund, uint256 proceeds);
+    event OrderClaimed(uint256 indexed order_id, address indexed owner, uint256 amount_out);
+    event OrderRefunded(uint256 indexed order_id, address indexed owner, uint256 amount_in);
+}
+
+// Payouts for the pair in 23_pair.rs: withdrawing and cancelling long-term
+// orders (23_pair_twamm.rs), and claiming settled batch orders
+// (23_pair_batch.rs) or refunding those of a cancelled batch. The pair
+// delegate_calls this facet for the `IPairClaims` selectors and it runs on the
+// pair's storage (see pair_state.rs for the layout).
+//
+// What an order is owed already sits in escrow, so paying out never touches
+// the reserves. Long-term orders are caught up through the TWAMM facet first,
+// so they are paid up to the current block.
+sol_storage! {
+    #[entrypoint]
+    pub struct PairClaims {
+        #[borrow]
+        PairState state;
+        Namespaced<TwammState> twamm;
+        Namespaced<BatchState> batches;
+    }
+}
+
+impl PairClaims {
+    // Unpaid proceeds of a long-term order, as of the last execution, and the
+    // reward factor they run to.
+    fn long_term_proceeds(&self, order_id: U256) -> (U256, U256) {
+        let order = self.twamm.long_term_orders.get(order_id);
+        let (zero_for_one, sell_rate, expiry) = (order.zero_for_one.get(), order.sell_rate.get(), order.expiry_block.get());
+        let factor = if self.twamm.last_virtual_order_block.get() >= expiry {
+            if zero_for_one {
+                self.twamm.reward_factor0_at.get(expiry)
+            } else {
+                self.twamm.reward_factor1_at.get(expiry)
+            }
+        } else if zero_for_one {
+            self.twamm.reward_factor0.get()
+        } else {
+            self.twamm.reward_factor1.get()
+        };
+        (sell_rate * (factor - order.reward_factor_last.get()) / Q128, factor)
+    }
+
+    fn long_term_order_owner(&self, order_id: U256) -> Result<Address, PairError> {
+        let owner = self.twamm.long_term_orders.get(order_id).owner.get();
+        if owner == Address::ZERO {
+            return Err(PairError::UnknownOrder(UnknownOrder { order_id }));
+        }
+        if owner != msg::sender() {
+            return Err(PairError::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        Ok(owner)
+    }
+}
+
+#[external]
+impl PairClaims {
+    // Pays out what a long-term order has bought so far; works during and
+    // after the order.
+    pub fn withdraw_proceeds(&mut self, order_id: U256) -> Result<U256, PairError> {
+        let owner = self.long_term_order_owner(order_id)?;
+        self.execute_long_term_orders()?;
+        let (proceeds, factor) = self.long_term_proceeds(order_id);
+        self.twamm.long_term_orders.setter(order_id).reward_factor_last.set(factor);
+        // proceeds are in the token the order buys
+        let zero_for_one = self.twamm.long_term_orders.get(order_id).zero_for_one.get();
+        self.state.rebalance_escrow(!zero_for_one, proceeds, U256::ZERO)?;
+        if proceeds > U256::ZERO {
+            self.safe_transfer(self.state.token(!zero_for_one), owner, proceeds)?;
+        }
+        evm::log(ProceedsWithdrawn { order_id, owner, proceeds });
+        Ok(proceeds)
+    }
+
+    // Stops an open long-term order, refunding the unsold input along with the
+    // proceeds.
+    pub fn cancel_long_term_order(&mut self, order_id: U256) -> Result<(U256, U256), PairError> {
+        let owner = self.long_term_order_owner(order_id)?;
+        self.execute_long_term_orders()?;
+        let now = U256::from(block::number());
+        let (zero_for_one, sell_rate, expiry_block) = {
+            let order = self.twamm.long_term_orders.get(order_id);
+            (order.zero_for_one.get(), order.sell_rate.get(), order.expiry_block.get())
+        };
+        if sell_rate == U256::ZERO || now >= expiry_block {
+            return Err(PairError::LongTermOrderClosed(LongTermOrderClosed { order_id }));
+        }
+        let (proceeds, _) = self.long_term_proceeds(order_id);
+        let refund = sell_rate * (expiry_block - now);
+        self.twamm.long_term_orders.setter(order_id).sell_rate.set(U256::ZERO);
+
+        let (rate, ending, _) = self.twamm.stream(zero_for_one);
+        rate.set(PairState::rebalance(rate.get(), sell_rate, U256::ZERO)?);
+        let total = ending.get(expiry_block);
+        ending.setter(expiry_block).set(PairState::rebalance(total, sell_rate, U256::ZERO)?);
+        self.state.rebalance_escrow(zero_for_one, refund, U256::ZERO)?;
+        self.state.rebalance_escrow(!zero_for_one, proceeds, U256::ZERO)?;
+        self.safe_transfer(self.state.token(zero_for_one), owner, refund)?;
+        if proceeds > U256::ZERO {
+            self.safe_transfer(self.state.token(!zero_for_one), owner, proceeds)?;
+        }
+        evm::log(LongTermOrderCancelled { order_id, owner, refund, proceeds });
+        Ok((refund, proceeds))
+    }
+
+    // (owner, zero_for_one, sell_rate, expiry_block, unpaid proceeds)
+    pub fn long_term_order(&self, order_id: U256) -> Result<(Address, bool, U256, U256, U256), PairError> {
+        let order = self.twamm.long_term_orders.get(order_id);
+        let (proceeds, _) = self.long_term_proceeds(order_id);
+        Ok((order.owner.get(), order.zero_for_one.get(), order.sell_rate.get(), order.expiry_block.get(), proceeds))
+    }
+
+    // Sends a settled batch order's output to its owner. Anyone can call it.
+    pub fn claim(&mut self, order_id: U256) -> Result<U256, PairError> {
+        let (owner, _, zero_for_one, _, claimed) = self.order(order_id)?;
+        if claimed {
+            return Err(PairError::OrderAlreadyClaimed(OrderAlreadyClaimed { order_id }));
+        }
+        let amount_out = self.claimable(order_id)?;
+        self.batches.orders.setter(order_id).claimed.set(true);
+        // the output is in the token the order buys
+        self.state.rebalance_escrow(!zero_for_one, amount_out, U256::ZERO)?;
+        if amount_out > U256::ZERO {
+            self.safe_transfer(self.state.token(!zero_for_one), owner, amount_out)?;
+        }
+        evm::log(OrderClaimed { order_id, owner, amount_out });
+        Ok(amount_out)
+    }
+
+    // Sends a cancelled batch order's input back to its owner. Anyone can call it.
+    pub fn refund(&mut self, order_id: U256) -> Result<U256, PairError> {
+        let (owner, batch_id, zero_for_one, amount_in, claimed) = self.order(order_id)?;
+        if !self.batches.batches.get(batch_id).cancelled.get() {
+            return Err(PairError::BatchNotCancelled(BatchNotCancelled { batch_id }));
+        }
+        if claimed {
+            return Err(PairError::OrderAlreadyClaimed(OrderAlreadyClaimed { order_id }));
+        }
+        self.batches.orders.setter(order_id).claimed.set(true);
+        self.state.rebalance_escrow(zero_for_one, amount_in, U256::ZERO)?;
+        self.safe_transfer(self.state.token(zero_for_one), owner, amount_in)?;
+        evm::log(OrderRefunded { order_id, owner, amount_in });
+        Ok(amount_in)
+    }
+
+    // Output owed to a batch order once its batch has settled.
+    pub fn claimable(&self, order_id: U256) -> Result<U256, PairError> {
+        let (_, batch_id, zero_for_one, amount_in, claimed) = self.order(order_id)?;
+        let batch = self.batches.batches.get(batch_id);
+        if !batch.settled.get() {
+            return Err(PairError::BatchNotSettled(BatchNotSettled { batch_id }));
+        }
+        if batch.cancelled.get() {
+            return Err(PairError::BatchWasCancelled(BatchWasCancelled { batch_id }));
+        }
+        if claimed {
+            return Ok(U256::ZERO);
+        }
+        let price = ClearingPrice { numerator: batch.price_numerator.get(), denominator: batch.price_denominator.get() };
+        let amount_in = after_fee(amount_in, batch.fee_bps.get().saturating_to::<u64>());
+        let amount_out = if zero_for_one { amount1_out(amount_in, &price) } else { amount0_out(amount_in, &price) };
+        amount_out.ok_or_else(PairState::overflow)
+    }
+
+    // (owner, batch_id, zero_for_one, amount_in, claimed)
+    pub fn order(&self, order_id: U256) -> Result<(Address, U256, bool, U256, bool), PairError> {
+        let order = self.batches.orders.get(order_id);
+        if order.owner.get() == Address::ZERO {
+            return Err(PairError::UnknownOrder(UnknownOrder { order_id }));
+        }
+        Ok((order.owner.get(), order.batch_id.get(), order.zero_for_one.get(), order.amount_in.get(), order.claimed.get()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use amm_math::batch_auction::settle;
+    use stylus_sdk::abi::Router;
+    use test_vm::{
+        calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, CallKind, UnwrapOk, BLOCK_NUMBER, BOB, SENDER,
+    };
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const TWAMM: Address = Address::repeat_byte(0x22);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // The pair's state as its init and first mint leave it: 1_000 / 1_000 with
+    // a flat 0.3% fee, and long-term orders enabled.
+    fn pair() -> PairClaims {
+        let mut pair = deploy::<PairClaims>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+        }
+        let state = &mut pair.state;
+        state.ownable.initialize(0).unwrap_ok();
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.twamm_facet.set(TWAMM);
+        state.order_block_interval.set(U256::from(100));
+        state.update(U256::from(1_000 * E18), U256::from(1_000 * E18));
+        pair
+    }
+
+    // Whatever the pair holds is either reserves or escrow.
+    fn assert_balanced(pair: &PairClaims) {
+        let state = &pair.state;
+        assert_eq!(erc20_balance(TOKEN0, contract()), state.reserve0.get() + state.escrow0.get());
+        assert_eq!(erc20_balance(TOKEN1, contract()), state.reserve1.get() + state.escrow1.get());
+    }
+
+    // Tokens sent into escrow, as the order facets pull them.
+    fn escrow(pair: &mut PairClaims, zero: bool, amount: U256) {
+        mint_erc20(pair.state.token(zero), contract(), amount);
+        pair.state.rebalance_escrow(zero, U256::ZERO, amount).unwrap_ok();
+    }
+
+    // A long-term order as the TWAMM facet leaves it, placed this block.
+    fn long_term_order(pair: &mut PairClaims, owner: Address, zero_for_one: bool, sell_rate: U256, expiry_block: U256) -> U256 {
+        escrow(pair, zero_for_one, sell_rate * (expiry_block - U256::from(BLOCK_NUMBER)));
+        let (rate, ending, reward_factor) = pair.twamm.stream(zero_for_one);
+        rate.set(rate.get() + sell_rate);
+        let total = ending.get(expiry_block);
+        ending.setter(expiry_block).set(total + sell_rate);
+        let reward_factor = reward_factor.get();
+        let order_id = pair.twamm.next_long_term_order_id.get() + U256::from(1);
+        pair.twamm.next_long_term_order_id.set(order_id);
+        let mut order = pair.twamm.long_term_orders.setter(order_id);
+        order.owner.set(owner);
+        order.zero_for_one.set(zero_for_one);
+        order.sell_rate.set(sell_rate);
+        order.expiry_block.set(expiry_block);
+        order.reward_factor_last.set(reward_factor);
+        order_id
+    }
+
+    // What a catch-up leaves for the orders selling token0: `bought` of token1
+    // shared by their sell rate.
+    fn buy_for_sellers_of_token0(pair: &mut PairClaims, bought: U256) {
+        escrow(pair, false, bought);
+        let factor = pair.twamm.reward_factor0.get() + bought * Q128 / pair.twamm.sell_rate0.get();
+        pair.twamm.reward_factor0.set(factor);
+    }
+
+    // A batch order as the batch facet leaves it, in batch 1.
+    fn batch_order(pair: &mut PairClaims, zero_for_one: bool, amount_in: U256) -> U256 {
+        escrow(pair, zero_for_one, amount_in);
+        let mut batch = pair.batches.batches.setter(U256::from(1));
+        batch.end_block.set(U256::from(BLOCK_NUMBER));
+        let total = if zero_for_one { &mut batch.amount0_in } else { &mut batch.amount1_in };
+        total.set(total.get() + amount_in);
+        let order_id = pair.batches.next_order_id.get() + U256::from(1);
+        pair.batches.next_order_id.set(order_id);
+        let mut order = pair.batches.orders.setter(order_id);
+        order.owner.set(SENDER);
+        order.batch_id.set(U256::from(1));
+        order.zero_for_one.set(zero_for_one);
+        order.amount_in.set(amount_in);
+        order_id
+    }
+
+    #[test]
+    fn serves_every_selector_the_pair_forwards() {
+        let mut pair = pair();
+        for selector in IPairClaims::IPairClaimsCalls::SELECTORS {
+            let routed = <PairClaims as Router<PairClaims>>::route(&mut pair, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn withdraw_and_cancel_pay_out_the_escrow() {
+        let mut pair = pair();
+        let rate = U256::from(E18);
+        let expiry = U256::from(BLOCK_NUMBER + 300);
+        let sell0 = long_term_order(&mut pair, SENDER, true, rate, expiry);
+        let bobs = long_term_order(&mut pair, BOB, false, rate, expiry);
+        assert_balanced(&pair);
+
+        buy_for_sellers_of_token0(&mut pair, U256::from(5 * E18));
+        assert_eq!(pair.long_term_order(sell0).unwrap_ok(), (SENDER, true, rate, expiry, U256::from(5 * E18)));
+        let before = erc20_balance(TOKEN1, SENDER);
+        assert_eq!(pair.withdraw_proceeds(sell0).unwrap_ok(), U256::from(5 * E18));
+        assert_eq!(erc20_balance(TOKEN1, SENDER) - before, U256::from(5 * E18));
+        assert_eq!(pair.withdraw_proceeds(sell0).unwrap_ok(), U256::ZERO);
+        assert_balanced(&pair);
+        assert!(matches!(pair.withdraw_proceeds(bobs), Err(PairError::Unauthorized(_))));
+        assert!(matches!(pair.withdraw_proceeds(U256::from(9)), Err(PairError::UnknownOrder(_))));
+
+        // stopped with all 300 blocks to go
+        buy_for_sellers_of_token0(&mut pair, U256::from(2 * E18));
+        let (before0, before1) = (erc20_balance(TOKEN0, SENDER), erc20_balance(TOKEN1, SENDER));
+        let refund = rate * U256::from(300);
+        assert_eq!(pair.cancel_long_term_order(sell0).unwrap_ok(), (refund, U256::from(2 * E18)));
+        assert_eq!((erc20_balance(TOKEN0, SENDER) - before0, erc20_balance(TOKEN1, SENDER) - before1), (refund, U256::from(2 * E18)));
+        assert_eq!((pair.twamm.sell_rate0.get(), pair.twamm.sell_rate0_ending.get(expiry)), (U256::ZERO, U256::ZERO));
+        assert_eq!(pair.state.escrow0.get(), U256::ZERO);
+        assert_balanced(&pair);
+        assert!(matches!(pair.cancel_long_term_order(sell0), Err(PairError::LongTermOrderClosed(_))));
+        assert_eq!(pair.withdraw_proceeds(sell0).unwrap_ok(), U256::ZERO);
+
+        // every payout caught the orders up first, on the pair's storage
+        let catch_up = IPairTwamm::executeVirtualOrdersCall {}.abi_encode();
+        let twamm_calls: Vec<_> = calls().into_iter().filter(|call| call.to == TWAMM).collect();
+        assert_eq!(twamm_calls.len(), 5);
+        assert!(twamm_calls.iter().all(|call| call.kind == CallKind::Delegate && call.data == catch_up));
+        mock_call(TWAMM, |_| Err(Vec::new()));
+        assert!(matches!(pair.withdraw_proceeds(sell0), Err(PairError::FacetCallFailed(_))));
+    }
+
+    #[test]
+    fn batch_escrow_covers_every_claim() {
+        let mut pair = pair();
+        let orders = [(true, 10 * E18), (true, E18 / 3 + 7), (false, 4 * E18), (false, 1), (true, 999)];
+        let ids: Vec<U256> =
+            orders.iter().map(|(zero_for_one, amount)| batch_order(&mut pair, *zero_for_one, U256::from(*amount))).collect();
+        assert_balanced(&pair);
+        assert!(matches!(pair.claim(ids[0]), Err(PairError::BatchNotSettled(_))));
+        assert!(matches!(pair.claim(U256::from(9)), Err(PairError::UnknownOrder(_))));
+
+        // what the batch facet's settle_batch writes
+        let (amount0_in, amount1_in) = (pair.state.escrow0.get(), pair.state.escrow1.get());
+        let (reserve0, reserve1) = (pair.state.reserve0.get(), pair.state.reserve1.get());
+        let expected = settle(reserve0, reserve1, amount0_in, amount1_in, 30).unwrap();
+        let mut batch = pair.batches.batches.setter(U256::from(1));
+        batch.price_numerator.set(expected.price.numerator);
+        batch.price_denominator.set(expected.price.denominator);
+        batch.fee_bps.set(U256::from(30));
+        batch.settled.set(true);
+        pair.state.rebalance_escrow(true, amount0_in, expected.amount0_out).unwrap_ok();
+        pair.state.rebalance_escrow(false, amount1_in, expected.amount1_out).unwrap_ok();
+        pair.state.update(expected.reserve0, expected.reserve1);
+        assert_balanced(&pair);
+
+        let (mut paid0, mut paid1) = (U256::ZERO, U256::ZERO);
+        for (id, (zero_for_one, _)) in ids.iter().zip(orders) {
+            let owed = pair.claimable(*id).unwrap_ok();
+            assert_eq!(pair.claim(*id).unwrap_ok(), owed);
+            if zero_for_one {
+                paid1 += owed;
+            } else {
+                paid0 += owed;
+            }
+            assert_balanced(&pair);
+        }
+        assert!(paid0 <= expected.amount0_out && paid1 <= expected.amount1_out);
+        // only rounding dust is left behind
+        assert!(pair.state.escrow0.get() < U256::from(orders.len()) && pair.state.escrow1.get() < U256::from(orders.len()));
+        assert_eq!(pair.claimable(ids[0]).unwrap_ok(), U256::ZERO);
+        assert!(matches!(pair.claim(ids[0]), Err(PairError::OrderAlreadyClaimed(_))));
+        assert_eq!(pair.order(ids[0]).unwrap_ok(), (SENDER, U256::from(1), true, U256::from(10 * E18), true));
+    }
+
+    #[test]
+    fn cancelled_batches_refund_their_orders() {
+        let mut pair = pair();
+        let sell0 = batch_order(&mut pair, true, U256::from(3 * E18));
+        let sell1 = batch_order(&mut pair, false, U256::from(E18));
+        assert!(matches!(pair.refund(sell0), Err(PairError::BatchNotCancelled(_))));
+        // a settled batch without a price owes nothing it can compute
+        pair.batches.batches.setter(U256::from(1)).settled.set(true);
+        assert!(matches!(pair.claimable(sell0), Err(PairError::MathOverflow(_))));
+
+        // what the batch facet's settle_batch writes when the batch can't clear
+        pair.batches.batches.setter(U256::from(1)).cancelled.set(true);
+        assert!(matches!(pair.claimable(sell0), Err(PairError::BatchWasCancelled(_))));
+        assert!(matches!(pair.claim(sell0), Err(PairError::BatchWasCancelled(_))));
+
+        let (before0, before1) = (erc20_balance(TOKEN0, SENDER), erc20_balance(TOKEN1, SENDER));
+        assert_eq!(pair.refund(sell0).unwrap_ok(), U256::from(3 * E18));
+        assert_eq!(pair.refund(sell1).unwrap_ok(), U256::from(E18));
+        assert_eq!(
+            (erc20_balance(TOKEN0, SENDER) - before0, erc20_balance(TOKEN1, SENDER) - before1),
+            (U256::from(3 * E18), U256::from(E18))
+        );
+        assert_eq!((pair.state.escrow0.get(), pair.state.escrow1.get()), (U256::ZERO, U256::ZERO));
+        assert_balanced(&pair);
+        assert!(matches!(pair.refund(sell0), Err(PairError::OrderAlreadyClaimed(_))));
+    }
+}
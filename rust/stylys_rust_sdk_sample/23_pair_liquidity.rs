@@ -0,0 +1,183 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod pair_state;
+use crate::erc20::Erc20;
+use crate::pair_state::*;
+use alloy_sol_types::sol;
+use amm_math::full_math::sqrt;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    contract, evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event Mint(address indexed sender, uint256 amount0, uint256 amount1);
+    event Burn(address indexed sender, uint256 amount0, uint256 amount1, address indexed to);
+}
+
+// Locked forever on the first mint so the LP supply can never return to zero.
+const MINIMUM_LIQUIDITY: u64 = 1000;
+
+// Mint and burn for the pair in 23_pair.rs. As with swaps, tokens (or LP
+// tokens) are sent to the pair first and the call settles against the recorded
+// reserves. The pair delegate_calls this facet for the `IPairLiquidity`
+// selectors, so it runs on the pair's storage and mints and burns the pair's
+// own LP token, which follows `PairState` (see pair_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct PairLiquidity {
+        #[borrow]
+        PairState state;
+        Erc20<PairParams> erc20;
+    }
+}
+
+#[external]
+impl PairLiquidity {
+    // Mints LP for whatever was transferred in since the last update.
+    pub fn mint(&mut self, to: Address) -> Result<U256, PairError> {
+        self.execute_long_term_orders()?;
+        let (reserve0, reserve1) = (self.state.reserve0.get(), self.state.reserve1.get());
+        let (balance0, balance1) = self.pool_balances()?;
+        let amount0 = balance0.checked_sub(reserve0).ok_or_else(PairState::overflow)?;
+        let amount1 = balance1.checked_sub(reserve1).ok_or_else(PairState::overflow)?;
+
+        let supply = self.erc20.total_supply.get();
+        let liquidity = if supply == U256::ZERO {
+            let root = sqrt(amount0.checked_mul(amount1).ok_or_else(PairState::overflow)?);
+            if root <= U256::from(MINIMUM_LIQUIDITY) {
+                return Err(PairError::InsufficientLiquidityMinted(InsufficientLiquidityMinted {}));
+            }
+            self.erc20.mint(Address::ZERO, U256::from(MINIMUM_LIQUIDITY));
+            root - U256::from(MINIMUM_LIQUIDITY)
+        } else {
+            let liquidity0 = amount0.checked_mul(supply).ok_or_else(PairState::overflow)? / reserve0;
+            let liquidity1 = amount1.checked_mul(supply).ok_or_else(PairState::overflow)? / reserve1;
+            liquidity0.min(liquidity1)
+        };
+        if liquidity == U256::ZERO {
+            return Err(PairError::InsufficientLiquidityMinted(InsufficientLiquidityMinted {}));
+        }
+        self.erc20.mint(to, liquidity);
+        self.state.update(balance0, balance1);
+        evm::log(Mint { sender: msg::sender(), amount0, amount1 });
+        Ok(liquidity)
+    }
+
+    // Burns the LP tokens held by the pair and sends out the matching reserves.
+    pub fn burn(&mut self, to: Address) -> Result<(U256, U256), PairError> {
+        self.execute_long_term_orders()?;
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        let (balance0, balance1) = self.pool_balances()?;
+        let liquidity = self.erc20.balance_of(contract::address())?;
+
+        let supply = self.erc20.total_supply.get();
+        let amount0 = liquidity * balance0 / supply;
+        let amount1 = liquidity * balance1 / supply;
+        if amount0 == U256::ZERO || amount1 == U256::ZERO {
+            return Err(PairError::InsufficientLiquidityBurned(InsufficientLiquidityBurned {}));
+        }
+        self.erc20.burn(contract::address(), liquidity)?;
+        self.safe_transfer(token0, to, amount0)?;
+        self.safe_transfer(token1, to, amount1)?;
+        let (balance0, balance1) = self.pool_balances()?;
+        self.state.update(balance0, balance1);
+        evm::log(Burn { sender: msg::sender(), amount0, amount1, to });
+        Ok((amount0, amount1))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use stylus_sdk::abi::Router;
+    use test_vm::{calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, transfer_erc20, CallKind, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const TWAMM: Address = Address::repeat_byte(0x21);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // The pair's state as its init leaves it, then a first mint of 1_000 / 1_000.
+    fn pair() -> PairLiquidity {
+        let mut pair = deploy::<PairLiquidity>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+            mint_erc20(token, SENDER, U256::from(1_000 * E18));
+        }
+        let state = &mut pair.state;
+        state.ownable.initialize(0).unwrap_ok();
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.twamm_facet.set(TWAMM);
+        pair.mint(SENDER).unwrap_ok();
+        pair
+    }
+
+    #[test]
+    fn serves_every_selector_the_pair_forwards() {
+        let mut pair = pair();
+        for selector in IPairLiquidity::IPairLiquidityCalls::SELECTORS {
+            let routed = <PairLiquidity as Router<PairLiquidity>>::route(&mut pair, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn first_mint_locks_the_minimum_liquidity() {
+        let mut pair = pair();
+        let supply = pair.erc20.total_supply.get();
+        assert_eq!(supply, U256::from(1_000 * E18));
+        assert_eq!(pair.erc20.balance_of(Address::ZERO).unwrap_ok(), U256::from(MINIMUM_LIQUIDITY));
+        assert_eq!(pair.erc20.balance_of(SENDER).unwrap_ok(), supply - U256::from(MINIMUM_LIQUIDITY));
+
+        // burning it all back out leaves only the locked share's reserves
+        let liquidity = pair.erc20.balance_of(SENDER).unwrap_ok();
+        pair.erc20.transfer_impl(SENDER, contract(), liquidity).unwrap_ok();
+        pair.burn(SENDER).unwrap_ok();
+        assert_eq!(pair.state.reserve0.get(), U256::from(MINIMUM_LIQUIDITY));
+        assert_eq!(erc20_balance(TOKEN0, contract()), U256::from(MINIMUM_LIQUIDITY));
+    }
+
+    #[test]
+    fn oversized_balances_revert_instead_of_wrapping() {
+        let mut pair = pair();
+        mint_erc20(TOKEN0, contract(), U256::MAX / U256::from(2));
+        assert!(matches!(pair.mint(SENDER), Err(PairError::MathOverflow(_))));
+    }
+
+    #[test]
+    fn long_term_orders_catch_up_before_each_mint_and_burn() {
+        let mut pair = pair();
+        let catch_up = IPairTwamm::executeVirtualOrdersCall {}.abi_encode();
+        let hook_calls = || calls().iter().filter(|call| call.to == TWAMM && call.data == catch_up).count();
+        assert_eq!(hook_calls(), 0);
+
+        pair.state.order_block_interval.set(U256::from(100));
+        transfer_erc20(TOKEN0, SENDER, contract(), U256::from(E18));
+        transfer_erc20(TOKEN1, SENDER, contract(), U256::from(E18));
+        pair.mint(SENDER).unwrap_ok();
+        assert_eq!(hook_calls(), 1);
+        assert!(calls().iter().filter(|call| call.to == TWAMM).all(|call| call.kind == CallKind::Delegate));
+
+        mock_call(TWAMM, |_| Err(Vec::new()));
+        assert!(matches!(pair.burn(SENDER), Err(PairError::FacetCallFailed(_))));
+    }
+}
@@ -0,0 +1,236 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod pair_state;
+use crate::pair_state::*;
+use alloy_sol_types::sol;
+use amm_math::constant_product::BPS;
+use amm_math::dynamic_fee::{dynamic_fee, volatility};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    block,
+    call::{call, Call},
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event Swap(address indexed sender, uint256 amount0_in, uint256 amount1_in, uint256 amount0_out, uint256 amount1_out, address indexed to, uint256 fee_bps);
+}
+
+// Swaps for the pair in 23_pair.rs, with the V2 flow: tokens are sent in first,
+// then `swap` settles against the recorded reserves. The pair delegate_calls
+// this facet for the `IPairSwap` selectors and it runs on the pair's storage
+// (see pair_state.rs for the layout).
+//
+// Instead of a fixed 0.3% the fee follows the pair's own realized volatility
+// (amm_math::dynamic_fee). The first swap of each block records the price left by
+// the previous blocks, so a single block can't pump its own fee estimate.
+sol_storage! {
+    #[entrypoint]
+    pub struct PairSwap {
+        #[borrow]
+        PairState state;
+    }
+}
+
+#[external]
+impl PairSwap {
+    // Optimistic transfer out, optional callback to `to` with `data`, then the
+    // fee-adjusted k check at the fee in force for this block.
+    pub fn swap(&mut self, amount0_out: U256, amount1_out: U256, to: Address, data: Bytes) -> Result<(), PairError> {
+        if self.state.batch_mode.get() {
+            return Err(PairError::BatchModeEnabled(BatchModeEnabled {}));
+        }
+        if amount0_out == U256::ZERO && amount1_out == U256::ZERO {
+            return Err(PairError::InsufficientOutputAmount(InsufficientOutputAmount {}));
+        }
+        self.execute_long_term_orders()?;
+        let (reserve0, reserve1) = (self.state.reserve0.get(), self.state.reserve1.get());
+        if amount0_out >= reserve0 || amount1_out >= reserve1 {
+            return Err(PairError::InsufficientLiquidity(InsufficientLiquidity { reserve0, reserve1 }));
+        }
+        let (token0, token1) = (self.state.token0.get(), self.state.token1.get());
+        if to == token0 || to == token1 {
+            return Err(PairError::InvalidTo(InvalidTo { to }));
+        }
+        self.state.observe(U256::from(block::number()));
+        let fee_bps = self.state.fee_bps();
+
+        if amount0_out > U256::ZERO {
+            self.safe_transfer(token0, to, amount0_out)?;
+        }
+        if amount1_out > U256::ZERO {
+            self.safe_transfer(token1, to, amount1_out)?;
+        }
+        if !data.is_empty() {
+            call(Call::new_in(self), to, &data).map_err(|_| PairError::CallbackFailed(CallbackFailed { to }))?;
+        }
+
+        let (balance0, balance1) = self.pool_balances()?;
+        let amount0_in = balance0.saturating_sub(reserve0 - amount0_out);
+        let amount1_in = balance1.saturating_sub(reserve1 - amount1_out);
+        if amount0_in == U256::ZERO && amount1_in == U256::ZERO {
+            return Err(PairError::InsufficientInputAmount(InsufficientInputAmount {}));
+        }
+        // balances this large can't come from a real token, so overflow just reverts
+        let (bps, fee) = (U256::from(BPS), U256::from(fee_bps));
+        let adjusted = |balance: U256, amount_in: U256| balance.checked_mul(bps)?.checked_sub(amount_in.checked_mul(fee)?);
+        let k = reserve0.checked_mul(reserve1).and_then(|k| k.checked_mul(bps * bps)).ok_or_else(PairState::overflow)?;
+        let k_after = adjusted(balance0, amount0_in)
+            .zip(adjusted(balance1, amount1_in))
+            .and_then(|(balance0, balance1)| balance0.checked_mul(balance1))
+            .ok_or_else(PairState::overflow)?;
+        if k_after < k {
+            return Err(PairError::KInvariantViolated(KInvariantViolated { before: k, after: k_after }));
+        }
+
+        self.state.update(balance0, balance1);
+        evm::log(Swap { sender: msg::sender(), amount0_in, amount1_in, amount0_out, amount1_out, to, fee_bps: fee });
+        Ok(())
+    }
+
+    // Fee in bps a swap would pay right now, including this block's observation.
+    pub fn current_fee(&self) -> Result<u64, PairError> {
+        let (variance, _) = self.state.pending_observation(U256::from(block::number()));
+        Ok(dynamic_fee(volatility(variance), &self.state.config()))
+    }
+
+    // Per-block volatility of log returns, WAD.
+    pub fn volatility(&self) -> Result<U256, PairError> {
+        Ok(volatility(self.state.pending_observation(U256::from(block::number())).0))
+    }
+
+    // (base_fee_bps, volatility_multiplier, max_fee_bps, decay)
+    pub fn fee_config(&self) -> Result<(u64, U256, u64, U256), PairError> {
+        let config = self.state.config();
+        Ok((config.base_fee_bps, config.volatility_multiplier, config.max_fee_bps, self.state.decay.get()))
+    }
+
+    pub fn set_fee_config(
+        &mut self,
+        base_fee_bps: u64,
+        volatility_multiplier: U256,
+        max_fee_bps: u64,
+        decay: U256,
+    ) -> Result<(), PairError> {
+        self.state.ownable.only_owner()?;
+        self.state.set_fee_config(base_fee_bps, volatility_multiplier, max_fee_bps, decay)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use amm_math::constant_product::{get_amount_out, get_amount_out_with_fee};
+    use stylus_sdk::abi::Router;
+    use test_vm::{calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, transfer_erc20, CallKind, UnwrapOk, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const TWAMM: Address = Address::repeat_byte(0x21);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // The pair's state as its init and first mint leave it: 1_000 / 1_000 with
+    // a flat 0.3% fee.
+    fn pair() -> PairSwap {
+        let mut pair = deploy::<PairSwap>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+            mint_erc20(token, SENDER, U256::from(1_000 * E18));
+        }
+        let state = &mut pair.state;
+        state.ownable.initialize(0).unwrap_ok();
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.twamm_facet.set(TWAMM);
+        state.set_fee_config(30, U256::ZERO, 30, U256::from(94 * E18 / 100)).unwrap_ok();
+        state.update(U256::from(1_000 * E18), U256::from(1_000 * E18));
+        pair
+    }
+
+    // Whatever the pair holds is either reserves or escrow.
+    fn assert_balanced(pair: &PairSwap) {
+        let state = &pair.state;
+        assert_eq!(erc20_balance(TOKEN0, contract()), state.reserve0.get() + state.escrow0.get());
+        assert_eq!(erc20_balance(TOKEN1, contract()), state.reserve1.get() + state.escrow1.get());
+    }
+
+    #[test]
+    fn serves_every_selector_the_pair_forwards() {
+        let mut pair = pair();
+        for selector in IPairSwap::IPairSwapCalls::SELECTORS {
+            let routed = <PairSwap as Router<PairSwap>>::route(&mut pair, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn swaps_are_checked_at_the_current_fee() {
+        let mut pair = pair();
+        pair.set_fee_config(1_000, U256::ZERO, 1_000, U256::from(94 * E18 / 100)).unwrap_ok();
+        assert_eq!(pair.current_fee().unwrap_ok(), 1_000);
+        let amount_in = U256::from(10 * E18);
+
+        transfer_erc20(TOKEN0, SENDER, contract(), amount_in);
+        let amount_out = get_amount_out_with_fee(amount_in, pair.state.reserve0.get(), pair.state.reserve1.get(), 1_000);
+        pair.swap(U256::ZERO, amount_out, SENDER, Bytes(Vec::new())).unwrap_ok();
+        assert_balanced(&pair);
+
+        // a quote at the fixed 0.3% asks for more than a 10% fee leaves
+        transfer_erc20(TOKEN0, SENDER, contract(), amount_in);
+        let amount_out = get_amount_out(amount_in, pair.state.reserve0.get(), pair.state.reserve1.get());
+        assert!(matches!(pair.swap(U256::ZERO, amount_out, SENDER, Bytes(Vec::new())), Err(PairError::KInvariantViolated(_))));
+    }
+
+    #[test]
+    fn oversized_balances_revert_instead_of_wrapping() {
+        let mut pair = pair();
+        // times 10_000 bps this wraps, and the wrapped k could come out either way
+        mint_erc20(TOKEN0, contract(), U256::MAX / U256::from(2));
+        assert!(matches!(pair.swap(U256::ZERO, U256::from(E18), SENDER, Bytes(Vec::new())), Err(PairError::MathOverflow(_))));
+    }
+
+    #[test]
+    fn batch_mode_turns_swaps_off() {
+        let mut pair = pair();
+        // what the batch facet's set_batch_mode writes
+        pair.state.batch_mode.set(true);
+        assert!(matches!(pair.swap(U256::from(1), U256::ZERO, SENDER, Bytes(Vec::new())), Err(PairError::BatchModeEnabled(_))));
+    }
+
+    #[test]
+    fn long_term_orders_catch_up_before_each_swap() {
+        let mut pair = pair();
+        let catch_up = IPairTwamm::executeVirtualOrdersCall {}.abi_encode();
+        let hook_calls = || calls().iter().filter(|call| call.to == TWAMM && call.data == catch_up).count();
+        assert_eq!(hook_calls(), 0);
+
+        pair.state.order_block_interval.set(U256::from(100));
+        let amount_out = get_amount_out(U256::from(E18), pair.state.reserve0.get(), pair.state.reserve1.get()) / U256::from(2);
+        transfer_erc20(TOKEN0, SENDER, contract(), U256::from(E18));
+        pair.swap(U256::ZERO, amount_out, SENDER, Bytes(Vec::new())).unwrap_ok();
+        assert_eq!(hook_calls(), 1);
+        assert!(calls().iter().filter(|call| call.to == TWAMM).all(|call| call.kind == CallKind::Delegate));
+
+        mock_call(TWAMM, |_| Err(Vec::new()));
+        transfer_erc20(TOKEN0, SENDER, contract(), U256::from(E18));
+        assert!(matches!(pair.swap(U256::ZERO, amount_out, SENDER, Bytes(Vec::new())), Err(PairError::FacetCallFailed(_))));
+    }
+}
@@ -0,0 +1,327 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod namespaced;
+mod ownable;
+mod pair_orders;
+mod pair_state;
+use crate::namespaced::Namespaced;
+use crate::pair_orders::*;
+use crate::pair_state::*;
+use alloy_sol_types::sol;
+use amm_math::tick_math::Q128;
+use amm_math::twamm::execute_virtual_orders;
+use stylus_sdk::{alloy_primitives::U256, block, evm, msg, prelude::*};
+
+sol! {
+    event LongTermOrderPlaced(uint256 indexed order_id, address indexed owner, bool zero_for_one, uint256 amount_in, uint256 sell_rate, uint256 expiry_block);
+}
+
+// Shortest long-term order interval. Catching up reads one expiry bitmap word per
+// 256 intervals crossed, so this bounds the cost per block of elapsed time.
+const MIN_ORDER_BLOCK_INTERVAL: u64 = 100;
+
+// Long-term orders (amm_math::twamm) for the pair in 23_pair.rs, which
+// delegate_calls this facet for the `IPairTwamm` selectors and runs on the
+// pair's storage (see pair_state.rs for the layout).
+//
+// Orders sell at a fixed rate per block until an expiry on the interval grid.
+// They run lazily: every state-changing call first trades the elapsed blocks
+// against the curve in closed form, so the stored reserves can lag until the
+// next call (or `execute_virtual_orders`). Only boundaries where orders end
+// split the trade, found through a bitmap. Proceeds are tracked per unit of
+// sell rate, so withdrawing doesn't touch other orders; withdrawals and
+// cancels are in 23_pair_claims.rs, which pays out both kinds of order.
+sol_storage! {
+    #[entrypoint]
+    pub struct PairTwamm {
+        #[borrow]
+        PairState state;
+        Namespaced<TwammState> twamm;
+    }
+}
+
+impl PairTwamm {
+    // Trades `blocks` of both streams at the current rates.
+    fn execute_span(&mut self, blocks: U256, fee_bps: u64) -> Result<(), PairError> {
+        let (rate0, rate1) = (self.twamm.sell_rate0.get(), self.twamm.sell_rate1.get());
+        let (amount0_in, amount1_in) = (rate0 * blocks, rate1 * blocks);
+        if amount0_in == U256::ZERO && amount1_in == U256::ZERO {
+            return Ok(());
+        }
+        let (reserve0, reserve1) = (self.state.reserve0.get(), self.state.reserve1.get());
+        let trade = execute_virtual_orders(reserve0, reserve1, amount0_in, amount1_in, fee_bps)
+            .ok_or(PairError::InsufficientLiquidity(InsufficientLiquidity { reserve0, reserve1 }))?;
+        let twamm = &mut *self.twamm;
+        if rate0 > U256::ZERO {
+            twamm.reward_factor0.set(twamm.reward_factor0.get() + trade.amount1_out * Q128 / rate0);
+        }
+        if rate1 > U256::ZERO {
+            twamm.reward_factor1.set(twamm.reward_factor1.get() + trade.amount0_out * Q128 / rate1);
+        }
+        self.state.rebalance_escrow(true, amount0_in, trade.amount0_out)?;
+        self.state.rebalance_escrow(false, amount1_in, trade.amount1_out)?;
+        self.state.update(trade.reserve0, trade.reserve1);
+        Ok(())
+    }
+
+    // Marks `expiry` as a boundary that orders end at. Bits stay set after a
+    // cancel; crossing such a boundary just records the factors.
+    fn mark_expiry(&mut self, expiry: U256, interval: U256) {
+        let index = expiry / interval;
+        let (word_pos, bit_pos) = (index >> 8, (index & U256::from(255)).saturating_to::<usize>());
+        let word = self.twamm.expiry_bitmap.get(word_pos);
+        self.twamm.expiry_bitmap.setter(word_pos).set(word | (U256::from(1) << bit_pos));
+    }
+
+    // First marked boundary after `after`, if there is one up to `until`. Reads
+    // one bitmap word per 256 boundaries.
+    fn next_expiry(&self, after: U256, until: U256, interval: U256) -> Option<U256> {
+        let mut index = after / interval + U256::from(1);
+        while index * interval <= until {
+            let (word_pos, bit_pos) = (index >> 8, (index & U256::from(255)).saturating_to::<usize>());
+            let masked = self.twamm.expiry_bitmap.get(word_pos) & (U256::MAX << bit_pos);
+            if masked > U256::ZERO {
+                let boundary = ((word_pos << 8) + U256::from(masked.trailing_zeros())) * interval;
+                return (boundary <= until).then_some(boundary);
+            }
+            index = (word_pos + U256::from(1)) << 8;
+        }
+        None
+    }
+
+    // Catches the long-term orders up to block `now`, one span per boundary
+    // that orders end at so they drop out at their expiry. Once no order is
+    // active the rest of the gap is skipped, so an idle pair catches up at once.
+    fn execute_long_term_orders_at(&mut self, now: U256) -> Result<(), PairError> {
+        let interval = self.state.order_block_interval.get();
+        if interval == U256::ZERO {
+            return Ok(());
+        }
+        let mut last = self.twamm.last_virtual_order_block.get();
+        if last >= now {
+            return Ok(());
+        }
+        let fee_bps = self.state.fee_bps();
+        while last < now && (self.twamm.sell_rate0.get() > U256::ZERO || self.twamm.sell_rate1.get() > U256::ZERO) {
+            let expiry = self.next_expiry(last, now, interval);
+            let until = expiry.unwrap_or(now);
+            self.execute_span(until - last, fee_bps)?;
+            if let Some(boundary) = expiry {
+                let twamm = &mut *self.twamm;
+                let (factor0, factor1) = (twamm.reward_factor0.get(), twamm.reward_factor1.get());
+                twamm.reward_factor0_at.setter(boundary).set(factor0);
+                twamm.reward_factor1_at.setter(boundary).set(factor1);
+                let ending0 = twamm.sell_rate0_ending.get(boundary);
+                let ending1 = twamm.sell_rate1_ending.get(boundary);
+                twamm.sell_rate0.set(PairState::rebalance(twamm.sell_rate0.get(), ending0, U256::ZERO)?);
+                twamm.sell_rate1.set(PairState::rebalance(twamm.sell_rate1.get(), ending1, U256::ZERO)?);
+            }
+            last = until;
+        }
+        self.twamm.last_virtual_order_block.set(now);
+        Ok(())
+    }
+
+    fn long_term_swap_at(&mut self, zero_for_one: bool, amount_in: U256, intervals: u64, now: U256) -> Result<U256, PairError> {
+        let interval = self.state.order_block_interval.get();
+        if interval == U256::ZERO {
+            return Err(PairError::LongTermOrdersDisabled(LongTermOrdersDisabled {}));
+        }
+        if intervals == 0 {
+            return Err(PairError::InvalidInterval(InvalidInterval {}));
+        }
+        self.execute_long_term_orders_at(now)?;
+        let (reserve0, reserve1) = (self.state.reserve0.get(), self.state.reserve1.get());
+        if reserve0 == U256::ZERO || reserve1 == U256::ZERO {
+            return Err(PairError::InsufficientLiquidity(InsufficientLiquidity { reserve0, reserve1 }));
+        }
+        let expiry_block = (now / interval + U256::from(intervals) + U256::from(1)) * interval;
+        let sell_rate = amount_in / (expiry_block - now);
+        if sell_rate == U256::ZERO {
+            return Err(PairError::InsufficientInputAmount(InsufficientInputAmount {}));
+        }
+        let amount_in = sell_rate * (expiry_block - now);
+        self.mark_expiry(expiry_block, interval);
+
+        let owner = msg::sender();
+        self.pull(self.state.token(zero_for_one), owner, amount_in)?;
+        self.state.rebalance_escrow(zero_for_one, U256::ZERO, amount_in)?;
+
+        let (rate, ending, reward_factor) = self.twamm.stream(zero_for_one);
+        rate.set(rate.get() + sell_rate);
+        let total = ending.get(expiry_block);
+        ending.setter(expiry_block).set(total + sell_rate);
+        let reward_factor = reward_factor.get();
+
+        let order_id = self.twamm.next_long_term_order_id.get() + U256::from(1);
+        self.twamm.next_long_term_order_id.set(order_id);
+        let mut order = self.twamm.long_term_orders.setter(order_id);
+        order.owner.set(owner);
+        order.zero_for_one.set(zero_for_one);
+        order.sell_rate.set(sell_rate);
+        order.expiry_block.set(expiry_block);
+        order.reward_factor_last.set(reward_factor);
+        evm::log(LongTermOrderPlaced { order_id, owner, zero_for_one, amount_in, sell_rate, expiry_block });
+        Ok(order_id)
+    }
+}
+
+#[external]
+impl PairTwamm {
+    // One-way: expiries already on the grid would be skipped by a new interval.
+    pub fn enable_long_term_orders(&mut self, order_block_interval: u64) -> Result<(), PairError> {
+        self.state.ownable.only_owner()?;
+        if self.state.order_block_interval.get() != U256::ZERO {
+            return Err(PairError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if order_block_interval < MIN_ORDER_BLOCK_INTERVAL {
+            return Err(PairError::InvalidInterval(InvalidInterval {}));
+        }
+        self.state.order_block_interval.set(U256::from(order_block_interval));
+        self.twamm.last_virtual_order_block.set(U256::from(block::number()));
+        Ok(())
+    }
+
+    // Also what the pair calls before each of its own trades.
+    pub fn execute_virtual_orders(&mut self) -> Result<(), PairError> {
+        self.execute_long_term_orders_at(U256::from(block::number()))
+    }
+
+    // Sells `amount_in` evenly until the end of the `intervals`-th full interval
+    // from now. Only a whole number per block is pulled (approve the pair first).
+    pub fn long_term_swap(&mut self, zero_for_one: bool, amount_in: U256, intervals: u64) -> Result<U256, PairError> {
+        self.long_term_swap_at(zero_for_one, amount_in, intervals, U256::from(block::number()))
+    }
+
+    // (sell_rate0, sell_rate1, last_virtual_order_block)
+    pub fn long_term_state(&self) -> Result<(U256, U256, U256), PairError> {
+        Ok((self.twamm.sell_rate0.get(), self.twamm.sell_rate1.get(), self.twamm.last_virtual_order_block.get()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::{abi::Router, alloy_primitives::Address};
+    use test_vm::{contract, deploy, erc20_balance, mint_erc20, mock_erc20, UnwrapOk, BLOCK_NUMBER, SENDER};
+
+    const TOKEN0: Address = Address::repeat_byte(0x10);
+    const TOKEN1: Address = Address::repeat_byte(0x11);
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    // The pair's state as its init and first mint leave it: 1_000 / 1_000 with
+    // a flat 0.3% fee, and 100-block intervals starting at BLOCK_NUMBER.
+    fn pair() -> PairTwamm {
+        let mut pair = deploy::<PairTwamm>();
+        for token in [TOKEN0, TOKEN1] {
+            mock_erc20(token);
+            mint_erc20(token, contract(), U256::from(1_000 * E18));
+            mint_erc20(token, SENDER, U256::from(1_000 * E18));
+        }
+        let state = &mut pair.state;
+        state.ownable.initialize(0).unwrap_ok();
+        state.token0.set(TOKEN0);
+        state.token1.set(TOKEN1);
+        state.base_fee_bps.set(U256::from(30));
+        state.max_fee_bps.set(U256::from(30));
+        state.decay.set(U256::from(94 * E18 / 100));
+        state.update(U256::from(1_000 * E18), U256::from(1_000 * E18));
+        assert!(matches!(pair.enable_long_term_orders(MIN_ORDER_BLOCK_INTERVAL - 1), Err(PairError::InvalidInterval(_))));
+        pair.enable_long_term_orders(MIN_ORDER_BLOCK_INTERVAL).unwrap_ok();
+        pair
+    }
+
+    // Whatever the pair holds is either reserves or escrow.
+    fn assert_balanced(pair: &PairTwamm) {
+        let state = &pair.state;
+        assert_eq!(erc20_balance(TOKEN0, contract()), state.reserve0.get() + state.escrow0.get());
+        assert_eq!(erc20_balance(TOKEN1, contract()), state.reserve1.get() + state.escrow1.get());
+    }
+
+    #[test]
+    fn serves_every_selector_the_pair_forwards() {
+        let mut pair = pair();
+        for selector in IPairTwamm::IPairTwammCalls::SELECTORS {
+            let routed = <PairTwamm as Router<PairTwamm>>::route(&mut pair, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn idle_pair_catches_up_in_one_step() {
+        let mut pair = pair();
+        // one loop per interval would never finish this
+        let later = U256::from(BLOCK_NUMBER) + U256::from(10u64).pow(U256::from(15));
+        pair.execute_long_term_orders_at(later).unwrap_ok();
+        assert_eq!(pair.long_term_state().unwrap_ok(), (U256::ZERO, U256::ZERO, later));
+
+        let id = pair.long_term_swap_at(true, U256::from(10 * E18), 2, later).unwrap_ok();
+        let expiry = pair.twamm.long_term_orders.get(id).expiry_block.get();
+        assert_eq!(expiry, (later / U256::from(100) + U256::from(3)) * U256::from(100));
+        // a far-off order only costs a bitmap read per 256 intervals on the way
+        pair.long_term_swap_at(false, U256::from(E18), 1_000_000, later).unwrap_ok();
+        let much_later = later + later;
+        pair.execute_long_term_orders_at(much_later).unwrap_ok();
+        assert_eq!(pair.long_term_state().unwrap_ok(), (U256::ZERO, U256::ZERO, much_later));
+        // nothing sold token0 after the first order ended
+        assert_eq!(pair.twamm.reward_factor0_at.get(expiry), pair.twamm.reward_factor0.get());
+        assert!(pair.twamm.reward_factor0.get() > U256::ZERO && pair.twamm.reward_factor1.get() > U256::ZERO);
+        assert_balanced(&pair);
+    }
+
+    #[test]
+    fn orders_trade_out_of_the_escrow_until_they_expire() {
+        let mut pair = pair();
+        let start = U256::from(BLOCK_NUMBER);
+        let at = |blocks: u64| start + U256::from(blocks);
+        let (held0, held1) = (erc20_balance(TOKEN0, SENDER), erc20_balance(TOKEN1, SENDER));
+
+        // sells token0 until 1300, token1 until 1500
+        let sell0 = pair.long_term_swap_at(true, U256::from(10 * E18), 2, start).unwrap_ok();
+        let sell1 = pair.long_term_swap_at(false, U256::from(5 * E18), 4, start).unwrap_ok();
+        let order = |pair: &PairTwamm, id| {
+            let order = pair.twamm.long_term_orders.get(id);
+            (order.owner.get(), order.zero_for_one.get(), order.sell_rate.get(), order.expiry_block.get())
+        };
+        let (owner, zero_for_one, rate0, expiry0) = order(&pair, sell0);
+        let (_, _, rate1, expiry1) = order(&pair, sell1);
+        assert_eq!((owner, zero_for_one), (SENDER, true));
+        assert_eq!((expiry0, expiry1), (at(300), at(500)));
+        let (in0, in1) = (rate0 * U256::from(300), rate1 * U256::from(500));
+        assert_eq!((held0 - erc20_balance(TOKEN0, SENDER), held1 - erc20_balance(TOKEN1, SENDER)), (in0, in1));
+        assert_eq!((pair.state.escrow0.get(), pair.state.escrow1.get()), (in0, in1));
+        assert_eq!(pair.long_term_state().unwrap_ok(), (rate0, rate1, start));
+        assert_balanced(&pair);
+
+        // halfway through the first order both sides have bought something
+        pair.execute_long_term_orders_at(at(150)).unwrap_ok();
+        assert!(pair.twamm.reward_factor0.get() > U256::ZERO && pair.twamm.reward_factor1.get() > U256::ZERO);
+        assert_balanced(&pair);
+
+        // each drops out at its expiry
+        pair.execute_long_term_orders_at(at(400)).unwrap_ok();
+        assert_eq!(pair.long_term_state().unwrap_ok(), (U256::ZERO, rate1, at(400)));
+        assert_eq!(pair.twamm.reward_factor0_at.get(expiry0), pair.twamm.reward_factor0.get());
+        pair.execute_long_term_orders_at(at(1_000)).unwrap_ok();
+        assert_eq!(pair.long_term_state().unwrap_ok(), (U256::ZERO, U256::ZERO, at(1_000)));
+        assert_balanced(&pair);
+        // all that's left in escrow is what the orders bought, waiting to be withdrawn
+        let bought0 = rate1 * pair.twamm.reward_factor1.get() / Q128;
+        let bought1 = rate0 * pair.twamm.reward_factor0.get() / Q128;
+        assert!(pair.state.escrow0.get() >= bought0 && pair.state.escrow0.get() - bought0 < U256::from(10));
+        assert!(pair.state.escrow1.get() >= bought1 && pair.state.escrow1.get() - bought1 < U256::from(10));
+    }
+}
@@ -0,0 +1,278 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc721;
+mod il_insurance_state;
+mod ownable;
+use crate::erc721::Erc721;
+use crate::il_insurance_state::*;
+use crate::ownable::Ownable;
+use alloc::vec::Vec;
+use alloy_sol_types::SolInterface;
+use amm_math::constant_product::BPS;
+use core::borrow::{Borrow, BorrowMut};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256},
+    call::{self, delegate_call},
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+// ILInsurance from notes/amm/2_constant_product_formula.md.
+//
+// Underwriters deposit ETH for shares of the vault. An LP insures a position by
+// escrowing `liquidity` of the pair's LP token for `duration` seconds. Cover is
+// paid in ETH, so pools are expected to quote token0 in WETH (token1), and the
+// notional `amount` is the position's value in wei when the policy is written.
+// The premium is the expected loss `sigma^2 * t / 8` at the pool's current
+// volatility, plus a loading. The entry price is the pool's TWAP, and policies
+// are refused while spot is more than MAX_SPOT_DEVIATION_BPS away from it, so a
+// move the TWAP hasn't caught up with can't be insured after the fact. At
+// expiry the ERC-721 holder gets the LP tokens back plus `amount * IL(entry,
+// exit)` at the TWAP then, capped at `max_coverage_bps` of the notional. That
+// cap is locked from the vault while the policy is open, so the vault can
+// always pay every policy in full.
+//
+// The TWAP comes from two checkpoints of the pair's price0 cumulative per pool,
+// at least `twap_window` apart; `poke` rolls them forward and is called on every
+// policy write and claim.
+//
+// The vault keeps the settings and the policy NFT. Quoting, writing and
+// claiming policies lives in 24_il_insurance_policies.rs, and the underwriters'
+// capital and the pool oracles in 24_il_insurance_underwriting.rs. The vault
+// delegate_calls those facets for their selectors, so callers only ever see
+// the vault's address (see il_insurance_state.rs for the shared layout).
+sol_storage! {
+    pub struct IlInsurance {
+        #[borrow]
+        IlInsuranceState state;
+        #[borrow]
+        Erc721<PolicyParams> erc721;
+    }
+}
+
+// `#[entrypoint]` goes on `dispatch` below, which adds the facets.
+unsafe impl TopLevelStorage for IlInsurance {}
+
+impl Borrow<Ownable> for IlInsurance {
+    fn borrow(&self) -> &Ownable {
+        &self.state.ownable
+    }
+}
+
+impl BorrowMut<Ownable> for IlInsurance {
+    fn borrow_mut(&mut self) -> &mut Ownable {
+        &mut self.state.ownable
+    }
+}
+
+// Runs the vault's own methods, then hands any selector a facet serves to that
+// facet, on the vault's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut insurance = unsafe { IlInsurance::new(U256::ZERO, 0) };
+    if let Some(result) = <IlInsurance as Router<IlInsurance>>::route(&mut insurance, u32::from_be_bytes(selector), &input[4..]) {
+        return result;
+    }
+    let Some(facet) = insurance.facet(selector) else {
+        return Err(Vec::new());
+    };
+    unsafe { delegate_call(&mut insurance, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
+
+impl IlInsurance {
+    fn facet(&self, selector: [u8; 4]) -> Option<Address> {
+        if IIlInsurancePolicies::IIlInsurancePoliciesCalls::valid_selector(selector) {
+            Some(self.state.policies_facet.get())
+        } else if IIlInsuranceUnderwriting::IIlInsuranceUnderwritingCalls::valid_selector(selector) {
+            Some(self.state.underwriting_facet.get())
+        } else {
+            None
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn store_parameters(
+        &mut self,
+        twap_window: U256,
+        blocks_per_year: U256,
+        loading_bps: U256,
+        min_premium_bps: U256,
+        max_coverage_bps: U256,
+        min_duration: U256,
+        max_duration: U256,
+    ) -> Result<(), InsuranceError> {
+        if twap_window == U256::ZERO
+            || blocks_per_year == U256::ZERO
+            || max_coverage_bps == U256::ZERO
+            || max_coverage_bps > U256::from(BPS)
+            || min_duration < twap_window
+            || min_duration > max_duration
+        {
+            return Err(InsuranceError::InvalidParameters(InvalidParameters {}));
+        }
+        let state = &mut self.state;
+        state.twap_window.set(twap_window);
+        state.blocks_per_year.set(blocks_per_year);
+        state.loading_bps.set(loading_bps);
+        state.min_premium_bps.set(min_premium_bps);
+        state.max_coverage_bps.set(max_coverage_bps);
+        state.min_duration.set(min_duration);
+        state.max_duration.set(max_duration);
+        Ok(())
+    }
+}
+
+#[external]
+#[inherit(Erc721<PolicyParams>, Ownable)]
+impl IlInsurance {
+    // e.g. window 1800s, 31_536_000 * 4 blocks/year, 20% loading, 0.1% floor,
+    // 50% cover cap, 1 day to 1 year. The facets are fixed here for good:
+    // swapping one would change the vault's code.
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        twap_window: U256,
+        blocks_per_year: U256,
+        loading_bps: U256,
+        min_premium_bps: U256,
+        max_coverage_bps: U256,
+        min_duration: U256,
+        max_duration: U256,
+        policies_facet: Address,
+        underwriting_facet: Address,
+        deploy_nonce: u64,
+    ) -> Result<(), InsuranceError> {
+        if self.state.twap_window.get() != U256::ZERO {
+            return Err(InsuranceError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.state.ownable.initialize(deploy_nonce)?;
+        self.state.policies_facet.set(policies_facet);
+        self.state.underwriting_facet.set(underwriting_facet);
+        self.store_parameters(twap_window, blocks_per_year, loading_bps, min_premium_bps, max_coverage_bps, min_duration, max_duration)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_parameters(
+        &mut self,
+        twap_window: U256,
+        blocks_per_year: U256,
+        loading_bps: U256,
+        min_premium_bps: U256,
+        max_coverage_bps: U256,
+        min_duration: U256,
+        max_duration: U256,
+    ) -> Result<(), InsuranceError> {
+        self.state.ownable.only_owner()?;
+        self.store_parameters(twap_window, blocks_per_year, loading_bps, min_premium_bps, max_coverage_bps, min_duration, max_duration)
+    }
+
+    // (pool, liquidity, amount, entry_price, expiry, max_payout, claimed)
+    #[allow(clippy::type_complexity)]
+    pub fn policy(&self, policy_id: U256) -> Result<(Address, U256, U256, U256, U256, U256, bool), InsuranceError> {
+        let policy = self.state.policies.get(policy_id);
+        Ok((
+            policy.pool.get(),
+            policy.liquidity.get(),
+            policy.amount.get(),
+            policy.entry_price.get(),
+            policy.expiry.get(),
+            policy.max_payout.get(),
+            policy.claimed.get(),
+        ))
+    }
+
+    // (policies_facet, underwriting_facet)
+    pub fn facets(&self) -> Result<(Address, Address), InsuranceError> {
+        Ok((self.state.policies_facet.get(), self.state.underwriting_facet.get()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{calls, deploy, mock_call, CallKind, UnwrapOk, SENDER};
+
+    const POLICIES: Address = Address::repeat_byte(0xde);
+    const UNDERWRITING: Address = Address::repeat_byte(0xdf);
+    const DAY: u64 = 86_400;
+
+    // 30 minute TWAP, 4 blocks a second, 20% loading, 0.1% floor, 50% cover cap, 1 day to 1 year.
+    fn init(insurance: &mut IlInsurance, twap_window: u64) -> Result<(), InsuranceError> {
+        let year = U256::from(31_536_000);
+        insurance.init(
+            U256::from(twap_window),
+            year * U256::from(4),
+            U256::from(2_000),
+            U256::from(10),
+            U256::from(5_000),
+            U256::from(DAY),
+            year,
+            POLICIES,
+            UNDERWRITING,
+            0,
+        )
+    }
+
+    #[test]
+    fn init_checks_the_parameters() {
+        let mut insurance = deploy::<IlInsurance>();
+        init(&mut insurance, 1_800).unwrap_ok();
+        assert_eq!(insurance.facets().unwrap_ok(), (POLICIES, UNDERWRITING));
+        assert!(matches!(init(&mut insurance, 1_800), Err(InsuranceError::AlreadyInitialized(_))));
+        let set = |insurance: &mut IlInsurance, twap_window: u64, max_coverage_bps: u64| {
+            let day = U256::from(DAY);
+            insurance.set_parameters(U256::from(twap_window), U256::from(1), U256::ZERO, U256::ZERO, U256::from(max_coverage_bps), day, day)
+        };
+        assert!(matches!(set(&mut insurance, 1_800, 0), Err(InsuranceError::InvalidParameters(_))));
+        // the shortest policy has to outlast the TWAP window
+        assert!(matches!(set(&mut insurance, DAY + 1, 5_000), Err(InsuranceError::InvalidParameters(_))));
+        set(&mut insurance, 1_800, 5_000).unwrap_ok();
+        assert_eq!(insurance.state.max_duration.get(), U256::from(DAY));
+    }
+
+    #[test]
+    fn facet_selectors_run_on_the_facets() {
+        let mut insurance = deploy::<IlInsurance>();
+        init(&mut insurance, 1_800).unwrap_ok();
+        mock_call(POLICIES, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        let data = IIlInsurancePolicies::claimCall { policy_id: U256::from(1) }.abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+
+        // facet reverts come back as they are
+        mock_call(UNDERWRITING, |_| Err(b"underwriting says no".to_vec()));
+        let data = IIlInsuranceUnderwriting::pokeCall { pool: SENDER }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"underwriting says no".to_vec());
+        assert_eq!(calls().last().unwrap().to, UNDERWRITING);
+        // the vault's own methods don't leave it
+        assert_eq!(insurance.facet(stylus_sdk::function_selector!("policy", U256)), None);
+        let calls_before = calls().len();
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+        assert_eq!(calls().len(), calls_before);
+    }
+}
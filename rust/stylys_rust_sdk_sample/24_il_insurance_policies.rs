@@ -0,0 +1,415 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc721;
+mod il_insurance_state;
+mod ownable;
+use crate::erc721::Erc721;
+use crate::il_insurance_state::*;
+use alloy_sol_types::sol;
+use amm_math::constant_product::BPS;
+use amm_math::fixed_point::WAD;
+use amm_math::impermanent_loss::{expected_impermanent_loss, impermanent_loss, SECONDS_PER_YEAR};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+    call::{transfer_eth, Call},
+    contract, evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event PolicyIssued(uint256 indexed policy_id, address indexed holder, address indexed pool, uint256 liquidity, uint256 amount, uint256 premium, uint256 entry_price, uint256 expiry);
+    event PolicyClaimed(uint256 indexed policy_id, address indexed holder, uint256 exit_price, uint256 payout);
+}
+
+// Furthest spot may sit from the TWAP for a policy to be written (1%).
+const MAX_SPOT_DEVIATION_BPS: u64 = 100;
+
+// The policy desk for the IL insurance vault in 24_il_insurance.rs: quoting,
+// writing and claiming policies. The vault delegate_calls this facet for the
+// `IIlInsurancePolicies` selectors, so it runs on the vault's storage, takes
+// premiums into and pays cover out of the vault's capital, and mints the
+// vault's own policy NFTs, which follow `IlInsuranceState` (see
+// il_insurance_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct IlInsurancePolicies {
+        #[borrow]
+        IlInsuranceState state;
+        Erc721<PolicyParams> erc721;
+    }
+}
+
+impl IlInsurancePolicies {
+    // Spot price0 of the pool, checked against `twap`.
+    fn check_spot(&self, pool: Address, twap: U256) -> Result<(), InsuranceError> {
+        let (reserve0, reserve1, _) = IPair::new(pool).get_reserves(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        if reserve0 == U256::ZERO {
+            return Err(InsuranceError::InvalidAmount(InvalidAmount {}));
+        }
+        let spot = reserve1 * WAD / reserve0;
+        let gap = if spot > twap { spot - twap } else { twap - spot };
+        if gap * U256::from(BPS) > twap * U256::from(MAX_SPOT_DEVIATION_BPS) {
+            return Err(InsuranceError::PriceDeviation(PriceDeviation { pool, spot, twap }));
+        }
+        Ok(())
+    }
+
+    // Value in wei (token1) of `liquidity` LP tokens: both halves of the
+    // position are worth its token1 reserve share.
+    fn position_value(&self, pool: Address, liquidity: U256) -> Result<U256, InsuranceError> {
+        let pair = IPair::new(pool);
+        let (_, reserve1, _) = pair.get_reserves(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        let supply = pair.total_supply(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        if supply == U256::ZERO {
+            return Err(InsuranceError::InvalidAmount(InvalidAmount {}));
+        }
+        Ok(U256::from(2) * reserve1 * liquidity / supply)
+    }
+
+    fn premium(&self, pool: Address, amount: U256, duration: U256) -> Result<U256, InsuranceError> {
+        let sigma = IPair::new(pool).volatility(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        // per-block variance scaled up to the policy's horizon
+        let variance = sigma * sigma / WAD * self.state.blocks_per_year.get() * duration / U256::from(SECONDS_PER_YEAR);
+        let expected = amount * expected_impermanent_loss(variance) / WAD;
+        let loaded = expected * (U256::from(BPS) + self.state.loading_bps.get()) / U256::from(BPS);
+        let floor = amount * self.state.min_premium_bps.get() / U256::from(BPS);
+        Ok(loaded.max(floor))
+    }
+
+    fn check_duration(&self, duration: U256) -> Result<(), InsuranceError> {
+        let (min, max) = (self.state.min_duration.get(), self.state.max_duration.get());
+        if duration < min || duration > max {
+            return Err(InsuranceError::InvalidDuration(InvalidDuration { duration, min, max }));
+        }
+        Ok(())
+    }
+
+    fn insure_at(&mut self, pool: Address, liquidity: U256, duration: U256, now: U256) -> Result<U256, InsuranceError> {
+        if liquidity == U256::ZERO {
+            return Err(InsuranceError::InvalidAmount(InvalidAmount {}));
+        }
+        self.check_duration(duration)?;
+        self.roll(pool, now)?;
+        let entry_price = self.twap(pool, now)?;
+        self.check_spot(pool, entry_price)?;
+        let amount = self.position_value(pool, liquidity)?;
+        if amount == U256::ZERO {
+            return Err(InsuranceError::InvalidAmount(InvalidAmount {}));
+        }
+        let premium = self.premium(pool, amount, duration)?;
+        let paid = msg::value();
+        if paid < premium {
+            return Err(InsuranceError::InsufficientPremium(InsufficientPremium { paid, premium }));
+        }
+
+        self.state.total_capital.set(self.state.total_capital.get() + premium);
+        let max_payout = amount * self.state.max_coverage_bps.get() / U256::from(BPS);
+        let free = self.state.free_capital();
+        if free < max_payout {
+            return Err(InsuranceError::InsufficientCapital(InsufficientCapital { free, needed: max_payout }));
+        }
+        self.state.locked_capital.set(self.state.locked_capital.get() + max_payout);
+
+        let holder = msg::sender();
+        let ok = IPair::new(pool)
+            .transfer_from(Call::new_in(self), holder, contract::address(), liquidity)
+            .map_err(|_| IlInsuranceState::failed(pool))?;
+        if !ok {
+            return Err(IlInsuranceState::failed(pool));
+        }
+
+        let policy_id = self.state.next_policy_id.get();
+        self.state.next_policy_id.set(policy_id + U256::from(1));
+        let expiry = now + duration;
+        let mut policy = self.state.policies.setter(policy_id);
+        policy.pool.set(pool);
+        policy.liquidity.set(liquidity);
+        policy.amount.set(amount);
+        policy.entry_price.set(entry_price);
+        policy.expiry.set(expiry);
+        policy.max_payout.set(max_payout);
+
+        self.erc721
+            ._safe_mint(holder, policy_id)
+            .map_err(|_| InsuranceError::MintFailed(MintFailed { to: holder, token_id: policy_id }))?;
+        if paid > premium {
+            transfer_eth(holder, paid - premium).map_err(|_| IlInsuranceState::failed(holder))?;
+        }
+        evm::log(PolicyIssued { policy_id, holder, pool, liquidity, amount, premium, entry_price, expiry });
+        Ok(policy_id)
+    }
+
+    fn claim_at(&mut self, policy_id: U256, now: U256) -> Result<U256, InsuranceError> {
+        let policy = self.state.policies.get(policy_id);
+        let (pool, liquidity, amount, entry_price) =
+            (policy.pool.get(), policy.liquidity.get(), policy.amount.get(), policy.entry_price.get());
+        let (expiry, max_payout, claimed) = (policy.expiry.get(), policy.max_payout.get(), policy.claimed.get());
+        if pool == Address::ZERO {
+            return Err(InsuranceError::UnknownPolicy(UnknownPolicy { policy_id }));
+        }
+        if claimed {
+            return Err(InsuranceError::PolicyAlreadyClaimed(PolicyAlreadyClaimed { policy_id }));
+        }
+        if now < expiry {
+            return Err(InsuranceError::PolicyNotExpired(PolicyNotExpired { policy_id, expiry }));
+        }
+
+        // a disabled pool still settles off its last checkpoints
+        if self.state.pools.get(pool).enabled.get() {
+            self.roll(pool, now)?;
+        }
+        let exit_price = self.twap(pool, now)?;
+        let loss = impermanent_loss(entry_price, exit_price).unwrap_or_default();
+        let payout = (amount * loss / WAD).min(max_payout);
+
+        self.state.policies.setter(policy_id).claimed.set(true);
+        self.state.locked_capital.set(self.state.locked_capital.get() - max_payout);
+        self.state.total_capital.set(self.state.total_capital.get() - payout);
+
+        let holder = self.erc721.owner_of(policy_id).map_err(|_| InsuranceError::UnknownPolicy(UnknownPolicy { policy_id }))?;
+        let ok = IPair::new(pool).transfer(Call::new_in(self), holder, liquidity).map_err(|_| IlInsuranceState::failed(pool))?;
+        if !ok {
+            return Err(IlInsuranceState::failed(pool));
+        }
+        if payout > U256::ZERO {
+            transfer_eth(holder, payout).map_err(|_| IlInsuranceState::failed(holder))?;
+        }
+        evm::log(PolicyClaimed { policy_id, holder, exit_price, payout });
+        Ok(payout)
+    }
+}
+
+#[external]
+impl IlInsurancePolicies {
+    pub fn quote_premium(&self, pool: Address, liquidity: U256, duration: U256) -> Result<U256, InsuranceError> {
+        self.check_duration(duration)?;
+        let amount = self.position_value(pool, liquidity)?;
+        self.premium(pool, amount, duration)
+    }
+
+    // Escrows `liquidity` LP tokens of `pool` (approve them first). The premium
+    // is paid in msg.value; anything over the quote is refunded.
+    #[payable]
+    pub fn insure_position(&mut self, pool: Address, liquidity: U256, duration: U256) -> Result<U256, InsuranceError> {
+        self.insure_at(pool, liquidity, duration, U256::from(block::timestamp()))
+    }
+
+    // Settles an expired policy, paying whoever holds its token and returning
+    // the escrowed LP tokens to them. Anyone may call it, so underwriters can
+    // release the locked cover of forgotten policies.
+    pub fn claim(&mut self, policy_id: U256) -> Result<U256, InsuranceError> {
+        self.claim_at(policy_id, U256::from(block::timestamp()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use stylus_sdk::{abi::Router, function_selector};
+    use test_vm::{balance, contract, deploy, mock_call, set_balance, UnwrapOk, MSG_VALUE, SENDER, TIMESTAMP};
+
+    const PAIR: Address = Address::repeat_byte(0x9a);
+    const E18: u128 = 1_000_000_000_000_000_000;
+    const WINDOW: u64 = 1_800;
+    const DAY: u64 = 86_400;
+
+    // The oracle side of 23_pair.rs plus its LP token.
+    #[derive(Default)]
+    struct Pair {
+        reserves: (U256, U256),
+        cumulative: U256,
+        last: u64,
+        volatility: U256,
+        supply: U256,
+        lp: HashMap<Address, U256>,
+    }
+
+    impl Pair {
+        // Someone trades the pair to new reserves at `now`.
+        fn trade(&mut self, reserve0: u128, reserve1: u128, now: u64) {
+            let (r0, r1) = self.reserves;
+            self.cumulative += r1 * WAD / r0 * U256::from(now - self.last);
+            self.reserves = (U256::from(reserve0), U256::from(reserve1));
+            self.last = now;
+        }
+
+        fn move_lp(&mut self, from: Address, to: Address, amount: U256) -> Vec<u8> {
+            *self.lp.get_mut(&from).unwrap() -= amount;
+            *self.lp.entry(to).or_default() += amount;
+            word(U256::from(1))
+        }
+    }
+
+    fn word(value: U256) -> Vec<u8> {
+        value.to_be_bytes::<32>().to_vec()
+    }
+
+    // 1_000 token0 / 1_000 WETH, with SENDER holding `liquidity` of the 1_000 LP tokens.
+    fn mock_pair(liquidity: u128) -> Rc<RefCell<Pair>> {
+        let pair = Rc::new(RefCell::new(Pair {
+            reserves: (U256::from(1_000 * E18), U256::from(1_000 * E18)),
+            last: TIMESTAMP,
+            supply: U256::from(1_000 * E18),
+            ..Pair::default()
+        }));
+        pair.borrow_mut().lp.insert(SENDER, U256::from(liquidity));
+        let state = pair.clone();
+        mock_call(PAIR, move |call| {
+            let mut pair = state.borrow_mut();
+            let arg = |i: usize| U256::from_be_slice(&call.data[4 + 32 * i..36 + 32 * i]);
+            let account = |i: usize| Address::from_slice(&call.data[16 + 32 * i..36 + 32 * i]);
+            let selector: [u8; 4] = call.data[..4].try_into().unwrap();
+            let (r0, r1) = pair.reserves;
+            Ok(match selector {
+                s if s == function_selector!("getReserves") => [word(r0), word(r1), word(U256::from(pair.last))].concat(),
+                s if s == function_selector!("price0CumulativeLast") => word(pair.cumulative),
+                s if s == function_selector!("volatility") => word(pair.volatility),
+                s if s == function_selector!("totalSupply") => word(pair.supply),
+                s if s == function_selector!("transfer", Address, U256) => pair.move_lp(contract(), account(0), arg(1)),
+                s if s == function_selector!("transferFrom", Address, Address, U256) => pair.move_lp(account(0), account(1), arg(2)),
+                _ => return Err(Vec::new()),
+            })
+        });
+        pair
+    }
+
+    // 30 minute TWAP, 4 blocks a second, 20% loading, 50% cover cap, 1 day to 1 year,
+    // with the pool enabled at TIMESTAMP and a 1 ETH underwriter deposit, as the
+    // vault's init, set_pool and deposit leave it.
+    fn insurance(min_premium_bps: u64, liquidity: u128) -> (IlInsurancePolicies, Rc<RefCell<Pair>>) {
+        let mut insurance = deploy::<IlInsurancePolicies>();
+        let pair = mock_pair(liquidity);
+        let state = &mut insurance.state;
+        state.twap_window.set(U256::from(WINDOW));
+        state.blocks_per_year.set(U256::from(SECONDS_PER_YEAR * 4));
+        state.loading_bps.set(U256::from(2_000));
+        state.min_premium_bps.set(U256::from(min_premium_bps));
+        state.max_coverage_bps.set(U256::from(5_000));
+        state.min_duration.set(U256::from(DAY));
+        state.max_duration.set(U256::from(SECONDS_PER_YEAR));
+        let mut oracle = state.pools.setter(PAIR);
+        oracle.enabled.set(true);
+        oracle.older_timestamp.set(U256::from(TIMESTAMP));
+        oracle.newer_timestamp.set(U256::from(TIMESTAMP));
+        state.total_capital.set(MSG_VALUE);
+        // the deposit plus the premium sent with insure_position
+        set_balance(contract(), MSG_VALUE * U256::from(2));
+        (insurance, pair)
+    }
+
+    fn at(seconds: u64) -> U256 {
+        U256::from(TIMESTAMP + seconds)
+    }
+
+    // (total_capital, locked_capital)
+    fn capital(insurance: &IlInsurancePolicies) -> (U256, U256) {
+        (insurance.state.total_capital.get(), insurance.state.locked_capital.get())
+    }
+
+    #[test]
+    fn serves_every_selector_the_vault_forwards() {
+        let (mut insurance, _) = insurance(10, E18 / 10);
+        for selector in IIlInsurancePolicies::IIlInsurancePoliciesCalls::SELECTORS {
+            let routed = <IlInsurancePolicies as Router<IlInsurancePolicies>>::route(&mut insurance, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn a_policy_escrows_the_position_and_pays_il_at_expiry() {
+        let (mut insurance, pair) = insurance(10, E18 / 10);
+        let liquidity = U256::from(E18 / 10);
+        let id = insurance.insure_at(PAIR, liquidity, U256::from(DAY), at(WINDOW)).unwrap_ok();
+
+        // 0.1 of 1_000 LP tokens is worth 0.2 WETH; no volatility, so the 0.1% floor
+        let notional = U256::from(E18 / 5);
+        let premium = notional / U256::from(1_000);
+        let policy = insurance.state.policies.get(id);
+        let (expiry, max_payout) = (policy.expiry.get(), policy.max_payout.get());
+        assert_eq!(
+            (policy.liquidity.get(), policy.amount.get(), policy.entry_price.get(), max_payout),
+            (liquidity, notional, WAD, notional / U256::from(2))
+        );
+        assert_eq!(insurance.erc721.owner_of(id).unwrap_ok(), SENDER);
+        assert_eq!(pair.borrow().lp[&contract()], liquidity);
+        assert_eq!(balance(SENDER), MSG_VALUE - premium);
+        assert_eq!(capital(&insurance), (MSG_VALUE + premium, max_payout));
+
+        // token0 quadruples right after the policy is written
+        pair.borrow_mut().trade(500 * E18, 2_000 * E18, TIMESTAMP + WINDOW + 1);
+        let result = insurance.claim_at(id, expiry - U256::from(1));
+        assert!(matches!(result, Err(InsuranceError::PolicyNotExpired(_))));
+        insurance.roll(PAIR, expiry - U256::from(WINDOW)).unwrap_ok();
+        let payout = insurance.claim_at(id, expiry).unwrap_ok();
+
+        assert_eq!(payout, notional * impermanent_loss(WAD, U256::from(4) * WAD).unwrap() / WAD);
+        assert_eq!(balance(SENDER), MSG_VALUE - premium + payout);
+        assert_eq!(pair.borrow().lp[&SENDER], U256::from(E18 / 10));
+        assert_eq!(pair.borrow().lp[&contract()], U256::ZERO);
+        assert_eq!(capital(&insurance), (MSG_VALUE + premium - payout, U256::ZERO));
+        assert!(matches!(insurance.claim_at(id, expiry), Err(InsuranceError::PolicyAlreadyClaimed(_))));
+    }
+
+    #[test]
+    fn policies_need_spot_near_the_twap() {
+        let (mut insurance, pair) = insurance(10, E18 / 10);
+        let (liquidity, duration) = (U256::from(E18 / 10), U256::from(DAY));
+        assert!(matches!(insurance.insure_at(PAIR, liquidity, duration, at(WINDOW - 1)), Err(InsuranceError::TwapNotReady(_))));
+
+        // a 2% jump the TWAP hasn't seen yet can't be insured at the old price
+        pair.borrow_mut().trade(1_000 * E18, 1_020 * E18, TIMESTAMP + WINDOW);
+        assert!(matches!(insurance.insure_at(PAIR, liquidity, duration, at(WINDOW)), Err(InsuranceError::PriceDeviation(_))));
+        assert_eq!(pair.borrow().lp[&SENDER], liquidity);
+
+        pair.borrow_mut().trade(1_000 * E18, 1_005 * E18, TIMESTAMP + WINDOW);
+        let id = insurance.insure_at(PAIR, liquidity, duration, at(WINDOW)).unwrap_ok();
+        assert_eq!(insurance.state.policies.get(id).entry_price.get(), WAD);
+    }
+
+    #[test]
+    fn premiums_follow_volatility() {
+        let (insurance, pair) = insurance(10, E18 / 10);
+        let (liquidity, duration) = (U256::from(E18 / 10), U256::from(DAY));
+        let notional = U256::from(E18 / 5);
+        assert_eq!(insurance.quote_premium(PAIR, liquidity, duration).unwrap_ok(), notional / U256::from(1_000));
+
+        // 0.1% per block over 345_600 blocks: variance 0.3456, expected IL 4.32%, plus 20%
+        pair.borrow_mut().volatility = U256::from(E18 / 1_000);
+        let premium = insurance.quote_premium(PAIR, liquidity, duration).unwrap_ok();
+        assert_eq!(premium, notional * U256::from(432 * 12) / U256::from(100_000));
+        let longer = insurance.quote_premium(PAIR, liquidity, duration * U256::from(2)).unwrap_ok();
+        assert_eq!(longer, premium * U256::from(2));
+        assert!(matches!(insurance.quote_premium(PAIR, liquidity, U256::from(DAY - 1)), Err(InsuranceError::InvalidDuration(_))));
+    }
+
+    #[test]
+    fn cover_is_capped_by_what_the_vault_holds() {
+        // free cover: 1 of 1_000 LP tokens is 2 WETH notional, capped at the whole 1 ETH vault
+        let (mut insurance, pair) = insurance(0, E18);
+        let id = insurance.insure_at(PAIR, U256::from(E18), U256::from(DAY), at(WINDOW)).unwrap_ok();
+        assert_eq!(capital(&insurance), (MSG_VALUE, MSG_VALUE));
+        let result = insurance.insure_at(PAIR, U256::from(1), U256::from(DAY), at(WINDOW));
+        assert!(matches!(result, Err(InsuranceError::InsufficientCapital(_))));
+
+        // a 16x move is a 53% loss, past the 50% cap
+        let expiry = insurance.state.policies.get(id).expiry.get();
+        pair.borrow_mut().trade(250 * E18, 4_000 * E18, TIMESTAMP + WINDOW + 1);
+        insurance.roll(PAIR, expiry - U256::from(WINDOW)).unwrap_ok();
+        assert_eq!(insurance.claim_at(id, expiry).unwrap_ok(), MSG_VALUE);
+        assert_eq!(capital(&insurance), (U256::ZERO, U256::ZERO));
+    }
+}
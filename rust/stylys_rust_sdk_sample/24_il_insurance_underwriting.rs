@@ -0,0 +1,227 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc721;
+mod il_insurance_state;
+mod ownable;
+use crate::il_insurance_state::*;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+    call::transfer_eth,
+    evm, msg,
+    prelude::*,
+};
+
+sol! {
+    event PoolConfigured(address indexed pool, bool enabled);
+    event Deposited(address indexed underwriter, uint256 amount, uint256 shares);
+    event Withdrawn(address indexed underwriter, uint256 amount, uint256 shares);
+}
+
+// Shares are priced as in 8_smart_vault.rs, against 10^3 virtual shares and one
+// virtual wei. Premiums raise the capital without minting shares, so without
+// them a first underwriter could buy cover from themselves until the next
+// deposit rounds down to nothing.
+const DECIMALS_OFFSET: u64 = 3;
+
+// x * y / d, rounded down
+fn mul_div(x: U256, y: U256, d: U256) -> Result<U256, InsuranceError> {
+    Ok(x.checked_mul(y).ok_or(InsuranceError::MathOverflow(MathOverflow {}))? / d)
+}
+
+fn virtual_shares() -> U256 {
+    U256::from(10).pow(U256::from(DECIMALS_OFFSET))
+}
+
+// Underwriting for the IL insurance vault in 24_il_insurance.rs: the
+// underwriters' capital, and the pools cover can be written on with their TWAP
+// checkpoints. The vault delegate_calls this facet for the
+// `IIlInsuranceUnderwriting` selectors, so it runs on the vault's storage and
+// holds the vault's ETH (see il_insurance_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct IlInsuranceUnderwriting {
+        #[borrow]
+        IlInsuranceState state;
+    }
+}
+
+#[external]
+impl IlInsuranceUnderwriting {
+    // Enabling starts the pool's TWAP; policies can be written one window later.
+    pub fn set_pool(&mut self, pool: Address, enabled: bool) -> Result<(), InsuranceError> {
+        self.state.ownable.only_owner()?;
+        if enabled && !self.state.pools.get(pool).enabled.get() {
+            let now = U256::from(block::timestamp());
+            let cumulative = self.current_cumulative(pool, now)?;
+            let mut oracle = self.state.pools.setter(pool);
+            oracle.older_cumulative.set(cumulative);
+            oracle.older_timestamp.set(now);
+            oracle.newer_cumulative.set(cumulative);
+            oracle.newer_timestamp.set(now);
+        }
+        self.state.pools.setter(pool).enabled.set(enabled);
+        evm::log(PoolConfigured { pool, enabled });
+        Ok(())
+    }
+
+    pub fn poke(&mut self, pool: Address) -> Result<(), InsuranceError> {
+        self.roll(pool, U256::from(block::timestamp()))
+    }
+
+    pub fn twap_price(&self, pool: Address) -> Result<U256, InsuranceError> {
+        self.twap(pool, U256::from(block::timestamp()))
+    }
+
+    // Reverts once payouts have taken every wei while shares are outstanding:
+    // new capital would be split with shares that are worth nothing.
+    #[payable]
+    pub fn deposit(&mut self) -> Result<U256, InsuranceError> {
+        let amount = msg::value();
+        if amount == U256::ZERO {
+            return Err(InsuranceError::InvalidAmount(InvalidAmount {}));
+        }
+        let (capital, supply) = (self.state.total_capital.get(), self.state.total_shares.get());
+        if capital == U256::ZERO && supply != U256::ZERO {
+            return Err(InsuranceError::VaultDrained(VaultDrained {}));
+        }
+        let shares = mul_div(amount, supply + virtual_shares(), capital + U256::from(1))?;
+        if shares == U256::ZERO {
+            return Err(InsuranceError::ZeroShares(ZeroShares { amount }));
+        }
+        let overflow = || InsuranceError::MathOverflow(MathOverflow {});
+        let underwriter = msg::sender();
+        let balance = self.state.shares.get(underwriter);
+        self.state.shares.setter(underwriter).set(balance.checked_add(shares).ok_or_else(overflow)?);
+        self.state.total_shares.set(supply.checked_add(shares).ok_or_else(overflow)?);
+        self.state.total_capital.set(capital.checked_add(amount).ok_or_else(overflow)?);
+        evm::log(Deposited { underwriter, amount, shares });
+        Ok(shares)
+    }
+
+    // Only capital not backing open policies can leave.
+    pub fn withdraw(&mut self, shares: U256) -> Result<U256, InsuranceError> {
+        let underwriter = msg::sender();
+        let have = self.state.shares.get(underwriter);
+        if shares == U256::ZERO || shares > have {
+            return Err(InsuranceError::InsufficientShares(InsufficientShares { have, want: shares }));
+        }
+        let (capital, supply) = (self.state.total_capital.get(), self.state.total_shares.get());
+        let amount = mul_div(shares, capital + U256::from(1), supply + virtual_shares())?;
+        let free = self.state.free_capital();
+        if amount > free {
+            return Err(InsuranceError::InsufficientCapital(InsufficientCapital { free, needed: amount }));
+        }
+        let overflow = || InsuranceError::MathOverflow(MathOverflow {});
+        self.state.shares.setter(underwriter).set(have - shares);
+        self.state.total_shares.set(supply.checked_sub(shares).ok_or_else(overflow)?);
+        self.state.total_capital.set(capital.checked_sub(amount).ok_or_else(overflow)?);
+        transfer_eth(underwriter, amount).map_err(|_| IlInsuranceState::failed(underwriter))?;
+        evm::log(Withdrawn { underwriter, amount, shares });
+        Ok(amount)
+    }
+
+    pub fn shares_of(&self, underwriter: Address) -> Result<U256, InsuranceError> {
+        Ok(self.state.shares.get(underwriter))
+    }
+
+    // (total_capital, locked_capital, total_shares)
+    pub fn vault(&self) -> Result<(U256, U256, U256), InsuranceError> {
+        Ok((self.state.total_capital.get(), self.state.locked_capital.get(), self.state.total_shares.get()))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use amm_math::fixed_point::WAD;
+    use stylus_sdk::{abi::Router, function_selector};
+    use test_vm::{contract, deploy, mock_call, set_balance, UnwrapOk, MSG_VALUE, TIMESTAMP};
+
+    const PAIR: Address = Address::repeat_byte(0x9a);
+
+    #[test]
+    fn serves_every_selector_the_vault_forwards() {
+        let mut insurance = deploy::<IlInsuranceUnderwriting>();
+        for selector in IIlInsuranceUnderwriting::IIlInsuranceUnderwritingCalls::SELECTORS {
+            let routed =
+                <IlInsuranceUnderwriting as Router<IlInsuranceUnderwriting>>::route(&mut insurance, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn enabling_a_pool_starts_its_twap() {
+        let mut insurance = deploy::<IlInsuranceUnderwriting>();
+        // a pair last traded 100s ago at price 2, with 7 accumulated before that
+        mock_call(PAIR, |call| {
+            let word = |value: u64| U256::from(value).to_be_bytes::<32>().to_vec();
+            if call.data[..4] == function_selector!("getReserves") {
+                Ok([word(1_000), word(2_000), word(TIMESTAMP - 100)].concat())
+            } else {
+                Ok(word(7))
+            }
+        });
+        insurance.state.twap_window.set(U256::from(1_800));
+        assert!(matches!(insurance.set_pool(PAIR, true), Err(InsuranceError::Unauthorized(_))));
+        insurance.state.ownable.initialize(0).unwrap_ok();
+
+        insurance.set_pool(PAIR, true).unwrap_ok();
+        let oracle = insurance.state.pools.get(PAIR);
+        let cumulative = U256::from(7) + U256::from(2) * WAD * U256::from(100);
+        assert_eq!((oracle.newer_cumulative.get(), oracle.newer_timestamp.get()), (cumulative, U256::from(TIMESTAMP)));
+        assert_eq!(oracle.older_cumulative.get(), cumulative);
+        // no price until a window has passed
+        assert!(matches!(insurance.twap_price(PAIR), Err(InsuranceError::TwapNotReady(_))));
+        insurance.poke(PAIR).unwrap_ok();
+
+        insurance.set_pool(PAIR, false).unwrap_ok();
+        assert!(matches!(insurance.poke(PAIR), Err(InsuranceError::PoolNotEnabled(_))));
+    }
+
+    #[test]
+    fn deposits_into_a_drained_vault_revert() {
+        let mut insurance = deploy::<IlInsuranceUnderwriting>();
+        let shares = insurance.deposit().unwrap_ok();
+        assert_eq!(shares, MSG_VALUE * virtual_shares());
+        // a claim capped at the whole vault paid it all out
+        insurance.state.total_capital.set(U256::ZERO);
+
+        assert!(matches!(insurance.deposit(), Err(InsuranceError::VaultDrained(_))));
+        assert_eq!(insurance.withdraw(shares).unwrap_ok(), U256::ZERO);
+        assert_eq!(insurance.vault().unwrap_ok(), (U256::ZERO, U256::ZERO, U256::ZERO));
+        assert_eq!(insurance.deposit().unwrap_ok(), shares);
+    }
+
+    #[test]
+    fn premiums_cannot_round_a_deposit_to_nothing() {
+        // one share's worth of deposit, then 2 ETH of premiums paid to itself
+        let mut insurance = deploy::<IlInsuranceUnderwriting>();
+        insurance.state.total_shares.set(U256::from(1));
+        insurance.state.total_capital.set(MSG_VALUE * U256::from(2));
+        set_balance(contract(), MSG_VALUE * U256::from(3));
+
+        let shares = insurance.deposit().unwrap_ok();
+        assert_eq!(shares, MSG_VALUE * U256::from(1_001) / (MSG_VALUE * U256::from(2) + U256::from(1)));
+        // the virtual shares took most of the premiums, not the victim's deposit
+        let withdrawn = insurance.withdraw(shares).unwrap_ok();
+        assert!(withdrawn > MSG_VALUE * U256::from(998) / U256::from(1_000), "{withdrawn}");
+
+        // rounding it down would now take 1_000 times the deposit, and reverts
+        insurance.state.total_capital.set(MSG_VALUE * U256::from(1_001));
+        assert!(matches!(insurance.deposit(), Err(InsuranceError::ZeroShares(_))));
+    }
+}
@@ -0,0 +1,134 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod ownable;
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloc::string::String;
+use alloy_sol_types::sol;
+use stylus_sdk::{evm, prelude::*, storage::Erase};
+
+sol! {
+    event NameChanged(string name);
+    event ActiveChanged(bool active);
+}
+
+#[derive(SolidityError)]
+pub enum StorageDemoError {
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for StorageDemoError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => StorageDemoError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => StorageDemoError::InvalidOwner(e),
+        }
+    }
+}
+
+// The quests from 2_storage.rs as one contract: an owner, a flag and a string,
+// read with `get`, written with `set` and cleared with `erase`.
+sol_storage! {
+    #[entrypoint]
+    pub struct StorageDemo {
+        #[borrow]
+        Ownable ownable;
+        bool active;
+        string name;
+    }
+}
+
+#[external]
+#[inherit(Ownable)]
+impl StorageDemo {
+    pub fn init(&mut self, name: String, deploy_nonce: u64) -> Result<(), StorageDemoError> {
+        self.ownable.initialize(deploy_nonce)?;
+        self.active.set(true);
+        self.name.set_str(&name);
+        evm::log(NameChanged { name });
+        evm::log(ActiveChanged { active: true });
+        Ok(())
+    }
+
+    pub fn name(&self) -> Result<String, StorageDemoError> {
+        Ok(self.name.get_string())
+    }
+
+    pub fn active(&self) -> Result<bool, StorageDemoError> {
+        Ok(self.active.get())
+    }
+
+    pub fn set_name(&mut self, name: String) -> Result<(), StorageDemoError> {
+        self.ownable.only_owner()?;
+        self.name.set_str(&name);
+        evm::log(NameChanged { name });
+        Ok(())
+    }
+
+    pub fn set_active(&mut self, active: bool) -> Result<(), StorageDemoError> {
+        self.ownable.only_owner()?;
+        self.active.set(active);
+        evm::log(ActiveChanged { active });
+        Ok(())
+    }
+
+    // Zeroes the flag and the string; the owner stays.
+    pub fn clear(&mut self) -> Result<(), StorageDemoError> {
+        self.ownable.only_owner()?;
+        self.active.erase();
+        self.name.erase();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_primitives::Address;
+    use test_vm::{deploy, events, UnwrapOk, SENDER};
+
+    #[test]
+    fn init_sets_owner_name_and_flag() {
+        let mut demo = deploy::<StorageDemo>();
+        demo.init("demo".into(), 0).unwrap_ok();
+        assert_eq!(demo.ownable.owner().unwrap_ok(), SENDER);
+        assert_eq!(demo.name().unwrap_ok(), "demo");
+        assert!(demo.active().unwrap_ok());
+        assert!(demo.init("again".into(), 0).is_err());
+    }
+
+    #[test]
+    fn only_the_deployer_can_init() {
+        let mut demo = deploy::<StorageDemo>();
+        assert!(matches!(demo.init("demo".into(), 1), Err(StorageDemoError::Unauthorized(_))));
+        assert_eq!(demo.ownable.owner().unwrap_ok(), Address::ZERO);
+    }
+
+    #[test]
+    fn setters_write_and_clear_erases() {
+        let mut demo = deploy::<StorageDemo>();
+        demo.init("demo".into(), 0).unwrap_ok();
+        demo.set_name("a much longer name that spills past one storage slot".into()).unwrap_ok();
+        assert_eq!(demo.name().unwrap_ok(), "a much longer name that spills past one storage slot");
+        demo.set_active(false).unwrap_ok();
+        assert!(!demo.active().unwrap_ok());
+        assert_eq!(events::<NameChanged>().len(), 2);
+
+        demo.clear().unwrap_ok();
+        assert_eq!(demo.name().unwrap_ok(), "");
+        assert!(!demo.active().unwrap_ok());
+        assert_eq!(demo.ownable.owner().unwrap_ok(), SENDER);
+    }
+}
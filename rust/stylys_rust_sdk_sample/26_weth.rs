@@ -0,0 +1,129 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod erc20;
+use crate::erc20::{Erc20, Erc20Error, Erc20Params, InsufficientAllowance, InsufficientBalance};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    call::transfer_eth,
+    msg,
+    prelude::*,
+};
+
+struct WethParams;
+
+impl Erc20Params for WethParams {
+    const NAME: &'static str = "Wrapped Ether";
+    const SYMBOL: &'static str = "WETH";
+    const DECIMALS: u8 = 18;
+}
+
+sol! {
+    error EthTransferFailed(address to, uint256 amount);
+}
+
+#[derive(SolidityError)]
+pub enum WethError {
+    InsufficientBalance(InsufficientBalance),
+    InsufficientAllowance(InsufficientAllowance),
+    EthTransferFailed(EthTransferFailed),
+}
+
+impl From<Erc20Error> for WethError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => WethError::InsufficientBalance(e),
+            Erc20Error::InsufficientAllowance(e) => WethError::InsufficientAllowance(e),
+        }
+    }
+}
+
+// The Weth from 3_method.rs: deposits mint 1:1 against msg.value, and
+// withdrawals burn before sending the ETH back.
+sol_storage! {
+    #[entrypoint]
+    pub struct Weth {
+        #[borrow]
+        Erc20<WethParams> erc20;
+    }
+}
+
+#[external]
+#[inherit(Erc20<WethParams>)]
+impl Weth {
+    #[payable]
+    pub fn deposit(&mut self) -> Result<(), WethError> {
+        self.erc20.mint(msg::sender(), msg::value());
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, amount: U256) -> Result<(), WethError> {
+        self.erc20.burn(msg::sender(), amount)?;
+        let to: Address = msg::sender();
+        transfer_eth(to, amount).map_err(|_| WethError::EthTransferFailed(EthTransferFailed { to, amount }))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::erc20::Transfer;
+    use test_vm::{balance, contract, deploy, events, set_balance, UnwrapOk, BOB, MSG_VALUE, SENDER};
+
+    #[test]
+    fn deposit_mints_msg_value() {
+        let mut weth = deploy::<Weth>();
+        weth.deposit().unwrap_ok();
+        weth.deposit().unwrap_ok();
+        assert_eq!(weth.erc20.balance_of(SENDER).unwrap_ok(), MSG_VALUE * U256::from(2));
+        assert_eq!(weth.erc20.total_supply().unwrap_ok(), MSG_VALUE * U256::from(2));
+        let minted = events::<Transfer>();
+        assert_eq!(minted.len(), 2);
+        assert_eq!((minted[0].from, minted[0].to, minted[0].value), (Address::ZERO, SENDER, MSG_VALUE));
+    }
+
+    #[test]
+    fn withdraw_burns_and_pays_out() {
+        let mut weth = deploy::<Weth>();
+        weth.deposit().unwrap_ok();
+        set_balance(contract(), MSG_VALUE);
+        let amount = MSG_VALUE / U256::from(4);
+        weth.withdraw(amount).unwrap_ok();
+        assert_eq!(weth.erc20.balance_of(SENDER).unwrap_ok(), MSG_VALUE - amount);
+        assert_eq!(weth.erc20.total_supply().unwrap_ok(), MSG_VALUE - amount);
+        assert_eq!(balance(SENDER), amount);
+        assert_eq!(balance(contract()), MSG_VALUE - amount);
+    }
+
+    #[test]
+    fn withdraw_more_than_balance_reverts() {
+        let mut weth = deploy::<Weth>();
+        weth.deposit().unwrap_ok();
+        assert!(matches!(weth.withdraw(MSG_VALUE + U256::from(1)), Err(WethError::InsufficientBalance(_))));
+    }
+
+    #[test]
+    fn transfer_from_spends_allowance() {
+        let mut weth = deploy::<Weth>();
+        weth.deposit().unwrap_ok();
+        weth.erc20.approve(SENDER, U256::from(10)).unwrap_ok();
+        weth.erc20.transfer_from(SENDER, BOB, U256::from(4)).unwrap_ok();
+        assert_eq!(weth.erc20.balance_of(BOB).unwrap_ok(), U256::from(4));
+        assert_eq!(weth.erc20.allowance(SENDER, SENDER).unwrap_ok(), U256::from(6));
+        assert!(matches!(
+            weth.erc20.transfer_from(SENDER, BOB, U256::from(7)).map_err(WethError::from),
+            Err(WethError::InsufficientAllowance(_))
+        ));
+    }
+}
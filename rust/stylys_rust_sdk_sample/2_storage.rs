@@ -85,10 +85,12 @@ impl Contract {
     }
 
     /// Updates the owner in storage
+    /// (ownable.rs does this in two steps: transfer_ownership, then accept_ownership)
     pub fn set_owner(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
-        if msg::sender() == self.owner()? {  // we'll discuss msg::sender later
-            self.owner.set(new_owner);
+        if msg::sender() != self.owner()? {  // we'll discuss msg::sender later
+            return Err("Unauthorized".into());
         }
+        self.owner.set(new_owner);
         Ok(())
     }
 }
@@ -98,17 +100,30 @@ impl Contract {
 sol_storage! {
     pub struct SubStruct {
         mapping(address => uint) balances;  
-        Delegate delegates[];              
+        Delegate[] delegates;              
+    }
+
+    pub struct Delegate {
+        address delegatee;
+        uint256 weight;
+        uint64 expiry;
     }
 }
 
 impl SubStruct {
-    pub fn add_delegate(&mut self, delegate: Delegate) -> Result<(), Vec<u8>> {
-        self.delegates.push(delegate);
+    // Vectors of structs grow in place, then each field is set through the guard
+    pub fn add_delegate(&mut self, delegatee: Address, weight: U256, expiry: u64) -> Result<(), Vec<u8>> {
+        let mut delegate = self.delegates.grow();
+        delegate.delegatee.set(delegatee);
+        delegate.weight.set(weight);
+        delegate.expiry.set(U64::from(expiry));
+        Ok(())
     }
 
     pub fn track_balance(&mut self, address: Address) -> Result<U256, Vec<u8>> {
-        self.balances.insert(address, address.balance());
+        let balance = address.balance();
+        self.balances.insert(address, balance);
+        Ok(balance)
     }
 }
 
@@ -179,6 +194,9 @@ contract DeleteDemo{
 
 
 // 3.3 Example
+mod enumerable;
+use crate::enumerable::AddressUintMap;
+
 sol_storage! {
     #[derive(Erase)]
     pub struct Contract {
@@ -190,6 +208,12 @@ sol_storage! {
         mapping(address => uint) balances; // can't erase a map
         mapping(uint => uint)[] roots;     // can't erase vector of maps
     }
+
+    // tracking the keys makes it erasable (see 12_enumerable_storage.rs)
+    #[derive(Erase)]
+    pub struct Erasable {
+        AddressUintMap balances;
+    }
 }
 
 
@@ -67,20 +67,32 @@ function outerMethod() external {
 
 
 // 1.3 Example
+sol! {
+    error CounterOverflow(uint256 number);
+}
+
+#[derive(SolidityError)]
+pub enum CounterError {
+    CounterOverflow(CounterOverflow),
+}
+
 #[external]
 impl Counter {
-    pub fn number(&self) -> Result<U256, Vec<u8>> {
+    pub fn number(&self) -> Result<U256, CounterError> {
         Ok(self.number.get())
     }
 
-    pub fn set_number(&mut self, new_number: U256) -> Result<(), Vec<u8>> {
+    pub fn set_number(&mut self, new_number: U256) -> Result<(), CounterError> {
         self.number.set(new_number);
         Ok(())
     }
 
-    pub fn increment(&mut self) -> Result<(), Vec<u8>> {
+    pub fn increment(&mut self) -> Result<(), CounterError> {
         let number = self.number.get();
-        self.set_number(number + U256::from(1))
+        let new_number = number
+            .checked_add(U256::from(1))
+            .ok_or(CounterError::CounterOverflow(CounterOverflow { number }))?;
+        self.set_number(new_number)
     }
 }
 
@@ -55,13 +55,28 @@ pub fn do_call(account: IService, user: Address) -> Result<String, Error> {
 
 // 3. Low-Level Calls of Contract
 // 3.1 Real Use Case
+sol! {
+    error InsufficientOutputAmount();
+    error InsufficientLiquidity(uint256 reserve0, uint256 reserve1);
+    error CallbackFailed(address to);
+    error KInvariantViolated(uint256 before, uint256 after);
+}
+
+#[derive(SolidityError)]
+pub enum PairError {
+    InsufficientOutputAmount(InsufficientOutputAmount),
+    InsufficientLiquidity(InsufficientLiquidity),
+    CallbackFailed(CallbackFailed),
+    KInvariantViolated(KInvariantViolated),
+}
+
 pub fn swap(
     &mut self,
     amount0_out: U256,
     amount1_out: U256,
     to: Address,
     data: Vec<u8>,
-) -> Result<(), Vec<u8>> {
+) -> Result<(), PairError> {
 
     // ...
 
@@ -71,14 +86,15 @@ pub fn swap(
     if amount1_out > U256::ZERO { self._safeTransfer(self.token1.get(), to, amount1_out)?; }
 
     if !data.is_empty() {
-        call(Call::new(), to, &data)?;
+        call(Call::new(), to, &data).map_err(|_| PairError::CallbackFailed(CallbackFailed { to }))?;
     }
 
     // ...
 
     let k = _reserve0.checked_mul(_reserve1).unwrap().checked_mul(U256::from(1000)).unwrap();
-    if balance0_adjusted.checked_mul(balance1_adjusted).unwrap() < k {
-        return Err("K".into());
+    let k_after = balance0_adjusted.checked_mul(balance1_adjusted).unwrap();
+    if k_after < k {
+        return Err(PairError::KInvariantViolated(KInvariantViolated { before: k, after: k_after }));
     }
 
     // ...
@@ -118,11 +134,24 @@ let return_data = delegate_call(Call::new(), target_contract, call_data)?;
 
 
 // 4.2 Example
+sol! {
+    error Unauthorized(address caller);
+    error ImplementationNotSet();
+    error DelegateCallFailed(address implementation, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum ProxyError {
+    Unauthorized(Unauthorized),
+    ImplementationNotSet(ImplementationNotSet),
+    DelegateCallFailed(DelegateCallFailed),
+}
+
 #[external]
 impl Proxy {
     //...
 
-    pub fn relay_to_implementation(&mut self, data: Vec<u8>) -> Result<Vec<u8>, Vec<u8>> {
+    pub fn relay_to_implementation(&mut self, data: Vec<u8>) -> Result<Vec<u8>, ProxyError> {
         let implementation_address = self.get_implementation()?;
         let res;
         unsafe {
@@ -131,24 +160,47 @@ impl Proxy {
 
         match res {
             Ok(res) => Ok(res.into()),
-            Err(e) => Err(format!("Error: {:?}", e).into()),
+            Err(e) => Err(ProxyError::DelegateCallFailed(DelegateCallFailed {
+                implementation: implementation_address,
+                reason: match e {
+                    call::Error::Revert(reason) => reason.into(),
+                    _ => Vec::new().into(),
+                },
+            })),
         }
     }
 }
 
 
 // 4.3 Quest
-pub fn invoke_logic_contract(&mut self, spender: Address, value: U256) -> Result<bool, Erc20Error> {
-    let logic_address = self.get_logic()?;
-    let res;
-    unsafe {
-        res = delegate_call(self, logic_address, &data[..])
-    };
+// The logic address is looked up by selector (see 9_diamond_logic.rs for the full contract)
+sol! {
+    error FunctionNotFound(bytes4 selector);
+    error DelegateCallFailed(address facet, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum DiamondError {
+    FunctionNotFound(FunctionNotFound),
+    DelegateCallFailed(DelegateCallFailed),
+}
+
+pub fn invoke_logic_contract(&mut self, spender: Address, value: U256) -> Result<bool, DiamondError> {
+    let selector = function_selector!("approve", Address, U256);
+    let logic_address = self.facet_address(selector.into())?;
+    let data = [
+        &selector[..],
+        &spender.into_word()[..],
+        &value.to_be_bytes::<32>(),
+    ].concat();
+
+    let res = unsafe { delegate_call(self, logic_address, &data[..]) };
 
     match res {
-        Ok(res) => Ok(res.into()),
-        Err(e) => Err(format!("Error: {:?}", e).into()),
+        Ok(res) => Ok(res.last() == Some(&1)),
+        Err(_) => Err(DiamondError::DelegateCallFailed(DelegateCallFailed {
+            facet: logic_address,
+            reason: Vec::new().into(),
+        })),
     }
-
-    Ok(true)
 }
@@ -127,10 +127,10 @@ pub fn approve(&mut self, spender: Address, amount: U256) -> Result<bool> {
 
 // 2.4 Quest
 impl Proxy {
-    pub fn only_owner(&mut self) -> Result<(), Vec<u8>> {
+    pub fn only_owner(&mut self) -> Result<(), ProxyError> {
         let owner = self.meta_information.owner.get();
         if owner != msg::sender() {
-            return Err(format!("Invalid").into());
+            return Err(ProxyError::Unauthorized(Unauthorized { caller: msg::sender() }));
         }
         Ok(())
 
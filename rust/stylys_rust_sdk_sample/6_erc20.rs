@@ -1,11 +1,18 @@
-#![cfg_attr(not(feature = "export-abi"), no_main, no_std)]
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
 extern crate alloc;
 
+#[cfg(target_arch = "wasm32")]
 #[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
 
 mod erc20;
-use crate::erc20::{Erc20, Erc20Params}
+use crate::erc20::{Erc20, Erc20Params};
 use alloc::vec::Vec;
 use stylus_sdk::{alloy_primitives::U256, msg, prelude::*};
 
@@ -19,7 +26,7 @@ impl Erc20Params for StylusERC20Params {
 
 sol_storage! {
     #[entrypoint]
-    struct StylusERC20 {
+    pub struct StylusERC20 {
         #[borrow]
         Erc20<StylusERC20Params> erc20;
     }
@@ -33,3 +40,29 @@ impl StylusERC20 {
         Ok(())
     }
 }
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::erc20::{Erc20Error, Transfer};
+    use test_vm::{deploy, events, UnwrapOk, BOB, SENDER};
+
+    #[test]
+    fn metadata() {
+        assert_eq!(Erc20::<StylusERC20Params>::name().unwrap_ok(), "ERC20 Example");
+        assert_eq!(Erc20::<StylusERC20Params>::symbol().unwrap_ok(), "EE");
+        assert_eq!(Erc20::<StylusERC20Params>::decimals().unwrap_ok(), 18);
+    }
+
+    #[test]
+    fn mint_and_transfer() {
+        let mut token = deploy::<StylusERC20>();
+        token.mint(U256::from(100)).unwrap_ok();
+        assert!(token.erc20.transfer(BOB, U256::from(30)).unwrap_ok());
+        assert_eq!(token.erc20.balance_of(SENDER).unwrap_ok(), U256::from(70));
+        assert_eq!(token.erc20.balance_of(BOB).unwrap_ok(), U256::from(30));
+        assert_eq!(token.erc20.total_supply().unwrap_ok(), U256::from(100));
+        assert_eq!(events::<Transfer>().len(), 2);
+        assert!(matches!(token.erc20.transfer(BOB, U256::from(71)), Err(Erc20Error::InsufficientBalance(_))));
+    }
+}
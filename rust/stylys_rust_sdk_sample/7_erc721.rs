@@ -1,53 +1,22 @@
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-#![cfg_attr(not(any(feature = "export-abi", test)), no_main)]
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
 extern crate alloc;
 
+#[cfg(target_arch = "wasm32")]
 #[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
 
-use stylus_sdk::{msg, prelude::*};
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use stylus_sdk::prelude::*;
 mod erc721;
 
-use crate::erc721::{Erc721, Erc721Params};
+use crate::erc721::{Erc721, Erc721Error, Erc721Params, InvalidTokenId, NotApproved, NotOwner, ReceiverRefused, TransferToZero};
 use alloy_primitives::{Address, U256};
-use erc721::{Erc721Error, NotAuthorized};
+use alloy_sol_types::sol;
 
 struct StylusNFTParams;
 impl Erc721Params for StylusNFTParams {
@@ -55,23 +24,96 @@ impl Erc721Params for StylusNFTParams {
     const SYMBOL: &'static str = "SNFT";
 }
 
+sol! {
+    error InvalidRecipient(address to);
+    error CounterOverflow(uint256 counter);
+}
+
+#[derive(SolidityError)]
+pub enum StylusNFTError {
+    InvalidRecipient(InvalidRecipient),
+    CounterOverflow(CounterOverflow),
+    InvalidTokenId(InvalidTokenId),
+    NotOwner(NotOwner),
+    NotApproved(NotApproved),
+    TransferToZero(TransferToZero),
+    ReceiverRefused(ReceiverRefused),
+}
+
+impl From<Erc721Error> for StylusNFTError {
+    fn from(err: Erc721Error) -> Self {
+        match err {
+            Erc721Error::InvalidTokenId(e) => StylusNFTError::InvalidTokenId(e),
+            Erc721Error::NotOwner(e) => StylusNFTError::NotOwner(e),
+            Erc721Error::NotApproved(e) => StylusNFTError::NotApproved(e),
+            Erc721Error::TransferToZero(e) => StylusNFTError::TransferToZero(e),
+            Erc721Error::ReceiverRefused(e) => StylusNFTError::ReceiverRefused(e),
+        }
+    }
+}
+
 sol_storage! {
     #[entrypoint]
-    struct StylusNFT{
+    pub struct StylusNFT {
         #[borrow]
         Erc721<StylusNFTParams> erc721;
         uint256 counter;
     }
 }
 
-
 #[external]
 #[inherit(Erc721< StylusNFTParams >)]
-impl StylusNFT{
-    pub fn safe_mint(&mut self, to: Address) -> Result<(), Erc721Error> {}
-    let token_id = self.counter.get();
-    self.erc721._safe_mint(to, token_id)?;
-    let new_value = token_id + U256::from(1);
-    self.counter.set(new_value);
-    Ok(())
+impl StylusNFT {
+    pub fn safe_mint(&mut self, to: Address) -> Result<(), StylusNFTError> {
+        if to == Address::ZERO {
+            return Err(StylusNFTError::InvalidRecipient(InvalidRecipient { to }));
+        }
+        let token_id = self.counter.get();
+        let new_value =
+            token_id.checked_add(U256::from(1)).ok_or(StylusNFTError::CounterOverflow(CounterOverflow { counter: token_id }))?;
+        self.erc721._safe_mint(to, token_id)?;
+        self.counter.set(new_value);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::erc721::Transfer;
+    use test_vm::{deploy, events, mock_call, UnwrapOk, BOB, CAROL};
+
+    #[test]
+    fn safe_mint_assigns_sequential_ids() {
+        let mut nft = deploy::<StylusNFT>();
+        nft.safe_mint(BOB).unwrap_ok();
+        nft.safe_mint(BOB).unwrap_ok();
+        assert_eq!(nft.erc721.owner_of(U256::from(1)).unwrap_ok(), BOB);
+        assert_eq!(nft.erc721.balance_of(BOB).unwrap_ok(), U256::from(2));
+        assert_eq!(events::<Transfer>()[0].from, Address::ZERO);
+        assert!(matches!(nft.erc721.owner_of(U256::from(2)), Err(Erc721Error::InvalidTokenId(_))));
+    }
+
+    #[test]
+    fn rejects_zero_address() {
+        let mut nft = deploy::<StylusNFT>();
+        assert!(matches!(nft.safe_mint(Address::ZERO), Err(StylusNFTError::InvalidRecipient(_))));
+    }
+
+    #[test]
+    fn contract_recipients_must_accept() {
+        let mut nft = deploy::<StylusNFT>();
+        mock_call(CAROL, |_| Ok(vec![0; 32]));
+        assert!(matches!(nft.safe_mint(CAROL), Err(StylusNFTError::ReceiverRefused(_))));
+
+        // a revert would roll the failed mint back; start over instead
+        let mut nft = deploy::<StylusNFT>();
+        mock_call(CAROL, |_| {
+            let mut accepted = vec![0; 32];
+            accepted[..4].copy_from_slice(&0x150b7a02_u32.to_be_bytes());
+            Ok(accepted)
+        });
+        nft.safe_mint(CAROL).unwrap_ok();
+        assert_eq!(nft.erc721.owner_of(U256::ZERO).unwrap_ok(), CAROL);
+    }
 }
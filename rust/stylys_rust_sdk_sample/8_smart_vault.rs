@@ -1,147 +1,411 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+// Storage fields and methods keep the ERC-4626 camelCase names.
+#![allow(non_snake_case)]
+extern crate alloc;
 
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
 
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
 
+mod deployer;
+mod erc20;
+mod ownable;
+mod vault_state;
+use crate::erc20::Erc20;
+use crate::ownable::Ownable;
+use crate::vault_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::SolInterface;
+use core::borrow::{Borrow, BorrowMut};
+use stylus_sdk::{
+    abi::Router,
+    alloy_primitives::{Address, U256, U8},
+    call::{self, delegate_call, RawCall},
+    contract, function_selector, msg,
+    prelude::*,
+    storage::TopLevelStorage,
+    ArbResult,
+};
+
+// Larger offsets make a donation attack cost more; 18 is already far past any realistic attacker
+const MAX_DECIMALS_OFFSET: u8 = 18;
+
+// An ERC-4626 style vault that lends its asset to strategies. The vault keeps
+// the share token, deposits and withdrawals; adding, tuning and harvesting
+// strategies lives in 8_smart_vault_strategies.rs, which the vault
+// delegate_calls for the `IVaultStrategies` selectors so callers only ever see
+// the vault's address (see vault_state.rs for the shared layout).
+sol_storage! {
+    pub struct Vault {
+        #[borrow]
+        VaultState state;
+        #[borrow]
+        Erc20<VaultParams> erc20;
+    }
+}
 
+// `#[entrypoint]` goes on `dispatch` below, which adds the strategy manager.
+unsafe impl TopLevelStorage for Vault {}
 
+impl Borrow<Ownable> for Vault {
+    fn borrow(&self) -> &Ownable {
+        &self.state.ownable
+    }
+}
 
+impl BorrowMut<Ownable> for Vault {
+    fn borrow_mut(&mut self) -> &mut Ownable {
+        &mut self.state.ownable
+    }
+}
 
+// Runs the vault's own methods, then hands the strategy manager's selectors to
+// it, on the vault's storage. Its revert data comes back unchanged.
+#[entrypoint]
+fn dispatch(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let selector = [input[0], input[1], input[2], input[3]];
+    let mut vault = unsafe { Vault::new(U256::ZERO, 0) };
+    if let Some(result) = <Vault as Router<Vault>>::route(&mut vault, u32::from_be_bytes(selector), &input[4..]) {
+        return result;
+    }
+    if !IVaultStrategies::IVaultStrategiesCalls::valid_selector(selector) {
+        return Err(Vec::new());
+    }
+    let facet = vault.state.strategiesFacet.get();
+    unsafe { delegate_call(&mut vault, facet, &input) }.map_err(|e| match e {
+        call::Error::Revert(reason) => reason,
+        _ => Vec::new(),
+    })
+}
 
+// Share price uses virtual shares (10^offset) and one virtual asset:
+//
+//     shares = assets * (supply + 10^offset) / (totalAssets + 1)
+//
+// With `supply == 0 -> shares = amount`, a first depositor could mint 1 share,
+// donate assets straight to the vault and round the next deposit down to 0 shares,
+// then redeem everything. The virtual shares own part of any donation, so the
+// attacker loses more to them than they can take from the victim, and the offset
+// scales that loss by 10^offset.
+impl Vault {
+    fn to_shares(&self, assets: U256, round_up: bool) -> Result<U256, VaultError> {
+        let supply = self.erc20.total_supply.get() + self.state.virtual_shares();
+        let total_assets = self.total_assets()? + U256::from(1);
+        mul_div(assets, supply, total_assets, round_up)
+    }
 
+    fn to_assets(&self, shares: U256, round_up: bool) -> Result<U256, VaultError> {
+        let supply = self.erc20.total_supply.get() + self.state.virtual_shares();
+        let total_assets = self.total_assets()? + U256::from(1);
+        mul_div(shares, total_assets, supply, round_up)
+    }
+}
 
+#[external]
+#[inherit(Erc20<VaultParams>, Ownable)]
+impl Vault {
+    // The strategy manager is fixed here for good: swapping it would change the vault's code.
+    pub fn init(&mut self, _asset: Address, _decimals_offset: u8, strategies_facet: Address, deploy_nonce: u64) -> Result<(), VaultError> {
+        if _decimals_offset > MAX_DECIMALS_OFFSET {
+            return Err(VaultError::InvalidDecimalsOffset(InvalidDecimalsOffset { offset: _decimals_offset }));
+        }
+        self.state.ownable.initialize(deploy_nonce)?;
+        self.state.asset.set(_asset);
+        self.state.decimalsOffset.set(U8::from(_decimals_offset));
+        self.state.strategiesFacet.set(strategies_facet);
+        Ok(())
+    }
 
+    pub fn setAsset(&mut self, _asset: Address) -> Result<Address, VaultError> {
+        self.state.ownable.only_owner()?;
+        self.state.asset.set(_asset);
+        Ok(_asset)
+    }
 
+    // Shares are priced before the transfer, so the deposit can't inflate its own price.
+    pub fn deposit(&mut self, amount: U256) -> Result<U256, VaultError> {
+        let shares = self.to_shares(amount, false)?;
+        if shares == U256::ZERO {
+            return Err(VaultError::ZeroShares(ZeroShares { assets: amount }));
+        }
+
+        let asset = self.state.asset.get();
+        let failed = || VaultError::AssetTransferFailed(AssetTransferFailed { asset, amount });
+        let selector = function_selector!("transferFrom", Address, Address, U256);
+        let data =
+            [&selector[..], &msg::sender().into_word()[..], &contract::address().into_word()[..], &amount.to_be_bytes::<32>()].concat();
+        let returned = RawCall::new().call(asset, &data).map_err(|_| failed())?;
+        // Tokens that return nothing are accepted, as long as there is a contract
+        // to have done the transfer; anything else has to be an ABI `true`.
+        let ok = match returned.len() {
+            0 => asset.has_code(),
+            32 => U256::from_be_slice(&returned) == U256::from(1),
+            _ => false,
+        };
+        if !ok {
+            return Err(failed());
+        }
+        self.erc20.mint(msg::sender(), shares);
+        Ok(shares)
+    }
 
+    // Burns enough shares to cover `amount`, rounding against the withdrawer.
+    pub fn withdraw(&mut self, amount: U256) -> Result<U256, VaultError> {
+        self.withdrawWithMaxLoss(amount, U256::ZERO)
+    }
 
+    // When idle assets fall short, strategies are drained in withdrawal-queue order.
+    // Losses they realize on the way are paid by this withdrawer, up to `maxLoss` bps.
+    pub fn withdrawWithMaxLoss(&mut self, amount: U256, maxLoss: U256) -> Result<U256, VaultError> {
+        let shares = self.to_shares(amount, true)?;
+        let mut value = amount;
+        let mut total_loss = U256::ZERO;
+
+        let mut idle = self.idle()?;
+        let mut i = 0;
+        while idle < value && i < self.state.withdrawalQueue.len() {
+            let strategy = self.state.withdrawalQueue.get(i).unwrap();
+            i += 1;
+            let debt = self.state.strategies.get(strategy).totalDebt.get();
+            let needed = (value - idle).min(debt);
+            if needed == U256::ZERO {
+                continue;
+            }
+            let (received, loss) = self.withdraw_from_strategy(strategy, needed)?;
+            idle += received;
+            value -= loss.min(value);
+            total_loss += loss;
+        }
+
+        let max_loss = mul_div(amount, maxLoss, U256::from(MAX_BPS), false)?;
+        if total_loss > max_loss {
+            return Err(VaultError::ExcessiveLoss(ExcessiveLoss { loss: total_loss, max_loss }));
+        }
 
+        self.erc20.burn(msg::sender(), shares)?;
+        // the shares were priced for the full amount, so paying less would shortchange the withdrawer
+        if idle < value {
+            return Err(VaultError::WithdrawalShortfall(WithdrawalShortfall { available: idle, requested: value }));
+        }
+        self.transfer_asset(msg::sender(), value)?;
+        Ok(shares)
+    }
 
+    pub fn asset(&self) -> Result<Address, VaultError> {
+        Ok(self.state.asset.get())
+    }
 
+    pub fn decimalsOffset(&self) -> Result<u8, VaultError> {
+        Ok(self.state.decimalsOffset.get().saturating_to::<u8>())
+    }
 
+    pub fn strategiesFacet(&self) -> Result<Address, VaultError> {
+        Ok(self.state.strategiesFacet.get())
+    }
 
+    pub fn totalAssets(&self) -> Result<U256, VaultError> {
+        self.total_assets()
+    }
 
+    pub fn convertToShares(&self, assets: U256) -> Result<U256, VaultError> {
+        self.to_shares(assets, false)
+    }
 
+    pub fn convertToAssets(&self, shares: U256) -> Result<U256, VaultError> {
+        self.to_assets(shares, false)
+    }
 
+    pub fn previewWithdraw(&self, assets: U256) -> Result<U256, VaultError> {
+        self.to_shares(assets, true)
+    }
 
+    pub fn totalDebt(&self) -> Result<U256, VaultError> {
+        Ok(self.state.totalDebt.get())
+    }
+}
 
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use test_vm::{
+        calls, contract, deploy, erc20_balance, mint_erc20, mock_call, mock_erc20, transfer_erc20, CallKind, UnwrapOk, BOB, SENDER,
+    };
+
+    const ASSET: Address = Address::repeat_byte(0xa5);
+    const STRATEGY: Address = Address::repeat_byte(0x57);
+    const STRATEGIES: Address = Address::repeat_byte(0x5f);
+
+    fn vault(offset: u8) -> Vault {
+        let mut vault = deploy::<Vault>();
+        mock_erc20(ASSET);
+        mint_erc20(ASSET, SENDER, U256::from(1_000_000));
+        vault.init(ASSET, offset, STRATEGIES, 0).unwrap_ok();
+        vault
+    }
 
+    #[test]
+    fn deposit_and_withdraw_round_trip() {
+        let mut vault = vault(3);
+        let shares = vault.deposit(U256::from(1_000)).unwrap_ok();
+        assert_eq!(shares, U256::from(1_000_000));
+        assert_eq!(vault.totalAssets().unwrap_ok(), U256::from(1_000));
+        assert_eq!(erc20_balance(ASSET, contract()), U256::from(1_000));
+
+        let burned = vault.withdraw(U256::from(400)).unwrap_ok();
+        assert_eq!(burned, U256::from(400_000));
+        assert_eq!(vault.erc20.balance_of(SENDER).unwrap_ok(), U256::from(600_000));
+        assert_eq!(erc20_balance(ASSET, SENDER), U256::from(999_400));
+    }
 
+    #[test]
+    fn withdraw_rounds_against_the_withdrawer() {
+        let mut vault = vault(0);
+        vault.deposit(U256::from(10)).unwrap_ok();
+        mint_erc20(ASSET, contract(), U256::from(5));
+        // 10 shares now back 15 assets; 1 asset costs ceil(1 * 11 / 16) shares
+        assert_eq!(vault.previewWithdraw(U256::from(1)).unwrap_ok(), U256::from(1));
+        assert_eq!(vault.convertToShares(U256::from(1)).unwrap_ok(), U256::ZERO);
+    }
 
+    #[test]
+    fn rejects_bad_init_and_zero_shares() {
+        let mut vault = deploy::<Vault>();
+        assert!(matches!(vault.init(ASSET, 19, STRATEGIES, 0), Err(VaultError::InvalidDecimalsOffset(_))));
+        let mut vault = self::vault(0);
+        assert!(matches!(vault.deposit(U256::ZERO), Err(VaultError::ZeroShares(_))));
+        assert!(matches!(vault.withdraw(U256::from(1)), Err(VaultError::InsufficientShares(_))));
+        assert_eq!(vault.setAsset(BOB).unwrap_ok(), BOB);
+    }
 
+    #[test]
+    fn share_transfer_from_reports_allowance() {
+        let mut vault = vault(0);
+        vault.deposit(U256::from(10)).unwrap_ok();
+        vault.erc20.approve(SENDER, U256::from(3)).unwrap_ok();
+        assert!(matches!(
+            vault.erc20.transfer_from(SENDER, BOB, U256::from(4)).map_err(VaultError::from),
+            Err(VaultError::InsufficientAllowance(_))
+        ));
+    }
 
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-#![cfg_attr(not(feature = "export-abi"), no_main, no_std)]
-extern crate alloc;
-
-#[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
-
-mod erc20;
-use crate::erc20::{Erc20, Erc20Params};
-use alloc::vec::Vec;
-use stylus_sdk::{alloy_primitives::{U256, Address}, call, call::RawCall, msg, contract, function_selector, prelude::*};
-
-struct VaultParams;
-
-impl Erc20Params for VaultParams {
-    const NAME: &'static str = "Vault Example";
-    const SYMBOL: &'static str = "VAULT";
-    const DECIMALS: u8 = 18;
-}
-
-sol_storage! {
-    #[entrypoint]
-    struct Vault {
-        address asset;
-        uint totalSupply;
-        #[borrow]
-        Erc20<VaultParams> erc20;
+    // A strategy holding half the vault's assets, as a harvest at a 50% debt
+    // ratio leaves it, that runs `on_withdraw` for withdrawals and reports no loss.
+    fn vault_with_strategy(on_withdraw: impl Fn() + 'static) -> Vault {
+        let mut vault = vault(0);
+        mock_call(STRATEGY, move |call| {
+            assert_eq!(call.data[..4], function_selector!("withdraw", U256));
+            on_withdraw();
+            Ok(vec![0; 32])
+        });
+        vault.deposit(U256::from(1_000)).unwrap_ok();
+        transfer_erc20(ASSET, contract(), STRATEGY, U256::from(500));
+        let state = &mut vault.state;
+        state.strategies.setter(STRATEGY).active.set(true);
+        state.withdrawalQueue.push(STRATEGY);
+        state.change_debt(STRATEGY, U256::from(500), U256::ZERO);
+        assert_eq!(vault.totalAssets().unwrap_ok(), U256::from(1_000));
+        vault
     }
-}
 
-#[external]
-#[inherit(Erc20<VaultParams>)] 
-impl Vault {
-    pub fn setAsset(&mut self, _asset: Address) -> Result<Address, Vec<u8>> {
-        self.asset.set(_asset);
-        Ok(_asset)
+    #[test]
+    fn strategy_selectors_run_on_the_manager() {
+        let vault = vault(0);
+        assert_eq!(vault.strategiesFacet().unwrap_ok(), STRATEGIES);
+        mock_call(STRATEGIES, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            Ok(U256::from(7).to_be_bytes::<32>().to_vec())
+        });
+        let data = IVaultStrategies::harvestCall { strategy: STRATEGY }.abi_encode();
+        assert_eq!(dispatch(data.clone()).unwrap_ok(), U256::from(7).to_be_bytes::<32>().to_vec());
+        assert_eq!(calls().last().unwrap().data, data);
+
+        // manager reverts come back as they are
+        mock_call(STRATEGIES, |_| Err(b"manager says no".to_vec()));
+        let data = IVaultStrategies::setPerformanceFeeCall { fee: U256::from(1), recipient: BOB }.abi_encode();
+        assert_eq!(dispatch(data).err().unwrap(), b"manager says no".to_vec());
+        // unknown selectors don't leave the vault
+        let calls_before = calls().len();
+        assert_eq!(dispatch(vec![1, 2, 3, 4]).err().unwrap(), Vec::<u8>::new());
+        assert_eq!(calls().len(), calls_before);
     }
-    
-    #[payable]
-    pub fn deposit(&mut self, amount: U256) -> Result<(), Vec<u8>> {
-        let selector = function_selector!("transferFrom(address,address,uint256)");
-        let data = [
-            &selector[..],
-            &msg::sender().into_array(),
-            &self.asset.get().into_array(),
-            &amount.to_be_bytes::<32>(),
-        ].concat();
-        RawCall::new().call(self.asset.get(), &data);
-        let supply = self.totalSupply.get();
-        let shares = if supply == U256::ZERO { amount }
-        else {     
-            amount.checked_mul(supply).ok_or("Overflow")?.checked_div(self.totalAssets()?).ok_or("Divide by zero")?
-        };
-        self.erc20.mint(msg::sender(), shares);
-        Ok(())
+
+    #[test]
+    fn withdraw_reverts_when_strategies_fall_short() {
+        // the strategy reports no loss but sends back only half of what it's asked for
+        let mut vault = vault_with_strategy(|| transfer_erc20(ASSET, STRATEGY, contract(), U256::from(150)));
+        assert!(matches!(
+            vault.withdraw(U256::from(800)),
+            Err(VaultError::WithdrawalShortfall(WithdrawalShortfall { available, requested }))
+                if available == U256::from(650) && requested == U256::from(800)
+        ));
+
+        let mut vault = vault_with_strategy(|| transfer_erc20(ASSET, STRATEGY, contract(), U256::from(300)));
+        vault.withdraw(U256::from(800)).unwrap_ok();
+        assert_eq!(erc20_balance(ASSET, SENDER), U256::from(999_800));
     }
-    pub fn withdraw(&mut self, amount: U256) -> Result<(), Vec<u8>> {
-        let supply = self.totalSupply.get();
-        let shares = if supply == U256::ZERO {amount}
-        else { 
-            amount.checked_mul(supply).ok_or("Overflow")?.checked_div(self.totalAssets()?).ok_or("Divide by zero")?
-        };
-        
-        self.erc20.burn(msg::sender(), shares)?;
-        call::transfer_eth(msg::sender(), amount)
+
+    #[test]
+    fn withdraw_rejects_a_strategy_that_drains_the_vault() {
+        let mut vault = vault_with_strategy(|| transfer_erc20(ASSET, contract(), STRATEGY, U256::from(100)));
+        assert!(matches!(vault.withdraw(U256::from(800)), Err(VaultError::StrategyCallFailed(_))));
     }
-    
-    pub fn asset(&self) -> Result<Address, Vec<u8>> {
-        Ok(self.asset.get())	
+
+    #[test]
+    fn deposit_checks_the_transfer_result() {
+        const FALSE_TOKEN: Address = Address::repeat_byte(0xfa);
+        let mut vault = deploy::<Vault>();
+        mock_call(FALSE_TOKEN, |_| Ok(vec![0; 32]));
+        vault.init(FALSE_TOKEN, 0, STRATEGIES, 0).unwrap_ok();
+        assert!(matches!(vault.deposit(U256::from(10)), Err(VaultError::AssetTransferFailed(_))));
+
+        // a token that returns nothing still counts as a transfer
+        let mut vault = deploy::<Vault>();
+        mock_call(FALSE_TOKEN, |call| Ok(if call.kind == CallKind::Static { vec![0; 32] } else { Vec::new() }));
+        vault.init(FALSE_TOKEN, 0, STRATEGIES, 0).unwrap_ok();
+        assert_eq!(vault.deposit(U256::from(10)).unwrap_ok(), U256::from(10));
     }
-    
-    pub fn totalAssets(&self) -> Result<U256, Vec<u8>> {
-        Ok(self.totalSupply.get())
+
+    // An attacker deposits `a`, donates `d` straight to the vault and waits for a
+    // victim's deposit of `v`. Whatever the numbers, the attacker can't redeem
+    // more than they put in, and the victim's rounding loss shrinks by 10^offset.
+    #[test]
+    fn donation_attack_does_not_pay() {
+        let amounts = [0_u128, 1, 999, 1_000_000, 10_u128.pow(18), 10_u128.pow(24)];
+        for offset in [0_u8, 3, 6] {
+            for a in [1_u128, 10, 1_000] {
+                for d in amounts {
+                    for v in amounts.into_iter().filter(|v| *v > 0) {
+                        let mut vault = vault(offset);
+                        mint_erc20(ASSET, SENDER, U256::from(a + v));
+                        let attacker_shares = vault.deposit(U256::from(a)).unwrap_ok();
+                        vault.erc20.transfer(BOB, attacker_shares).unwrap_ok();
+                        mint_erc20(ASSET, contract(), U256::from(d));
+
+                        let victim_assets = match vault.deposit(U256::from(v)) {
+                            Ok(shares) => vault.convertToAssets(shares).unwrap_ok(),
+                            // the victim's transaction reverts and they keep their assets
+                            Err(VaultError::ZeroShares(_)) => U256::from(v),
+                            Err(_) => panic!("deposit failed"),
+                        };
+                        let attacker_assets = vault.convertToAssets(attacker_shares).unwrap_ok();
+                        assert!(attacker_assets <= U256::from(a + d), "attacker profits: {offset} {a} {d} {v}");
+
+                        let share_price = U256::from(a + d + 1) / (U256::from(a + 1) * vault.state.virtual_shares());
+                        let victim_loss = U256::from(v) - victim_assets;
+                        assert!(victim_loss <= share_price + U256::from(2), "victim loses: {offset} {a} {d} {v}");
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,293 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+// Methods keep the camelCase names the vault's callers already use.
+#![allow(non_snake_case)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod erc20;
+mod ownable;
+mod vault_state;
+use crate::erc20::Erc20;
+use crate::vault_state::*;
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    block,
+    call::Call,
+    evm,
+    prelude::*,
+};
+
+sol! {
+    event StrategyAdded(address indexed strategy, uint256 debt_ratio);
+    event StrategyUpdated(address indexed strategy, uint256 debt_ratio);
+    event StrategyReported(address indexed strategy, uint256 gain, uint256 loss, uint256 total_debt, uint256 fee_shares);
+}
+
+const MAX_PERFORMANCE_FEE: u64 = 5_000;
+const MAX_STRATEGIES: usize = 20;
+
+// Strategy management for the vault in 8_smart_vault.rs: adding strategies,
+// setting their debt ratios and withdrawal order, and harvesting them. The
+// vault delegate_calls this facet for the `IVaultStrategies` selectors, so it
+// runs on the vault's storage and mints the performance fee in the vault's own
+// shares, which follow `VaultState` (see vault_state.rs for the layout).
+sol_storage! {
+    #[entrypoint]
+    pub struct VaultStrategies {
+        #[borrow]
+        VaultState state;
+        Erc20<VaultParams> erc20;
+    }
+}
+
+impl VaultStrategies {
+    fn deposit_to_strategy(&mut self, strategy: Address, amount: U256) -> Result<(), VaultError> {
+        self.transfer_asset(strategy, amount)?;
+        IStrategy::new(strategy).deposit(Call::new_in(self), amount).map_err(|_| VaultState::strategy_failed(strategy))?;
+        self.state.change_debt(strategy, amount, U256::ZERO);
+        Ok(())
+    }
+}
+
+#[external]
+impl VaultStrategies {
+    pub fn addStrategy(&mut self, strategy: Address, debtRatio: U256) -> Result<(), VaultError> {
+        self.state.ownable.only_owner()?;
+        let asset = IStrategy::new(strategy).asset(&*self).map_err(|_| VaultState::strategy_failed(strategy))?;
+        let state = &mut self.state;
+        if strategy == Address::ZERO || asset != state.asset.get() || state.strategies.get(strategy).active.get() {
+            return Err(VaultError::InvalidStrategy(InvalidStrategy { strategy }));
+        }
+        if state.withdrawalQueue.len() >= MAX_STRATEGIES {
+            return Err(VaultError::WithdrawalQueueFull(WithdrawalQueueFull {}));
+        }
+        let total_debt_ratio = state.totalDebtRatio.get() + debtRatio;
+        if total_debt_ratio > U256::from(MAX_BPS) {
+            return Err(VaultError::DebtRatioTooHigh(DebtRatioTooHigh { total_debt_ratio }));
+        }
+
+        state.totalDebtRatio.set(total_debt_ratio);
+        let mut params = state.strategies.setter(strategy);
+        params.active.set(true);
+        params.debtRatio.set(debtRatio);
+        params.lastReport.set(U64::from(block::timestamp()));
+        state.withdrawalQueue.push(strategy);
+        evm::log(StrategyAdded { strategy, debt_ratio: debtRatio });
+        Ok(())
+    }
+
+    // A ratio of zero makes the next harvest pull everything back (revoking the strategy).
+    pub fn updateStrategyDebtRatio(&mut self, strategy: Address, debtRatio: U256) -> Result<(), VaultError> {
+        let state = &mut self.state;
+        state.ownable.only_owner()?;
+        state.check_strategy(strategy)?;
+        let old_ratio = state.strategies.get(strategy).debtRatio.get();
+        let total_debt_ratio = state.totalDebtRatio.get() - old_ratio + debtRatio;
+        if total_debt_ratio > U256::from(MAX_BPS) {
+            return Err(VaultError::DebtRatioTooHigh(DebtRatioTooHigh { total_debt_ratio }));
+        }
+        state.totalDebtRatio.set(total_debt_ratio);
+        state.strategies.setter(strategy).debtRatio.set(debtRatio);
+        evm::log(StrategyUpdated { strategy, debt_ratio: debtRatio });
+        Ok(())
+    }
+
+    pub fn setWithdrawalQueue(&mut self, queue: Vec<Address>) -> Result<(), VaultError> {
+        let state = &mut self.state;
+        state.ownable.only_owner()?;
+        if queue.len() > MAX_STRATEGIES {
+            return Err(VaultError::WithdrawalQueueFull(WithdrawalQueueFull {}));
+        }
+        for strategy in queue.iter() {
+            state.check_strategy(*strategy)?;
+        }
+        while !state.withdrawalQueue.is_empty() {
+            state.withdrawalQueue.pop();
+        }
+        for strategy in queue {
+            state.withdrawalQueue.push(strategy);
+        }
+        Ok(())
+    }
+
+    pub fn setPerformanceFee(&mut self, fee: U256, recipient: Address) -> Result<(), VaultError> {
+        let state = &mut self.state;
+        state.ownable.only_owner()?;
+        if fee > U256::from(MAX_PERFORMANCE_FEE) {
+            return Err(VaultError::FeeTooHigh(FeeTooHigh { fee }));
+        }
+        state.performanceFee.set(fee);
+        state.feeRecipient.set(recipient);
+        Ok(())
+    }
+
+    // Marks the strategy to market, charges the performance fee on any gain by
+    // minting shares, then moves its debt toward `debtRatio` of total assets.
+    pub fn harvest(&mut self, strategy: Address) -> Result<(U256, U256), VaultError> {
+        self.state.ownable.only_owner()?;
+        self.state.check_strategy(strategy)?;
+        IStrategy::new(strategy).harvest(Call::new_in(self)).map_err(|_| VaultState::strategy_failed(strategy))?;
+        let assets = IStrategy::new(strategy).total_assets(&*self).map_err(|_| VaultState::strategy_failed(strategy))?;
+
+        let debt = self.state.strategies.get(strategy).totalDebt.get();
+        let (gain, loss) = if assets >= debt { (assets - debt, U256::ZERO) } else { (U256::ZERO, debt - assets) };
+        self.state.realize_loss(strategy, loss);
+
+        let mut fee_shares = U256::ZERO;
+        if gain > U256::ZERO {
+            self.state.change_debt(strategy, gain, U256::ZERO);
+            let mut params = self.state.strategies.setter(strategy);
+            let total_gain = params.totalGain.get() + gain;
+            params.totalGain.set(total_gain);
+
+            // priced so the new shares are worth exactly `fee` after they're minted
+            let fee = mul_div(gain, self.state.performanceFee.get(), U256::from(MAX_BPS), false)?;
+            let recipient = self.state.feeRecipient.get();
+            if fee > U256::ZERO && recipient != Address::ZERO {
+                let supply = self.erc20.total_supply.get() + self.state.virtual_shares();
+                let total_assets = self.total_assets()? + U256::from(1);
+                fee_shares = mul_div(fee, supply, total_assets - fee, false)?;
+                self.erc20.mint(recipient, fee_shares);
+            }
+        }
+
+        let target = mul_div(self.total_assets()?, self.state.strategies.get(strategy).debtRatio.get(), U256::from(MAX_BPS), false)?;
+        let debt = self.state.strategies.get(strategy).totalDebt.get();
+        if debt < target {
+            let amount = (target - debt).min(self.idle()?);
+            if amount > U256::ZERO {
+                self.deposit_to_strategy(strategy, amount)?;
+            }
+        } else if debt > target {
+            self.withdraw_from_strategy(strategy, debt - target)?;
+        }
+
+        self.state.strategies.setter(strategy).lastReport.set(U64::from(block::timestamp()));
+        let total_debt = self.state.strategies.get(strategy).totalDebt.get();
+        evm::log(StrategyReported { strategy, gain, loss, total_debt, fee_shares });
+        Ok((gain, loss))
+    }
+
+    pub fn strategy(&self, strategy: Address) -> Result<(bool, U256, U256, U256, U256, u64), VaultError> {
+        let params = self.state.strategies.get(strategy);
+        Ok((
+            params.active.get(),
+            params.debtRatio.get(),
+            params.totalDebt.get(),
+            params.totalGain.get(),
+            params.totalLoss.get(),
+            params.lastReport.get().saturating_to::<u64>(),
+        ))
+    }
+
+    pub fn withdrawalQueue(&self) -> Result<Vec<Address>, VaultError> {
+        let mut queue = Vec::new();
+        for i in 0..self.state.withdrawalQueue.len() {
+            queue.push(self.state.withdrawalQueue.get(i).unwrap());
+        }
+        Ok(queue)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use stylus_sdk::{abi::Router, function_selector};
+    use test_vm::{contract, deploy, erc20_balance, events, mint_erc20, mock_call, mock_erc20, transfer_erc20, UnwrapOk, BOB};
+
+    const ASSET: Address = Address::repeat_byte(0xa5);
+    const STRATEGY: Address = Address::repeat_byte(0x57);
+
+    // The vault as its init and a first deposit of 1_000 leave it, with a
+    // strategy that hands back whatever it's asked for.
+    fn vault() -> VaultStrategies {
+        let mut vault = deploy::<VaultStrategies>();
+        mock_erc20(ASSET);
+        mint_erc20(ASSET, contract(), U256::from(1_000));
+        vault.erc20.mint(BOB, U256::from(1_000));
+        vault.state.ownable.initialize(0).unwrap_ok();
+        vault.state.asset.set(ASSET);
+        mock_call(STRATEGY, move |call| {
+            let selector: [u8; 4] = call.data[..4].try_into().unwrap();
+            Ok(match selector {
+                s if s == function_selector!("asset") => ASSET.into_word().to_vec(),
+                s if s == function_selector!("totalAssets") => erc20_balance(ASSET, STRATEGY).to_be_bytes::<32>().to_vec(),
+                s if s == function_selector!("withdraw", U256) => {
+                    let amount = U256::from_be_slice(&call.data[4..36]);
+                    transfer_erc20(ASSET, STRATEGY, contract(), amount);
+                    vec![0; 32]
+                }
+                _ => Vec::new(),
+            })
+        });
+        vault
+    }
+
+    #[test]
+    fn serves_every_selector_the_vault_forwards() {
+        let mut vault = vault();
+        for selector in IVaultStrategies::IVaultStrategiesCalls::SELECTORS {
+            let routed = <VaultStrategies as Router<VaultStrategies>>::route(&mut vault, u32::from_be_bytes(*selector), &[]);
+            assert!(routed.is_some(), "{selector:?}");
+        }
+    }
+
+    #[test]
+    fn harvest_lends_toward_the_debt_ratio() {
+        let mut vault = vault();
+        vault.addStrategy(STRATEGY, U256::from(5_000)).unwrap_ok();
+        assert_eq!(vault.withdrawalQueue().unwrap_ok(), vec![STRATEGY]);
+        assert_eq!(vault.harvest(STRATEGY).unwrap_ok(), (U256::ZERO, U256::ZERO));
+        assert_eq!(erc20_balance(ASSET, STRATEGY), U256::from(500));
+        assert_eq!(vault.state.totalDebt.get(), U256::from(500));
+
+        // revoking pulls everything back on the next harvest
+        vault.updateStrategyDebtRatio(STRATEGY, U256::ZERO).unwrap_ok();
+        vault.harvest(STRATEGY).unwrap_ok();
+        assert_eq!(erc20_balance(ASSET, contract()), U256::from(1_000));
+        let (active, debt_ratio, total_debt, ..) = vault.strategy(STRATEGY).unwrap_ok();
+        assert_eq!((active, debt_ratio, total_debt), (true, U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn harvest_charges_the_performance_fee_in_shares() {
+        let mut vault = vault();
+        vault.setPerformanceFee(U256::from(1_000), BOB).unwrap_ok();
+        vault.addStrategy(STRATEGY, U256::from(5_000)).unwrap_ok();
+        vault.harvest(STRATEGY).unwrap_ok();
+        // the first harvest lends 500, the next finds 100 of gain and charges 10
+        mint_erc20(ASSET, STRATEGY, U256::from(100));
+        let (gain, loss) = vault.harvest(STRATEGY).unwrap_ok();
+        assert_eq!((gain, loss), (U256::from(100), U256::ZERO));
+        let reported = events::<StrategyReported>().pop().unwrap();
+        // 10 assets' worth of shares at 1_001 shares per 1_101 assets
+        assert_eq!(reported.fee_shares, U256::from(9));
+        assert_eq!(vault.erc20.total_supply.get(), U256::from(1_009));
+    }
+
+    #[test]
+    fn rejects_bad_strategies_and_queues() {
+        let mut vault = vault();
+        assert!(matches!(vault.addStrategy(Address::ZERO, U256::ZERO), Err(VaultError::InvalidStrategy(_))));
+        vault.addStrategy(STRATEGY, U256::from(5_000)).unwrap_ok();
+        assert!(matches!(vault.addStrategy(STRATEGY, U256::ZERO), Err(VaultError::InvalidStrategy(_))));
+        assert!(matches!(vault.updateStrategyDebtRatio(STRATEGY, U256::from(10_001)), Err(VaultError::DebtRatioTooHigh(_))));
+        assert!(matches!(vault.setWithdrawalQueue(vec![BOB]), Err(VaultError::InvalidStrategy(_))));
+        assert!(matches!(vault.setWithdrawalQueue(vec![STRATEGY; 21]), Err(VaultError::WithdrawalQueueFull(_))));
+        vault.setWithdrawalQueue(Vec::new()).unwrap_ok();
+        assert_eq!(vault.withdrawalQueue().unwrap_ok(), Vec::<Address>::new());
+        assert!(matches!(vault.setPerformanceFee(U256::from(5_001), BOB), Err(VaultError::FeeTooHigh(_))));
+    }
+}
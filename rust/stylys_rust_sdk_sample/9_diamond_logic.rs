@@ -0,0 +1,359 @@
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main, no_std)]
+extern crate alloc;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOC: mini_alloc::MiniAlloc = mini_alloc::MiniAlloc::INIT;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+mod deployer;
+mod namespaced;
+use crate::deployer::is_deployer;
+use crate::namespaced::{Namespace, Namespaced};
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    call::{self, delegate_call},
+    evm, function_selector, msg,
+    prelude::*,
+};
+
+sol! {
+    event FacetAdded(address indexed facet, bytes4[] selectors);
+    event FacetRemoved(address indexed facet, bytes4[] selectors);
+
+    error NotOwner(address caller, address owner);
+    error NotDeployer(address caller);
+    error AlreadyInitialized();
+    error ZeroFacet();
+    error NoSelectors();
+    error SelectorExists(bytes4 selector, address facet);
+    error FunctionNotFound(bytes4 selector);
+    error DelegateCallFailed(address facet, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum DiamondError {
+    NotOwner(NotOwner),
+    NotDeployer(NotDeployer),
+    AlreadyInitialized(AlreadyInitialized),
+    ZeroFacet(ZeroFacet),
+    NoSelectors(NoSelectors),
+    SelectorExists(SelectorExists),
+    FunctionNotFound(FunctionNotFound),
+    DelegateCallFailed(DelegateCallFailed),
+}
+
+// Logic/data split contract (EIP-2535 style).
+// The storage lives here, the logic lives in facets which are reached with delegate_call,
+// so new features don't count against this contract's code size.
+//
+// Facets run on the diamond's storage, so the layout rule is: nothing lives at
+// slot 0 by default. The diamond's own bookkeeping is namespaced under
+// "stylus.diamond.storage" (see namespaced.rs), out of the way of any facet.
+// A facet may declare plain sol_storage! fields from slot 0 if it is the only
+// one that does; every other facet keeps its state in a `Namespaced` struct
+// under its own name.
+sol_storage! {
+    #[entrypoint]
+    pub struct Diamond {
+        Namespaced<DiamondStorage> state;
+    }
+
+    pub struct DiamondStorage {
+        address owner;
+        // selector => facet that implements it
+        mapping(bytes4 => address) facets;
+        // selector => index + 1 in facet_selectors[facet] (0 = not registered)
+        mapping(bytes4 => uint256) selector_positions;
+        mapping(address => bytes4[]) facet_selectors;
+        address[] facet_list;
+    }
+}
+
+impl Namespace for DiamondStorage {
+    const SLOT: U256 = namespaced::slot("stylus.diamond.storage");
+}
+
+impl Diamond {
+    pub fn only_owner(&self) -> Result<(), DiamondError> {
+        if self.state.owner.get() != msg::sender() {
+            return Err(DiamondError::NotOwner(NotOwner { caller: msg::sender(), owner: self.state.owner.get() }));
+        }
+        Ok(())
+    }
+
+    fn delegate(&mut self, data: &[u8]) -> Result<Vec<u8>, DiamondError> {
+        if data.len() < 4 {
+            return Err(DiamondError::FunctionNotFound(FunctionNotFound { selector: FixedBytes::ZERO }));
+        }
+        let selector = FixedBytes::<4>::from_slice(&data[..4]);
+        let facet = self.facet_address(selector)?;
+        let res = unsafe { delegate_call(self, facet, data) };
+
+        match res {
+            Ok(res) => Ok(res),
+            Err(e) => Err(DiamondError::DelegateCallFailed(DelegateCallFailed {
+                facet,
+                reason: match e {
+                    call::Error::Revert(reason) => reason.into(),
+                    _ => Vec::new().into(),
+                },
+            })),
+        }
+    }
+
+    fn remove_facet_from_list(&mut self, facet: Address) {
+        let len = self.state.facet_list.len();
+        for i in 0..len {
+            if self.state.facet_list.get(i) == Some(facet) {
+                let last = self.state.facet_list.get(len - 1).unwrap();
+                self.state.facet_list.setter(i).unwrap().set(last);
+                self.state.facet_list.pop();
+                return;
+            }
+        }
+    }
+}
+
+#[external]
+impl Diamond {
+    // Only the account that deployed the diamond, with `deploy_nonce`, can claim it.
+    pub fn init(&mut self, deploy_nonce: u64) -> Result<(), DiamondError> {
+        if self.state.owner.get() != Address::ZERO {
+            return Err(DiamondError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if !is_deployer(msg::sender(), deploy_nonce) {
+            return Err(DiamondError::NotDeployer(NotDeployer { caller: msg::sender() }));
+        }
+        self.state.owner.set(msg::sender());
+        Ok(())
+    }
+
+    pub fn owner(&self) -> Result<Address, DiamondError> {
+        Ok(self.state.owner.get())
+    }
+
+    // Registers `selectors` to be served by `facet`.
+    pub fn add_facet(&mut self, facet: Address, selectors: Vec<FixedBytes<4>>) -> Result<(), DiamondError> {
+        self.only_owner()?;
+        if facet == Address::ZERO {
+            return Err(DiamondError::ZeroFacet(ZeroFacet {}));
+        }
+        if selectors.is_empty() {
+            return Err(DiamondError::NoSelectors(NoSelectors {}));
+        }
+        if self.state.facet_selectors.get(facet).is_empty() {
+            self.state.facet_list.push(facet);
+        }
+        for selector in selectors.iter() {
+            let existing = self.state.facets.get(*selector);
+            if existing != Address::ZERO {
+                return Err(DiamondError::SelectorExists(SelectorExists { selector: *selector, facet: existing }));
+            }
+            self.state.facets.insert(*selector, facet);
+            let mut list = self.state.facet_selectors.setter(facet);
+            list.push(*selector);
+            let position = U256::from(list.len());
+            self.state.selector_positions.insert(*selector, position);
+        }
+        evm::log(FacetAdded { facet, selectors });
+        Ok(())
+    }
+
+    // Unregisters `selectors`; a facet with no selectors left drops out of `facets()`.
+    // Emits one FacetRemoved per facet the selectors belonged to.
+    pub fn remove_selectors(&mut self, selectors: Vec<FixedBytes<4>>) -> Result<(), DiamondError> {
+        self.only_owner()?;
+        let mut removed: Vec<(Address, Vec<FixedBytes<4>>)> = Vec::new();
+        for selector in selectors.iter() {
+            let facet = self.state.facets.get(*selector);
+            if facet == Address::ZERO {
+                return Err(DiamondError::FunctionNotFound(FunctionNotFound { selector: *selector }));
+            }
+            // swap-and-pop the selector out of the facet's list
+            let index = self.state.selector_positions.get(*selector).saturating_to::<usize>() - 1;
+            let mut list = self.state.facet_selectors.setter(facet);
+            let last_index = list.len() - 1;
+            if index != last_index {
+                let last = list.get(last_index).unwrap();
+                list.setter(index).unwrap().set(last);
+                self.state.selector_positions.insert(last, U256::from(index + 1));
+            }
+            let mut list = self.state.facet_selectors.setter(facet);
+            list.pop();
+            let empty = list.is_empty();
+
+            self.state.facets.delete(*selector);
+            self.state.selector_positions.delete(*selector);
+            if empty {
+                self.remove_facet_from_list(facet);
+            }
+            match removed.iter_mut().find(|(f, _)| *f == facet) {
+                Some((_, list)) => list.push(*selector),
+                None => removed.push((facet, alloc::vec![*selector])),
+            }
+        }
+        for (facet, selectors) in removed {
+            evm::log(FacetRemoved { facet, selectors });
+        }
+        Ok(())
+    }
+
+    // Calls `approve(spender, value)` on whichever facet implements it.
+    pub fn invoke_logic_contract(&mut self, spender: Address, value: U256) -> Result<bool, DiamondError> {
+        let selector = function_selector!("approve", Address, U256);
+        let data = [&selector[..], &spender.into_word()[..], &value.to_be_bytes::<32>()].concat();
+
+        let res = self.delegate(&data)?;
+        Ok(res.last() == Some(&1))
+    }
+
+    // Forwards raw calldata to the facet registered for its selector.
+    #[payable]
+    pub fn relay(&mut self, data: Vec<u8>) -> Result<Vec<u8>, DiamondError> {
+        self.delegate(&data)
+    }
+
+    // Loupe functions
+    pub fn facets(&self) -> Result<Vec<Address>, DiamondError> {
+        let mut facets = Vec::new();
+        for i in 0..self.state.facet_list.len() {
+            facets.push(self.state.facet_list.get(i).unwrap());
+        }
+        Ok(facets)
+    }
+
+    pub fn facet_function_selectors(&self, facet: Address) -> Result<Vec<FixedBytes<4>>, DiamondError> {
+        let list = self.state.facet_selectors.get(facet);
+        let mut selectors = Vec::new();
+        for i in 0..list.len() {
+            selectors.push(list.get(i).unwrap());
+        }
+        Ok(selectors)
+    }
+
+    pub fn facet_address(&self, selector: FixedBytes<4>) -> Result<Address, DiamondError> {
+        let facet = self.state.facets.get(selector);
+        if facet == Address::ZERO {
+            return Err(DiamondError::FunctionNotFound(FunctionNotFound { selector }));
+        }
+        Ok(facet)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::deployer::create_address;
+    use stylus_sdk::alloy_primitives::address;
+    use test_vm::{contract, deploy, events, mock_call, CallKind, UnwrapOk, BOB, CAROL, SENDER};
+
+    sol_storage! {
+        // Laid out from slot 0, like any sol_storage! struct.
+        pub struct CounterFacet {
+            address owner;
+            uint256 count;
+        }
+    }
+
+    fn selector(byte: u8) -> FixedBytes<4> {
+        FixedBytes::repeat_byte(byte)
+    }
+
+    fn diamond() -> Diamond {
+        let mut diamond = deploy::<Diamond>();
+        diamond.init(0).unwrap_ok();
+        diamond
+    }
+
+    #[test]
+    fn create_address_matches_rlp_encoding() {
+        assert_eq!(create_address(SENDER, 0), contract());
+        let deployer = address!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(create_address(deployer, 0), address!("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"));
+        assert_eq!(create_address(deployer, 1), address!("343c43a37d37dff08ae8c4a11544c718abb4fcf8"));
+        // nonces past 0x7f get a length prefix
+        assert_ne!(create_address(deployer, 0x7f), create_address(deployer, 0x80));
+    }
+
+    #[test]
+    fn only_the_deployer_can_init() {
+        let mut diamond = deploy::<Diamond>();
+        assert!(matches!(diamond.init(1), Err(DiamondError::NotDeployer(_))));
+        diamond.init(0).unwrap_ok();
+        assert_eq!(diamond.owner().unwrap_ok(), SENDER);
+        assert!(matches!(diamond.init(0), Err(DiamondError::AlreadyInitialized(_))));
+    }
+
+    #[test]
+    fn add_facet_rejects_empty_and_lists_facets_once() {
+        let mut diamond = diamond();
+        assert!(matches!(diamond.add_facet(BOB, Vec::new()), Err(DiamondError::NoSelectors(_))));
+        diamond.add_facet(BOB, vec![selector(1)]).unwrap_ok();
+        diamond.add_facet(BOB, vec![selector(2)]).unwrap_ok();
+        assert_eq!(diamond.facets().unwrap_ok(), vec![BOB]);
+        assert_eq!(diamond.facet_function_selectors(BOB).unwrap_ok(), vec![selector(1), selector(2)]);
+        assert!(matches!(diamond.add_facet(CAROL, vec![selector(1)]), Err(DiamondError::SelectorExists(_))));
+    }
+
+    #[test]
+    fn remove_selectors_reports_every_facet() {
+        let mut diamond = diamond();
+        diamond.add_facet(BOB, vec![selector(1), selector(2)]).unwrap_ok();
+        diamond.add_facet(CAROL, vec![selector(3)]).unwrap_ok();
+        diamond.remove_selectors(vec![selector(1), selector(3)]).unwrap_ok();
+
+        let removed = events::<FacetRemoved>();
+        assert_eq!(removed.len(), 2);
+        assert_eq!((removed[0].facet, removed[0].selectors.clone()), (BOB, vec![selector(1)]));
+        assert_eq!((removed[1].facet, removed[1].selectors.clone()), (CAROL, vec![selector(3)]));
+        assert_eq!(diamond.facets().unwrap_ok(), vec![BOB]);
+        assert_eq!(diamond.facet_function_selectors(BOB).unwrap_ok(), vec![selector(2)]);
+        assert!(matches!(diamond.facet_address(selector(3)), Err(DiamondError::FunctionNotFound(_))));
+    }
+
+    #[test]
+    fn relays_to_the_registered_facet() {
+        let mut diamond = diamond();
+        let approve = FixedBytes::from(function_selector!("approve", Address, U256));
+        diamond.add_facet(BOB, vec![approve]).unwrap_ok();
+        mock_call(BOB, |call| {
+            assert_eq!(call.data[..4], function_selector!("approve", Address, U256));
+            Ok(U256::from(1).to_be_bytes::<32>().to_vec())
+        });
+        assert!(diamond.invoke_logic_contract(CAROL, U256::from(5)).unwrap_ok());
+        assert!(matches!(diamond.relay(vec![9, 9, 9, 9]), Err(DiamondError::FunctionNotFound(_))));
+    }
+
+    #[test]
+    fn facet_storage_leaves_the_diamond_alone() {
+        let mut diamond = diamond();
+        let bump = FixedBytes::from(function_selector!("bump"));
+        diamond.add_facet(BOB, vec![bump]).unwrap_ok();
+        // the facet's code runs on the diamond's storage
+        mock_call(BOB, |call| {
+            assert_eq!(call.kind, CallKind::Delegate);
+            let mut facet = unsafe { CounterFacet::new(U256::ZERO, 0) };
+            facet.owner.set(CAROL);
+            facet.count.set(facet.count.get() + U256::from(1));
+            Ok(Vec::new())
+        });
+        diamond.relay(bump.to_vec()).unwrap_ok();
+        diamond.relay(bump.to_vec()).unwrap_ok();
+
+        // fresh handles, so nothing is answered from a cached read
+        let diamond = unsafe { Diamond::new(U256::ZERO, 0) };
+        let facet = unsafe { CounterFacet::new(U256::ZERO, 0) };
+        assert_eq!((facet.owner.get(), facet.count.get()), (CAROL, U256::from(2)));
+        assert_eq!(diamond.owner().unwrap_ok(), SENDER);
+        assert_eq!(diamond.facet_address(bump).unwrap_ok(), BOB);
+        assert_eq!(diamond.facets().unwrap_ok(), vec![BOB]);
+    }
+}
@@ -0,0 +1,169 @@
+// Shared `main` for the contract crates' export-abi binaries.
+//
+// With no arguments it prints the Solidity interface exactly like the
+// `print_abi` that `#[entrypoint]` generates, so `cargo stylus export-abi`
+// keeps working. `--json` prints the JSON ABI instead, and `--out <dir>`
+// writes both to `<dir>/<Name>.sol` and `<dir>/<Name>.json`.
+//
+// Functions and errors come from the Solidity interface. Stylus leaves events
+// out of it, so they are read from the `event ...;` declarations in the
+// contract's sources; each binary passes those in with `include_str!`.
+#![cfg(feature = "export-abi")]
+
+use alloy_json_abi::{Error, Event, Function, JsonAbi, StateMutability};
+use std::{fmt, fs, marker::PhantomData, path::Path};
+use stylus_sdk::abi::export::GenerateAbi;
+
+pub const LICENSE: &str = "MIT-OR-APACHE-2.0";
+pub const PRAGMA: &str = "pragma solidity ^0.8.23;";
+
+struct Printer<T>(PhantomData<T>);
+
+impl<T: GenerateAbi> fmt::Display for Printer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt_abi(f)
+    }
+}
+
+/// The Solidity interface, as `cargo stylus export-abi` prints it.
+pub fn solidity<T: GenerateAbi>() -> String {
+    format!(
+        "/**\n \
+         * This file was automatically generated by Stylus and represents a Rust program.\n \
+         * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).\n \
+         */\n\n\
+         // SPDX-License-Identifier: {LICENSE}\n\
+         {PRAGMA}\n\n\
+         {}",
+        Printer::<T>(PhantomData)
+    )
+}
+
+// Whether `outputs` is one parenthesized list. A lone tuple array comes out as
+// `returns (address,uint256)[] memory`, which isn't.
+fn is_parenthesized(outputs: &str) -> bool {
+    let mut depth = 0;
+    for (i, c) in outputs.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => return false,
+            _ => {}
+        }
+        if depth == 0 {
+            return i == outputs.len() - 1;
+        }
+    }
+    false
+}
+
+// The signature parser takes `name(inputs) returns (outputs)`, so the
+// visibility and mutability keywords are split off first.
+fn parse_function(line: &str) -> Function {
+    let (head, returns) = line.split_once(" returns ").map_or((line, None), |(h, r)| (h, Some(r)));
+    let close = head.rfind(')').unwrap_or_else(|| panic!("{line}: no parameter list"));
+    let signature = match returns {
+        Some(returns) if !is_parenthesized(returns) => format!("{} returns ({returns})", &head[..=close]),
+        Some(returns) => format!("{} returns {returns}", &head[..=close]),
+        None => head[..=close].to_string(),
+    };
+    let mut function = Function::parse(&signature).unwrap_or_else(|e| panic!("{line}: {e}"));
+    function.state_mutability = match head[close + 1..].split_whitespace().last() {
+        Some("view") => StateMutability::View,
+        Some("pure") => StateMutability::Pure,
+        Some("payable") => StateMutability::Payable,
+        _ => StateMutability::NonPayable,
+    };
+    function
+}
+
+// `error` lines in the interface drop parameter names; a declaration in the
+// sources with the same signature puts them back.
+fn named_error(error: Error, declared: &[Error]) -> Error {
+    declared.iter().find(|d| d.signature() == error.signature()).cloned().unwrap_or(error)
+}
+
+fn declarations<'a>(sources: &'a [&str], keyword: &'a str) -> impl Iterator<Item = &'a str> {
+    sources
+        .iter()
+        .flat_map(|source| source.lines())
+        .map(str::trim)
+        .filter(move |line| line.starts_with(keyword) && line.ends_with(';'))
+        .map(|line| line.trim_end_matches(';'))
+}
+
+pub fn json<T: GenerateAbi>(sources: &[&str]) -> JsonAbi {
+    let declared: Vec<Error> =
+        declarations(sources, "error ").map(|line| Error::parse(line).unwrap_or_else(|e| panic!("{line}: {e}"))).collect();
+    let mut abi = JsonAbi::new();
+    let add_error = |abi: &mut JsonAbi, error: Error| {
+        let errors = abi.errors.entry(error.name.clone()).or_default();
+        if !errors.iter().any(|e| e.signature() == error.signature()) {
+            errors.push(error);
+        }
+    };
+
+    for line in solidity::<T>().lines().map(str::trim) {
+        let line = line.trim_end_matches(';');
+        if line.starts_with("function ") {
+            let function = parse_function(line);
+            let functions = abi.functions.entry(function.name.clone()).or_default();
+            if !functions.iter().any(|f| f.selector() == function.selector()) {
+                functions.push(function);
+            }
+        } else if line.starts_with("error ") {
+            let error = Error::parse(line).unwrap_or_else(|e| panic!("{line}: {e}"));
+            add_error(&mut abi, named_error(error, &declared));
+        }
+    }
+    for line in declarations(sources, "event ") {
+        let event = Event::parse(line).unwrap_or_else(|e| panic!("{line}: {e}"));
+        let events = abi.events.entry(event.name.clone()).or_default();
+        if !events.iter().any(|e| e.selector() == event.selector()) {
+            events.push(event);
+        }
+    }
+    abi
+}
+
+fn json_string<T: GenerateAbi>(sources: &[&str]) -> String {
+    serde_json::to_string_pretty(&json::<T>(sources)).unwrap() + "\n"
+}
+
+pub fn write<T: GenerateAbi>(dir: &Path, sources: &[&str]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{}.sol", T::NAME)), solidity::<T>())?;
+    fs::write(dir.join(format!("{}.json", T::NAME)), json_string::<T>(sources))
+}
+
+pub fn run<T: GenerateAbi>(sources: &[&str]) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => print!("{}", solidity::<T>()),
+        ["--json"] => print!("{}", json_string::<T>(sources)),
+        ["--out", dir] => write::<T>(Path::new(dir), sources).unwrap_or_else(|e| panic!("writing {dir}: {e}")),
+        _ => {
+            eprintln!("usage: [--json | --out <dir>]");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Fails when the checked-in `<dir>/<Name>.sol` or `.json` differs from the
+/// current ABI. Run with `UPDATE_ABI=1` to rewrite them after an intended change.
+pub fn check_snapshot<T: GenerateAbi>(dir: &str, sources: &[&str]) {
+    let dir = Path::new(dir);
+    if std::env::var_os("UPDATE_ABI").is_some() {
+        write::<T>(dir, sources).unwrap();
+        return;
+    }
+    for (ext, current) in [("sol", solidity::<T>()), ("json", json_string::<T>(sources))] {
+        let path = dir.join(format!("{}.{ext}", T::NAME));
+        let snapshot = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            snapshot == current,
+            "{} is out of date; rerun with UPDATE_ABI=1 if the ABI change is intended\n--- current ---\n{current}",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,450 @@
+// State and helpers shared by the concentrated liquidity pool
+// (19_concentrated_liquidity_pool.rs) and its facets: positions in
+// 19_concentrated_liquidity_positions.rs, swaps in
+// 19_concentrated_liquidity_swap.rs, limit orders in
+// 19_concentrated_liquidity_limit_orders.rs. Together they are too big for one
+// Stylus program, so the pool delegate_calls the facets and they run on the
+// pool's storage.
+//
+// Layout rule: the pool and its facets start their storage with
+// `ConcentratedLiquidityState` at slot 0.
+#![allow(dead_code)]
+use alloy_sol_types::sol;
+use amm_math::{
+    full_math::mul_div,
+    sqrt_price_math::{add_delta, get_amounts_for_liquidity},
+    tick_bitmap::{self, compress, flip, next_initialized_tick_within_one_word},
+    tick_math::{get_sqrt_ratio_at_tick, MAX_TICK, MIN_TICK, Q128},
+};
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, Signed, B256, I128, I16, U128, U256},
+    call::Call,
+    contract, crypto,
+    prelude::*,
+    storage::TopLevelStorage,
+};
+
+sol_interface! {
+    interface IERC20 {
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+// What each facet serves; the pool forwards these selectors to it.
+sol! {
+    interface IConcentratedLiquidityPositions {
+        function mint(address recipient, int32 tick_lower, int32 tick_upper, uint128 amount) external returns (uint256, uint256);
+        function burn(int32 tick_lower, int32 tick_upper, uint128 amount) external returns (uint256, uint256);
+        function collect(address recipient, int32 tick_lower, int32 tick_upper, uint256 amount0_requested, uint256 amount1_requested) external returns (uint256, uint256);
+    }
+
+    interface IConcentratedLiquiditySwap {
+        function swap(address recipient, bool zero_for_one, uint256 amount, bool exact_input, uint256 sqrt_price_limit_x96) external returns (uint256, uint256);
+    }
+
+    interface IConcentratedLiquidityLimitOrders {
+        function placeLimitOrder(int32 tick_lower, bool zero_for_one, uint128 liquidity) external returns (uint256);
+        function cancelLimitOrder(uint256 order_id) external returns (uint256, uint256);
+    }
+
+    interface IConcentratedLiquidityLimitFills {
+        function claimLimitOrder(uint256 order_id) external returns (uint256, uint256);
+    }
+}
+
+// The swap facet delegate_calls the limit fill facet with this for the ticks a
+// swap crossed. The pool doesn't forward it, so nothing else reaches it on the
+// pool's storage.
+sol! {
+    interface ILimitEpochFills {
+        function fillLimitEpochs(int32[] ticks, bool zero_for_one) external;
+    }
+}
+
+sol! {
+    error AlreadyInitialized();
+    error NotInitialized();
+    error InvalidTickSpacing(uint32 tick_spacing);
+    error InvalidFee(uint32 fee);
+    error InvalidPrice(uint256 sqrt_price_x96);
+    error InvalidTickRange(int24 tick_lower, int24 tick_upper);
+    error InvalidPriceLimit(uint256 sqrt_price_limit_x96);
+    error ZeroLiquidity();
+    error TickLiquidityOverflow(int24 tick);
+    error InsufficientPositionLiquidity(uint128 have, uint128 want);
+    error MathOverflow();
+    error ExternalCallFailed(address target);
+    error LimitOrderInRange(int24 tick_lower, int24 tick);
+    error UnknownLimitOrder(uint256 order_id);
+    error LimitOrderClosed(uint256 order_id);
+    error LimitOrderNotFilled(uint256 order_id);
+    error LimitOrderFilled(uint256 order_id);
+    error NotLimitOrderOwner(uint256 order_id, address caller);
+}
+
+#[derive(SolidityError)]
+pub enum PoolError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotInitialized(NotInitialized),
+    InvalidTickSpacing(InvalidTickSpacing),
+    InvalidFee(InvalidFee),
+    InvalidPrice(InvalidPrice),
+    InvalidTickRange(InvalidTickRange),
+    InvalidPriceLimit(InvalidPriceLimit),
+    ZeroLiquidity(ZeroLiquidity),
+    TickLiquidityOverflow(TickLiquidityOverflow),
+    InsufficientPositionLiquidity(InsufficientPositionLiquidity),
+    MathOverflow(MathOverflow),
+    ExternalCallFailed(ExternalCallFailed),
+    LimitOrderInRange(LimitOrderInRange),
+    UnknownLimitOrder(UnknownLimitOrder),
+    LimitOrderClosed(LimitOrderClosed),
+    LimitOrderNotFilled(LimitOrderNotFilled),
+    LimitOrderFilled(LimitOrderFilled),
+    NotLimitOrderOwner(NotLimitOrderOwner),
+}
+
+sol_storage! {
+    pub struct ConcentratedLiquidityState {
+        address token0;
+        address token1;
+        // hundredths of a basis point, 3000 = 0.3%
+        uint32 fee;
+        uint32 tick_spacing;
+        uint128 max_liquidity_per_tick;
+
+        uint256 sqrt_price_x96;
+        int24 tick;
+        // liquidity of every position whose range contains the current tick
+        uint128 liquidity;
+        uint256 fee_growth_global0_x128;
+        uint256 fee_growth_global1_x128;
+
+        mapping(int24 => TickInfo) ticks;
+        // compressed tick >> 8 => one bit per spaced tick
+        mapping(int16 => uint256) tick_bitmap;
+        // keccak(owner, tick_lower, tick_upper) => position
+        mapping(bytes32 => PositionInfo) positions;
+
+        // keccak(tick_lower, zero_for_one) => epoch still taking orders, 0 if none
+        mapping(bytes32 => uint256) open_limit_epochs;
+        mapping(uint256 => LimitEpoch) limit_epochs;
+        uint256 next_limit_epoch;
+        mapping(uint256 => LimitOrder) limit_orders;
+        uint256 next_limit_order_id;
+
+        // set once by the pool's init
+        address positions_facet;
+        address swap_facet;
+        address limit_order_facet;
+        address limit_fill_facet;
+    }
+
+    #[derive(Erase)]
+    pub struct TickInfo {
+        // liquidity of every position using this tick as a bound
+        uint128 liquidity_gross;
+        // added to active liquidity when the price crosses the tick going up
+        int128 liquidity_net;
+        // fee growth on the other side of this tick from the current price
+        uint256 fee_growth_outside0_x128;
+        uint256 fee_growth_outside1_x128;
+    }
+
+    pub struct PositionInfo {
+        uint128 liquidity;
+        uint256 fee_growth_inside0_last_x128;
+        uint256 fee_growth_inside1_last_x128;
+        uint256 tokens_owed0;
+        uint256 tokens_owed1;
+    }
+
+    pub struct LimitEpoch {
+        int24 tick_lower;
+        // sells token0 for token1, filled by the price rising through the range
+        bool zero_for_one;
+        // kept as the total at fill time so claims can be pro-rata
+        uint128 liquidity;
+        bool filled;
+        // what the epoch's liquidity was worth when filled, fees included
+        uint256 amount0;
+        uint256 amount1;
+    }
+
+    pub struct LimitOrder {
+        address owner;
+        uint256 epoch;
+        uint128 liquidity;
+        // ORDER_OPEN, ORDER_CLAIMED or ORDER_CANCELLED
+        uint8 status;
+    }
+}
+
+// `limit_order` status codes; filled is an open order whose epoch has filled.
+pub const ORDER_NONE: u8 = 0;
+pub const ORDER_OPEN: u8 = 1;
+pub const ORDER_FILLED: u8 = 2;
+pub const ORDER_CLAIMED: u8 = 3;
+pub const ORDER_CANCELLED: u8 = 4;
+
+// alloy-primitives has no alias for the storage type of an `int24`
+pub type I24 = Signed<24, 1>;
+
+// Ticks are range-checked before they reach storage, so the int24 / int128
+// conversions below can't fail.
+pub fn tick_key(tick: i32) -> I24 {
+    I24::try_from(tick).unwrap()
+}
+
+pub fn to_i128(value: I128) -> i128 {
+    i128::try_from(value).unwrap()
+}
+
+pub fn overflow() -> PoolError {
+    PoolError::MathOverflow(MathOverflow {})
+}
+
+pub fn sqrt_ratio_at(tick: i32) -> Result<U256, PoolError> {
+    get_sqrt_ratio_at_tick(tick).ok_or_else(overflow)
+}
+
+impl ConcentratedLiquidityState {
+    pub fn failed(target: Address) -> PoolError {
+        PoolError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    pub fn current_tick(&self) -> i32 {
+        i32::try_from(self.tick.get()).unwrap()
+    }
+
+    pub fn spacing(&self) -> i32 {
+        self.tick_spacing.get().saturating_to::<u32>() as i32
+    }
+
+    pub fn active_liquidity(&self) -> u128 {
+        self.liquidity.get().saturating_to::<u128>()
+    }
+
+    pub fn position_key(owner: Address, tick_lower: i32, tick_upper: i32) -> B256 {
+        crypto::keccak([owner.as_slice(), &tick_lower.to_be_bytes(), &tick_upper.to_be_bytes()].concat())
+    }
+
+    pub fn check_ticks(&self, tick_lower: i32, tick_upper: i32) -> Result<(), PoolError> {
+        let spacing = self.spacing();
+        if tick_lower >= tick_upper
+            || tick_lower < MIN_TICK
+            || tick_upper > MAX_TICK
+            || tick_lower % spacing != 0
+            || tick_upper % spacing != 0
+        {
+            return Err(PoolError::InvalidTickRange(InvalidTickRange { tick_lower, tick_upper }));
+        }
+        Ok(())
+    }
+
+    fn flip_tick(&mut self, tick: i32) {
+        let (word_pos, bit_pos) = tick_bitmap::position(compress(tick, self.spacing()));
+        let key = I16::try_from(word_pos).unwrap();
+        let word = self.tick_bitmap.get(key);
+        self.tick_bitmap.insert(key, flip(word, bit_pos));
+    }
+
+    pub fn next_initialized_tick(&self, tick: i32, lte: bool) -> (i32, bool) {
+        let spacing = self.spacing();
+        let start = if lte { compress(tick, spacing) } else { compress(tick, spacing) + 1 };
+        let (word_pos, _) = tick_bitmap::position(start);
+        let word = self.tick_bitmap.get(I16::try_from(word_pos).unwrap());
+        next_initialized_tick_within_one_word(word, tick, spacing, lte)
+    }
+
+    // Adds `delta` to a bound; returns true if the tick went from unused to used or back.
+    fn update_tick(&mut self, tick: i32, delta: i128, upper: bool) -> Result<bool, PoolError> {
+        let current = self.current_tick();
+        let (global0, global1) = (self.fee_growth_global0_x128.get(), self.fee_growth_global1_x128.get());
+        let max_liquidity = self.max_liquidity_per_tick.get().saturating_to::<u128>();
+
+        let mut info = self.ticks.setter(tick_key(tick));
+        let gross_before = info.liquidity_gross.get().saturating_to::<u128>();
+        let gross_after = add_delta(gross_before, delta).ok_or_else(overflow)?;
+        if gross_after > max_liquidity {
+            return Err(PoolError::TickLiquidityOverflow(TickLiquidityOverflow { tick }));
+        }
+        // by convention all growth so far happened below a newly used tick
+        if gross_before == 0 && tick <= current {
+            info.fee_growth_outside0_x128.set(global0);
+            info.fee_growth_outside1_x128.set(global1);
+        }
+        let net = to_i128(info.liquidity_net.get());
+        let net = if upper { net.checked_sub(delta) } else { net.checked_add(delta) }.ok_or_else(overflow)?;
+        info.liquidity_gross.set(U128::from(gross_after));
+        info.liquidity_net.set(I128::try_from(net).unwrap());
+        Ok((gross_after == 0) != (gross_before == 0))
+    }
+
+    // Flips which side of the tick its outside growth refers to; returns liquidity_net.
+    pub fn cross_tick(&mut self, tick: i32, global0: U256, global1: U256) -> i128 {
+        let mut info = self.ticks.setter(tick_key(tick));
+        let outside0 = info.fee_growth_outside0_x128.get();
+        let outside1 = info.fee_growth_outside1_x128.get();
+        info.fee_growth_outside0_x128.set(global0.wrapping_sub(outside0));
+        info.fee_growth_outside1_x128.set(global1.wrapping_sub(outside1));
+        to_i128(info.liquidity_net.get())
+    }
+
+    fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        let current = self.current_tick();
+        let (global0, global1) = (self.fee_growth_global0_x128.get(), self.fee_growth_global1_x128.get());
+        let lower = self.ticks.get(tick_key(tick_lower));
+        let upper = self.ticks.get(tick_key(tick_upper));
+        let (lower0, lower1) = (lower.fee_growth_outside0_x128.get(), lower.fee_growth_outside1_x128.get());
+        let (upper0, upper1) = (upper.fee_growth_outside0_x128.get(), upper.fee_growth_outside1_x128.get());
+
+        let (below0, below1) =
+            if current >= tick_lower { (lower0, lower1) } else { (global0.wrapping_sub(lower0), global1.wrapping_sub(lower1)) };
+        let (above0, above1) =
+            if current < tick_upper { (upper0, upper1) } else { (global0.wrapping_sub(upper0), global1.wrapping_sub(upper1)) };
+        // accumulators are allowed to wrap; only differences matter
+        (global0.wrapping_sub(below0).wrapping_sub(above0), global1.wrapping_sub(below1).wrapping_sub(above1))
+    }
+
+    // Applies a liquidity change to a position and its ticks, crediting the fees
+    // it earned since last touched. Returns the token amounts the change is worth,
+    // rounded up when adding and down when removing.
+    pub fn modify_position(&mut self, owner: Address, tick_lower: i32, tick_upper: i32, delta: i128) -> Result<(U256, U256), PoolError> {
+        if self.sqrt_price_x96.get() == U256::ZERO {
+            return Err(PoolError::NotInitialized(NotInitialized {}));
+        }
+        self.check_ticks(tick_lower, tick_upper)?;
+
+        let (mut flipped_lower, mut flipped_upper) = (false, false);
+        if delta != 0 {
+            flipped_lower = self.update_tick(tick_lower, delta, false)?;
+            flipped_upper = self.update_tick(tick_upper, delta, true)?;
+            if flipped_lower {
+                self.flip_tick(tick_lower);
+            }
+            if flipped_upper {
+                self.flip_tick(tick_upper);
+            }
+        }
+
+        let (inside0, inside1) = self.fee_growth_inside(tick_lower, tick_upper);
+        let mut position = self.positions.setter(Self::position_key(owner, tick_lower, tick_upper));
+        let liquidity = position.liquidity.get().saturating_to::<u128>();
+        let liquidity_next = add_delta(liquidity, delta).ok_or_else(|| {
+            PoolError::InsufficientPositionLiquidity(InsufficientPositionLiquidity { have: liquidity, want: delta.unsigned_abs() })
+        })?;
+        let owed0 =
+            mul_div(inside0.wrapping_sub(position.fee_growth_inside0_last_x128.get()), U256::from(liquidity), Q128).ok_or_else(overflow)?;
+        let owed1 =
+            mul_div(inside1.wrapping_sub(position.fee_growth_inside1_last_x128.get()), U256::from(liquidity), Q128).ok_or_else(overflow)?;
+        let tokens_owed0 = position.tokens_owed0.get() + owed0;
+        let tokens_owed1 = position.tokens_owed1.get() + owed1;
+        position.liquidity.set(U128::from(liquidity_next));
+        position.fee_growth_inside0_last_x128.set(inside0);
+        position.fee_growth_inside1_last_x128.set(inside1);
+        position.tokens_owed0.set(tokens_owed0);
+        position.tokens_owed1.set(tokens_owed1);
+
+        // a tick nobody uses any more is cleared, so its old growth can't leak into a new range
+        if delta < 0 {
+            if flipped_lower {
+                self.ticks.delete(tick_key(tick_lower));
+            }
+            if flipped_upper {
+                self.ticks.delete(tick_key(tick_upper));
+            }
+        }
+
+        let current = self.current_tick();
+        if delta != 0 && tick_lower <= current && current < tick_upper {
+            let active = add_delta(self.active_liquidity(), delta).ok_or_else(overflow)?;
+            self.liquidity.set(U128::from(active));
+        }
+        get_amounts_for_liquidity(
+            self.sqrt_price_x96.get(),
+            sqrt_ratio_at(tick_lower)?,
+            sqrt_ratio_at(tick_upper)?,
+            delta.unsigned_abs(),
+            delta > 0,
+        )
+        .ok_or_else(overflow)
+    }
+
+    // Takes what a position is owed, leaving it at zero.
+    pub fn take_owed(&mut self, owner: Address, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        let mut position = self.positions.setter(Self::position_key(owner, tick_lower, tick_upper));
+        let owed = (position.tokens_owed0.get(), position.tokens_owed1.get());
+        position.tokens_owed0.set(U256::ZERO);
+        position.tokens_owed1.set(U256::ZERO);
+        owed
+    }
+
+    // An order's share of its filled epoch, rounded down.
+    pub fn limit_order_proceeds(&self, order_id: U256) -> Result<(U256, U256), PoolError> {
+        let order = self.limit_orders.get(order_id);
+        let epoch = self.limit_epochs.get(order.epoch.get());
+        let liquidity = U256::from(order.liquidity.get());
+        let total = U256::from(epoch.liquidity.get());
+        Ok((
+            mul_div(epoch.amount0.get(), liquidity, total).ok_or_else(overflow)?,
+            mul_div(epoch.amount1.get(), liquidity, total).ok_or_else(overflow)?,
+        ))
+    }
+
+    // (owner, epoch, liquidity, epoch filled) of an order not yet claimed or cancelled.
+    pub fn open_limit_order(&self, order_id: U256) -> Result<(Address, U256, u128, bool), PoolError> {
+        let order = self.limit_orders.get(order_id);
+        match order.status.get().saturating_to::<u8>() {
+            ORDER_NONE => Err(PoolError::UnknownLimitOrder(UnknownLimitOrder { order_id })),
+            ORDER_OPEN => {
+                let epoch = order.epoch.get();
+                let filled = self.limit_epochs.get(epoch).filled.get();
+                Ok((order.owner.get(), epoch, order.liquidity.get().saturating_to::<u128>(), filled))
+            }
+            _ => Err(PoolError::LimitOrderClosed(LimitOrderClosed { order_id })),
+        }
+    }
+
+    pub fn epoch_key(tick_lower: i32, zero_for_one: bool) -> B256 {
+        crypto::keccak([&tick_lower.to_be_bytes()[..], &[zero_for_one as u8]].concat())
+    }
+
+    // Owner of an epoch's position: a hash nobody holds the key for, so `burn`
+    // and `collect` can't reach it.
+    pub fn epoch_owner(epoch: U256) -> Address {
+        Address::from_word(crypto::keccak(epoch.to_be_bytes::<32>()))
+    }
+}
+
+// What needs the contract itself, for token calls. Any storage struct that
+// starts with `ConcentratedLiquidityState` gets these.
+pub trait Pool: TopLevelStorage + BorrowMut<ConcentratedLiquidityState> + Sized {
+    fn pull(&mut self, token: Address, from: Address, amount: U256) -> Result<(), PoolError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let ok = IERC20::new(token)
+            .transfer_from(Call::new_in(self), from, contract::address(), amount)
+            .map_err(|_| ConcentratedLiquidityState::failed(token))?;
+        if !ok {
+            return Err(ConcentratedLiquidityState::failed(token));
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, token: Address, to: Address, amount: U256) -> Result<(), PoolError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let ok = IERC20::new(token).transfer(Call::new_in(self), to, amount).map_err(|_| ConcentratedLiquidityState::failed(token))?;
+        if !ok {
+            return Err(ConcentratedLiquidityState::failed(token));
+        }
+        Ok(())
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<ConcentratedLiquidityState>> Pool for T {}
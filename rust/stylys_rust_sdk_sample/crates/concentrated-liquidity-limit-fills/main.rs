@@ -0,0 +1,14 @@
+use stylus_concentrated_liquidity_limit_fills::ConcentratedLiquidityLimitFills;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] =
+    &[include_str!("../../19_concentrated_liquidity_limit_fills.rs"), include_str!("../../concentrated_liquidity_state.rs")];
+
+fn main() {
+    abi_export::run::<ConcentratedLiquidityLimitFills>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<ConcentratedLiquidityLimitFills>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
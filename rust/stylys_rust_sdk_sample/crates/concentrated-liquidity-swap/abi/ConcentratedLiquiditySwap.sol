@@ -0,0 +1,47 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IConcentratedLiquiditySwap {
+    function swap(address recipient, bool zero_for_one, uint256 amount, bool exact_input, uint256 sqrt_price_limit_x96) external returns (uint256, uint256);
+
+    error AlreadyInitialized();
+
+    error NotInitialized();
+
+    error InvalidTickSpacing(uint32);
+
+    error InvalidFee(uint32);
+
+    error InvalidPrice(uint256);
+
+    error InvalidTickRange(int24, int24);
+
+    error InvalidPriceLimit(uint256);
+
+    error ZeroLiquidity();
+
+    error TickLiquidityOverflow(int24);
+
+    error InsufficientPositionLiquidity(uint128, uint128);
+
+    error MathOverflow();
+
+    error ExternalCallFailed(address);
+
+    error LimitOrderInRange(int24, int24);
+
+    error UnknownLimitOrder(uint256);
+
+    error LimitOrderClosed(uint256);
+
+    error LimitOrderNotFilled(uint256);
+
+    error LimitOrderFilled(uint256);
+
+    error NotLimitOrderOwner(uint256, address);
+}
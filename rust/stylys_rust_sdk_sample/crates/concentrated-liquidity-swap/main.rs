@@ -0,0 +1,13 @@
+use stylus_concentrated_liquidity_swap::ConcentratedLiquiditySwap;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../19_concentrated_liquidity_swap.rs"), include_str!("../../concentrated_liquidity_state.rs")];
+
+fn main() {
+    abi_export::run::<ConcentratedLiquiditySwap>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<ConcentratedLiquiditySwap>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,75 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IConcentratedLiquidityPool {
+    function init(address token0, address token1, uint32 fee, uint32 tick_spacing, uint256 sqrt_price_x96, address positions_facet, address swap_facet, address limit_order_facet, address limit_fill_facet) external;
+
+    function facets() external view returns (address, address, address, address);
+
+    function limitOrder(uint256 order_id) external view returns (address, uint256, int32, bool, uint128, uint8);
+
+    function limitOrderClaimable(uint256 order_id) external view returns (uint256, uint256);
+
+    function limitEpoch(uint256 epoch) external view returns (int32, bool, uint128, bool, uint256, uint256);
+
+    function openLimitEpoch(int32 tick_lower, bool zero_for_one) external view returns (uint256);
+
+    function slot0() external view returns (uint256, int32);
+
+    function liquidity() external view returns (uint128);
+
+    function feeGrowthGlobal() external view returns (uint256, uint256);
+
+    function tokens() external view returns (address, address);
+
+    function fee() external view returns (uint32);
+
+    function tickSpacing() external view returns (uint32);
+
+    function ticks(int32 tick) external view returns (uint128, int128, uint256, uint256);
+
+    function position(address owner, int32 tick_lower, int32 tick_upper) external view returns (uint128, uint256, uint256);
+
+    function tickBitmap(int16 word_pos) external view returns (uint256);
+
+    error AlreadyInitialized();
+
+    error NotInitialized();
+
+    error InvalidTickSpacing(uint32);
+
+    error InvalidFee(uint32);
+
+    error InvalidPrice(uint256);
+
+    error InvalidTickRange(int24, int24);
+
+    error InvalidPriceLimit(uint256);
+
+    error ZeroLiquidity();
+
+    error TickLiquidityOverflow(int24);
+
+    error InsufficientPositionLiquidity(uint128, uint128);
+
+    error MathOverflow();
+
+    error ExternalCallFailed(address);
+
+    error LimitOrderInRange(int24, int24);
+
+    error UnknownLimitOrder(uint256);
+
+    error LimitOrderClosed(uint256);
+
+    error LimitOrderNotFilled(uint256);
+
+    error LimitOrderFilled(uint256);
+
+    error NotLimitOrderOwner(uint256, address);
+}
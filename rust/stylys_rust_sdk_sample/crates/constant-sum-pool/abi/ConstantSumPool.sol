@@ -0,0 +1,93 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IErc20 {
+    function name() external pure returns (string memory);
+
+    function symbol() external pure returns (string memory);
+
+    function decimals() external pure returns (uint8);
+
+    function totalSupply() external view returns (uint256);
+
+    function balanceOf(address _address) external view returns (uint256);
+
+    function transfer(address to, uint256 value) external returns (bool);
+
+    function approve(address spender, uint256 value) external returns (bool);
+
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    error InsufficientBalance(address, uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+}
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IConstantSumPool is IErc20, IOwnable {
+    function init(address token0, address token1, uint64 fee_bps, uint64 threshold_bps, uint8 fallback_mode, uint64 deploy_nonce) external;
+
+    function getReserves() external view returns (uint256, uint256);
+
+    function parameters() external view returns (uint256, uint256, uint8, bool, bool);
+
+    function getAmountOut(bool zero_for_one, uint256 amount_in) external view returns (uint256, bool);
+
+    function swap(bool zero_for_one, uint256 amount_in, uint256 min_amount_out) external returns (uint256);
+
+    function addLiquidity(uint256 amount0, uint256 amount1, uint256 min_shares) external returns (uint256);
+
+    function removeLiquidity(uint256 shares) external returns (uint256, uint256);
+
+    function setParameters(uint64 fee_bps, uint64 threshold_bps, uint8 fallback_mode) external;
+
+    function resume() external;
+
+    error AlreadyInitialized();
+
+    error DecimalsMismatch(uint8, uint8);
+
+    error InvalidParameters(uint256, uint256, uint8);
+
+    error PoolHalted();
+
+    error ExceedsThreshold(uint256, uint256);
+
+    error InsufficientLiquidity(uint256, uint256);
+
+    error Slippage(uint256, uint256);
+
+    error ZeroShares();
+
+    error InsufficientShares(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error MathOverflow();
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,13 @@
+use stylus_constant_sum_pool::ConstantSumPool;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../22_constant_sum_pool.rs"), include_str!("../../erc20.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<ConstantSumPool>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<ConstantSumPool>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,63 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface ICounter is IOwnable {
+    function init(uint256 min, uint256 max, uint64 deploy_nonce) external;
+
+    function bounds() external view returns (uint256, uint256);
+
+    function number() external view returns (uint256);
+
+    function lastUpdate() external view returns (address, uint64);
+
+    function setNumber(uint256 new_number) external;
+
+    function increment() external;
+
+    function decrement() external;
+
+    function add(uint256 n) external;
+
+    function reset() external;
+
+    function counterOf(address account) external view returns (uint256);
+
+    function incrementMine() external;
+
+    function decrementMine() external;
+
+    function addMine(uint256 n) external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+
+    error InvalidBounds(uint256, uint256);
+
+    error CounterOverflow(uint256);
+
+    error CounterUnderflow(uint256);
+
+    error OutOfBounds(uint256, uint256, uint256);
+}
@@ -0,0 +1,13 @@
+use stylus_counter::Counter;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../10_counter.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<Counter>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<Counter>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
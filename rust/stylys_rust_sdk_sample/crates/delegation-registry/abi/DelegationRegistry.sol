@@ -0,0 +1,87 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface ISubStruct {
+    function add(address delegatee, uint256 weight, uint64 expiry) external;
+
+    function update(address delegatee, uint256 weight, uint64 expiry) external;
+
+    function remove(address delegatee) external;
+
+    function removeExpired(address delegator, address delegatee) external;
+
+    function delegatesOf(address delegator) external view returns (address,uint256,uint64)[] memory;
+
+    function delegatorsOf(address delegatee) external view returns (address[] memory);
+
+    function delegation(address delegator, address delegatee) external view returns (uint256, uint64);
+
+    function totalWeightOf(address delegator) external view returns (uint256);
+
+    function delegatedPower(address delegatee) external view returns (uint256);
+
+    function delegatedPowerAt(address delegatee, uint64 block_number) external view returns (uint256);
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error InvalidToken(address);
+
+    error TokenCallFailed(address);
+
+    error ZeroDelegatee();
+
+    error ZeroWeight();
+
+    error SelfDelegation(address);
+
+    error DelegateExists(address, address);
+
+    error DelegateNotFound(address, address);
+
+    error WeightTooHigh(uint256, uint256);
+
+    error ExpiryInPast(uint64, uint64);
+
+    error DelegateNotExpired(uint64, uint64);
+
+    error FutureLookup(uint64, uint64);
+}
+
+interface IDelegationRegistry is ISubStruct {
+    function init(address token, uint64 deploy_nonce) external;
+
+    function token() external view returns (address);
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error InvalidToken(address);
+
+    error TokenCallFailed(address);
+
+    error ZeroDelegatee();
+
+    error ZeroWeight();
+
+    error SelfDelegation(address);
+
+    error DelegateExists(address, address);
+
+    error DelegateNotFound(address, address);
+
+    error WeightTooHigh(uint256, uint256);
+
+    error ExpiryInPast(uint64, uint64);
+
+    error DelegateNotExpired(uint64, uint64);
+
+    error FutureLookup(uint64, uint64);
+}
@@ -0,0 +1,13 @@
+use stylus_delegation_registry::DelegationRegistry;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../11_delegation_registry.rs")];
+
+fn main() {
+    abi_export::run::<DelegationRegistry>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<DelegationRegistry>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,43 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IDiamond {
+    function init(uint64 deploy_nonce) external;
+
+    function owner() external view returns (address);
+
+    function addFacet(address facet, bytes4[] memory selectors) external;
+
+    function removeSelectors(bytes4[] memory selectors) external;
+
+    function invokeLogicContract(address spender, uint256 value) external returns (bool);
+
+    function relay(uint8[] memory data) external payable returns (uint8[] memory);
+
+    function facets() external view returns (address[] memory);
+
+    function facetFunctionSelectors(address facet) external view returns (bytes4[] memory);
+
+    function facetAddress(bytes4 selector) external view returns (address);
+
+    error NotOwner(address, address);
+
+    error NotDeployer(address);
+
+    error AlreadyInitialized();
+
+    error ZeroFacet();
+
+    error NoSelectors();
+
+    error SelectorExists(bytes4, address);
+
+    error FunctionNotFound(bytes4);
+
+    error DelegateCallFailed(address, bytes);
+}
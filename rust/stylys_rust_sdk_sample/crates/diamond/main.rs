@@ -0,0 +1,13 @@
+use stylus_diamond::Diamond;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../9_diamond_logic.rs")];
+
+fn main() {
+    abi_export::run::<Diamond>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<Diamond>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,33 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface ILpRegistry {
+    function init() external;
+
+    function recordLiquidity(address lp, uint256 amount) external;
+
+    function liquidityOf(address lp) external view returns (uint256);
+
+    function lpCount() external view returns (uint256);
+
+    function lps(uint256 offset, uint256 limit) external view returns (address[] memory, uint256[] memory);
+
+    function deprecate() external;
+
+    function eraseLps(uint256 limit) external returns (uint256);
+
+    function deprecated() external view returns (bool);
+
+    error NotOwner(address, address);
+
+    error AlreadyInitialized();
+
+    error PoolIsDeprecated();
+
+    error PoolNotDeprecated();
+}
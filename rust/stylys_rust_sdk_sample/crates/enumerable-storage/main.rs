@@ -0,0 +1,13 @@
+use stylus_enumerable_storage::LpRegistry;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../12_enumerable_storage.rs")];
+
+fn main() {
+    abi_export::run::<LpRegistry>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<LpRegistry>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
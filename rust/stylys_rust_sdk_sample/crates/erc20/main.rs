@@ -0,0 +1,13 @@
+use stylus_erc20::StylusERC20;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../6_erc20.rs"), include_str!("../../erc20.rs")];
+
+fn main() {
+    abi_export::run::<StylusERC20>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<StylusERC20>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,75 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IGovernor {
+    function init(address token, address guardian, uint64 voting_delay, uint64 voting_period, uint64 timelock_delay, uint256 quorum_numerator, uint256 proposal_threshold, uint64 deploy_nonce) external;
+
+    function propose(address[] memory targets, uint256[] memory values, bytes[] memory calldatas, string calldata description) external returns (bytes32);
+
+    function castVote(bytes32 proposal_id, uint8 support) external returns (uint256);
+
+    function queue(bytes32 proposal_id) external returns (uint64);
+
+    function execute(address[] memory targets, uint256[] memory values, bytes[] memory calldatas, bytes32 description_hash) external payable returns (bytes32);
+
+    function cancel(bytes32 proposal_id) external;
+
+    function state(bytes32 proposal_id) external view returns (uint8);
+
+    function quorum(uint64 block_number) external view returns (uint256);
+
+    function proposalVotes(bytes32 proposal_id) external view returns (uint256, uint256, uint256);
+
+    function proposalEta(bytes32 proposal_id) external view returns (uint64);
+
+    function hasVoted(bytes32 proposal_id, address account) external view returns (bool);
+
+    function settings() external view returns (address, uint64, uint64, uint64, uint256, uint256);
+
+    function setVotingPeriod(uint64 voting_period) external;
+
+    function setTimelockDelay(uint64 timelock_delay) external;
+
+    function setQuorumNumerator(uint256 quorum_numerator) external;
+
+    function setProposalThreshold(uint256 proposal_threshold) external;
+
+    function setGuardian(address guardian) external;
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error InvalidQuorum(uint256);
+
+    error OnlyGovernance(address);
+
+    error InvalidProposalLength(uint256, uint256, uint256);
+
+    error EmptyProposal();
+
+    error ProposalExists(bytes32);
+
+    error UnknownProposal(bytes32);
+
+    error BelowProposalThreshold(address, uint256, uint256);
+
+    error UnexpectedProposalState(bytes32, uint8);
+
+    error AlreadyVoted(address);
+
+    error InvalidVoteType(uint8);
+
+    error NotReady(bytes32, uint64);
+
+    error Unauthorized(address);
+
+    error VotesLookupFailed(address);
+
+    error CallFailed(address, bytes);
+}
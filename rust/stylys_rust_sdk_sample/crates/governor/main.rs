@@ -0,0 +1,13 @@
+use stylus_governor::Governor;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../14_governor.rs")];
+
+fn main() {
+    abi_export::run::<Governor>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<Governor>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
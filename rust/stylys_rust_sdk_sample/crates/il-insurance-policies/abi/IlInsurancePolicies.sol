@@ -0,0 +1,55 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IIlInsurancePolicies {
+    function quotePremium(address pool, uint256 liquidity, uint256 duration) external view returns (uint256);
+
+    function insurePosition(address pool, uint256 liquidity, uint256 duration) external payable returns (uint256);
+
+    function claim(uint256 policy_id) external returns (uint256);
+
+    error AlreadyInitialized();
+
+    error PoolNotEnabled(address);
+
+    error InvalidAmount();
+
+    error InvalidDuration(uint256, uint256, uint256);
+
+    error InvalidParameters();
+
+    error TwapNotReady(address, uint256, uint256);
+
+    error PriceDeviation(address, uint256, uint256);
+
+    error InsufficientPremium(uint256, uint256);
+
+    error InsufficientCapital(uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error VaultDrained();
+
+    error ZeroShares(uint256);
+
+    error MathOverflow();
+
+    error UnknownPolicy(uint256);
+
+    error PolicyNotExpired(uint256, uint256);
+
+    error PolicyAlreadyClaimed(uint256);
+
+    error MintFailed(address, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,18 @@
+use stylus_il_insurance_policies::IlInsurancePolicies;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../24_il_insurance_policies.rs"),
+    include_str!("../../il_insurance_state.rs"),
+    include_str!("../../erc721.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<IlInsurancePolicies>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<IlInsurancePolicies>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,14 @@
+use stylus_il_insurance_underwriting::IlInsuranceUnderwriting;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] =
+    &[include_str!("../../24_il_insurance_underwriting.rs"), include_str!("../../il_insurance_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<IlInsuranceUnderwriting>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<IlInsuranceUnderwriting>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
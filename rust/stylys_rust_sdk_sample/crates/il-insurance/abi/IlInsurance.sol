@@ -0,0 +1,111 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IErc721 {
+    function name() external pure returns (string memory);
+
+    function symbol() external pure returns (string memory);
+
+    function tokenURI(uint256 token_id) external view returns (string memory);
+
+    function supportsInterface(bytes4 _interface) external pure returns (bool);
+
+    function balanceOf(address owner) external view returns (uint256);
+
+    function ownerOf(uint256 token_id) external view returns (address);
+
+    function safeTransferFrom(address from, address to, uint256 token_id, bytes calldata data) external;
+
+    function safeTransferFrom(address from, address to, uint256 token_id) external;
+
+    function transferFrom(address from, address to, uint256 token_id) external;
+
+    function approve(address approved, uint256 token_id) external;
+
+    function setApprovalForAll(address operator, bool approved) external;
+
+    function getApproved(uint256 token_id) external view returns (address);
+
+    function isApprovedForAll(address owner, address operator) external view returns (bool);
+
+    error InvalidTokenId(uint256);
+
+    error NotOwner(address, uint256, address);
+
+    error NotApproved(address, address, uint256);
+
+    error TransferToZero(uint256);
+
+    error ReceiverRefused(address, uint256, bytes4);
+}
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IIlInsurance is IErc721, IOwnable {
+    function init(uint256 twap_window, uint256 blocks_per_year, uint256 loading_bps, uint256 min_premium_bps, uint256 max_coverage_bps, uint256 min_duration, uint256 max_duration, address policies_facet, address underwriting_facet, uint64 deploy_nonce) external;
+
+    function setParameters(uint256 twap_window, uint256 blocks_per_year, uint256 loading_bps, uint256 min_premium_bps, uint256 max_coverage_bps, uint256 min_duration, uint256 max_duration) external;
+
+    function policy(uint256 policy_id) external view returns (address, uint256, uint256, uint256, uint256, uint256, bool);
+
+    function facets() external view returns (address, address);
+
+    error AlreadyInitialized();
+
+    error PoolNotEnabled(address);
+
+    error InvalidAmount();
+
+    error InvalidDuration(uint256, uint256, uint256);
+
+    error InvalidParameters();
+
+    error TwapNotReady(address, uint256, uint256);
+
+    error PriceDeviation(address, uint256, uint256);
+
+    error InsufficientPremium(uint256, uint256);
+
+    error InsufficientCapital(uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error VaultDrained();
+
+    error ZeroShares(uint256);
+
+    error MathOverflow();
+
+    error UnknownPolicy(uint256);
+
+    error PolicyNotExpired(uint256, uint256);
+
+    error PolicyAlreadyClaimed(uint256);
+
+    error MintFailed(address, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,39 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface ILpAutoVault {
+    function init(address pair, uint64 deploy_nonce) external;
+
+    function deposit(uint256 lp) external;
+
+    function zapIn(address token_in, uint256 amount_in, uint256 min_lp) external returns (uint256);
+
+    function withdraw(uint256 lp) external;
+
+    function position(address account) external view returns (uint256, uint256, uint256, uint256);
+
+    function positionReport(address account) external view returns (uint256, uint256, uint256, uint256);
+
+    function quoteZapIn(address token_in, uint256 amount_in) external view returns (uint256, uint256);
+
+    function totalLp() external view returns (uint256);
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error InvalidToken(address);
+
+    error ZeroAmount();
+
+    error InsufficientLiquidityMinted(uint256, uint256);
+
+    error InsufficientPosition(uint256, uint256);
+
+    error ExternalCallFailed(address);
+}
@@ -0,0 +1,13 @@
+use stylus_lp_auto_vault::LpAutoVault;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../18_lp_auto_vault.rs")];
+
+fn main() {
+    abi_export::run::<LpAutoVault>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<LpAutoVault>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
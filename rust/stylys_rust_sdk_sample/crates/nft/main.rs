@@ -0,0 +1,13 @@
+use stylus_nft::StylusNFT;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../7_erc721.rs"), include_str!("../../erc721.rs")];
+
+fn main() {
+    abi_export::run::<StylusNFT>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<StylusNFT>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,79 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IPairBatch {
+    function setBatchMode(bool enabled, uint64 epoch_blocks) external;
+
+    function commitOrder(bool zero_for_one, uint256 amount_in) external returns (uint256);
+
+    function settleBatch(uint256 batch_id) external;
+
+    function openBatch() external view returns (uint256);
+
+    function batch(uint256 batch_id) external view returns (uint256, uint256, uint256, uint256, uint256, bool, bool);
+
+    error AlreadyInitialized();
+
+    error InsufficientOutputAmount();
+
+    error InsufficientInputAmount();
+
+    error InsufficientLiquidity(uint256, uint256);
+
+    error InsufficientLiquidityMinted();
+
+    error InsufficientLiquidityBurned();
+
+    error InvalidTo(address);
+
+    error CallbackFailed(address);
+
+    error KInvariantViolated(uint256, uint256);
+
+    error InvalidFeeConfig(uint256, uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error BatchModeEnabled();
+
+    error BatchModeDisabled();
+
+    error InvalidEpoch();
+
+    error UnknownBatch(uint256);
+
+    error BatchNotEnded(uint256, uint256);
+
+    error BatchAlreadySettled(uint256);
+
+    error BatchNotSettled(uint256);
+
+    error BatchWasCancelled(uint256);
+
+    error BatchNotCancelled(uint256);
+
+    error UnknownOrder(uint256);
+
+    error OrderAlreadyClaimed(uint256);
+
+    error LongTermOrdersDisabled();
+
+    error InvalidInterval();
+
+    error LongTermOrderClosed(uint256);
+
+    error ExternalCallFailed(address);
+
+    error MathOverflow();
+
+    error FacetCallFailed(address, bytes);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
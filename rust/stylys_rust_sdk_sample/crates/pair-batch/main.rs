@@ -0,0 +1,13 @@
+use stylus_pair_batch::PairBatch;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../23_pair_batch.rs"), include_str!("../../pair_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<PairBatch>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<PairBatch>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
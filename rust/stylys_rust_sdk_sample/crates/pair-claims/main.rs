@@ -0,0 +1,13 @@
+use stylus_pair_claims::PairClaims;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../23_pair_claims.rs"), include_str!("../../pair_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<PairClaims>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<PairClaims>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
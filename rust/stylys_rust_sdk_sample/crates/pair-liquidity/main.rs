@@ -0,0 +1,18 @@
+use stylus_pair_liquidity::PairLiquidity;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../23_pair_liquidity.rs"),
+    include_str!("../../pair_state.rs"),
+    include_str!("../../erc20.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<PairLiquidity>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<PairLiquidity>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
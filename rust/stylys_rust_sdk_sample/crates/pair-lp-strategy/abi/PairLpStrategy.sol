@@ -0,0 +1,43 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IPairLpStrategy {
+    function init(address vault, address asset, address pair, uint256 max_slippage_bps, uint64 deploy_nonce) external;
+
+    function asset() external view returns (address);
+
+    function vault() external view returns (address);
+
+    function pair() external view returns (address);
+
+    function updatePrice() external returns (uint256);
+
+    function deposit(uint256 _amount) external;
+
+    function withdraw(uint256 amount) external returns (uint256);
+
+    function totalAssets() external view returns (uint256);
+
+    function harvest() external;
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error OnlyVault(address);
+
+    error AssetNotInPair(address, address);
+
+    error InvalidSlippage(uint256);
+
+    error ExternalCallFailed(address);
+
+    error PriceNotReady(uint64);
+
+    error SlippageExceeded(uint256, uint256);
+}
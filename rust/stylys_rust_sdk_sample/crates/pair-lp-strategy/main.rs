@@ -0,0 +1,13 @@
+use stylus_pair_lp_strategy::PairLpStrategy;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../17_pair_lp_strategy.rs")];
+
+fn main() {
+    abi_export::run::<PairLpStrategy>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<PairLpStrategy>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,79 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IPairSwap {
+    function swap(uint256 amount0_out, uint256 amount1_out, address to, bytes calldata data) external;
+
+    function currentFee() external view returns (uint64);
+
+    function volatility() external view returns (uint256);
+
+    function feeConfig() external view returns (uint64, uint256, uint64, uint256);
+
+    function setFeeConfig(uint64 base_fee_bps, uint256 volatility_multiplier, uint64 max_fee_bps, uint256 decay) external;
+
+    error AlreadyInitialized();
+
+    error InsufficientOutputAmount();
+
+    error InsufficientInputAmount();
+
+    error InsufficientLiquidity(uint256, uint256);
+
+    error InsufficientLiquidityMinted();
+
+    error InsufficientLiquidityBurned();
+
+    error InvalidTo(address);
+
+    error CallbackFailed(address);
+
+    error KInvariantViolated(uint256, uint256);
+
+    error InvalidFeeConfig(uint256, uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error BatchModeEnabled();
+
+    error BatchModeDisabled();
+
+    error InvalidEpoch();
+
+    error UnknownBatch(uint256);
+
+    error BatchNotEnded(uint256, uint256);
+
+    error BatchAlreadySettled(uint256);
+
+    error BatchNotSettled(uint256);
+
+    error BatchWasCancelled(uint256);
+
+    error BatchNotCancelled(uint256);
+
+    error UnknownOrder(uint256);
+
+    error OrderAlreadyClaimed(uint256);
+
+    error LongTermOrdersDisabled();
+
+    error InvalidInterval();
+
+    error LongTermOrderClosed(uint256);
+
+    error ExternalCallFailed(address);
+
+    error MathOverflow();
+
+    error FacetCallFailed(address, bytes);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,13 @@
+use stylus_pair_swap::PairSwap;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../23_pair_swap.rs"), include_str!("../../pair_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<PairSwap>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<PairSwap>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
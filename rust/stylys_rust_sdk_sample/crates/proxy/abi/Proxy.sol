@@ -0,0 +1,41 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IProxy is IOwnable {
+    function init(address implementation, uint64 deploy_nonce) external;
+
+    function implementation() external view returns (address);
+
+    function setImplementation(address implementation) external;
+
+    function relayToImplementation(uint8[] memory data) external payable returns (uint8[] memory);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+
+    error ImplementationNotSet();
+
+    error DelegateCallFailed(address, bytes);
+}
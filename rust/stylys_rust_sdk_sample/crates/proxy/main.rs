@@ -0,0 +1,13 @@
+use stylus_proxy::Proxy;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../15_proxy.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<Proxy>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<Proxy>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,49 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IStableSwapExchange {
+    function getDy(uint256 i, uint256 j, uint256 dx) external view returns (uint256);
+
+    function exchange(uint256 i, uint256 j, uint256 dx, uint256 min_dy) external returns (uint256);
+
+    function calcWithdrawOneCoin(uint256 token_amount, uint256 i) external view returns (uint256);
+
+    function removeLiquidityOneCoin(uint256 token_amount, uint256 i, uint256 min_amount) external returns (uint256);
+
+    error AlreadyInitialized();
+
+    error InvalidCoins(uint256);
+
+    error InvalidCoinIndex(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error InvalidA(uint256);
+
+    error RampTooSoon(uint256, uint256);
+
+    error FeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error EmptyPool();
+
+    error InsufficientBalance(uint256, uint256, uint256);
+
+    error InvariantDidNotConverge();
+
+    error InvariantDecreased(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,14 @@
+use stylus_stable_swap_exchange::StableSwapExchange;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] =
+    &[include_str!("../../20_stable_swap_exchange.rs"), include_str!("../../stable_swap_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<StableSwapExchange>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<StableSwapExchange>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
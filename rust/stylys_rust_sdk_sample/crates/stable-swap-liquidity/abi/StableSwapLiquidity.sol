@@ -0,0 +1,51 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IStableSwapLiquidity {
+    function getVirtualPrice() external view returns (uint256);
+
+    function calcTokenAmount(uint256[] memory amounts, bool is_deposit) external view returns (uint256);
+
+    function addLiquidity(uint256[] memory amounts, uint256 min_mint_amount) external returns (uint256);
+
+    function removeLiquidity(uint256 amount, uint256[] memory min_amounts) external returns (uint256[] memory);
+
+    function removeLiquidityImbalance(uint256[] memory amounts, uint256 max_burn_amount) external returns (uint256);
+
+    error AlreadyInitialized();
+
+    error InvalidCoins(uint256);
+
+    error InvalidCoinIndex(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error InvalidA(uint256);
+
+    error RampTooSoon(uint256, uint256);
+
+    error FeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error EmptyPool();
+
+    error InsufficientBalance(uint256, uint256, uint256);
+
+    error InvariantDidNotConverge();
+
+    error InvariantDecreased(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,101 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IErc20 {
+    function name() external pure returns (string memory);
+
+    function symbol() external pure returns (string memory);
+
+    function decimals() external pure returns (uint8);
+
+    function totalSupply() external view returns (uint256);
+
+    function balanceOf(address _address) external view returns (uint256);
+
+    function transfer(address to, uint256 value) external returns (bool);
+
+    function approve(address spender, uint256 value) external returns (bool);
+
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    error InsufficientBalance(address, uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+}
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IStableSwapPool is IErc20, IOwnable {
+    function init(address[] memory coins, uint256 a, uint256 fee, address exchange_facet, address liquidity_facet, uint64 deploy_nonce) external;
+
+    function coins() external view returns (address[] memory);
+
+    function balances() external view returns (uint256[] memory);
+
+    function a() external view returns (uint256);
+
+    function aPrecise() external view returns (uint256);
+
+    function fee() external view returns (uint256);
+
+    function facets() external view returns (address, address);
+
+    function rampA(uint256 future_a, uint256 future_time) external;
+
+    function stopRampA() external;
+
+    function setFee(uint256 fee) external;
+
+    error AlreadyInitialized();
+
+    error InvalidCoins(uint256);
+
+    error InvalidCoinIndex(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error InvalidA(uint256);
+
+    error RampTooSoon(uint256, uint256);
+
+    error FeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error InsufficientShares(uint256, uint256);
+
+    error EmptyPool();
+
+    error InsufficientBalance(uint256, uint256, uint256);
+
+    error InvariantDidNotConverge();
+
+    error InvariantDecreased(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
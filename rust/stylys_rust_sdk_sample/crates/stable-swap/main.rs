@@ -0,0 +1,18 @@
+use stylus_stable_swap::StableSwapPool;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../20_stable_swap_pool.rs"),
+    include_str!("../../stable_swap_state.rs"),
+    include_str!("../../erc20.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<StableSwapPool>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<StableSwapPool>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
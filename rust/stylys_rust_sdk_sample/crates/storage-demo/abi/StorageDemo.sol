@@ -0,0 +1,41 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IStorageDemo is IOwnable {
+    function init(string calldata name, uint64 deploy_nonce) external;
+
+    function name() external view returns (string memory);
+
+    function active() external view returns (bool);
+
+    function setName(string calldata name) external;
+
+    function setActive(bool active) external;
+
+    function clear() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,13 @@
+use stylus_storage_demo::StorageDemo;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../25_storage_demo.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<StorageDemo>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<StorageDemo>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
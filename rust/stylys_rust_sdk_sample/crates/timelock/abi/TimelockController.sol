@@ -0,0 +1,79 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface ITimelockController {
+    function init(uint256 min_delay, address[] memory proposers, address[] memory executors, address admin, uint64 deploy_nonce) external;
+
+    function hashOperation(address target, uint256 value, bytes calldata data, bytes32 predecessor, bytes32 salt) external view returns (bytes32);
+
+    function hashOperationBatch(address[] memory targets, uint256[] memory values, bytes[] memory payloads, bytes32 predecessor, bytes32 salt) external view returns (bytes32);
+
+    function schedule(address target, uint256 value, bytes calldata data, bytes32 predecessor, bytes32 salt, uint256 delay) external returns (bytes32);
+
+    function scheduleBatch(address[] memory targets, uint256[] memory values, bytes[] memory payloads, bytes32 predecessor, bytes32 salt, uint256 delay) external returns (bytes32);
+
+    function execute(address target, uint256 value, bytes calldata payload, bytes32 predecessor, bytes32 salt) external payable;
+
+    function executeBatch(address[] memory targets, uint256[] memory values, bytes[] memory payloads, bytes32 predecessor, bytes32 salt) external payable;
+
+    function cancel(bytes32 id) external;
+
+    function updateDelay(uint256 new_delay) external;
+
+    function getMinDelay() external view returns (uint256);
+
+    function getTimestamp(bytes32 id) external view returns (uint256);
+
+    function getOperationState(bytes32 id) external view returns (uint8);
+
+    function isOperation(bytes32 id) external view returns (bool);
+
+    function isOperationPending(bytes32 id) external view returns (bool);
+
+    function isOperationReady(bytes32 id) external view returns (bool);
+
+    function isOperationDone(bytes32 id) external view returns (bool);
+
+    function hasRole(bytes32 role, address account) external view returns (bool);
+
+    function grantRole(bytes32 role, address account) external;
+
+    function revokeRole(bytes32 role, address account) external;
+
+    function renounceRole(bytes32 role) external;
+
+    function PROPOSER_ROLE() external view returns (bytes32);
+
+    function EXECUTOR_ROLE() external view returns (bytes32);
+
+    function CANCELLER_ROLE() external view returns (bytes32);
+
+    function TIMELOCK_ADMIN_ROLE() external view returns (bytes32);
+
+    function receiveEth() external payable;
+
+    error AlreadyInitialized();
+
+    error NotDeployer(address);
+
+    error MissingRole(address, bytes32);
+
+    error InvalidOperationLength(uint256, uint256, uint256);
+
+    error InsufficientDelay(uint256, uint256);
+
+    error DelayOverflow(uint256);
+
+    error UnexpectedOperationState(bytes32, uint8);
+
+    error UnexecutedPredecessor(bytes32);
+
+    error UnauthorizedCaller(address);
+
+    error CallFailed(address, bytes);
+}
@@ -0,0 +1,13 @@
+use stylus_timelock::TimelockController;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../16_timelock.rs")];
+
+fn main() {
+    abi_export::run::<TimelockController>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<TimelockController>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
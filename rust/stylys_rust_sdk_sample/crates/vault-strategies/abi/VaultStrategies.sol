@@ -0,0 +1,55 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IVaultStrategies {
+    function addStrategy(address strategy, uint256 debtRatio) external;
+
+    function updateStrategyDebtRatio(address strategy, uint256 debtRatio) external;
+
+    function setWithdrawalQueue(address[] memory queue) external;
+
+    function setPerformanceFee(uint256 fee, address recipient) external;
+
+    function harvest(address strategy) external returns (uint256, uint256);
+
+    function strategy(address strategy) external view returns (bool, uint256, uint256, uint256, uint256, uint64);
+
+    function withdrawalQueue() external view returns (address[] memory);
+
+    error Overflow();
+
+    error DivideByZero();
+
+    error InsufficientShares(uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+
+    error AssetTransferFailed(address, uint256);
+
+    error ZeroShares(uint256);
+
+    error InvalidDecimalsOffset(uint8);
+
+    error StrategyCallFailed(address);
+
+    error InvalidStrategy(address);
+
+    error DebtRatioTooHigh(uint256);
+
+    error WithdrawalQueueFull();
+
+    error FeeTooHigh(uint256);
+
+    error ExcessiveLoss(uint256, uint256);
+
+    error WithdrawalShortfall(uint256, uint256);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
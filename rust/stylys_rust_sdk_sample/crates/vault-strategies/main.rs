@@ -0,0 +1,18 @@
+use stylus_vault_strategies::VaultStrategies;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../8_smart_vault_strategies.rs"),
+    include_str!("../../vault_state.rs"),
+    include_str!("../../erc20.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<VaultStrategies>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<VaultStrategies>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,107 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IErc20 {
+    function name() external pure returns (string memory);
+
+    function symbol() external pure returns (string memory);
+
+    function decimals() external pure returns (uint8);
+
+    function totalSupply() external view returns (uint256);
+
+    function balanceOf(address _address) external view returns (uint256);
+
+    function transfer(address to, uint256 value) external returns (bool);
+
+    function approve(address spender, uint256 value) external returns (bool);
+
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    error InsufficientBalance(address, uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+}
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IVault is IErc20, IOwnable {
+    function init(address _asset, uint8 _decimals_offset, address strategies_facet, uint64 deploy_nonce) external;
+
+    function setAsset(address _asset) external returns (address);
+
+    function deposit(uint256 amount) external returns (uint256);
+
+    function withdraw(uint256 amount) external returns (uint256);
+
+    function withdrawWithMaxLoss(uint256 amount, uint256 maxLoss) external returns (uint256);
+
+    function asset() external view returns (address);
+
+    function decimalsOffset() external view returns (uint8);
+
+    function strategiesFacet() external view returns (address);
+
+    function totalAssets() external view returns (uint256);
+
+    function convertToShares(uint256 assets) external view returns (uint256);
+
+    function convertToAssets(uint256 shares) external view returns (uint256);
+
+    function previewWithdraw(uint256 assets) external view returns (uint256);
+
+    function totalDebt() external view returns (uint256);
+
+    error Overflow();
+
+    error DivideByZero();
+
+    error InsufficientShares(uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+
+    error AssetTransferFailed(address, uint256);
+
+    error ZeroShares(uint256);
+
+    error InvalidDecimalsOffset(uint8);
+
+    error StrategyCallFailed(address);
+
+    error InvalidStrategy(address);
+
+    error DebtRatioTooHigh(uint256);
+
+    error WithdrawalQueueFull();
+
+    error FeeTooHigh(uint256);
+
+    error ExcessiveLoss(uint256, uint256);
+
+    error WithdrawalShortfall(uint256, uint256);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,49 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IWeightedPoolLiquidity {
+    function getInvariant() external view returns (uint256);
+
+    function initialJoin(uint256[] memory amounts_in) external returns (uint256);
+
+    function joinPool(uint256 bpt_out, uint256[] memory max_amounts_in) external returns (uint256[] memory);
+
+    function joinSwapExternAmountIn(uint256 index, uint256 amount_in, uint256 min_bpt_out) external returns (uint256);
+
+    function exitPool(uint256 bpt_in, uint256[] memory min_amounts_out) external returns (uint256[] memory);
+
+    function exitSwapPoolAmountIn(uint256 index, uint256 bpt_in, uint256 min_amount_out) external returns (uint256);
+
+    error AlreadyInitialized();
+
+    error NotInitialized();
+
+    error InvalidTokens(uint256);
+
+    error InvalidTokenIndex(uint256);
+
+    error SameToken(uint256);
+
+    error InvalidWeights(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error SwapFeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error RatioTooLarge();
+
+    error InsufficientShares(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,18 @@
+use stylus_weighted_pool_liquidity::WeightedPoolLiquidity;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../21_weighted_pool_liquidity.rs"),
+    include_str!("../../weighted_pool_state.rs"),
+    include_str!("../../erc20.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<WeightedPoolLiquidity>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<WeightedPoolLiquidity>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
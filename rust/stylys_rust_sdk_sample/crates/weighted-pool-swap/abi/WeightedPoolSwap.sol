@@ -0,0 +1,47 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IWeightedPoolSwap {
+    function getSpotPrice(uint256 i, uint256 o) external view returns (uint256);
+
+    function getAmountOut(uint256 i, uint256 o, uint256 amount_in) external view returns (uint256);
+
+    function getAmountIn(uint256 i, uint256 o, uint256 amount_out) external view returns (uint256);
+
+    function swapExactAmountIn(uint256 i, uint256 amount_in, uint256 o, uint256 min_amount_out) external returns (uint256);
+
+    function swapExactAmountOut(uint256 i, uint256 max_amount_in, uint256 o, uint256 amount_out) external returns (uint256);
+
+    error AlreadyInitialized();
+
+    error NotInitialized();
+
+    error InvalidTokens(uint256);
+
+    error InvalidTokenIndex(uint256);
+
+    error SameToken(uint256);
+
+    error InvalidWeights(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error SwapFeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error RatioTooLarge();
+
+    error InsufficientShares(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
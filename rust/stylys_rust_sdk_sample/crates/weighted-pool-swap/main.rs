@@ -0,0 +1,14 @@
+use stylus_weighted_pool_swap::WeightedPoolSwap;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] =
+    &[include_str!("../../21_weighted_pool_swap.rs"), include_str!("../../weighted_pool_state.rs"), include_str!("../../ownable.rs")];
+
+fn main() {
+    abi_export::run::<WeightedPoolSwap>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<WeightedPoolSwap>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,91 @@
+/**
+ * This file was automatically generated by Stylus and represents a Rust program.
+ * For more information, please see [The Stylus SDK](https://github.com/OffchainLabs/stylus-sdk-rs).
+ */
+
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+interface IErc20 {
+    function name() external pure returns (string memory);
+
+    function symbol() external pure returns (string memory);
+
+    function decimals() external pure returns (uint8);
+
+    function totalSupply() external view returns (uint256);
+
+    function balanceOf(address _address) external view returns (uint256);
+
+    function transfer(address to, uint256 value) external returns (bool);
+
+    function approve(address spender, uint256 value) external returns (bool);
+
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    error InsufficientBalance(address, uint256, uint256);
+
+    error InsufficientAllowance(address, address, uint256, uint256);
+}
+
+interface IOwnable {
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address new_owner) external;
+
+    function acceptOwnership() external;
+
+    function renounceOwnership() external;
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
+
+interface IWeightedPool is IErc20, IOwnable {
+    function init(address[] memory tokens, uint256[] memory weights, uint256 swap_fee, address swap_facet, address liquidity_facet, uint64 deploy_nonce) external;
+
+    function getTokens() external view returns (address[] memory);
+
+    function getBalances() external view returns (uint256[] memory);
+
+    function getNormalizedWeights() external view returns (uint256[] memory);
+
+    function getSwapFee() external view returns (uint256);
+
+    function facets() external view returns (address, address);
+
+    function setSwapFee(uint256 swap_fee) external;
+
+    error AlreadyInitialized();
+
+    error NotInitialized();
+
+    error InvalidTokens(uint256);
+
+    error InvalidTokenIndex(uint256);
+
+    error SameToken(uint256);
+
+    error InvalidWeights(uint256);
+
+    error InvalidAmounts(uint256, uint256);
+
+    error SwapFeeTooHigh(uint256);
+
+    error Slippage(uint256, uint256);
+
+    error RatioTooLarge();
+
+    error InsufficientShares(uint256, uint256);
+
+    error ExternalCallFailed(address);
+
+    error Unauthorized(address);
+
+    error InvalidOwner(address);
+}
@@ -0,0 +1,18 @@
+use stylus_weighted_pool::WeightedPool;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[
+    include_str!("../../21_weighted_pool.rs"),
+    include_str!("../../weighted_pool_state.rs"),
+    include_str!("../../erc20.rs"),
+    include_str!("../../ownable.rs"),
+];
+
+fn main() {
+    abi_export::run::<WeightedPool>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<WeightedPool>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
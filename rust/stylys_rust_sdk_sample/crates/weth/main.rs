@@ -0,0 +1,13 @@
+use stylus_weth::Weth;
+
+// Sources whose `event` and `error` declarations fill in the JSON ABI.
+const SOURCES: &[&str] = &[include_str!("../../26_weth.rs"), include_str!("../../erc20.rs")];
+
+fn main() {
+    abi_export::run::<Weth>(SOURCES);
+}
+
+#[test]
+fn abi_matches_snapshot() {
+    abi_export::check_snapshot::<Weth>(concat!(env!("CARGO_MANIFEST_DIR"), "/abi"), SOURCES);
+}
@@ -0,0 +1,30 @@
+// Stylus 0.5 has no constructors: a program is deployed first and set up by a
+// later `init` call, which anyone watching the mempool could send first.
+// `init` takes the nonce the deployer used for the CREATE transaction, and only
+// the account whose CREATE address at that nonce is this contract can pass.
+use alloc::vec::Vec;
+use stylus_sdk::{alloy_primitives::Address, contract, crypto};
+
+// keccak(rlp([deployer, nonce]))[12..]
+pub fn create_address(deployer: Address, nonce: u64) -> Address {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+    let mut rlp = Vec::with_capacity(31);
+    rlp.push(0);
+    rlp.push(0x80 + 20);
+    rlp.extend_from_slice(deployer.as_slice());
+    match nonce_bytes {
+        [] => rlp.push(0x80),
+        [byte] if *byte < 0x80 => rlp.push(*byte),
+        bytes => {
+            rlp.push(0x80 + bytes.len() as u8);
+            rlp.extend_from_slice(bytes);
+        }
+    }
+    rlp[0] = 0xc0 + (rlp.len() - 1) as u8;
+    Address::from_slice(&crypto::keccak(&rlp)[12..])
+}
+
+pub fn is_deployer(account: Address, deploy_nonce: u64) -> bool {
+    create_address(account, deploy_nonce) == contract::address()
+}
@@ -0,0 +1,161 @@
+// Mappings can't be erased because their keys aren't known (see NotErase in 2_storage.rs).
+// These types record every key next to the mapping, so they can be iterated and erased.
+// Erasing touches every entry, so large collections should be cleared a page at
+// a time with `erase_n` rather than with `Erase::erase`.
+#![allow(dead_code)]
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+    storage::{Erase, SimpleStorageType, StorageAddress, StorageKey, StorageMap, StorageType, StorageU256, StorageVec},
+};
+
+// A storage slot type whose value can key a mapping, i.e. what a set can hold.
+pub trait SetItem: for<'a> SimpleStorageType<'a> + for<'a> StorageType<Wraps<'a> = Self::Value> {
+    type Value: StorageKey + Copy + PartialEq;
+}
+
+impl SetItem for StorageAddress {
+    type Value = Address;
+}
+
+impl SetItem for StorageU256 {
+    type Value = U256;
+}
+
+#[solidity_storage]
+pub struct EnumerableSet<S: SetItem> {
+    values: StorageVec<S>,
+    // value => index + 1 in values (0 = not in the set)
+    positions: StorageMap<S::Value, StorageU256>,
+}
+
+pub type AddressSet = EnumerableSet<StorageAddress>;
+pub type UintSet = EnumerableSet<StorageU256>;
+
+impl<S: SetItem> EnumerableSet<S> {
+    pub fn contains(&self, value: S::Value) -> bool {
+        self.positions.get(value) != U256::ZERO
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn at(&self, index: usize) -> Option<S::Value> {
+        self.values.get(index)
+    }
+
+    // Returns false if the value was already present.
+    pub fn add(&mut self, value: S::Value) -> bool {
+        if self.contains(value) {
+            return false;
+        }
+        self.values.push(value);
+        self.positions.insert(value, U256::from(self.values.len()));
+        true
+    }
+
+    // Swap-and-pop, so the order of the remaining values may change.
+    pub fn remove(&mut self, value: S::Value) -> bool {
+        let position = self.positions.get(value);
+        if position == U256::ZERO {
+            return false;
+        }
+        let index = position.saturating_to::<usize>() - 1;
+        let last_index = self.values.len() - 1;
+        if index != last_index {
+            let last = self.values.get(last_index).unwrap();
+            self.values.setter(index).unwrap().set_by_wrapped(last);
+            self.positions.insert(last, position);
+        }
+        self.values.erase_last();
+        self.positions.delete(value);
+        true
+    }
+
+    // Pops up to `limit` values off the end and returns how many are left.
+    pub fn erase_n(&mut self, limit: usize) -> usize {
+        for _ in 0..limit.min(self.values.len()) {
+            let value = self.values.get(self.values.len() - 1).unwrap();
+            self.positions.delete(value);
+            self.values.erase_last();
+        }
+        self.values.len()
+    }
+
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<S::Value> {
+        let end = self.len().min(offset.saturating_add(limit));
+        (offset..end).map(|i| self.values.get(i).unwrap()).collect()
+    }
+}
+
+impl<S: SetItem> Erase for EnumerableSet<S> {
+    fn erase(&mut self) {
+        self.erase_n(self.values.len());
+    }
+}
+
+sol_storage! {
+    pub struct AddressUintMap {
+        AddressSet keys;
+        mapping(address => uint256) values;
+    }
+}
+
+impl AddressUintMap {
+    pub fn contains(&self, key: Address) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn get(&self, key: Address) -> U256 {
+        self.values.get(key)
+    }
+
+    pub fn at(&self, index: usize) -> Option<(Address, U256)> {
+        let key = self.keys.at(index)?;
+        Some((key, self.values.get(key)))
+    }
+
+    pub fn insert(&mut self, key: Address, value: U256) -> bool {
+        self.values.insert(key, value);
+        self.keys.add(key)
+    }
+
+    pub fn remove(&mut self, key: Address) -> bool {
+        self.values.delete(key);
+        self.keys.remove(key)
+    }
+
+    // Deletes up to `limit` entries from the end and returns how many are left.
+    pub fn erase_n(&mut self, limit: usize) -> usize {
+        let len = self.keys.len();
+        for i in len - limit.min(len)..len {
+            let key = self.keys.at(i).unwrap();
+            self.values.delete(key);
+        }
+        self.keys.erase_n(limit)
+    }
+
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<(Address, U256)> {
+        self.keys.page(offset, limit).into_iter().map(|key| (key, self.values.get(key))).collect()
+    }
+}
+
+impl Erase for AddressUintMap {
+    fn erase(&mut self) {
+        self.erase_n(self.keys.len());
+    }
+}
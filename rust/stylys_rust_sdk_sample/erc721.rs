@@ -0,0 +1,234 @@
+// The ERC-721 module the NFT lessons build on, as in the Stylus erc721
+// example: `Erc721<T>` is inherited by the entrypoint, and `T` supplies name,
+// symbol and token URIs at compile time.
+// Each lesson only uses part of it.
+#![allow(dead_code)]
+use alloc::{string::String, vec, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use core::marker::PhantomData;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256},
+    call::RawCall,
+    evm, msg,
+    prelude::*,
+};
+
+pub trait Erc721Params {
+    const NAME: &'static str;
+    const SYMBOL: &'static str;
+
+    fn token_uri(_token_id: U256) -> String {
+        String::new()
+    }
+}
+
+sol_storage! {
+    pub struct Erc721<T> {
+        mapping(uint256 => address) owners;
+        mapping(uint256 => address) approved;
+        mapping(address => uint256) balance;
+        mapping(address => mapping(address => bool)) approved_for_all;
+        uint256 total_supply;
+        PhantomData<T> phantom;
+    }
+}
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
+    event Approval(address indexed owner, address indexed approved, uint256 indexed token_id);
+    event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+
+    error InvalidTokenId(uint256 token_id);
+    error NotOwner(address from, uint256 token_id, address real_owner);
+    error NotApproved(address owner, address spender, uint256 token_id);
+    error TransferToZero(uint256 token_id);
+    error ReceiverRefused(address receiver, uint256 token_id, bytes4 returned);
+}
+
+#[derive(SolidityError)]
+pub enum Erc721Error {
+    InvalidTokenId(InvalidTokenId),
+    NotOwner(NotOwner),
+    NotApproved(NotApproved),
+    TransferToZero(TransferToZero),
+    ReceiverRefused(ReceiverRefused),
+}
+
+sol! {
+    function onERC721Received(address operator, address from, uint256 token_id, bytes data) external returns (bytes4);
+}
+
+const ERC721_TOKEN_RECEIVER_ID: u32 = 0x150b7a02;
+
+impl<T: Erc721Params> Erc721<T> {
+    fn require_authorized_to_spend(&self, from: Address, token_id: U256) -> Result<(), Erc721Error> {
+        let owner = self.owner_of(token_id)?;
+        if from != owner {
+            return Err(Erc721Error::NotOwner(NotOwner { from, token_id, real_owner: owner }));
+        }
+        if msg::sender() == owner || self.approved_for_all.getter(owner).get(msg::sender()) || msg::sender() == self.approved.get(token_id)
+        {
+            return Ok(());
+        }
+        Err(Erc721Error::NotApproved(NotApproved { owner, spender: msg::sender(), token_id }))
+    }
+
+    pub fn _transfer(&mut self, token_id: U256, from: Address, to: Address) -> Result<(), Erc721Error> {
+        let mut owner = self.owners.setter(token_id);
+        let previous_owner = owner.get();
+        if previous_owner != from {
+            return Err(Erc721Error::NotOwner(NotOwner { from, token_id, real_owner: previous_owner }));
+        }
+        owner.set(to);
+
+        // `from` can be the zero address when minting, and `to` when burning.
+        let mut from_balance = self.balance.setter(from);
+        let balance = from_balance.get() - U256::from(1);
+        from_balance.set(balance);
+
+        let mut to_balance = self.balance.setter(to);
+        let balance = to_balance.get() + U256::from(1);
+        to_balance.set(balance);
+
+        self.approved.delete(token_id);
+        evm::log(Transfer { from, to, token_id });
+        Ok(())
+    }
+
+    // Asks a contract recipient to accept the token; EOAs always accept.
+    fn call_receiver(token_id: U256, from: Address, to: Address, data: Vec<u8>) -> Result<(), Erc721Error> {
+        if !to.has_code() {
+            return Ok(());
+        }
+        let call = onERC721ReceivedCall { operator: msg::sender(), from, token_id, data: data.into() };
+        let returned = RawCall::new()
+            .call(to, &call.abi_encode())
+            .ok()
+            .and_then(|output| onERC721ReceivedCall::abi_decode_returns(&output, true).ok())
+            .map(|output| output._0)
+            .unwrap_or_default();
+        if u32::from_be_bytes(returned.0) != ERC721_TOKEN_RECEIVER_ID {
+            return Err(Erc721Error::ReceiverRefused(ReceiverRefused { receiver: to, token_id, returned }));
+        }
+        Ok(())
+    }
+
+    pub fn safe_transfer(&mut self, token_id: U256, from: Address, to: Address, data: Vec<u8>) -> Result<(), Erc721Error> {
+        self._transfer(token_id, from, to)?;
+        Self::call_receiver(token_id, from, to, data)
+    }
+
+    pub fn mint(&mut self, to: Address) -> Result<U256, Erc721Error> {
+        let token_id = self.total_supply.get();
+        self._mint(to, token_id)?;
+        self.total_supply.set(token_id + U256::from(1));
+        Ok(token_id)
+    }
+
+    // Mints an id picked by the caller; it fails if the id already exists.
+    pub fn _mint(&mut self, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::TransferToZero(TransferToZero { token_id }));
+        }
+        self._transfer(token_id, Address::ZERO, to)
+    }
+
+    // `_mint` plus the IERC721TokenReceiver check for contract recipients.
+    pub fn _safe_mint(&mut self, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self._mint(to, token_id)?;
+        Self::call_receiver(token_id, Address::ZERO, to, Vec::new())
+    }
+
+    pub fn burn(&mut self, from: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self._transfer(token_id, from, Address::ZERO)
+    }
+}
+
+#[external]
+impl<T: Erc721Params> Erc721<T> {
+    pub fn name() -> Result<String, Erc721Error> {
+        Ok(T::NAME.into())
+    }
+
+    pub fn symbol() -> Result<String, Erc721Error> {
+        Ok(T::SYMBOL.into())
+    }
+
+    #[selector(name = "tokenURI")]
+    pub fn token_uri(&self, token_id: U256) -> Result<String, Erc721Error> {
+        self.owner_of(token_id)?;
+        Ok(T::token_uri(token_id))
+    }
+
+    pub fn supports_interface(interface: FixedBytes<4>) -> Result<bool, Erc721Error> {
+        let interface_slice_array: [u8; 4] = interface.as_slice().try_into().unwrap();
+        if u32::from_be_bytes(interface_slice_array) == 0xffffffff {
+            // special cased in the ERC165 standard
+            return Ok(false);
+        }
+        const IERC165: u32 = 0x01ffc9a7;
+        const IERC721: u32 = 0x80ac58cd;
+        const IERC721_METADATA: u32 = 0x5b5e139f;
+        Ok(matches!(u32::from_be_bytes(interface_slice_array), IERC165 | IERC721 | IERC721_METADATA))
+    }
+
+    pub fn balance_of(&self, owner: Address) -> Result<U256, Erc721Error> {
+        Ok(self.balance.get(owner))
+    }
+
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        let owner = self.owners.get(token_id);
+        if owner.is_zero() {
+            return Err(Erc721Error::InvalidTokenId(InvalidTokenId { token_id }));
+        }
+        Ok(owner)
+    }
+
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data(&mut self, from: Address, to: Address, token_id: U256, data: Bytes) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::TransferToZero(TransferToZero { token_id }));
+        }
+        self.require_authorized_to_spend(from, token_id)?;
+        self.safe_transfer(token_id, from, to, data.0)
+    }
+
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self.safe_transfer_from_with_data(from, to, token_id, Bytes(vec![]))
+    }
+
+    pub fn transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::TransferToZero(TransferToZero { token_id }));
+        }
+        self.require_authorized_to_spend(from, token_id)?;
+        self._transfer(token_id, from, to)
+    }
+
+    pub fn approve(&mut self, approved: Address, token_id: U256) -> Result<(), Erc721Error> {
+        let owner = self.owner_of(token_id)?;
+        if msg::sender() != owner && !self.approved_for_all.getter(owner).get(msg::sender()) {
+            return Err(Erc721Error::NotApproved(NotApproved { owner, spender: msg::sender(), token_id }));
+        }
+        self.approved.insert(token_id, approved);
+        evm::log(Approval { owner, approved, token_id });
+        Ok(())
+    }
+
+    pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<(), Erc721Error> {
+        let owner = msg::sender();
+        self.approved_for_all.setter(owner).insert(operator, approved);
+        evm::log(ApprovalForAll { owner, operator, approved });
+        Ok(())
+    }
+
+    pub fn get_approved(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        Ok(self.approved.get(token_id))
+    }
+
+    pub fn is_approved_for_all(&self, owner: Address, operator: Address) -> Result<bool, Erc721Error> {
+        Ok(self.approved_for_all.getter(owner).get(operator))
+    }
+}
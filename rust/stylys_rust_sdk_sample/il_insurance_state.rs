@@ -0,0 +1,230 @@
+// State and helpers shared by the IL insurance vault (24_il_insurance.rs), its
+// policy desk (24_il_insurance_policies.rs) and its underwriting
+// (24_il_insurance_underwriting.rs). Together they are too big for one Stylus
+// program, so the vault delegate_calls the other two and they run on the
+// vault's storage.
+//
+// Layout rule: all three start their storage with `IlInsuranceState` at slot 0,
+// and the vault's policy NFT comes right after it, where the desk finds it to
+// mint and look up policies.
+#![allow(dead_code)]
+use crate::erc721::Erc721Params;
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloy_sol_types::sol;
+use amm_math::fixed_point::WAD;
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    prelude::*,
+    storage::TopLevelStorage,
+};
+
+pub struct PolicyParams;
+
+impl Erc721Params for PolicyParams {
+    const NAME: &'static str = "IL Insurance Policy";
+    const SYMBOL: &'static str = "ILP";
+}
+
+// The pair from 23_pair.rs: spot reserves, V2 cumulative prices, the per-block
+// volatility that also drives its fee, and its LP token.
+sol_interface! {
+    interface IPair {
+        function getReserves() external view returns (uint256, uint256, uint32);
+        function price0CumulativeLast() external view returns (uint256);
+        function volatility() external view returns (uint256);
+        function totalSupply() external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+// What each facet serves; the vault forwards these selectors to it.
+sol! {
+    interface IIlInsurancePolicies {
+        function quotePremium(address pool, uint256 liquidity, uint256 duration) external view returns (uint256);
+        function insurePosition(address pool, uint256 liquidity, uint256 duration) external payable returns (uint256);
+        function claim(uint256 policy_id) external returns (uint256);
+    }
+
+    interface IIlInsuranceUnderwriting {
+        function setPool(address pool, bool enabled) external;
+        function poke(address pool) external;
+        function twapPrice(address pool) external view returns (uint256);
+        function deposit() external payable returns (uint256);
+        function withdraw(uint256 shares) external returns (uint256);
+        function sharesOf(address underwriter) external view returns (uint256);
+        function vault() external view returns (uint256, uint256, uint256);
+    }
+}
+
+sol! {
+    error AlreadyInitialized();
+    error PoolNotEnabled(address pool);
+    error InvalidAmount();
+    error InvalidDuration(uint256 duration, uint256 min, uint256 max);
+    error InvalidParameters();
+    error TwapNotReady(address pool, uint256 elapsed, uint256 window);
+    error PriceDeviation(address pool, uint256 spot, uint256 twap);
+    error InsufficientPremium(uint256 paid, uint256 premium);
+    error InsufficientCapital(uint256 free, uint256 needed);
+    error InsufficientShares(uint256 have, uint256 want);
+    error VaultDrained();
+    error ZeroShares(uint256 amount);
+    error MathOverflow();
+    error UnknownPolicy(uint256 policy_id);
+    error PolicyNotExpired(uint256 policy_id, uint256 expiry);
+    error PolicyAlreadyClaimed(uint256 policy_id);
+    error MintFailed(address to, uint256 token_id);
+    error ExternalCallFailed(address target);
+}
+
+#[derive(SolidityError)]
+pub enum InsuranceError {
+    AlreadyInitialized(AlreadyInitialized),
+    PoolNotEnabled(PoolNotEnabled),
+    InvalidAmount(InvalidAmount),
+    InvalidDuration(InvalidDuration),
+    InvalidParameters(InvalidParameters),
+    TwapNotReady(TwapNotReady),
+    PriceDeviation(PriceDeviation),
+    InsufficientPremium(InsufficientPremium),
+    InsufficientCapital(InsufficientCapital),
+    InsufficientShares(InsufficientShares),
+    VaultDrained(VaultDrained),
+    ZeroShares(ZeroShares),
+    MathOverflow(MathOverflow),
+    UnknownPolicy(UnknownPolicy),
+    PolicyNotExpired(PolicyNotExpired),
+    PolicyAlreadyClaimed(PolicyAlreadyClaimed),
+    MintFailed(MintFailed),
+    ExternalCallFailed(ExternalCallFailed),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for InsuranceError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => InsuranceError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => InsuranceError::InvalidOwner(e),
+        }
+    }
+}
+
+sol_storage! {
+    pub struct IlInsuranceState {
+        mapping(address => PoolOracle) pools;
+        mapping(uint256 => Policy) policies;
+        uint256 next_policy_id;
+
+        // vault: premiums in, payouts out
+        uint256 total_capital;
+        // sum of max payouts of unclaimed policies
+        uint256 locked_capital;
+        uint256 total_shares;
+        mapping(address => uint256) shares;
+
+        uint256 twap_window;
+        uint256 blocks_per_year;
+        uint256 loading_bps;
+        uint256 min_premium_bps;
+        uint256 max_coverage_bps;
+        uint256 min_duration;
+        uint256 max_duration;
+
+        // set once by the vault's init
+        address policies_facet;
+        address underwriting_facet;
+        #[borrow]
+        Ownable ownable;
+    }
+
+    pub struct PoolOracle {
+        bool enabled;
+        uint256 older_cumulative;
+        uint256 older_timestamp;
+        uint256 newer_cumulative;
+        uint256 newer_timestamp;
+    }
+
+    pub struct Policy {
+        address pool;
+        // LP tokens held in escrow until the claim
+        uint256 liquidity;
+        // notional, wei
+        uint256 amount;
+        uint256 entry_price;
+        uint256 expiry;
+        uint256 max_payout;
+        bool claimed;
+    }
+}
+
+impl IlInsuranceState {
+    pub fn failed(target: Address) -> InsuranceError {
+        InsuranceError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    pub fn free_capital(&self) -> U256 {
+        self.total_capital.get().saturating_sub(self.locked_capital.get())
+    }
+}
+
+// The TWAP oracle, which needs the contract itself for the pair calls. Any
+// storage struct that starts with `IlInsuranceState` gets these.
+pub trait Oracle: TopLevelStorage + BorrowMut<IlInsuranceState> + Sized {
+    // The pair's price0 cumulative as of `now`, extended past its last update with
+    // the current spot price (the V2 oracle library's counterfactual).
+    fn current_cumulative(&self, pool: Address, now: U256) -> Result<U256, InsuranceError> {
+        let pair = IPair::new(pool);
+        let cumulative = pair.price_0_cumulative_last(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        let (reserve0, reserve1, last) = pair.get_reserves(self).map_err(|_| IlInsuranceState::failed(pool))?;
+        let elapsed = now.wrapping_to::<u32>().wrapping_sub(last);
+        if elapsed == 0 || reserve0 == U256::ZERO {
+            return Ok(cumulative);
+        }
+        Ok(cumulative.wrapping_add(reserve1 * WAD / reserve0 * U256::from(elapsed)))
+    }
+
+    // Moves the newer checkpoint to `now` once it is a full window old.
+    fn roll(&mut self, pool: Address, now: U256) -> Result<(), InsuranceError> {
+        let state: &IlInsuranceState = self.borrow();
+        if !state.pools.get(pool).enabled.get() {
+            return Err(InsuranceError::PoolNotEnabled(PoolNotEnabled { pool }));
+        }
+        let (newer_cumulative, newer_timestamp) = {
+            let oracle = state.pools.get(pool);
+            (oracle.newer_cumulative.get(), oracle.newer_timestamp.get())
+        };
+        if now - newer_timestamp < state.twap_window.get() {
+            return Ok(());
+        }
+        let cumulative = self.current_cumulative(pool, now)?;
+        let state: &mut IlInsuranceState = self.borrow_mut();
+        let mut oracle = state.pools.setter(pool);
+        oracle.older_cumulative.set(newer_cumulative);
+        oracle.older_timestamp.set(newer_timestamp);
+        oracle.newer_cumulative.set(cumulative);
+        oracle.newer_timestamp.set(now);
+        Ok(())
+    }
+
+    // Average price0 since the newest checkpoint at least a window old.
+    fn twap(&self, pool: Address, now: U256) -> Result<U256, InsuranceError> {
+        let state: &IlInsuranceState = self.borrow();
+        let window = state.twap_window.get();
+        let oracle = state.pools.get(pool);
+        let (mut cumulative, mut timestamp) = (oracle.newer_cumulative.get(), oracle.newer_timestamp.get());
+        if now - timestamp < window {
+            (cumulative, timestamp) = (oracle.older_cumulative.get(), oracle.older_timestamp.get());
+        }
+        let elapsed = now - timestamp;
+        if timestamp == U256::ZERO || elapsed < window || elapsed == U256::ZERO {
+            return Err(InsuranceError::TwapNotReady(TwapNotReady { pool, elapsed, window }));
+        }
+        Ok(self.current_cumulative(pool, now)?.wrapping_sub(cumulative) / elapsed)
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<IlInsuranceState>> Oracle for T {}
@@ -0,0 +1,66 @@
+// Diamond storage (EIP-2535): a storage struct rooted at keccak256(name)
+// instead of at the next free slot of the struct that holds it.
+//
+// Contracts that run on one account's storage through delegate_call (the
+// diamond and its facets, the pair and its facets) would otherwise all lay
+// their fields out from slot 0 over each other. Each one keeps its own state
+// under its own name instead, so the only way two of them share a slot is by
+// naming the same namespace. A `Namespaced` field takes no slots in its parent.
+use core::ops::{Deref, DerefMut};
+use stylus_sdk::{
+    alloy_primitives::U256,
+    keccak_const::Keccak256,
+    storage::{StorageGuard, StorageGuardMut, StorageType},
+};
+
+// A storage struct that lives under a fixed name.
+pub trait Namespace: StorageType {
+    const SLOT: U256;
+}
+
+// keccak256(name) as a slot.
+pub const fn slot(name: &str) -> U256 {
+    U256::from_be_bytes(Keccak256::new().update(name.as_bytes()).finalize())
+}
+
+pub struct Namespaced<T: Namespace>(T);
+
+impl<T: Namespace> StorageType for Namespaced<T> {
+    type Wraps<'a>
+        = StorageGuard<'a, Self>
+    where
+        Self: 'a;
+    type WrapsMut<'a>
+        = StorageGuardMut<'a, Self>
+    where
+        Self: 'a;
+
+    // no bytes and no slots, so the next field of the parent starts where this one would have
+    const SLOT_BYTES: usize = 0;
+
+    unsafe fn new(_slot: U256, _offset: u8) -> Self {
+        Self(T::new(T::SLOT, 0))
+    }
+
+    fn load<'s>(self) -> Self::Wraps<'s> {
+        StorageGuard::new(self)
+    }
+
+    fn load_mut<'s>(self) -> Self::WrapsMut<'s> {
+        StorageGuardMut::new(self)
+    }
+}
+
+impl<T: Namespace> Deref for Namespaced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Namespace> DerefMut for Namespaced<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,95 @@
+// Two-step ownership shared by the Proxy, Vault and Counter samples.
+// `transfer_ownership` only nominates a pending owner; the nominee has to call
+// `accept_ownership`, so a typo in the new address can't brick the contract.
+use crate::deployer::is_deployer;
+use alloy_sol_types::sol;
+use stylus_sdk::{alloy_primitives::Address, evm, msg, prelude::*};
+
+sol! {
+    event OwnershipTransferStarted(address indexed previous_owner, address indexed new_owner);
+    event OwnershipTransferred(address indexed previous_owner, address indexed new_owner);
+
+    error Unauthorized(address caller);
+    error InvalidOwner(address owner);
+}
+
+#[derive(SolidityError)]
+pub enum OwnableError {
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+sol_storage! {
+    pub struct Ownable {
+        address owner;
+        address pending_owner;
+        // stays set after `renounce_ownership`, so nobody can initialize again
+        bool initialized;
+    }
+}
+
+impl Ownable {
+    // Makes the caller the first owner; can only run once, and only for the
+    // account that deployed the contract with `deploy_nonce` (see deployer.rs).
+    pub fn initialize(&mut self, deploy_nonce: u64) -> Result<(), OwnableError> {
+        let owner = msg::sender();
+        if self.initialized.get() {
+            return Err(OwnableError::InvalidOwner(InvalidOwner { owner }));
+        }
+        if !is_deployer(owner, deploy_nonce) {
+            return Err(OwnableError::Unauthorized(Unauthorized { caller: owner }));
+        }
+        self.initialized.set(true);
+        self.transfer(owner);
+        Ok(())
+    }
+
+    pub fn only_owner(&self) -> Result<(), OwnableError> {
+        if msg::sender() != self.owner.get() {
+            return Err(OwnableError::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, new_owner: Address) {
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        self.pending_owner.set(Address::ZERO);
+        evm::log(OwnershipTransferred { previous_owner, new_owner });
+    }
+}
+
+#[external]
+impl Ownable {
+    pub fn owner(&self) -> Result<Address, OwnableError> {
+        Ok(self.owner.get())
+    }
+
+    pub fn pending_owner(&self) -> Result<Address, OwnableError> {
+        Ok(self.pending_owner.get())
+    }
+
+    // Passing the zero address cancels a pending transfer.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), OwnableError> {
+        self.only_owner()?;
+        self.pending_owner.set(new_owner);
+        evm::log(OwnershipTransferStarted { previous_owner: self.owner.get(), new_owner });
+        Ok(())
+    }
+
+    pub fn accept_ownership(&mut self) -> Result<(), OwnableError> {
+        let caller = msg::sender();
+        if caller != self.pending_owner.get() {
+            return Err(OwnableError::Unauthorized(Unauthorized { caller }));
+        }
+        self.transfer(caller);
+        Ok(())
+    }
+
+    // Leaves the contract without an owner; owner-only functions become unreachable.
+    pub fn renounce_ownership(&mut self) -> Result<(), OwnableError> {
+        self.only_owner()?;
+        self.transfer(Address::ZERO);
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// The pair's two order books, shared by the facets that take orders
+// (23_pair_twamm.rs, 23_pair_batch.rs) and the one that pays them out
+// (23_pair_claims.rs). Each book is namespaced (see namespaced.rs), clear of
+// `PairState` and the LP token.
+#![allow(dead_code)]
+use crate::namespaced::{self, Namespace};
+use stylus_sdk::{
+    alloy_primitives::U256,
+    prelude::*,
+    storage::{StorageMap, StorageU256},
+};
+
+sol_storage! {
+    pub struct TwammState {
+        uint256 last_virtual_order_block;
+        // summed per-block rates of the active orders selling token0 / token1
+        uint256 sell_rate0;
+        uint256 sell_rate1;
+        mapping(uint256 => uint256) sell_rate0_ending;
+        mapping(uint256 => uint256) sell_rate1_ending;
+        // one bit per boundary that orders end at, 256 boundaries to a word
+        mapping(uint256 => uint256) expiry_bitmap;
+        // proceeds per unit of sell rate, Q128, and their values at each expiry
+        uint256 reward_factor0;
+        uint256 reward_factor1;
+        mapping(uint256 => uint256) reward_factor0_at;
+        mapping(uint256 => uint256) reward_factor1_at;
+        mapping(uint256 => LongTermOrder) long_term_orders;
+        uint256 next_long_term_order_id;
+    }
+
+    pub struct LongTermOrder {
+        address owner;
+        bool zero_for_one;
+        // zero once cancelled
+        uint256 sell_rate;
+        uint256 expiry_block;
+        // reward factor up to which proceeds have been paid
+        uint256 reward_factor_last;
+    }
+
+    pub struct BatchState {
+        uint256 epoch_blocks;
+        // ids start at 1; the open batch takes orders until its end block
+        uint256 open_batch;
+        mapping(uint256 => Batch) batches;
+        mapping(uint256 => Order) orders;
+        uint256 next_order_id;
+    }
+
+    pub struct Batch {
+        uint256 end_block;
+        uint256 amount0_in;
+        uint256 amount1_in;
+        uint256 price_numerator;
+        uint256 price_denominator;
+        uint256 fee_bps;
+        bool settled;
+        // settled without a price: every order gets its input back
+        bool cancelled;
+    }
+
+    pub struct Order {
+        address owner;
+        uint256 batch_id;
+        // selling token0 for token1
+        bool zero_for_one;
+        uint256 amount_in;
+        bool claimed;
+    }
+}
+
+impl Namespace for TwammState {
+    const SLOT: U256 = namespaced::slot("stylus.pair.twamm");
+}
+
+impl Namespace for BatchState {
+    const SLOT: U256 = namespaced::slot("stylus.pair.batch");
+}
+
+impl TwammState {
+    // Sell rate, rates ending per expiry and reward factor of the orders
+    // selling token0, or token1.
+    pub fn stream(&mut self, zero_for_one: bool) -> (&mut StorageU256, &mut StorageMap<U256, StorageU256>, &StorageU256) {
+        if zero_for_one {
+            (&mut self.sell_rate0, &mut self.sell_rate0_ending, &self.reward_factor0)
+        } else {
+            (&mut self.sell_rate1, &mut self.sell_rate1_ending, &self.reward_factor1)
+        }
+    }
+}
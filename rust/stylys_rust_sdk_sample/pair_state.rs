@@ -0,0 +1,391 @@
+// State and helpers shared by the pair (23_pair.rs) and its facets: mint and
+// burn in 23_pair_liquidity.rs, swaps in 23_pair_swap.rs, long-term orders in
+// 23_pair_twamm.rs, the batch auction in 23_pair_batch.rs and the payouts of
+// both kinds of order in 23_pair_claims.rs. Together they are too big for one
+// Stylus program, so the pair delegate_calls the facets and they run on the
+// pair's storage.
+//
+// Layout rule: the pair and its facets start their storage with `PairState`
+// at slot 0, so they all see the same reserves, escrow and config. The pair's
+// LP token comes right after it, where the liquidity facet finds it too. The
+// order books are namespaced (see pair_orders.rs), so they never lay fields
+// over each other or over the LP token. Each of the six uses part of it.
+#![allow(dead_code)]
+use crate::erc20::{Erc20Error, Erc20Params};
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use amm_math::dynamic_fee::{dynamic_fee, spot_price, update_variance, volatility, FeeConfig};
+use amm_math::fixed_point::WAD;
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+    call::{self, delegate_call, Call},
+    contract, evm,
+    prelude::*,
+    storage::{StorageCache, TopLevelStorage},
+};
+
+sol_interface! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+// What each facet serves; the pair forwards these selectors to it.
+sol! {
+    interface IPairLiquidity {
+        function mint(address to) external returns (uint256);
+        function burn(address to) external returns (uint256, uint256);
+    }
+
+    interface IPairSwap {
+        function swap(uint256 amount0_out, uint256 amount1_out, address to, bytes data) external;
+        function currentFee() external view returns (uint64);
+        function volatility() external view returns (uint256);
+        function feeConfig() external view returns (uint64, uint256, uint64, uint256);
+        function setFeeConfig(uint64 base_fee_bps, uint256 volatility_multiplier, uint64 max_fee_bps, uint256 decay) external;
+    }
+
+    interface IPairTwamm {
+        function enableLongTermOrders(uint64 order_block_interval) external;
+        function executeVirtualOrders() external;
+        function longTermSwap(bool zero_for_one, uint256 amount_in, uint64 intervals) external returns (uint256);
+        function longTermState() external view returns (uint256, uint256, uint256);
+    }
+
+    interface IPairBatch {
+        function setBatchMode(bool enabled, uint64 epoch_blocks) external;
+        function commitOrder(bool zero_for_one, uint256 amount_in) external returns (uint256);
+        function settleBatch(uint256 batch_id) external;
+        function openBatch() external view returns (uint256);
+        function batch(uint256 batch_id) external view returns (uint256, uint256, uint256, uint256, uint256, bool, bool);
+    }
+
+    interface IPairClaims {
+        function withdrawProceeds(uint256 order_id) external returns (uint256);
+        function cancelLongTermOrder(uint256 order_id) external returns (uint256, uint256);
+        function longTermOrder(uint256 order_id) external view returns (address, bool, uint256, uint256, uint256);
+        function claim(uint256 order_id) external returns (uint256);
+        function claimable(uint256 order_id) external view returns (uint256);
+        function refund(uint256 order_id) external returns (uint256);
+        function order(uint256 order_id) external view returns (address, uint256, bool, uint256, bool);
+    }
+}
+
+sol! {
+    event Sync(uint256 reserve0, uint256 reserve1);
+    event FeeConfigUpdated(uint256 base_fee_bps, uint256 volatility_multiplier, uint256 max_fee_bps, uint256 decay);
+
+    error AlreadyInitialized();
+    error InsufficientOutputAmount();
+    error InsufficientInputAmount();
+    error InsufficientLiquidity(uint256 reserve0, uint256 reserve1);
+    error InsufficientLiquidityMinted();
+    error InsufficientLiquidityBurned();
+    error InvalidTo(address to);
+    error CallbackFailed(address to);
+    error KInvariantViolated(uint256 before, uint256 after);
+    error InvalidFeeConfig(uint256 base_fee_bps, uint256 max_fee_bps, uint256 decay);
+    error InsufficientShares(uint256 have, uint256 want);
+    error BatchModeEnabled();
+    error BatchModeDisabled();
+    error InvalidEpoch();
+    error UnknownBatch(uint256 batch_id);
+    error BatchNotEnded(uint256 batch_id, uint256 end_block);
+    error BatchAlreadySettled(uint256 batch_id);
+    error BatchNotSettled(uint256 batch_id);
+    error BatchWasCancelled(uint256 batch_id);
+    error BatchNotCancelled(uint256 batch_id);
+    error UnknownOrder(uint256 order_id);
+    error OrderAlreadyClaimed(uint256 order_id);
+    error LongTermOrdersDisabled();
+    error InvalidInterval();
+    error LongTermOrderClosed(uint256 order_id);
+    error ExternalCallFailed(address target);
+    error MathOverflow();
+    error FacetCallFailed(address facet, bytes reason);
+}
+
+#[derive(SolidityError)]
+pub enum PairError {
+    AlreadyInitialized(AlreadyInitialized),
+    InsufficientOutputAmount(InsufficientOutputAmount),
+    InsufficientInputAmount(InsufficientInputAmount),
+    InsufficientLiquidity(InsufficientLiquidity),
+    InsufficientLiquidityMinted(InsufficientLiquidityMinted),
+    InsufficientLiquidityBurned(InsufficientLiquidityBurned),
+    InvalidTo(InvalidTo),
+    CallbackFailed(CallbackFailed),
+    KInvariantViolated(KInvariantViolated),
+    InvalidFeeConfig(InvalidFeeConfig),
+    InsufficientShares(InsufficientShares),
+    BatchModeEnabled(BatchModeEnabled),
+    BatchModeDisabled(BatchModeDisabled),
+    InvalidEpoch(InvalidEpoch),
+    UnknownBatch(UnknownBatch),
+    BatchNotEnded(BatchNotEnded),
+    BatchAlreadySettled(BatchAlreadySettled),
+    BatchNotSettled(BatchNotSettled),
+    BatchWasCancelled(BatchWasCancelled),
+    BatchNotCancelled(BatchNotCancelled),
+    UnknownOrder(UnknownOrder),
+    OrderAlreadyClaimed(OrderAlreadyClaimed),
+    LongTermOrdersDisabled(LongTermOrdersDisabled),
+    InvalidInterval(InvalidInterval),
+    LongTermOrderClosed(LongTermOrderClosed),
+    ExternalCallFailed(ExternalCallFailed),
+    MathOverflow(MathOverflow),
+    FacetCallFailed(FacetCallFailed),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for PairError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => PairError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => PairError::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<Erc20Error> for PairError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => PairError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+            Erc20Error::InsufficientAllowance(e) => PairError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+        }
+    }
+}
+
+// The pair's LP token.
+pub struct PairParams;
+
+impl Erc20Params for PairParams {
+    const NAME: &'static str = "Stylus Pair LP";
+    const SYMBOL: &'static str = "SPLP";
+    const DECIMALS: u8 = 18;
+}
+
+pub const MAX_FEE_BPS: u64 = 1000;
+
+sol_storage! {
+    pub struct PairState {
+        address token0;
+        address token1;
+        uint256 reserve0;
+        uint256 reserve1;
+        uint256 block_timestamp_last;
+        // Sum of spot price * seconds since init, WAD; wraps like V2 so consumers
+        // take wrapping differences over their window.
+        uint256 price0_cumulative_last;
+        uint256 price1_cumulative_last;
+
+        uint256 base_fee_bps;
+        uint256 volatility_multiplier;
+        uint256 max_fee_bps;
+        // EWMA weight kept per block, WAD
+        uint256 decay;
+        // EWMA of squared per-block log returns, WAD
+        uint256 variance;
+        uint256 last_observed_price;
+        uint256 last_observed_block;
+
+        bool batch_mode;
+        // 0 until long-term orders are enabled; expiries fall on multiples of it
+        uint256 order_block_interval;
+        // batch and long-term order inputs not yet traded plus outputs not yet
+        // claimed, held apart from the reserves
+        uint256 escrow0;
+        uint256 escrow1;
+
+        // set once by the pair's init
+        address liquidity_facet;
+        address swap_facet;
+        address twamm_facet;
+        address batch_facet;
+        address claims_facet;
+
+        #[borrow]
+        Ownable ownable;
+    }
+}
+
+impl PairState {
+    pub fn failed(target: Address) -> PairError {
+        PairError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    pub fn overflow() -> PairError {
+        PairError::MathOverflow(MathOverflow {})
+    }
+
+    // `value - sub + add` for escrow and sell rate bookkeeping, reverting
+    // instead of wrapping if the books ever disagree.
+    pub fn rebalance(value: U256, sub: U256, add: U256) -> Result<U256, PairError> {
+        value.checked_sub(sub).and_then(|value| value.checked_add(add)).ok_or_else(Self::overflow)
+    }
+
+    // Token0 or token1, for code that handles both sides of a trade alike.
+    pub fn token(&self, zero: bool) -> Address {
+        if zero {
+            self.token0.get()
+        } else {
+            self.token1.get()
+        }
+    }
+
+    // Takes `sub` out of and puts `add` into the escrow of token0 or token1.
+    pub fn rebalance_escrow(&mut self, zero: bool, sub: U256, add: U256) -> Result<(), PairError> {
+        let escrow = if zero { &mut self.escrow0 } else { &mut self.escrow1 };
+        escrow.set(Self::rebalance(escrow.get(), sub, add)?);
+        Ok(())
+    }
+
+    pub fn config(&self) -> FeeConfig {
+        FeeConfig {
+            base_fee_bps: self.base_fee_bps.get().saturating_to::<u64>(),
+            volatility_multiplier: self.volatility_multiplier.get(),
+            max_fee_bps: self.max_fee_bps.get().saturating_to::<u64>(),
+        }
+    }
+
+    pub fn set_fee_config(
+        &mut self,
+        base_fee_bps: u64,
+        volatility_multiplier: U256,
+        max_fee_bps: u64,
+        decay: U256,
+    ) -> Result<(), PairError> {
+        if base_fee_bps > max_fee_bps || max_fee_bps > MAX_FEE_BPS || decay >= WAD {
+            return Err(PairError::InvalidFeeConfig(InvalidFeeConfig {
+                base_fee_bps: U256::from(base_fee_bps),
+                max_fee_bps: U256::from(max_fee_bps),
+                decay,
+            }));
+        }
+        self.base_fee_bps.set(U256::from(base_fee_bps));
+        self.volatility_multiplier.set(volatility_multiplier);
+        self.max_fee_bps.set(U256::from(max_fee_bps));
+        self.decay.set(decay);
+        evm::log(FeeConfigUpdated {
+            base_fee_bps: U256::from(base_fee_bps),
+            volatility_multiplier,
+            max_fee_bps: U256::from(max_fee_bps),
+            decay,
+        });
+        Ok(())
+    }
+
+    // Fee in bps at the last observed volatility.
+    pub fn fee_bps(&self) -> u64 {
+        dynamic_fee(volatility(self.variance.get()), &self.config())
+    }
+
+    // (variance, price) after folding in the current reserves, if block `number`
+    // hasn't been observed yet.
+    pub fn pending_observation(&self, number: U256) -> (U256, U256) {
+        let variance = self.variance.get();
+        let last_price = self.last_observed_price.get();
+        let Some(price) = spot_price(self.reserve0.get(), self.reserve1.get()) else {
+            return (variance, last_price);
+        };
+        let blocks = number.saturating_sub(self.last_observed_block.get()).saturating_to::<u64>();
+        if last_price == U256::ZERO || blocks == 0 {
+            return (variance, if last_price == U256::ZERO { price } else { last_price });
+        }
+        let variance = update_variance(variance, last_price, price, blocks, self.decay.get()).unwrap_or(variance);
+        (variance, price)
+    }
+
+    pub fn observe(&mut self, number: U256) {
+        if number <= self.last_observed_block.get() && self.last_observed_price.get() != U256::ZERO {
+            return;
+        }
+        let (variance, price) = self.pending_observation(number);
+        self.variance.set(variance);
+        self.last_observed_price.set(price);
+        self.last_observed_block.set(number);
+    }
+
+    pub fn update(&mut self, balance0: U256, balance1: U256) {
+        let now = block::timestamp() as u32;
+        let elapsed = now.wrapping_sub(self.block_timestamp_last.get().saturating_to::<u32>());
+        let (reserve0, reserve1) = (self.reserve0.get(), self.reserve1.get());
+        if elapsed > 0 && reserve0 != U256::ZERO && reserve1 != U256::ZERO {
+            let elapsed = U256::from(elapsed);
+            let cumulative0 = self.price0_cumulative_last.get().wrapping_add(reserve1 * WAD / reserve0 * elapsed);
+            let cumulative1 = self.price1_cumulative_last.get().wrapping_add(reserve0 * WAD / reserve1 * elapsed);
+            self.price0_cumulative_last.set(cumulative0);
+            self.price1_cumulative_last.set(cumulative1);
+        }
+        self.reserve0.set(balance0);
+        self.reserve1.set(balance1);
+        self.block_timestamp_last.set(U256::from(now));
+        evm::log(Sync { reserve0: balance0, reserve1: balance1 });
+    }
+}
+
+// What needs the contract itself, for token calls and the facet hook. Any
+// storage struct that starts with `PairState` gets these.
+pub trait Pool: TopLevelStorage + BorrowMut<PairState> + Sized {
+    fn balance_of(&self, token: Address) -> Result<U256, PairError> {
+        IERC20::new(token).balance_of(self, contract::address()).map_err(|_| PairState::failed(token))
+    }
+
+    // Token balances minus what the order escrow holds.
+    fn pool_balances(&self) -> Result<(U256, U256), PairError> {
+        let state: &PairState = self.borrow();
+        let (token0, token1, escrow0, escrow1) = (state.token0.get(), state.token1.get(), state.escrow0.get(), state.escrow1.get());
+        let pool0 = self.balance_of(token0)?.checked_sub(escrow0).ok_or_else(PairState::overflow)?;
+        let pool1 = self.balance_of(token1)?.checked_sub(escrow1).ok_or_else(PairState::overflow)?;
+        Ok((pool0, pool1))
+    }
+
+    fn safe_transfer(&mut self, token: Address, to: Address, amount: U256) -> Result<(), PairError> {
+        let ok = IERC20::new(token).transfer(Call::new_in(self), to, amount).map_err(|_| PairState::failed(token))?;
+        if !ok {
+            return Err(PairState::failed(token));
+        }
+        Ok(())
+    }
+
+    // Pulls `amount` of `token` from `from` (who approved the pair first).
+    fn pull(&mut self, token: Address, from: Address, amount: U256) -> Result<(), PairError> {
+        let ok = IERC20::new(token)
+            .transfer_from(Call::new_in(self), from, contract::address(), amount)
+            .map_err(|_| PairState::failed(token))?;
+        if !ok {
+            return Err(PairState::failed(token));
+        }
+        Ok(())
+    }
+
+    // Has the TWAMM facet trade the long-term orders up to this block, if they
+    // are enabled. Call it before reading the reserves, escrow or oracle: the
+    // facet writes them, and storage fields keep what they read first.
+    fn execute_long_term_orders(&mut self) -> Result<(), PairError> {
+        let state: &PairState = self.borrow();
+        if state.order_block_interval.get() == U256::ZERO {
+            return Ok(());
+        }
+        let facet = state.twamm_facet.get();
+        // the facet reads and writes through the EVM, so nothing may wait in the cache
+        StorageCache::clear();
+        let data = IPairTwamm::executeVirtualOrdersCall {}.abi_encode();
+        unsafe { delegate_call(self, facet, &data) }.map_err(|e| {
+            let reason = match e {
+                call::Error::Revert(reason) => reason,
+                _ => Vec::new(),
+            };
+            PairError::FacetCallFailed(FacetCallFailed { facet, reason: reason.into() })
+        })?;
+        Ok(())
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<PairState>> Pool for T {}
@@ -0,0 +1,246 @@
+// State and helpers shared by the StableSwap pool (20_stable_swap_pool.rs) and
+// its facets: swaps and single-coin withdrawals in 20_stable_swap_exchange.rs,
+// adds and the other removes in 20_stable_swap_liquidity.rs. Together they are
+// too big for one Stylus program, so the pool delegate_calls the facets and
+// they run on the pool's storage.
+//
+// Layout rule: the pool and its facets start their storage with
+// `StableSwapState` at slot 0, and the LP token comes right after it, where the
+// facets find it to mint and burn.
+#![allow(dead_code)]
+use crate::erc20::{Erc20Error, Erc20Params};
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use amm_math::stable_swap::{get_d, ramped_a};
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+    call::Call,
+    contract, msg,
+    prelude::*,
+    storage::TopLevelStorage,
+};
+
+// Every balance is scaled to this many decimals before any math.
+pub const PRECISION_DECIMALS: u8 = 18;
+
+pub struct StableSwapParams;
+
+impl Erc20Params for StableSwapParams {
+    const NAME: &'static str = "StableSwap LP";
+    const SYMBOL: &'static str = "SSLP";
+    const DECIMALS: u8 = 18;
+}
+
+sol_interface! {
+    interface IERC20 {
+        function decimals() external view returns (uint8);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+// What each facet serves; the pool forwards these selectors to it.
+sol! {
+    interface IStableSwapExchange {
+        function getDy(uint256 i, uint256 j, uint256 dx) external view returns (uint256);
+        function exchange(uint256 i, uint256 j, uint256 dx, uint256 min_dy) external returns (uint256);
+        function calcWithdrawOneCoin(uint256 token_amount, uint256 i) external view returns (uint256);
+        function removeLiquidityOneCoin(uint256 token_amount, uint256 i, uint256 min_amount) external returns (uint256);
+    }
+
+    interface IStableSwapLiquidity {
+        function getVirtualPrice() external view returns (uint256);
+        function calcTokenAmount(uint256[] amounts, bool is_deposit) external view returns (uint256);
+        function addLiquidity(uint256[] amounts, uint256 min_mint_amount) external returns (uint256);
+        function removeLiquidity(uint256 amount, uint256[] min_amounts) external returns (uint256[]);
+        function removeLiquidityImbalance(uint256[] amounts, uint256 max_burn_amount) external returns (uint256);
+    }
+}
+
+sol! {
+    error AlreadyInitialized();
+    error InvalidCoins(uint256 n_coins);
+    error InvalidCoinIndex(uint256 index);
+    error InvalidAmounts(uint256 expected, uint256 got);
+    error InvalidA(uint256 a);
+    error RampTooSoon(uint256 now, uint256 allowed_at);
+    error FeeTooHigh(uint256 fee);
+    error Slippage(uint256 amount, uint256 limit);
+    error InsufficientShares(uint256 have, uint256 want);
+    error EmptyPool();
+    error InsufficientBalance(uint256 index, uint256 have, uint256 want);
+    error InvariantDidNotConverge();
+    error InvariantDecreased(uint256 before, uint256 after);
+    error ExternalCallFailed(address target);
+}
+
+#[derive(SolidityError)]
+pub enum StableSwapError {
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidCoins(InvalidCoins),
+    InvalidCoinIndex(InvalidCoinIndex),
+    InvalidAmounts(InvalidAmounts),
+    InvalidA(InvalidA),
+    RampTooSoon(RampTooSoon),
+    FeeTooHigh(FeeTooHigh),
+    Slippage(Slippage),
+    InsufficientShares(InsufficientShares),
+    EmptyPool(EmptyPool),
+    InsufficientBalance(InsufficientBalance),
+    InvariantDidNotConverge(InvariantDidNotConverge),
+    InvariantDecreased(InvariantDecreased),
+    ExternalCallFailed(ExternalCallFailed),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for StableSwapError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => StableSwapError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => StableSwapError::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<Erc20Error> for StableSwapError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => StableSwapError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+            Erc20Error::InsufficientAllowance(e) => StableSwapError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+        }
+    }
+}
+
+// Balances are scaled by `rates` to 18 decimals before any math, so DAI and
+// USDC compare 1:1.
+sol_storage! {
+    pub struct StableSwapState {
+        address[] coins;
+        // raw token balances owned by LPs
+        uint256[] balances;
+        // 10^(18 - decimals) per coin
+        uint256[] rates;
+        uint256 fee;
+        // A * A_PRECISION at each end of the current ramp
+        uint256 initial_a;
+        uint256 future_a;
+        uint256 initial_a_time;
+        uint256 future_a_time;
+
+        // set once by the pool's init
+        address exchange_facet;
+        address liquidity_facet;
+
+        #[borrow]
+        Ownable ownable;
+    }
+}
+
+impl StableSwapState {
+    pub fn failed(target: Address) -> StableSwapError {
+        StableSwapError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    pub fn no_convergence() -> StableSwapError {
+        StableSwapError::InvariantDidNotConverge(InvariantDidNotConverge {})
+    }
+
+    // The LP `supply`, once it's known `amount` of it can be burned; withdrawals divide by it.
+    pub fn supply_covering(supply: U256, amount: U256) -> Result<U256, StableSwapError> {
+        if supply == U256::ZERO {
+            return Err(StableSwapError::EmptyPool(EmptyPool {}));
+        }
+        if amount > supply {
+            return Err(StableSwapError::InsufficientShares(InsufficientShares { have: supply, want: amount }));
+        }
+        Ok(supply)
+    }
+
+    pub fn n_coins(&self) -> usize {
+        self.coins.len()
+    }
+
+    pub fn check_index(&self, index: U256) -> Result<usize, StableSwapError> {
+        if index >= U256::from(self.n_coins()) {
+            return Err(StableSwapError::InvalidCoinIndex(InvalidCoinIndex { index }));
+        }
+        Ok(index.saturating_to::<usize>())
+    }
+
+    pub fn check_amounts(&self, amounts: &[U256]) -> Result<(), StableSwapError> {
+        if amounts.len() != self.n_coins() {
+            return Err(StableSwapError::InvalidAmounts(InvalidAmounts {
+                expected: U256::from(self.n_coins()),
+                got: U256::from(amounts.len()),
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn amp(&self) -> U256 {
+        ramped_a(
+            self.initial_a.get(),
+            self.initial_a_time.get().saturating_to::<u64>(),
+            self.future_a.get(),
+            self.future_a_time.get().saturating_to::<u64>(),
+            block::timestamp(),
+        )
+    }
+
+    pub fn stored_balances(&self) -> Vec<U256> {
+        (0..self.n_coins()).map(|i| self.balances.get(i).unwrap()).collect()
+    }
+
+    pub fn set_balances(&mut self, balances: &[U256]) {
+        for (i, balance) in balances.iter().enumerate() {
+            self.balances.setter(i).unwrap().set(*balance);
+        }
+    }
+
+    // Balances normalized to 18 decimals.
+    pub fn xp(&self, balances: &[U256]) -> Vec<U256> {
+        balances.iter().enumerate().map(|(i, b)| *b * self.rates.get(i).unwrap()).collect()
+    }
+
+    pub fn d(&self, balances: &[U256], amp: U256) -> Result<U256, StableSwapError> {
+        get_d(&self.xp(balances), amp).ok_or_else(Self::no_convergence)
+    }
+}
+
+// What needs the contract itself, for token calls. Any storage struct that
+// starts with `StableSwapState` gets these.
+pub trait Pool: TopLevelStorage + BorrowMut<StableSwapState> + Sized {
+    fn pull(&mut self, index: usize, amount: U256) -> Result<(), StableSwapError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let state: &StableSwapState = self.borrow();
+        let token = state.coins.get(index).unwrap();
+        let ok = IERC20::new(token)
+            .transfer_from(Call::new_in(self), msg::sender(), contract::address(), amount)
+            .map_err(|_| StableSwapState::failed(token))?;
+        if !ok {
+            return Err(StableSwapState::failed(token));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, index: usize, to: Address, amount: U256) -> Result<(), StableSwapError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let state: &StableSwapState = self.borrow();
+        let token = state.coins.get(index).unwrap();
+        let ok = IERC20::new(token).transfer(Call::new_in(self), to, amount).map_err(|_| StableSwapState::failed(token))?;
+        if !ok {
+            return Err(StableSwapState::failed(token));
+        }
+        Ok(())
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<StableSwapState>> Pool for T {}
@@ -0,0 +1,515 @@
+// Host-side stand-ins for the Stylus `vm_hooks`, so the contract crates can run
+// `cargo test` natively. Linking this crate as a dev-dependency resolves every
+// hostio import against the functions below; state lives in a thread local,
+// so each test thread gets its own chain.
+//
+// The SDK caches `msg::sender()`, `msg::value()`, the block number and the
+// timestamp for the life of the process, so those are constants here: every
+// call comes from `SENDER` with `MSG_VALUE` attached, at `BLOCK_NUMBER` and
+// `TIMESTAMP`. Code that needs time to pass takes the block as a parameter
+// internally and is tested through that.
+//
+// Outgoing calls go to whatever `mock_call` or `mock_erc20` registered for the
+// target. Unmocked targets accept the call, move any ETH sent, and return an
+// ABI `true`, which is what token transfers expect.
+#![allow(clippy::missing_safety_doc)]
+
+use alloy_primitives::{address, Address, B256, U256};
+use alloy_sol_types::{sol, SolCall, SolEvent};
+use sha3::{Digest, Keccak256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use stylus_sdk::storage::StorageType;
+
+pub const SENDER: Address = address!("00000000000000000000000000000000000a11ce");
+pub const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+pub const CAROL: Address = address!("00000000000000000000000000000000000ca201");
+pub const BLOCK_NUMBER: u64 = 1_000;
+pub const TIMESTAMP: u64 = 1_700_000_000;
+pub const CHAIN_ID: u64 = 42_161;
+/// Sent with every call; only payable code reads it.
+pub const MSG_VALUE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// The contract under test, at the address `SENDER` deploys to with nonce 0.
+pub fn contract() -> Address {
+    // keccak(rlp([SENDER, 0]))
+    let mut rlp = vec![0xd6, 0x94];
+    rlp.extend_from_slice(SENDER.as_slice());
+    rlp.push(0x80);
+    Address::from_slice(&Keccak256::digest(&rlp)[12..])
+}
+
+sol! {
+    interface IERC20 {
+        function decimals() external view returns (uint8);
+        function totalSupply() external view returns (uint256);
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+        function approve(address spender, uint256 value) external returns (bool);
+        function allowance(address owner, address spender) external view returns (uint256);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    Delegate,
+    Static,
+}
+
+/// An outgoing call made by the contract under test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallRecord {
+    pub kind: CallKind,
+    pub to: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    pub topics: Vec<B256>,
+    pub data: Vec<u8>,
+}
+
+type Handler = Box<dyn FnMut(&CallRecord) -> Result<Vec<u8>, Vec<u8>>>;
+
+#[derive(Default)]
+struct Token {
+    balances: HashMap<Address, U256>,
+    supply: U256,
+}
+
+#[derive(Default)]
+struct Vm {
+    storage: HashMap<B256, B256>,
+    balances: HashMap<Address, U256>,
+    logs: Vec<Log>,
+    calls: Vec<CallRecord>,
+    return_data: Vec<u8>,
+    handlers: HashMap<Address, Handler>,
+    tokens: HashMap<Address, Token>,
+}
+
+thread_local! {
+    static VM: RefCell<Vm> = RefCell::new(Vm::default());
+}
+
+fn with<R>(f: impl FnOnce(&mut Vm) -> R) -> R {
+    VM.with(|vm| f(&mut vm.borrow_mut()))
+}
+
+/// Clears the chain and returns the contract's storage root.
+pub fn deploy<T: StorageType>() -> T {
+    reset();
+    unsafe { T::new(U256::ZERO, 0) }
+}
+
+pub fn reset() {
+    with(|vm| *vm = Vm::default());
+}
+
+pub fn set_balance(account: Address, balance: U256) {
+    with(|vm| vm.balances.insert(account, balance));
+}
+
+pub fn balance(account: Address) -> U256 {
+    with(|vm| vm.balances.get(&account).copied().unwrap_or_default())
+}
+
+pub fn logs() -> Vec<Log> {
+    with(|vm| vm.logs.clone())
+}
+
+/// Every `E` emitted so far, in order.
+pub fn events<E: SolEvent>() -> Vec<E> {
+    logs()
+        .into_iter()
+        .filter(|log| log.topics.first() == Some(&E::SIGNATURE_HASH))
+        .map(|log| E::decode_raw_log(log.topics.iter().copied(), &log.data, true).unwrap())
+        .collect()
+}
+
+/// `unwrap` for contract results: the `SolidityError` enums don't implement
+/// `Debug`, so this panics with the revert data instead.
+pub trait UnwrapOk<T> {
+    fn unwrap_ok(self) -> T;
+}
+
+impl<T, E: Into<Vec<u8>>> UnwrapOk<T> for Result<T, E> {
+    #[track_caller]
+    fn unwrap_ok(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(err) => panic!("reverted with {}", alloy_primitives::hex::encode_prefixed(err.into())),
+        }
+    }
+}
+
+pub fn calls() -> Vec<CallRecord> {
+    with(|vm| vm.calls.clone())
+}
+
+/// Answers calls to `target` with `handler`; `Err` reverts with its bytes.
+pub fn mock_call(target: Address, handler: impl FnMut(&CallRecord) -> Result<Vec<u8>, Vec<u8>> + 'static) {
+    with(|vm| vm.handlers.insert(target, Box::new(handler)));
+}
+
+/// Makes `token` a plain 18-decimal ERC-20. Allowances aren't tracked: `transferFrom`
+/// only checks the sender's balance.
+pub fn mock_erc20(token: Address) {
+    with(|vm| {
+        vm.tokens.entry(token).or_default();
+    });
+}
+
+pub fn mint_erc20(token: Address, to: Address, amount: U256) {
+    with(|vm| {
+        let state = vm.tokens.entry(token).or_default();
+        *state.balances.entry(to).or_default() += amount;
+        state.supply += amount;
+    });
+}
+
+/// Moves tokens as if `from` had called `transfer`, e.g. from a mocked contract.
+pub fn transfer_erc20(token: Address, from: Address, to: Address, amount: U256) {
+    with(|vm| {
+        let state = vm.tokens.entry(token).or_default();
+        let have = state.balances.get(&from).copied().unwrap_or_default();
+        assert!(have >= amount, "insufficient balance");
+        state.balances.insert(from, have - amount);
+        *state.balances.entry(to).or_default() += amount;
+    });
+}
+
+pub fn erc20_balance(token: Address, account: Address) -> U256 {
+    with(|vm| vm.tokens.get(&token).and_then(|state| state.balances.get(&account).copied()).unwrap_or_default())
+}
+
+fn word(value: U256) -> Vec<u8> {
+    value.to_be_bytes::<32>().to_vec()
+}
+
+fn token_call(state: &mut Token, caller: Address, data: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    let mut move_tokens = |from: Address, to: Address, value: U256| {
+        let have = state.balances.get(&from).copied().unwrap_or_default();
+        if have < value {
+            return Err(b"insufficient balance".to_vec());
+        }
+        state.balances.insert(from, have - value);
+        *state.balances.entry(to).or_default() += value;
+        Ok(word(U256::from(1)))
+    };
+    let selector: [u8; 4] = data.get(..4).and_then(|s| s.try_into().ok()).ok_or_else(Vec::new)?;
+    match selector {
+        IERC20::transferCall::SELECTOR => {
+            let call = IERC20::transferCall::abi_decode(data, true).map_err(|_| Vec::new())?;
+            move_tokens(caller, call.to, call.value)
+        }
+        IERC20::transferFromCall::SELECTOR => {
+            let call = IERC20::transferFromCall::abi_decode(data, true).map_err(|_| Vec::new())?;
+            move_tokens(call.from, call.to, call.value)
+        }
+        IERC20::balanceOfCall::SELECTOR => {
+            let call = IERC20::balanceOfCall::abi_decode(data, true).map_err(|_| Vec::new())?;
+            Ok(word(state.balances.get(&call.account).copied().unwrap_or_default()))
+        }
+        IERC20::decimalsCall::SELECTOR => Ok(word(U256::from(18))),
+        IERC20::totalSupplyCall::SELECTOR => Ok(word(state.supply)),
+        IERC20::approveCall::SELECTOR => Ok(word(U256::from(1))),
+        IERC20::allowanceCall::SELECTOR => Ok(word(U256::MAX)),
+        _ => Err(Vec::new()),
+    }
+}
+
+fn dispatch(record: CallRecord) -> Result<Vec<u8>, Vec<u8>> {
+    let caller = contract();
+    with(|vm| vm.calls.push(record.clone()));
+    if record.value > U256::ZERO {
+        let moved = with(|vm| {
+            let have = vm.balances.get(&caller).copied().unwrap_or_default();
+            if have < record.value {
+                return false;
+            }
+            vm.balances.insert(caller, have - record.value);
+            *vm.balances.entry(record.to).or_default() += record.value;
+            true
+        });
+        if !moved {
+            return Err(Vec::new());
+        }
+    }
+    if let Some(result) = with(|vm| vm.tokens.get_mut(&record.to).map(|state| token_call(state, caller, &record.data))) {
+        return result;
+    }
+    // the handler is taken out while it runs, so it can use the VM itself
+    let Some(mut handler) = with(|vm| vm.handlers.remove(&record.to)) else {
+        return Ok(if record.data.is_empty() { Vec::new() } else { word(U256::from(1)) });
+    };
+    let result = handler(&record);
+    with(|vm| {
+        vm.handlers.entry(record.to).or_insert(handler);
+    });
+    result
+}
+
+unsafe fn read_address(ptr: *const u8) -> Address {
+    Address::from_slice(std::slice::from_raw_parts(ptr, 20))
+}
+
+unsafe fn read_word(ptr: *const u8) -> B256 {
+    B256::from_slice(std::slice::from_raw_parts(ptr, 32))
+}
+
+unsafe fn write_bytes(dest: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), dest, bytes.len());
+}
+
+unsafe fn finish_call(result: Result<Vec<u8>, Vec<u8>>, return_data_len: *mut usize) -> u8 {
+    let (status, data) = match result {
+        Ok(data) => (0, data),
+        Err(data) => (1, data),
+    };
+    *return_data_len = data.len();
+    with(|vm| vm.return_data = data);
+    status
+}
+
+unsafe fn record(kind: CallKind, to: *const u8, calldata: *const u8, len: usize, value: U256) -> CallRecord {
+    CallRecord { kind, to: read_address(to), value, data: std::slice::from_raw_parts(calldata, len).to_vec() }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let value = with(|vm| vm.storage.get(&read_word(key)).copied().unwrap_or_default());
+    write_bytes(dest, value.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let (key, value) = (read_word(key), read_word(value));
+    with(|vm| vm.storage.insert(key, value));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8) {
+    let hash = Keccak256::digest(std::slice::from_raw_parts(bytes, len));
+    write_bytes(output, &hash);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write_bytes(sender, SENDER.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn msg_value(value: *mut u8) {
+    write_bytes(value, &MSG_VALUE.to_be_bytes::<32>());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn msg_reentrant() -> bool {
+    false
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tx_origin(origin: *mut u8) {
+    write_bytes(origin, SENDER.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tx_gas_price(gas_price: *mut u8) {
+    write_bytes(gas_price, &[0; 32]);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tx_ink_price() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn contract_address(address: *mut u8) {
+    write_bytes(address, contract().as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_number() -> u64 {
+    BLOCK_NUMBER
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_timestamp() -> u64 {
+    TIMESTAMP
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_basefee(basefee: *mut u8) {
+    write_bytes(basefee, &[0; 32]);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_coinbase(coinbase: *mut u8) {
+    write_bytes(coinbase, Address::ZERO.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_gas_limit() -> u64 {
+    u64::MAX
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn chainid() -> u64 {
+    CHAIN_ID
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn evm_gas_left() -> u64 {
+    u64::MAX
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn evm_ink_left() -> u64 {
+    u64::MAX
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pay_for_memory_grow(_pages: u16) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn account_balance(address: *const u8, dest: *mut u8) {
+    write_bytes(dest, &balance(read_address(address)).to_be_bytes::<32>());
+}
+
+// Mocked addresses have code; everything else is an EOA.
+fn has_code(account: Address) -> bool {
+    with(|vm| vm.handlers.contains_key(&account) || vm.tokens.contains_key(&account))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn account_code(address: *const u8, _offset: usize, _size: usize, _dest: *mut u8) -> usize {
+    let _ = read_address(address);
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn account_code_size(address: *const u8) -> usize {
+    if has_code(read_address(address)) {
+        1
+    } else {
+        0
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn account_codehash(address: *const u8, dest: *mut u8) {
+    let hash: [u8; 32] = if has_code(read_address(address)) { Keccak256::digest([0xfe]).into() } else { Keccak256::digest([]).into() };
+    write_bytes(dest, &hash);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let value = U256::from_be_bytes(read_word(value).0);
+    finish_call(dispatch(record(CallKind::Call, contract, calldata, calldata_len, value)), return_data_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn delegate_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    finish_call(dispatch(record(CallKind::Delegate, contract, calldata, calldata_len, U256::ZERO)), return_data_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    finish_call(dispatch(record(CallKind::Static, contract, calldata, calldata_len, U256::ZERO)), return_data_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    with(|vm| {
+        let data = vm.return_data.get(offset.min(vm.return_data.len())..).unwrap_or_default();
+        let written = size.min(data.len());
+        write_bytes(dest, &data[..written]);
+        written
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn return_data_size() -> usize {
+    with(|vm| vm.return_data.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let bytes = std::slice::from_raw_parts(data, len);
+    let (topic_bytes, data) = bytes.split_at(topics * 32);
+    let topics = topic_bytes.chunks(32).map(B256::from_slice).collect();
+    with(|vm| vm.logs.push(Log { topics, data: data.to_vec() }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn create1(
+    _code: *const u8,
+    _code_len: usize,
+    _endowment: *const u8,
+    _contract: *mut u8,
+    _revert_data_len: *mut usize,
+) {
+    unimplemented!("deployments aren't supported by the test VM")
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn create2(
+    _code: *const u8,
+    _code_len: usize,
+    _endowment: *const u8,
+    _salt: *const u8,
+    _contract: *mut u8,
+    _revert_data_len: *mut usize,
+) {
+    unimplemented!("deployments aren't supported by the test VM")
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn read_args(_dest: *mut u8) {
+    unimplemented!("tests call contract methods directly")
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn write_result(_data: *const u8, _len: usize) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn log_f32(_value: f32) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn log_f64(_value: f64) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn log_i32(_value: i32) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn log_i64(_value: i64) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn log_txt(_text: *const u8, _len: usize) {}
@@ -0,0 +1,239 @@
+// State and helpers shared by the vault (8_smart_vault.rs) and its strategy
+// manager (8_smart_vault_strategies.rs). Together they are too big for one
+// Stylus program, so the vault delegate_calls the manager and it runs on the
+// vault's storage.
+//
+// Layout rule: both start their storage with `VaultState` at slot 0, and the
+// vault's share token comes right after it, where the manager finds it to mint
+// the performance fee.
+#![allow(dead_code)]
+// Storage fields keep the ERC-4626 camelCase names.
+#![allow(non_snake_case)]
+use crate::erc20::{Erc20Error, Erc20Params, InsufficientAllowance};
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloy_sol_types::sol;
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    call::Call,
+    contract, evm,
+    prelude::*,
+    storage::TopLevelStorage,
+};
+
+pub struct VaultParams;
+
+impl Erc20Params for VaultParams {
+    const NAME: &'static str = "Vault Example";
+    const SYMBOL: &'static str = "VAULT";
+    const DECIMALS: u8 = 18;
+}
+
+sol_interface! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 value) external returns (bool);
+    }
+
+    // Strategies hold part of the vault's asset and put it to work.
+    // `withdraw` sends up to `amount` back to the vault and returns the loss it realized.
+    interface IStrategy {
+        function asset() external view returns (address);
+        function deposit(uint256 amount) external;
+        function withdraw(uint256 amount) external returns (uint256);
+        function totalAssets() external view returns (uint256);
+        function harvest() external;
+    }
+}
+
+// What the strategy manager serves; the vault forwards these selectors to it.
+sol! {
+    interface IVaultStrategies {
+        function addStrategy(address strategy, uint256 debtRatio) external;
+        function updateStrategyDebtRatio(address strategy, uint256 debtRatio) external;
+        function setWithdrawalQueue(address[] queue) external;
+        function setPerformanceFee(uint256 fee, address recipient) external;
+        function harvest(address strategy) external returns (uint256, uint256);
+        function strategy(address strategy) external view returns (bool, uint256, uint256, uint256, uint256, uint64);
+        function withdrawalQueue() external view returns (address[]);
+    }
+}
+
+sol! {
+    event StrategyLoss(address indexed strategy, uint256 loss);
+
+    error Overflow();
+    error DivideByZero();
+    error InsufficientShares(uint256 have, uint256 want);
+    error AssetTransferFailed(address asset, uint256 amount);
+    error ZeroShares(uint256 assets);
+    error InvalidDecimalsOffset(uint8 offset);
+    error StrategyCallFailed(address strategy);
+    error InvalidStrategy(address strategy);
+    error DebtRatioTooHigh(uint256 total_debt_ratio);
+    error WithdrawalQueueFull();
+    error FeeTooHigh(uint256 fee);
+    error ExcessiveLoss(uint256 loss, uint256 max_loss);
+    error WithdrawalShortfall(uint256 available, uint256 requested);
+}
+
+#[derive(SolidityError)]
+pub enum VaultError {
+    Overflow(Overflow),
+    DivideByZero(DivideByZero),
+    InsufficientShares(InsufficientShares),
+    InsufficientAllowance(InsufficientAllowance),
+    AssetTransferFailed(AssetTransferFailed),
+    ZeroShares(ZeroShares),
+    InvalidDecimalsOffset(InvalidDecimalsOffset),
+    StrategyCallFailed(StrategyCallFailed),
+    InvalidStrategy(InvalidStrategy),
+    DebtRatioTooHigh(DebtRatioTooHigh),
+    WithdrawalQueueFull(WithdrawalQueueFull),
+    FeeTooHigh(FeeTooHigh),
+    ExcessiveLoss(ExcessiveLoss),
+    WithdrawalShortfall(WithdrawalShortfall),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for VaultError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => VaultError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => VaultError::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<Erc20Error> for VaultError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => VaultError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+            Erc20Error::InsufficientAllowance(e) => VaultError::InsufficientAllowance(e),
+        }
+    }
+}
+
+// Debt ratios, performance fee and max loss are in basis points
+pub const MAX_BPS: u64 = 10_000;
+
+sol_storage! {
+    pub struct VaultState {
+        address asset;
+        uint8 decimalsOffset;
+        // asset lent out to strategies, as of their last report
+        uint256 totalDebt;
+        uint256 totalDebtRatio;
+        uint256 performanceFee;
+        address feeRecipient;
+        mapping(address => StrategyParams) strategies;
+        // strategies are drained in this order when idle assets can't cover a withdrawal
+        address[] withdrawalQueue;
+        // set once by the vault's init
+        address strategiesFacet;
+        #[borrow]
+        Ownable ownable;
+    }
+
+    pub struct StrategyParams {
+        bool active;
+        uint256 debtRatio;
+        uint256 totalDebt;
+        uint256 totalGain;
+        uint256 totalLoss;
+        uint64 lastReport;
+    }
+}
+
+// x * y / d, rounded down or up
+pub fn mul_div(x: U256, y: U256, d: U256, round_up: bool) -> Result<U256, VaultError> {
+    let product = x.checked_mul(y).ok_or(VaultError::Overflow(Overflow {}))?;
+    let quotient = product.checked_div(d).ok_or(VaultError::DivideByZero(DivideByZero {}))?;
+    if round_up && quotient * d != product {
+        return Ok(quotient + U256::from(1));
+    }
+    Ok(quotient)
+}
+
+impl VaultState {
+    pub fn strategy_failed(strategy: Address) -> VaultError {
+        VaultError::StrategyCallFailed(StrategyCallFailed { strategy })
+    }
+
+    pub fn check_strategy(&self, strategy: Address) -> Result<(), VaultError> {
+        if !self.strategies.get(strategy).active.get() {
+            return Err(VaultError::InvalidStrategy(InvalidStrategy { strategy }));
+        }
+        Ok(())
+    }
+
+    // Records a change in what `strategy` owes the vault.
+    pub fn change_debt(&mut self, strategy: Address, increase: U256, decrease: U256) {
+        let mut params = self.strategies.setter(strategy);
+        let debt = params.totalDebt.get() + increase;
+        let decrease = decrease.min(debt);
+        params.totalDebt.set(debt - decrease);
+        let total_debt = self.totalDebt.get() + increase;
+        self.totalDebt.set(total_debt - decrease.min(total_debt));
+    }
+
+    pub fn realize_loss(&mut self, strategy: Address, loss: U256) {
+        if loss == U256::ZERO {
+            return;
+        }
+        self.change_debt(strategy, U256::ZERO, loss);
+        let mut params = self.strategies.setter(strategy);
+        let total_loss = params.totalLoss.get() + loss;
+        params.totalLoss.set(total_loss);
+        evm::log(StrategyLoss { strategy, loss });
+    }
+
+    pub fn virtual_shares(&self) -> U256 {
+        U256::from(10).pow(U256::from(self.decimalsOffset.get().saturating_to::<u8>()))
+    }
+}
+
+// What needs the contract itself, for asset and strategy calls. Any storage
+// struct that starts with `VaultState` gets these.
+pub trait Lender: TopLevelStorage + BorrowMut<VaultState> + Sized {
+    fn idle(&self) -> Result<U256, VaultError> {
+        let state: &VaultState = self.borrow();
+        let asset = state.asset.get();
+        IERC20::new(asset)
+            .balance_of(self, contract::address())
+            .map_err(|_| VaultError::AssetTransferFailed(AssetTransferFailed { asset, amount: U256::ZERO }))
+    }
+
+    // Idle balance (including anything donated to the vault) plus what strategies owe.
+    fn total_assets(&self) -> Result<U256, VaultError> {
+        let state: &VaultState = self.borrow();
+        Ok(self.idle()? + state.totalDebt.get())
+    }
+
+    fn transfer_asset(&mut self, to: Address, amount: U256) -> Result<(), VaultError> {
+        let state: &VaultState = self.borrow();
+        let asset = state.asset.get();
+        let ok = IERC20::new(asset)
+            .transfer(Call::new_in(self), to, amount)
+            .map_err(|_| VaultError::AssetTransferFailed(AssetTransferFailed { asset, amount }))?;
+        if !ok {
+            return Err(VaultError::AssetTransferFailed(AssetTransferFailed { asset, amount }));
+        }
+        Ok(())
+    }
+
+    // Asks `strategy` for `amount`; returns (received, loss).
+    fn withdraw_from_strategy(&mut self, strategy: Address, amount: U256) -> Result<(U256, U256), VaultError> {
+        let before = self.idle()?;
+        let loss = IStrategy::new(strategy).withdraw(Call::new_in(self), amount).map_err(|_| VaultState::strategy_failed(strategy))?;
+        // a strategy that pulls assets out of the vault instead is broken, not generous
+        let received = self.idle()?.checked_sub(before).ok_or_else(|| VaultState::strategy_failed(strategy))?;
+        let state: &mut VaultState = self.borrow_mut();
+        state.change_debt(strategy, U256::ZERO, received);
+        state.realize_loss(strategy, loss);
+        Ok((received, loss))
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<VaultState>> Lender for T {}
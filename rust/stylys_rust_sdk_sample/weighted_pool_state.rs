@@ -0,0 +1,230 @@
+// State and helpers shared by the weighted pool (21_weighted_pool.rs) and its
+// facets: swaps in 21_weighted_pool_swap.rs, joins and exits in
+// 21_weighted_pool_liquidity.rs. Together they are too big for one Stylus
+// program, so the pool delegate_calls the facets and they run on the pool's
+// storage.
+//
+// Layout rule: the pool and its facets start their storage with
+// `WeightedPoolState` at slot 0, and the pool token comes right after it, where
+// the liquidity facet finds it to mint and burn.
+#![allow(dead_code)]
+use crate::erc20::{Erc20Error, Erc20Params};
+use crate::ownable::{InvalidOwner, Ownable, OwnableError, Unauthorized};
+use alloy_sol_types::sol;
+use core::borrow::BorrowMut;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    call::Call,
+    contract, msg,
+    prelude::*,
+    storage::TopLevelStorage,
+};
+
+pub struct WeightedPoolParams;
+
+impl Erc20Params for WeightedPoolParams {
+    const NAME: &'static str = "Weighted Pool Token";
+    const SYMBOL: &'static str = "WPT";
+    const DECIMALS: u8 = 18;
+}
+
+sol_interface! {
+    interface IERC20 {
+        function decimals() external view returns (uint8);
+        function transfer(address to, uint256 value) external returns (bool);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+}
+
+// What each facet serves; the pool forwards these selectors to it.
+sol! {
+    interface IWeightedPoolSwap {
+        function getSpotPrice(uint256 i, uint256 o) external view returns (uint256);
+        function getAmountOut(uint256 i, uint256 o, uint256 amount_in) external view returns (uint256);
+        function getAmountIn(uint256 i, uint256 o, uint256 amount_out) external view returns (uint256);
+        function swapExactAmountIn(uint256 i, uint256 amount_in, uint256 o, uint256 min_amount_out) external returns (uint256);
+        function swapExactAmountOut(uint256 i, uint256 max_amount_in, uint256 o, uint256 amount_out) external returns (uint256);
+    }
+
+    interface IWeightedPoolLiquidity {
+        function getInvariant() external view returns (uint256);
+        function initialJoin(uint256[] amounts_in) external returns (uint256);
+        function joinPool(uint256 bpt_out, uint256[] max_amounts_in) external returns (uint256[]);
+        function joinSwapExternAmountIn(uint256 index, uint256 amount_in, uint256 min_bpt_out) external returns (uint256);
+        function exitPool(uint256 bpt_in, uint256[] min_amounts_out) external returns (uint256[]);
+        function exitSwapPoolAmountIn(uint256 index, uint256 bpt_in, uint256 min_amount_out) external returns (uint256);
+    }
+}
+
+sol! {
+    error AlreadyInitialized();
+    error NotInitialized();
+    error InvalidTokens(uint256 n_tokens);
+    error InvalidTokenIndex(uint256 index);
+    error SameToken(uint256 index);
+    error InvalidWeights(uint256 total);
+    error InvalidAmounts(uint256 expected, uint256 got);
+    error SwapFeeTooHigh(uint256 swap_fee);
+    error Slippage(uint256 amount, uint256 limit);
+    error RatioTooLarge();
+    error InsufficientShares(uint256 have, uint256 want);
+    error ExternalCallFailed(address target);
+}
+
+#[derive(SolidityError)]
+pub enum WeightedPoolError {
+    AlreadyInitialized(AlreadyInitialized),
+    NotInitialized(NotInitialized),
+    InvalidTokens(InvalidTokens),
+    InvalidTokenIndex(InvalidTokenIndex),
+    SameToken(SameToken),
+    InvalidWeights(InvalidWeights),
+    InvalidAmounts(InvalidAmounts),
+    SwapFeeTooHigh(SwapFeeTooHigh),
+    Slippage(Slippage),
+    RatioTooLarge(RatioTooLarge),
+    InsufficientShares(InsufficientShares),
+    ExternalCallFailed(ExternalCallFailed),
+    Unauthorized(Unauthorized),
+    InvalidOwner(InvalidOwner),
+}
+
+impl From<OwnableError> for WeightedPoolError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::Unauthorized(e) => WeightedPoolError::Unauthorized(e),
+            OwnableError::InvalidOwner(e) => WeightedPoolError::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<Erc20Error> for WeightedPoolError {
+    fn from(err: Erc20Error) -> Self {
+        match err {
+            Erc20Error::InsufficientBalance(e) => WeightedPoolError::InsufficientShares(InsufficientShares { have: e.have, want: e.want }),
+            Erc20Error::InsufficientAllowance(e) => {
+                WeightedPoolError::InsufficientShares(InsufficientShares { have: e.have, want: e.want })
+            }
+        }
+    }
+}
+
+// Weights, fees and balances are WAD; balances are scaled to 18 decimals by
+// `rates` before any math.
+sol_storage! {
+    pub struct WeightedPoolState {
+        address[] tokens;
+        uint256[] balances;
+        // normalized, summing to 1e18
+        uint256[] weights;
+        // 10^(18 - decimals) per token
+        uint256[] rates;
+        uint256 swap_fee;
+
+        // set once by the pool's init
+        address swap_facet;
+        address liquidity_facet;
+
+        #[borrow]
+        Ownable ownable;
+    }
+}
+
+impl WeightedPoolState {
+    pub fn failed(target: Address) -> WeightedPoolError {
+        WeightedPoolError::ExternalCallFailed(ExternalCallFailed { target })
+    }
+
+    pub fn ratio_too_large() -> WeightedPoolError {
+        WeightedPoolError::RatioTooLarge(RatioTooLarge {})
+    }
+
+    pub fn n_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn check_index(&self, index: U256) -> Result<usize, WeightedPoolError> {
+        if index >= U256::from(self.n_tokens()) {
+            return Err(WeightedPoolError::InvalidTokenIndex(InvalidTokenIndex { index }));
+        }
+        Ok(index.saturating_to::<usize>())
+    }
+
+    // A token can't be priced against itself; the math would read the same
+    // balance as both sides of the trade.
+    pub fn check_pair(&self, i: U256, o: U256) -> Result<(usize, usize), WeightedPoolError> {
+        let (i, o) = (self.check_index(i)?, self.check_index(o)?);
+        if i == o {
+            return Err(WeightedPoolError::SameToken(SameToken { index: U256::from(i) }));
+        }
+        Ok((i, o))
+    }
+
+    // (balance, weight) of a token, balance scaled to 18 decimals.
+    pub fn scaled(&self, index: usize) -> (U256, U256) {
+        let balance = self.balances.get(index).unwrap() * self.rates.get(index).unwrap();
+        (balance, self.weights.get(index).unwrap())
+    }
+
+    pub fn upscale(&self, index: usize, amount: U256) -> U256 {
+        amount * self.rates.get(index).unwrap()
+    }
+
+    // Amounts leaving the pool round down, amounts entering round up.
+    pub fn downscale(&self, index: usize, amount: U256, round_up: bool) -> U256 {
+        let rate = self.rates.get(index).unwrap();
+        if round_up {
+            (amount + rate - U256::from(1)) / rate
+        } else {
+            amount / rate
+        }
+    }
+
+    fn add_balance(&mut self, index: usize, amount: U256) {
+        let mut balance = self.balances.setter(index).unwrap();
+        let new_balance = balance.get() + amount;
+        balance.set(new_balance);
+    }
+
+    fn sub_balance(&mut self, index: usize, amount: U256) {
+        let mut balance = self.balances.setter(index).unwrap();
+        let new_balance = balance.get() - amount;
+        balance.set(new_balance);
+    }
+}
+
+// What needs the contract itself, for token calls. Any storage struct that
+// starts with `WeightedPoolState` gets these.
+pub trait Pool: TopLevelStorage + BorrowMut<WeightedPoolState> + Sized {
+    fn pull(&mut self, index: usize, amount: U256) -> Result<(), WeightedPoolError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let state: &WeightedPoolState = self.borrow();
+        let token = state.tokens.get(index).unwrap();
+        let ok = IERC20::new(token)
+            .transfer_from(Call::new_in(self), msg::sender(), contract::address(), amount)
+            .map_err(|_| WeightedPoolState::failed(token))?;
+        if !ok {
+            return Err(WeightedPoolState::failed(token));
+        }
+        self.borrow_mut().add_balance(index, amount);
+        Ok(())
+    }
+
+    fn send(&mut self, index: usize, amount: U256) -> Result<(), WeightedPoolError> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        let state: &mut WeightedPoolState = self.borrow_mut();
+        state.sub_balance(index, amount);
+        let token = state.tokens.get(index).unwrap();
+        let ok = IERC20::new(token).transfer(Call::new_in(self), msg::sender(), amount).map_err(|_| WeightedPoolState::failed(token))?;
+        if !ok {
+            return Err(WeightedPoolState::failed(token));
+        }
+        Ok(())
+    }
+}
+
+impl<T: TopLevelStorage + BorrowMut<WeightedPoolState>> Pool for T {}